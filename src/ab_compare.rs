@@ -0,0 +1,171 @@
+//! A/B latency comparison: captures a source window (A) and a delayed copy of it (B),
+//! e.g. a game and its stream in a player window, and measures how far B lags behind.
+//!
+//! Every A frame is kept for a few seconds along with a tiny grayscale fingerprint. For
+//! each B frame the A frame with the most similar fingerprint is looked up; the difference
+//! of their capture times is the delay. The matched A frame is shown next to B, so both
+//! sides show the same content.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+
+use crate::capture::{self, Frame};
+
+/// Fingerprint size in pixels, small enough to be robust against scaling and compression.
+const FINGERPRINT_SIZE: [usize; 2] = [32, 18];
+
+/// How far back A frames are kept, i.e. the longest delay that can be measured.
+const HISTORY_US: u64 = 3_000_000;
+
+/// Upper bound of kept A frames, whatever their rate.
+const MAX_HISTORY_FRAMES: usize = 240;
+
+/// Mean absolute fingerprint difference (0–255) below which two frames show the same
+/// content.
+const MATCH_THRESHOLD: f32 = 12.0;
+
+/// Delay measurements the reported median is taken over.
+const DELAY_SAMPLES: usize = 15;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AbCompareConfig {
+    /// Title (or part of it) of the source window.
+    pub a: Option<String>,
+
+    /// Title (or part of it) of the window showing the delayed copy.
+    pub b: Option<String>,
+}
+
+/// The B frame and the A frame showing the same content.
+pub struct Pair {
+    pub a: Arc<Frame>,
+    pub b: Arc<Frame>,
+
+    /// Median delay of B behind A, if content could be matched recently.
+    pub delay_ms: Option<f32>,
+}
+
+#[derive(Default)]
+struct Comparison {
+    history: VecDeque<(Vec<u8>, Arc<Frame>)>,
+    matched_a: Option<Arc<Frame>>,
+    latest_b: Option<Arc<Frame>>,
+    delays_ms: VecDeque<f32>,
+}
+
+static COMPARISON: Lazy<Mutex<Comparison>> = Lazy::new(Default::default);
+
+/// Starts capturing both windows, each on a thread of its own.
+pub fn start(runtime: tokio::runtime::Handle, a: String, b: String) {
+    let sources = [(a, "A", on_a_frame as fn(Frame)), (b, "B", on_b_frame)];
+    for (title, name, on_frame) in sources {
+        let runtime = runtime.clone();
+        std::thread::Builder::new()
+            .name(format!("A/B source {name}"))
+            .spawn(move || {
+                if let Err(err) = capture::hold_window(&runtime, &title, on_frame) {
+                    eprintln!("A/B comparison, source {name}: {err:#}");
+                }
+            })
+            .expect("Failed to spawn an A/B comparison capture thread");
+    }
+}
+
+/// The latest B frame with its matching A frame, once both sources delivered frames.
+pub fn pair() -> Option<Pair> {
+    let comparison = COMPARISON.lock().unwrap();
+    let mut delays = comparison.delays_ms.iter().copied().collect::<Vec<_>>();
+    delays.sort_by(f32::total_cmp);
+    Some(Pair {
+        a: comparison.matched_a.clone()?,
+        b: comparison.latest_b.clone()?,
+        delay_ms: delays.get(delays.len() / 2).copied(),
+    })
+}
+
+fn on_a_frame(frame: Frame) {
+    let fingerprint = fingerprint(&frame);
+    let mut comparison = COMPARISON.lock().unwrap();
+    let oldest_kept = frame.capture_time_us.saturating_sub(HISTORY_US);
+    while comparison.history.len() >= MAX_HISTORY_FRAMES
+        || comparison
+            .history
+            .front()
+            .is_some_and(|(_, frame)| frame.capture_time_us < oldest_kept)
+    {
+        comparison.history.pop_front();
+    }
+    let frame = Arc::new(frame);
+    if comparison.matched_a.is_none() {
+        comparison.matched_a = Some(frame.clone());
+    }
+    comparison.history.push_back((fingerprint, frame));
+}
+
+fn on_b_frame(frame: Frame) {
+    puffin::profile_function!();
+
+    let fingerprint = fingerprint(&frame);
+    let frame = Arc::new(frame);
+    let mut comparison = COMPARISON.lock().unwrap();
+    let best = comparison
+        .history
+        .iter()
+        .filter(|(_, a)| a.capture_time_us <= frame.capture_time_us)
+        .map(|(a_fingerprint, a)| (difference(a_fingerprint, &fingerprint), a))
+        .min_by(|(left, _), (right, _)| left.total_cmp(right));
+    if let Some((best_difference, a)) = best {
+        // Static content matches every A frame equally well, only changes tell the delay.
+        let is_distinctive = comparison
+            .history
+            .iter()
+            .any(|(a_fingerprint, _)| difference(a_fingerprint, &fingerprint) > MATCH_THRESHOLD);
+        if best_difference < MATCH_THRESHOLD && is_distinctive {
+            let delay_ms = (frame.capture_time_us - a.capture_time_us) as f32 / 1000.0;
+            let a = a.clone();
+            comparison.matched_a = Some(a);
+            if comparison.delays_ms.len() == DELAY_SAMPLES {
+                comparison.delays_ms.pop_front();
+            }
+            comparison.delays_ms.push_back(delay_ms);
+        }
+    }
+    comparison.latest_b = Some(frame);
+}
+
+/// Grayscale of the frame, box filtered down to [`FINGERPRINT_SIZE`].
+fn fingerprint(frame: &Frame) -> Vec<u8> {
+    let bitmap = &frame.frame_bitmap;
+    let [width, height] = FINGERPRINT_SIZE;
+    let mut fingerprint = Vec::with_capacity(width * height);
+    for y in 0..height {
+        let rows = (y * bitmap.height / height)..((y + 1) * bitmap.height / height).max(1);
+        for x in 0..width {
+            let columns = (x * bitmap.width / width)..((x + 1) * bitmap.width / width).max(1);
+            let mut sum = 0u64;
+            let mut count = 0u64;
+            // Sampling every other pixel in both directions is plenty for a fingerprint.
+            for row in rows.clone().step_by(2) {
+                for pixel in bitmap.data[row * bitmap.width..][columns.clone()].iter().step_by(2) {
+                    sum += (pixel[0] as u64 + pixel[1] as u64 * 2 + pixel[2] as u64) / 4;
+                    count += 1;
+                }
+            }
+            fingerprint.push((sum / count.max(1)) as u8);
+        }
+    }
+    fingerprint
+}
+
+fn difference(a: &[u8], b: &[u8]) -> f32 {
+    let sum = a
+        .iter()
+        .zip(b)
+        .map(|(a, b)| a.abs_diff(*b) as u32)
+        .sum::<u32>();
+    sum as f32 / a.len().max(1) as f32
+}
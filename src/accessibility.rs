@@ -0,0 +1,85 @@
+//! High-contrast and reduced-motion modes.
+
+use re_renderer::Color32;
+use serde::Deserialize;
+
+use crate::framework;
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default)]
+pub struct Accessibility {
+    /// Draw overlays with a black/white/yellow palette instead of the demo colors.
+    pub high_contrast: bool,
+
+    /// Freeze everything that moves on its own: the overlap demo, the camera orbit and
+    /// any transitions.
+    pub reduced_motion: bool,
+}
+
+/// Colors used for the overlay geometry drawn on top of and around the capture.
+pub struct Palette {
+    pub outer_rect: Color32,
+    pub inner_rect: Color32,
+    pub radius_variations: Color32,
+    pub points: Color32,
+    pub overlap_points: Color32,
+    pub highlight: Color32,
+    pub annotation: Color32,
+    pub crosshair: Color32,
+
+    /// Whether lines may fade along their length, which lowers their contrast.
+    pub line_gradients: bool,
+}
+
+impl Accessibility {
+    pub fn palette(&self) -> Palette {
+        if self.high_contrast {
+            Palette {
+                outer_rect: Color32::WHITE,
+                inner_rect: Color32::YELLOW,
+                radius_variations: Color32::WHITE,
+                points: Color32::YELLOW,
+                overlap_points: Color32::YELLOW,
+                highlight: Color32::YELLOW,
+                annotation: Color32::YELLOW,
+                crosshair: Color32::WHITE,
+                line_gradients: false,
+            }
+        } else {
+            Palette {
+                outer_rect: Color32::BLUE,
+                inner_rect: Color32::from_rgb(255, 100, 1),
+                radius_variations: Color32::from_rgb(255, 180, 1),
+                points: Color32::from_rgb(55, 180, 1),
+                overlap_points: Color32::WHITE,
+                highlight: Color32::from_rgb(255, 200, 0),
+                annotation: Color32::from_rgb(255, 40, 120),
+                crosshair: Color32::from_rgb(0, 220, 255),
+                line_gradients: true,
+            }
+        }
+    }
+
+    /// Color of the `i`-th of `count` lines in the overlap demo.
+    pub fn overlap_line_color(&self, i: i16, count: i16) -> Color32 {
+        if self.high_contrast {
+            if i % 2 == 0 {
+                Color32::WHITE
+            } else {
+                Color32::BLACK
+            }
+        } else {
+            re_renderer::Hsva::new(0.25 / count as f32 * i as f32, 1.0, 0.5, 1.0).into()
+        }
+    }
+
+    /// Seconds that drive all animations, given the `current` ones; they stay where they
+    /// were when motion got reduced, so everything freezes in place instead of jumping.
+    pub fn animation_seconds(&self, time: &framework::Time, current: f32) -> f32 {
+        if self.reduced_motion {
+            current
+        } else {
+            time.seconds()
+        }
+    }
+}
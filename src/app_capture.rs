@@ -0,0 +1,257 @@
+//! Captures every window of an application as a separate stream and composites them into a
+//! grid, each window labeled with its title. Windows opening, closing and resizing are
+//! picked up within a second.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crabgrab::prelude::{CapturableWindow, CaptureStream, FrameBitmapBgraUnorm8x4};
+use serde::Deserialize;
+
+use crate::capture::{self, Frame};
+use crate::focus::ScreenRect;
+use crate::{capture_manager, status, text};
+
+/// How often the application's windows are enumerated.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Shortest time between two composites, each window frame would be too many.
+const COMPOSITE_INTERVAL: Duration = Duration::from_millis(33);
+
+/// Pixels between the grid cells.
+const GAP: usize = 16;
+
+/// Font pixel size of the window labels.
+const LABEL_SCALE: usize = 2;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AppCaptureConfig {
+    /// Name (or part of it) of the application whose windows are captured.
+    pub application: Option<String>,
+}
+
+struct Slot {
+    id: u64,
+    title: String,
+    frame: Option<Frame>,
+}
+
+struct Composite {
+    /// In the order the windows were found, which is the order of the grid cells.
+    slots: Vec<Slot>,
+    last_composite: Option<Instant>,
+    frames_composited: u64,
+    on_frame: Box<dyn FnMut(Frame) + Send>,
+}
+
+/// A captured window, owned by the thread that enumerates the windows.
+struct Tracked {
+    id: u64,
+    window: CapturableWindow,
+    stream: CaptureStream,
+}
+
+/// Captures the windows of `application` on a background thread, handing the composites
+/// to `on_frame`.
+pub fn start(
+    runtime: tokio::runtime::Handle,
+    application: String,
+    on_frame: impl FnMut(Frame) + Send + 'static,
+) {
+    status::update(|status| {
+        status.source = format!("windows of {application:?}");
+        status.source_name = "app".to_owned();
+    });
+    let composite = Arc::new(Mutex::new(Composite {
+        slots: Vec::new(),
+        last_composite: None,
+        frames_composited: 0,
+        on_frame: Box::new(on_frame),
+    }));
+    std::thread::Builder::new()
+        .name("app capture".to_owned())
+        .spawn(move || {
+            let owner = capture_manager::owner();
+            let mut tracked: Vec<Tracked> = Vec::new();
+            let mut next_id = 0;
+            loop {
+                if let Err(err) =
+                    update(&runtime, &application, &composite, &mut tracked, &mut next_id)
+                {
+                    eprintln!("Capturing {application:?}: {err:#}");
+                }
+                if !owner.wait(POLL_INTERVAL) {
+                    break;
+                }
+            }
+            for mut tracked in tracked {
+                tracked.stream.stop().ok();
+            }
+        })
+        .expect("Failed to spawn the app capture thread");
+}
+
+/// Stops capturing closed windows, starts capturing new ones and restarts the streams of
+/// resized ones, whose output size is fixed when they start.
+fn update(
+    runtime: &tokio::runtime::Handle,
+    application: &str,
+    composite: &Arc<Mutex<Composite>>,
+    tracked: &mut Vec<Tracked>,
+    next_id: &mut u64,
+) -> anyhow::Result<()> {
+    let windows = runtime.block_on(capture::find_windows(|window| {
+        window.application().name().contains(application)
+    }))?;
+
+    tracked.retain_mut(|tracked| {
+        let is_open = windows.contains(&tracked.window);
+        if !is_open {
+            println!("Window {:?} of {application:?} closed", tracked.window.title());
+            if let Err(err) = tracked.stream.stop() {
+                eprintln!("Failed to stop capturing a closed window: {err:?}");
+            }
+        }
+        is_open
+    });
+    composite
+        .lock()
+        .unwrap()
+        .slots
+        .retain(|slot| tracked.iter().any(|tracked| tracked.id == slot.id));
+
+    for tracked in tracked.iter_mut() {
+        let Some(window) = windows.iter().find(|window| **window == tracked.window) else {
+            continue;
+        };
+        let (before, now) = (tracked.window.rect().size, window.rect().size);
+        if before.width == now.width && before.height == now.height {
+            continue;
+        }
+        tracked.stream.stop().ok();
+        let on_window_frame = window_frame_sink(composite, tracked.id);
+        tracked.stream =
+            runtime.block_on(capture::capture_window(window.clone(), on_window_frame))?;
+        tracked.window = window.clone();
+    }
+
+    for window in windows {
+        if tracked.iter().any(|tracked| tracked.window == window) {
+            continue;
+        }
+        let id = *next_id;
+        *next_id += 1;
+        composite.lock().unwrap().slots.push(Slot {
+            id,
+            title: window.title(),
+            frame: None,
+        });
+        let on_window_frame = window_frame_sink(composite, id);
+        let stream = runtime.block_on(capture::capture_window(window.clone(), on_window_frame))?;
+        tracked.push(Tracked { id, window, stream });
+    }
+
+    let count = tracked.len();
+    status::update(|status| {
+        status.window_title = Some(format!("{count} windows of {application}"));
+    });
+    Ok(())
+}
+
+fn window_frame_sink(
+    composite: &Arc<Mutex<Composite>>,
+    id: u64,
+) -> impl FnMut(Frame) + Send + 'static {
+    let composite = composite.clone();
+    move |frame| on_window_frame(&composite, id, frame)
+}
+
+fn on_window_frame(composite: &Mutex<Composite>, id: u64, frame: Frame) {
+    let mut composite = composite.lock().unwrap();
+    if let Some(slot) = composite.slots.iter_mut().find(|slot| slot.id == id) {
+        slot.frame = Some(frame);
+    }
+    let now = Instant::now();
+    if composite
+        .last_composite
+        .is_some_and(|last| now - last < COMPOSITE_INTERVAL)
+    {
+        return;
+    }
+    composite.last_composite = Some(now);
+    composite.frames_composited += 1;
+
+    puffin::profile_scope!("composite app windows");
+    if let Some(frame) = grid(&composite.slots, composite.frames_composited) {
+        (composite.on_frame)(frame);
+    }
+}
+
+/// Lays out the latest frame of every window in a grid of equally sized cells, windows
+/// unscaled in the top left corner of their cell.
+fn grid(slots: &[Slot], frame_id: u64) -> Option<Frame> {
+    let windows = slots
+        .iter()
+        .filter_map(|slot| Some((slot.title.as_str(), slot.frame.as_ref()?)))
+        .collect::<Vec<_>>();
+    if windows.is_empty() {
+        return None;
+    }
+    let columns = (windows.len() as f32).sqrt().ceil() as usize;
+    let rows = windows.len().div_ceil(columns);
+    let cell_width = windows.iter().map(|(_, frame)| frame.frame_bitmap.width).max()?;
+    let cell_height = windows.iter().map(|(_, frame)| frame.frame_bitmap.height).max()?;
+    let width = columns * cell_width + (columns - 1) * GAP;
+    let height = rows * cell_height + (rows - 1) * GAP;
+
+    let mut data = vec![[0, 0, 0, 255]; width * height];
+    for (index, (title, frame)) in windows.iter().enumerate() {
+        let bitmap = &frame.frame_bitmap;
+        let x0 = index % columns * (cell_width + GAP);
+        let y0 = index / columns * (cell_height + GAP);
+        for y in 0..bitmap.height {
+            data[(y0 + y) * width + x0..][..bitmap.width]
+                .copy_from_slice(&bitmap.data[y * bitmap.width..][..bitmap.width]);
+        }
+        draw_label(&mut data, width, [x0, y0], [cell_width, cell_height], title);
+    }
+
+    Some(Frame {
+        frame_bitmap: FrameBitmapBgraUnorm8x4 {
+            data: data.into(),
+            width,
+            height,
+        },
+        frame_id,
+        capture_time_us: windows.iter().map(|(_, frame)| frame.capture_time_us).max()?,
+        source_rect: ScreenRect {
+            min: glam::Vec2::ZERO,
+            size: glam::vec2(width as f32, height as f32),
+        },
+        ycbcr: None,
+        hdr_data: None,
+    })
+}
+
+/// Blends `title` into the top left corner of a cell, cut off at the cell's edges.
+fn draw_label(
+    data: &mut [[u8; 4]],
+    width: usize,
+    [x0, y0]: [usize; 2],
+    [cell_width, cell_height]: [usize; 2],
+    title: &str,
+) {
+    let (rgba, text_width, text_height) =
+        text::rasterize(title, LABEL_SCALE, [255, 255, 255, 255], [0, 0, 0, 160]);
+    for y in 0..text_height.min(cell_height) {
+        for x in 0..text_width.min(cell_width) {
+            let source = &rgba[(y * text_width + x) * 4..][..4];
+            let target = &mut data[(y0 + y) * width + x0 + x];
+            let alpha = source[3] as u32;
+            for (target, source) in target[..3].iter_mut().zip([source[2], source[1], source[0]]) {
+                *target = ((source as u32 * alpha + *target as u32 * (255 - alpha)) / 255) as u8;
+            }
+        }
+    }
+}
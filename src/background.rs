@@ -0,0 +1,226 @@
+//! Selectable view backgrounds.
+//!
+//! Everything except the solid color is drawn as a rect covering the 2D view, behind all
+//! other draws. The 3D view shows the same rect as a backdrop behind the scene.
+
+use crabgrab::prelude::FrameBitmapBgraUnorm8x4;
+use re_renderer::{
+    renderer::{ColormappedTexture, RectangleOptions, TextureFilterMag, TextureFilterMin, TexturedRect},
+    resource_managers::{GpuTexture2D, Texture2DCreationDesc},
+    RenderContext, Rgba,
+};
+use serde::{Deserialize, Serialize};
+
+/// Edge length of a checkerboard cell in pixels.
+const CHECKER_CELL_SIZE: f32 = 16.0;
+
+/// Approximate width of the downsampled capture used for the blurred background.
+const BLUR_TARGET_WIDTH: usize = 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackgroundKind {
+    Transparent,
+    Solid,
+    Checkerboard,
+    BlurredCapture,
+}
+
+impl BackgroundKind {
+    pub const ALL: [Self; 4] = [
+        Self::Transparent,
+        Self::Solid,
+        Self::Checkerboard,
+        Self::BlurredCapture,
+    ];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Transparent => "transparent",
+            Self::Solid => "solid",
+            Self::Checkerboard => "checkerboard",
+            Self::BlurredCapture => "blurred_capture",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|kind| *kind == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct BackgroundConfig {
+    pub kind: BackgroundKind,
+
+    /// Color used by [`BackgroundKind::Solid`], as sRGB.
+    pub color: [u8; 3],
+}
+
+impl Default for BackgroundConfig {
+    fn default() -> Self {
+        Self {
+            kind: BackgroundKind::Transparent,
+            color: [32, 32, 32],
+        }
+    }
+}
+
+pub struct Background {
+    pub config: BackgroundConfig,
+
+    /// Checkerboard texture for the cell count it was generated for.
+    checkerboard: Option<([u32; 2], GpuTexture2D)>,
+
+    /// Blurred capture for the frame id it was generated from.
+    blurred_capture: Option<(u64, GpuTexture2D)>,
+
+    /// Keep showing the blurred capture there is instead of blurring new frames.
+    pub freeze_blur: bool,
+}
+
+impl Background {
+    pub fn new(config: BackgroundConfig) -> Self {
+        Self {
+            config,
+            checkerboard: None,
+            blurred_capture: None,
+            freeze_blur: false,
+        }
+    }
+
+    /// Color the views are cleared with before anything is drawn.
+    pub fn clear_color(&self) -> Rgba {
+        match self.config.kind {
+            BackgroundKind::Solid => {
+                let [r, g, b] = self.config.color;
+                re_renderer::Color32::from_rgb(r, g, b).into()
+            }
+            _ => Rgba::TRANSPARENT,
+        }
+    }
+
+    /// Rect covering `screen_size` that has to be drawn before everything else, if any.
+    ///
+    /// `capture` is the latest captured frame and its frame id.
+    pub fn rect(
+        &mut self,
+        re_ctx: &RenderContext,
+        screen_size: glam::Vec2,
+        capture: Option<(&FrameBitmapBgraUnorm8x4, u64)>,
+    ) -> Option<TexturedRect> {
+        puffin::profile_function!();
+
+        let (texture, texture_filter_magnification) = match self.config.kind {
+            BackgroundKind::Transparent | BackgroundKind::Solid => return None,
+            BackgroundKind::Checkerboard => {
+                (self.checkerboard(re_ctx, screen_size), TextureFilterMag::Nearest)
+            }
+            BackgroundKind::BlurredCapture => {
+                (self.blurred_capture(re_ctx, capture?), TextureFilterMag::Linear)
+            }
+        };
+
+        Some(TexturedRect {
+            // Behind the capture rects at z = -0.05.
+            top_left_corner_position: glam::vec3(0.0, 0.0, -0.1),
+            extent_u: screen_size.x * glam::Vec3::X,
+            extent_v: screen_size.y * glam::Vec3::Y,
+            colormapped_texture: ColormappedTexture::from_unorm_rgba(texture),
+            options: RectangleOptions {
+                texture_filter_magnification,
+                texture_filter_minification: TextureFilterMin::Linear,
+                depth_offset: -1,
+                ..Default::default()
+            },
+        })
+    }
+
+    fn checkerboard(&mut self, re_ctx: &RenderContext, screen_size: glam::Vec2) -> GpuTexture2D {
+        let cells = [
+            (screen_size.x / CHECKER_CELL_SIZE).ceil().max(1.0) as u32,
+            (screen_size.y / CHECKER_CELL_SIZE).ceil().max(1.0) as u32,
+        ];
+        if let Some((cached_cells, texture)) = &self.checkerboard {
+            if *cached_cells == cells {
+                return texture.clone();
+            }
+        }
+
+        let data = (0..cells[1])
+            .flat_map(|y| (0..cells[0]).map(move |x| (x + y) % 2 == 0))
+            .flat_map(|light| if light { [204, 204, 204, 255] } else { [153, 153, 153, 255] })
+            .collect::<Vec<u8>>();
+        let texture = re_ctx
+            .texture_manager_2d
+            .create(
+                &re_ctx.gpu_resources.textures,
+                &Texture2DCreationDesc {
+                    label: "checkerboard background".into(),
+                    data: data.into(),
+                    format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                    width: cells[0],
+                    height: cells[1],
+                },
+            )
+            .expect("Failed to create checkerboard texture");
+        self.checkerboard = Some((cells, texture.clone()));
+        texture
+    }
+
+    fn blurred_capture(
+        &mut self,
+        re_ctx: &RenderContext,
+        (bitmap, frame_id): (&FrameBitmapBgraUnorm8x4, u64),
+    ) -> GpuTexture2D {
+        if let Some((cached_frame_id, texture)) = &self.blurred_capture {
+            if *cached_frame_id == frame_id || self.freeze_blur {
+                return texture.clone();
+            }
+        }
+
+        // Averaging large blocks and magnifying the result with linear filtering is a
+        // cheap stand-in for a wide gaussian blur.
+        let block = (bitmap.width / BLUR_TARGET_WIDTH).max(1);
+        let width = bitmap.width.div_ceil(block);
+        let height = bitmap.height.div_ceil(block);
+        let mut data = Vec::with_capacity(width * height * 4);
+        for by in 0..height {
+            for bx in 0..width {
+                let mut sum = [0u32; 4];
+                let mut count = 0;
+                for y in (by * block)..((by + 1) * block).min(bitmap.height) {
+                    let row = &bitmap.data[y * bitmap.width..(y + 1) * bitmap.width];
+                    for pixel in &row[(bx * block)..((bx + 1) * block).min(bitmap.width)] {
+                        for (sum, channel) in sum.iter_mut().zip(pixel) {
+                            *sum += *channel as u32;
+                        }
+                        count += 1;
+                    }
+                }
+                data.extend(sum.map(|sum| (sum / count.max(1)) as u8));
+            }
+        }
+
+        let texture = re_ctx
+            .texture_manager_2d
+            .create(
+                &re_ctx.gpu_resources.textures,
+                &Texture2DCreationDesc {
+                    label: "blurred capture background".into(),
+                    data: data.into(),
+                    format: wgpu::TextureFormat::Bgra8Unorm,
+                    width: width as u32,
+                    height: height as u32,
+                },
+            )
+            .expect("Failed to create blurred capture texture");
+        self.blurred_capture = Some((frame_id, texture.clone()));
+        texture
+    }
+}
@@ -0,0 +1,175 @@
+//! Timed caption lines collected during a recording, written as SRT and WebVTT sidecars.
+//!
+//! A caption is shown from when it was added until the next one; an empty line clears it.
+
+use std::fmt::Write as _;
+use std::io::BufRead as _;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::Context as _;
+
+use crate::control::{self, ControlCommand};
+
+struct Caption {
+    text: String,
+    start: Duration,
+    end: Option<Duration>,
+}
+
+#[derive(Default)]
+pub struct Captions {
+    captions: Vec<Caption>,
+}
+
+impl Captions {
+    /// Shows `text` from `time` on, replacing the current caption.
+    pub fn add(&mut self, text: &str, time: Duration) {
+        if let Some(current) = self.captions.last_mut() {
+            current.end.get_or_insert(time);
+        }
+        let text = cue_text(text);
+        if !text.is_empty() {
+            self.captions.push(Caption {
+                text,
+                start: time,
+                end: None,
+            });
+        }
+    }
+
+    /// The caption shown at the moment, if any.
+    pub fn current(&self) -> Option<&str> {
+        self.captions
+            .last()
+            .filter(|caption| caption.end.is_none())
+            .map(|caption| caption.text.as_str())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.captions.is_empty()
+    }
+
+    /// Writes `<name>.srt` and `<name>.vtt` next to `recording`, open captions end at
+    /// `duration`.
+    pub fn write_sidecars(&self, recording: &Path, duration: Duration) -> anyhow::Result<()> {
+        let (srt, vtt) = self.sidecars(duration);
+        for (extension, contents) in [("srt", srt), ("vtt", vtt)] {
+            let path = recording.with_extension(extension);
+            std::fs::write(&path, contents)
+                .with_context(|| format!("failed to write {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// The SRT and WebVTT files, open captions end at `duration`.
+    fn sidecars(&self, duration: Duration) -> (String, String) {
+        let mut srt = String::new();
+        let mut vtt = String::from("WEBVTT\n\n");
+        for (i, caption) in self.captions.iter().enumerate() {
+            let end = caption.end.unwrap_or(duration).max(caption.start);
+            writeln!(
+                srt,
+                "{}\n{} --> {}\n{}\n",
+                i + 1,
+                timestamp(caption.start, ','),
+                timestamp(end, ','),
+                caption.text
+            )
+            .unwrap();
+            writeln!(
+                vtt,
+                "{} --> {}\n{}\n",
+                timestamp(caption.start, '.'),
+                timestamp(end, '.'),
+                caption.text
+            )
+            .unwrap();
+        }
+        (srt, vtt)
+    }
+}
+
+/// `text` as a cue's text: without blank lines, which end a cue, and without `-->`, which
+/// would make the line a cue timing.
+fn cue_text(text: &str) -> String {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+        .replace("-->", "->")
+}
+
+/// `HH:MM:SS,mmm` for SRT, `HH:MM:SS.mmm` for WebVTT.
+fn timestamp(time: Duration, separator: char) -> String {
+    let millis = time.as_millis();
+    format!(
+        "{:02}:{:02}:{:02}{separator}{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+/// Sends every line read from stdin as a caption.
+pub fn read_stdin_in_background() {
+    std::thread::Builder::new()
+        .name("caption reader".to_owned())
+        .spawn(|| {
+            for line in std::io::stdin().lock().lines().map_while(Result::ok) {
+                control::send(ControlCommand::Caption(line));
+            }
+        })
+        .expect("Failed to spawn caption reader thread");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamps_use_the_format_separator() {
+        let time = Duration::from_millis(3_723_045);
+        assert_eq!(timestamp(time, ','), "01:02:03,045");
+        assert_eq!(timestamp(time, '.'), "01:02:03.045");
+    }
+
+    #[test]
+    fn cue_text_has_no_blank_lines_or_timing_arrows() {
+        assert_eq!(cue_text("  one\n\n  two \n"), "one\ntwo");
+        assert_eq!(cue_text("a --> b"), "a -> b");
+        assert_eq!(cue_text(" \n "), "");
+    }
+
+    #[test]
+    fn captions_last_until_the_next_one() {
+        let mut captions = Captions::default();
+        captions.add("first", Duration::from_secs(1));
+        captions.add("second", Duration::from_secs(2));
+        assert_eq!(captions.current(), Some("second"));
+        captions.add("", Duration::from_secs(3));
+        assert_eq!(captions.current(), None);
+
+        let (srt, vtt) = captions.sidecars(Duration::from_secs(10));
+        assert_eq!(
+            srt,
+            "1\n00:00:01,000 --> 00:00:02,000\nfirst\n\n\
+             2\n00:00:02,000 --> 00:00:03,000\nsecond\n\n"
+        );
+        assert_eq!(
+            vtt,
+            "WEBVTT\n\n00:00:01.000 --> 00:00:02.000\nfirst\n\n\
+             00:00:02.000 --> 00:00:03.000\nsecond\n\n"
+        );
+    }
+
+    #[test]
+    fn open_captions_end_with_the_recording() {
+        let mut captions = Captions::default();
+        captions.add("open", Duration::from_secs(5));
+        let (srt, _) = captions.sidecars(Duration::from_secs(7));
+        assert_eq!(srt, "1\n00:00:05,000 --> 00:00:07,000\nopen\n\n");
+    }
+}
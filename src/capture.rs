@@ -0,0 +1,959 @@
+//! Screen capture via crabgrab.
+
+use std::mem::ManuallyDrop;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use anyhow::Context as _;
+use crossbeam_channel::Sender;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use crabgrab::prelude::{
+    CapturableContent, CapturableContentFilter, CapturableDisplay, CapturableWindow,
+    CapturableWindowFilter, CaptureConfig, CaptureStream, FrameBitmapBgraUnorm8x4, StreamEvent,
+    VideoFrameBitmap, WgpuCaptureConfigExt,
+};
+
+use crate::focus::ScreenRect;
+use crate::pixel_format::{self, PixelFormat, YCbCrPlanes};
+use crate::{capture_manager, config, events, exclusions, status};
+
+pub struct Frame {
+    pub frame_bitmap: FrameBitmapBgraUnorm8x4,
+    pub frame_id: u64,
+
+    /// Wall clock capture time in µs since the UNIX epoch, on the clock of this machine
+    /// (converted when received from a capture daemon on another machine).
+    pub capture_time_us: u64,
+
+    /// Area of the screen the frame shows, in global screen points.
+    pub source_rect: ScreenRect,
+
+    /// The planes [`Self::frame_bitmap`] was converted from, for 4:2:0 captures with
+    /// `[pixel_format] gpu_conversion` enabled.
+    pub ycbcr: Option<YCbCrPlanes>,
+
+    /// The pixels of [`Self::frame_bitmap`] with all 10 bits, packed like
+    /// `wgpu::TextureFormat::Rgb10a2Unorm`, for `argb2101010` captures with `[hdr]` enabled.
+    pub hdr_data: Option<Box<[u32]>>,
+}
+
+impl Frame {
+    /// The `size` pixels at `min`, which have to lie within the frame, with the matching
+    /// part of [`Self::source_rect`].
+    pub fn crop(&self, min: [usize; 2], size: [usize; 2]) -> Frame {
+        let bitmap = &self.frame_bitmap;
+        let ([x0, y0], [width, height]) = (min, size);
+        let points_per_pixel =
+            self.source_rect.size / glam::vec2(bitmap.width as f32, bitmap.height as f32);
+        Frame {
+            frame_bitmap: FrameBitmapBgraUnorm8x4 {
+                data: crop_rows(&bitmap.data, bitmap.width, min, size),
+                width,
+                height,
+            },
+            frame_id: self.frame_id,
+            capture_time_us: self.capture_time_us,
+            source_rect: ScreenRect {
+                min: self.source_rect.min
+                    + glam::vec2(x0 as f32, y0 as f32) * points_per_pixel,
+                size: glam::vec2(width as f32, height as f32) * points_per_pixel,
+            },
+            // Cropped frames are shown from the BGRA pixels.
+            ycbcr: None,
+            hdr_data: self.hdr_data.as_ref().map(|data| crop_rows(data, bitmap.width, min, size)),
+        }
+    }
+
+    /// The part of the frame within `rect`, in global screen points, whole pixels around
+    /// it. `None` if `rect` isn't in the frame, or the frame has no pixels.
+    pub fn crop_to_rect(&self, rect: ScreenRect) -> Option<Frame> {
+        let bitmap = &self.frame_bitmap;
+        if bitmap.width == 0 || bitmap.height == 0 {
+            return None;
+        }
+        let source_rect = self.source_rect;
+        let min = rect.min.max(source_rect.min);
+        let max = (rect.min + rect.size).min(source_rect.min + source_rect.size);
+        if min.x >= max.x || min.y >= max.y {
+            return None;
+        }
+
+        let pixels_per_point =
+            glam::vec2(bitmap.width as f32, bitmap.height as f32) / source_rect.size;
+        let size = glam::uvec2(bitmap.width as u32, bitmap.height as u32);
+        let pixel_min = ((min - source_rect.min) * pixels_per_point).as_uvec2().min(size - 1);
+        let pixel_max = ((max - source_rect.min) * pixels_per_point)
+            .ceil()
+            .as_uvec2()
+            .clamp(pixel_min + 1, size);
+        let [x0, y0] = pixel_min.to_array().map(|value| value as usize);
+        let [width, height] = (pixel_max - pixel_min).to_array().map(|value| value as usize);
+        Some(self.crop([x0, y0], [width, height]))
+    }
+}
+
+/// The `size` pixels at `min` of an image `stride` pixels wide.
+fn crop_rows<T: Copy>(pixels: &[T], stride: usize, min: [usize; 2], size: [usize; 2]) -> Box<[T]> {
+    let ([x0, y0], [width, height]) = (min, size);
+    let mut data = Vec::with_capacity(width * height);
+    for y in y0..y0 + height {
+        data.extend_from_slice(&pixels[y * stride + x0..][..width]);
+    }
+    data.into()
+}
+
+/// How often a captured window is checked for size changes.
+const WINDOW_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The window to capture instead of the first display, the first one matching all that's
+/// set.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct WindowConfig {
+    /// Text in the window's title.
+    pub title: Option<String>,
+
+    /// Regex the window's title has to match.
+    pub title_pattern: Option<String>,
+
+    /// Bundle id of the window's application, e.g. `com.apple.Safari`.
+    pub app: Option<String>,
+}
+
+impl WindowConfig {
+    /// Whether a window is captured instead of the first display.
+    pub fn is_set(&self) -> bool {
+        self.title.is_some() || self.title_pattern.is_some() || self.app.is_some()
+    }
+
+    /// Fails if `title_pattern` isn't a valid regex.
+    pub fn matcher(&self) -> anyhow::Result<WindowMatcher> {
+        let title_pattern = self
+            .title_pattern
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .context("invalid `[window] title_pattern`")?;
+        Ok(WindowMatcher {
+            title: self.title.clone(),
+            title_pattern,
+            app: self.app.clone(),
+        })
+    }
+}
+
+/// Picks the window to capture, see [`WindowConfig`].
+#[derive(Debug, Clone)]
+pub struct WindowMatcher {
+    title: Option<String>,
+    title_pattern: Option<Regex>,
+    app: Option<String>,
+}
+
+impl WindowMatcher {
+    pub fn matches(&self, window: &CapturableWindow) -> bool {
+        let title = window.title();
+        if self.title.as_ref().is_some_and(|part| !title.contains(part.as_str())) {
+            return false;
+        }
+        if self.title_pattern.as_ref().is_some_and(|pattern| !pattern.is_match(&title)) {
+            return false;
+        }
+        match &self.app {
+            Some(app) => window.application().identifier() == *app,
+            None => true,
+        }
+    }
+}
+
+impl std::fmt::Display for WindowMatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "window")?;
+        if let Some(title) = &self.title {
+            write!(f, " {title:?}")?;
+        }
+        if let Some(pattern) = &self.title_pattern {
+            write!(f, " matching /{pattern}/")?;
+        }
+        if let Some(app) = &self.app {
+            write!(f, " of {app}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Wall clock time in µs since the UNIX epoch.
+pub fn now_us() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |since_epoch| since_epoch.as_micros() as u64)
+}
+
+#[allow(unused)]
+struct Gfx {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+}
+
+impl AsRef<wgpu::Device> for Gfx {
+    fn as_ref(&self) -> &wgpu::Device {
+        &self.device
+    }
+}
+
+/// Captures the first display and hands every frame to `on_frame`, for the capture daemon.
+///
+/// Returns once the stream is running, the stream itself lives until the process exits.
+pub async fn run(on_frame: impl FnMut(Frame) + Send + 'static) {
+    let display = find_display(1).await.expect("Expected at least one capturable display");
+    let stream = capture_display(display, 1, on_frame, |_| {})
+        .await
+        .expect("Failed to capture the display");
+    let _ = ManuallyDrop::new(stream);
+}
+
+enum DisplayCommand {
+    /// Capture the display with this 1-based index instead.
+    Switch(usize),
+
+    /// Stop the stream for the time being, or start it again.
+    Pause,
+    Resume,
+
+    /// Start the stream over, so it picks up a new cursor setting or pixel format.
+    Restart,
+}
+
+/// Sent by [`switch_display`] and [`set_display_paused`] to the thread of
+/// [`follow_display`], dropped by [`stop_following_display`].
+static DISPLAY_COMMANDS: Lazy<Mutex<Option<Sender<DisplayCommand>>>> = Lazy::new(Default::default);
+
+/// Wait before capturing again after the stream of [`follow_display`] failed, doubling
+/// with every failure in a row up to [`MAX_RETRY_DELAY`].
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Why the stream of [`follow_display`] failed, and when it's tried again.
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptureFailure {
+    /// The screen recording permission was revoked, rather than the stream failing.
+    pub permission_lost: bool,
+    pub error: String,
+
+    /// Failures in a row, without a frame in between.
+    pub attempts: u32,
+
+    /// Wall clock time of the next attempt in µs since the UNIX epoch, see [`now_us`].
+    pub retry_at_us: u64,
+}
+
+/// Shown until the next captured frame.
+static FAILURE: Lazy<Mutex<Option<CaptureFailure>>> = Lazy::new(Default::default);
+
+/// The failure of the display capture, while it's waiting to capture again.
+pub fn failure() -> Option<CaptureFailure> {
+    FAILURE.lock().unwrap().clone()
+}
+
+fn set_failure(failure: Option<CaptureFailure>) {
+    status::update(|status| status.capture_failure = failure.clone());
+    *FAILURE.lock().unwrap() = failure;
+}
+
+/// How a stream failed, handed to the `on_failure` of [`capture_display`].
+#[derive(Debug, Clone)]
+pub struct StreamFailure {
+    pub permission_lost: bool,
+    pub error: String,
+}
+
+impl StreamFailure {
+    /// Capturing didn't start at all, possibly for lack of the permission as well.
+    fn starting(err: &anyhow::Error) -> Self {
+        Self {
+            permission_lost: CaptureStream::test_access(false).is_none(),
+            error: format!("{err:#}"),
+        }
+    }
+}
+
+/// Captures the first display on a background thread, switching to another one whenever
+/// asked by [`switch_display`], and stopping the stream while paused by
+/// [`set_display_paused`], until [`stop_following_display`].
+///
+/// When the stream fails or ends, e.g. because the screen recording permission was
+/// revoked, it's started again with a growing delay, asking for the permission again,
+/// until frames arrive. [`failure`] tells why meanwhile. If the display was disconnected
+/// or changed its resolution instead, capturing starts over right away on the display
+/// matching it best, see [`find_matching_display`].
+pub fn follow_display(
+    runtime: tokio::runtime::Handle,
+    on_frame: impl FnMut(Frame) + Clone + Send + 'static,
+) -> JoinHandle<()> {
+    let (sender, receiver) = crossbeam_channel::unbounded();
+    *DISPLAY_COMMANDS.lock().unwrap() = Some(sender);
+    std::thread::Builder::new()
+        .name("display capture".to_owned())
+        .spawn(move || {
+            let owner = capture_manager::owner();
+            // Streams report failures with the number they were started as, so failures of
+            // streams stopped since, which may end with an error, are ignored.
+            let (report_failure, failures) = crossbeam_channel::unbounded();
+            let mut streams_started = 0;
+            let mut failed_attempts = 0;
+            let mut retry_at: Option<Instant> = None;
+            // Set by the stream on its first frame, which ends a row of failures.
+            let mut delivered = Arc::new(AtomicBool::new(false));
+
+            let mut current = 1;
+            // The display last looked up to capture, and its rect then.
+            let mut last_display: Option<(CapturableDisplay, ScreenRect)> = None;
+            let mut paused = false;
+            let mut captured: Option<CapturedDisplay> = None;
+            let mut next_command = Some(DisplayCommand::Switch(1));
+            loop {
+                let retry = retry_at.map_or_else(crossbeam_channel::never, crossbeam_channel::at);
+                let command = match next_command.take() {
+                    Some(command) => command,
+                    None => crossbeam_channel::select! {
+                        recv(receiver) -> command => match command {
+                            Ok(command) => command,
+                            Err(_) => break,
+                        },
+                        recv(owner.stop_signal()) -> _ => break,
+                        recv(retry) -> _ => {
+                            retry_at = None;
+                            DisplayCommand::Restart
+                        }
+                        recv(failures) -> failure => {
+                            let Ok((number, failure)) = failure else {
+                                continue;
+                            };
+                            let is_current = captured
+                                .as_ref()
+                                .is_some_and(|captured| captured.number == number);
+                            if !is_current {
+                                continue;
+                            }
+                            let Some(mut failed) = captured.take() else {
+                                continue;
+                            };
+                            failed.stream.stop().ok();
+                            if delivered.load(Ordering::Relaxed) {
+                                failed_attempts = 0;
+                            }
+                            let displays = runtime.block_on(find_displays());
+                            let is_gone = displays.is_ok_and(|displays| {
+                                !displays.iter().any(|display| {
+                                    *display == failed.display
+                                        && display_rect(display) == failed.rect
+                                })
+                            });
+                            if is_gone {
+                                events::emit(
+                                    events::Severity::Info,
+                                    "display_changed",
+                                    &status::snapshot().source_name,
+                                    &format!(
+                                        "display {} was disconnected or changed, capturing \
+                                         the best matching one",
+                                        failed.index
+                                    ),
+                                );
+                                next_command = Some(DisplayCommand::Restart);
+                                continue;
+                            }
+                            let index = failed.index;
+                            retry_at = Some(schedule_retry(index, failure, &mut failed_attempts));
+                            continue;
+                        }
+                    },
+                };
+                // Look the display up first, so a missing one keeps the current capture.
+                let lookup = match command {
+                    DisplayCommand::Switch(index) => {
+                        if captured.as_ref().is_some_and(|captured| captured.index == index) {
+                            continue;
+                        }
+                        runtime.block_on(find_display(index)).map(|display| (index, display))
+                    }
+                    DisplayCommand::Pause => {
+                        paused = true;
+                        if let Some(mut captured) = captured.take() {
+                            println!("Pausing the capture of display {}", captured.index);
+                            captured.stream.stop().ok();
+                        }
+                        continue;
+                    }
+                    DisplayCommand::Resume => {
+                        paused = false;
+                        if captured.is_some() {
+                            continue;
+                        }
+                        runtime.block_on(find_matching_display(last_display.as_ref(), current))
+                    }
+                    DisplayCommand::Restart => {
+                        if paused {
+                            continue;
+                        }
+                        if let Some(mut captured) = captured.take() {
+                            captured.stream.stop().ok();
+                        }
+                        runtime.block_on(find_matching_display(last_display.as_ref(), current))
+                    }
+                };
+                let (index, display) = match lookup {
+                    Ok(found) => found,
+                    // Without the permission, displays can't even be listed.
+                    Err(err) if captured.is_none() && !paused => {
+                        let failure = StreamFailure::starting(&err);
+                        retry_at = Some(schedule_retry(current, failure, &mut failed_attempts));
+                        continue;
+                    }
+                    Err(err) => {
+                        eprintln!("Not switching displays: {err:#}");
+                        continue;
+                    }
+                };
+                current = index;
+                let rect = display_rect(&display);
+                last_display = Some((display.clone(), rect));
+                // A switch while paused takes effect when resuming.
+                if paused {
+                    continue;
+                }
+                if let Some(mut captured) = captured.take() {
+                    captured.stream.stop().ok();
+                }
+
+                streams_started += 1;
+                let number = streams_started;
+                delivered = Arc::new(AtomicBool::new(false));
+                let on_frame = {
+                    let mut on_frame = on_frame.clone();
+                    let delivered = delivered.clone();
+                    move |frame: Frame| {
+                        if !delivered.swap(true, Ordering::Relaxed) && failure().is_some() {
+                            set_failure(None);
+                            events::emit(
+                                events::Severity::Info,
+                                "capture_recovered",
+                                &status::snapshot().source_name,
+                                &format!("capturing display {index} again"),
+                            );
+                        }
+                        on_frame(frame)
+                    }
+                };
+                let report_failure = report_failure.clone();
+                let on_failure = move |failure| {
+                    report_failure.send((number, failure)).ok();
+                };
+                let capture = capture_display(display.clone(), index, on_frame, on_failure);
+                match runtime.block_on(capture) {
+                    Ok(stream) => {
+                        captured = Some(CapturedDisplay { index, display, rect, number, stream });
+                        retry_at = None;
+                    }
+                    Err(err) => {
+                        let failure = StreamFailure::starting(&err);
+                        retry_at = Some(schedule_retry(index, failure, &mut failed_attempts));
+                    }
+                }
+            }
+            if let Some(mut captured) = captured {
+                captured.stream.stop().ok();
+            }
+            set_failure(None);
+        })
+        .expect("Failed to spawn the display capture thread")
+}
+
+/// The stream of [`follow_display`] and what it captures.
+struct CapturedDisplay {
+    /// 1-based, as passed to [`switch_display`].
+    index: usize,
+    display: CapturableDisplay,
+
+    /// Rect of the display when the stream started.
+    rect: ScreenRect,
+
+    /// Which stream this is, counting all started by the thread.
+    number: u64,
+    stream: CaptureStream,
+}
+
+/// Records the `failure` of capturing display `index`, returns when to try again.
+fn schedule_retry(index: usize, failure: StreamFailure, failed_attempts: &mut u32) -> Instant {
+    *failed_attempts += 1;
+    let delay = (RETRY_DELAY * 2u32.saturating_pow(*failed_attempts - 1)).min(MAX_RETRY_DELAY);
+    eprintln!(
+        "Capturing display {index} failed: {}, trying again in {} s",
+        failure.error,
+        delay.as_secs()
+    );
+    set_failure(Some(CaptureFailure {
+        permission_lost: failure.permission_lost,
+        error: failure.error,
+        attempts: *failed_attempts,
+        retry_at_us: now_us() + delay.as_micros() as u64,
+    }));
+    Instant::now() + delay
+}
+
+/// Stops the capture of [`follow_display`]; the thread exits once the stream is stopped.
+pub fn stop_following_display() {
+    drop(DISPLAY_COMMANDS.lock().unwrap().take());
+}
+
+/// Switches the capture of [`follow_display`] to the display with this 1-based index.
+pub fn switch_display(index: usize) -> anyhow::Result<()> {
+    let commands = DISPLAY_COMMANDS.lock().unwrap();
+    let sender = commands
+        .as_ref()
+        .context("not capturing a display, can't switch to another one")?;
+    // Only fails once capturing stopped for good.
+    sender.send(DisplayCommand::Switch(index)).ok();
+    Ok(())
+}
+
+/// Starts the stream of [`follow_display`] over, when following a display.
+pub fn restart_display() {
+    if let Some(sender) = DISPLAY_COMMANDS.lock().unwrap().as_ref() {
+        sender.send(DisplayCommand::Restart).ok();
+    }
+}
+
+/// Stops the stream of [`follow_display`] while `paused`, when following a display. The
+/// last frame stays on screen meanwhile.
+pub fn set_display_paused(paused: bool) {
+    if let Some(sender) = DISPLAY_COMMANDS.lock().unwrap().as_ref() {
+        let command = if paused { DisplayCommand::Pause } else { DisplayCommand::Resume };
+        sender.send(command).ok();
+    }
+}
+
+/// Whether the mouse pointer is left out of the frames of streams started from now on,
+/// initially `hide_cursor` of the config.
+static CURSOR_HIDDEN: Lazy<AtomicBool> =
+    Lazy::new(|| AtomicBool::new(config::get().hide_cursor));
+
+pub fn is_cursor_hidden() -> bool {
+    CURSOR_HIDDEN.load(Ordering::Relaxed)
+}
+
+/// Shows or hides the mouse pointer in the capture of [`follow_display`], restarting its
+/// stream since the setting can't change while one runs.
+pub fn set_cursor_hidden(hidden: bool) -> anyhow::Result<()> {
+    let commands = DISPLAY_COMMANDS.lock().unwrap();
+    let sender = commands
+        .as_ref()
+        .context("not capturing a display, the cursor can only be toggled there")?;
+    CURSOR_HIDDEN.store(hidden, Ordering::Relaxed);
+    sender.send(DisplayCommand::Restart).ok();
+    Ok(())
+}
+
+/// The capturable display with this 1-based index.
+async fn find_display(index: usize) -> anyhow::Result<CapturableDisplay> {
+    let mut displays = find_displays().await?;
+    let count = displays.len();
+    anyhow::ensure!((1..=count).contains(&index), "no display {index}, there are {count}");
+    Ok(displays.swap_remove(index - 1))
+}
+
+/// The display to capture again instead of `previous`, with its 1-based index, after
+/// displays were connected, disconnected or rearranged: `previous` itself if it's still
+/// there, else the display at the same place, else one of the same size, else the first.
+/// Display `index` without a `previous` one.
+async fn find_matching_display(
+    previous: Option<&(CapturableDisplay, ScreenRect)>,
+    index: usize,
+) -> anyhow::Result<(usize, CapturableDisplay)> {
+    let Some((previous, previous_rect)) = previous else {
+        return find_display(index).await.map(|display| (index, display));
+    };
+    let mut displays = find_displays().await?;
+    anyhow::ensure!(!displays.is_empty(), "no display connected");
+    let find = |matches: &dyn Fn(&CapturableDisplay) -> bool| displays.iter().position(matches);
+    let position = find(&|display| display == previous)
+        .or_else(|| find(&|display| display_rect(display).min == previous_rect.min))
+        .or_else(|| find(&|display| display_rect(display).size == previous_rect.size))
+        .unwrap_or(0);
+    if position + 1 != index {
+        println!("Display {index} is now display {}", position + 1);
+    }
+    Ok((position + 1, displays.swap_remove(position)))
+}
+
+fn display_rect(display: &CapturableDisplay) -> ScreenRect {
+    let rect = display.rect();
+    ScreenRect {
+        min: glam::vec2(rect.origin.x as f32, rect.origin.y as f32),
+        size: glam::vec2(rect.size.width as f32, rect.size.height as f32),
+    }
+}
+
+/// All capturable displays, display `n` at index `n - 1`.
+pub async fn find_displays() -> anyhow::Result<Vec<CapturableDisplay>> {
+    let filter = CapturableContentFilter { windows: None, displays: true };
+    let content = CapturableContent::new(filter).await
+        .map_err(|err| anyhow::anyhow!("failed to enumerate displays: {err:?}"))?;
+    Ok(content.displays().collect())
+}
+
+/// Captures `display`, the one with this 1-based index, until the returned stream is
+/// stopped or dropped. `on_failure` is called once if the stream fails or ends by itself.
+/// The windows of `[exclude] windows` are blacked out in the frames.
+pub async fn capture_display(
+    display: CapturableDisplay,
+    index: usize,
+    mut on_frame: impl FnMut(Frame) + Send + 'static,
+    on_failure: impl FnOnce(StreamFailure) + Send + 'static,
+) -> anyhow::Result<CaptureStream> {
+    let gfx = create_gfx().await;
+
+    let source_rect = display_rect(&display);
+    let display_rect = display.rect();
+    println!("Capturing display {index}");
+    status::update(|status| {
+        status.source_name = format!("display{index}");
+        status.display = Some(index);
+        status.source = format!(
+            "display at {}, {} ({} x {} pt)",
+            display_rect.origin.x,
+            display_rect.origin.y,
+            display_rect.size.width,
+            display_rect.size.height,
+        );
+    });
+    let pixel_format = pixel_format::select(&display, &config::get().pixel_format).await;
+    let config = CaptureConfig::with_display(display, pixel_format.capture_format())
+        .with_wgpu_device(gfx.clone())
+        .map_err(|err| anyhow::anyhow!("failed to configure display capture: {err:?}"))?;
+
+    exclusions::watch();
+    let on_frame = move |mut frame: Frame| {
+        exclusions::mask(&mut frame);
+        on_frame(frame)
+    };
+    start_stream(config, source_rect, on_frame, on_failure).await
+}
+
+/// Captures the first window whose title contains `title` and hands every frame to
+/// `on_frame`, until capturing stops, see [`capture_manager`].
+pub fn hold_window(
+    runtime: &tokio::runtime::Handle,
+    title: &str,
+    on_frame: impl FnMut(Frame) + Send + 'static,
+) -> anyhow::Result<()> {
+    let owner = capture_manager::owner();
+    let window = runtime
+        .block_on(find_window(|window| window.title().contains(title)))?
+        .with_context(|| format!("no window with {title:?} in its title"))?;
+    let mut stream = runtime.block_on(capture_window(window, on_frame))?;
+    owner.wait_for_stop();
+    stream.stop().ok();
+    Ok(())
+}
+
+/// Captures the first window `target` matches on a background thread. The stream's output
+/// size is fixed when it starts, so it's restarted whenever the window is resized, and
+/// when the window is closed until it (or another matching one) shows up.
+pub fn follow_window(
+    runtime: tokio::runtime::Handle,
+    target: WindowMatcher,
+    on_frame: impl FnMut(Frame) + Clone + Send + 'static,
+) {
+    std::thread::Builder::new()
+        .name("window capture".to_owned())
+        .spawn(move || {
+            let owner = capture_manager::owner();
+            keep_following(
+                &runtime,
+                &target.to_string(),
+                |window| target.matches(window),
+                on_frame,
+                || owner.wait(WINDOW_POLL_INTERVAL),
+            );
+        })
+        .expect("Failed to spawn the window capture thread");
+}
+
+/// Captures the first window `matches` accepts like [`follow_window`], on the calling
+/// thread and until `keep_going` returns false. It's called between the checks for resizes,
+/// and should wait a bit. `name` describes the window in messages.
+pub fn keep_following(
+    runtime: &tokio::runtime::Handle,
+    name: &str,
+    matches: impl Fn(&CapturableWindow) -> bool,
+    on_frame: impl FnMut(Frame) + Clone + Send + 'static,
+    mut keep_going: impl FnMut() -> bool,
+) {
+    let mut captured: Option<(CapturableWindow, CaptureStream)> = None;
+    loop {
+        match runtime.block_on(find_window(&matches)) {
+            Ok(Some(window)) => {
+                let is_unchanged = captured.as_ref().is_some_and(|(captured, _)| {
+                    let (before, now) = (captured.rect().size, window.rect().size);
+                    *captured == window && before.width == now.width && before.height == now.height
+                });
+                if !is_unchanged {
+                    if let Some((_, mut stream)) = captured.take() {
+                        stream.stop().ok();
+                    }
+                    let stream = capture_window(window.clone(), on_frame.clone());
+                    match runtime.block_on(stream) {
+                        Ok(stream) => captured = Some((window, stream)),
+                        Err(err) => eprintln!("Capturing {name}: {err:#}"),
+                    }
+                }
+            }
+            Ok(None) => {
+                if let Some((_, mut stream)) = captured.take() {
+                    println!("{name} closed, waiting for it to show up again");
+                    stream.stop().ok();
+                }
+            }
+            Err(err) => eprintln!("Capturing {name}: {err:#}"),
+        }
+        if !keep_going() {
+            break;
+        }
+    }
+    if let Some((_, mut stream)) = captured {
+        stream.stop().ok();
+    }
+}
+
+/// Prints the capturable windows, for picking one with `--window`, `--window-title` or
+/// `--app`.
+pub async fn list_windows() -> anyhow::Result<()> {
+    for window in find_windows(|_| true).await? {
+        let rect = window.rect();
+        let application = window.application();
+        println!(
+            "{:?} of {} ({}, {} x {} pt)",
+            window.title(),
+            application.name(),
+            application.identifier(),
+            rect.size.width,
+            rect.size.height,
+        );
+    }
+    Ok(())
+}
+
+/// The first onscreen window `matches` accepts.
+pub async fn find_window(
+    matches: impl Fn(&CapturableWindow) -> bool,
+) -> anyhow::Result<Option<CapturableWindow>> {
+    Ok(find_windows(matches).await?.into_iter().next())
+}
+
+/// All onscreen windows `matches` accepts.
+pub async fn find_windows(
+    matches: impl Fn(&CapturableWindow) -> bool,
+) -> anyhow::Result<Vec<CapturableWindow>> {
+    let filter = CapturableContentFilter {
+        windows: Some(CapturableWindowFilter { desktop_windows: false, onscreen_only: true }),
+        displays: false,
+    };
+    let content = CapturableContent::new(filter).await
+        .map_err(|err| anyhow::anyhow!("failed to enumerate windows: {err:?}"))?;
+    let windows = content.windows().filter(|window| matches(window)).collect();
+    Ok(windows)
+}
+
+/// Captures `window` until the returned stream is stopped or dropped.
+pub async fn capture_window(
+    window: CapturableWindow,
+    on_frame: impl FnMut(Frame) + Send + 'static,
+) -> anyhow::Result<CaptureStream> {
+    let gfx = create_gfx().await;
+
+    let window_rect = window.rect();
+    let source_rect = ScreenRect {
+        min: glam::vec2(window_rect.origin.x as f32, window_rect.origin.y as f32),
+        size: glam::vec2(window_rect.size.width as f32, window_rect.size.height as f32),
+    };
+    println!("Capturing window {:?}", window.title());
+    let title = window.title();
+    status::update(|status| status.window_title = Some(title));
+    // The benchmark measures displays, windows only use an explicitly configured format.
+    let pixel_format = config::get().pixel_format.format.unwrap_or(PixelFormat::Bgra8888);
+    let config = CaptureConfig::with_window(window, pixel_format.capture_format())
+        .map_err(|err| anyhow::anyhow!("failed to configure window capture: {err:?}"))?
+        .with_wgpu_device(gfx.clone())
+        .map_err(|err| anyhow::anyhow!("failed to configure window capture: {err:?}"))?;
+
+    start_stream(config, source_rect, on_frame, |_| {}).await
+}
+
+async fn create_gfx() -> Arc<Gfx> {
+    let wgpu_instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        #[cfg(target_os = "windows")]
+        backends: wgpu::Backends::DX12,
+        #[cfg(target_os = "macos")]
+        backends: wgpu::Backends::METAL,
+        flags: wgpu::InstanceFlags::default(),
+        dx12_shader_compiler: wgpu::Dx12Compiler::default(),
+        gles_minor_version: wgpu::Gles3MinorVersion::default(),
+    });
+    let wgpu_adapter = wgpu_instance.request_adapter(&wgpu::RequestAdapterOptions {
+        power_preference: wgpu::PowerPreference::None,
+        force_fallback_adapter: false,
+        compatible_surface: None,
+    }).await.expect("Expected wgpu adapter");
+    let (wgpu_device, wgpu_queue) = wgpu_adapter.request_device(&wgpu::DeviceDescriptor {
+        label: Some("wgpu adapter"),
+        required_features: wgpu::Features::default(),
+        required_limits: wgpu::Limits::default(),
+    }, None).await.expect("Expected wgpu device");
+    Arc::new(Gfx {
+        device: wgpu_device,
+        queue: wgpu_queue,
+    })
+}
+
+/// Starts capturing with `config`, asking for the screen recording permission first if
+/// it's missing. `on_failure` is called once when the stream fails or ends.
+async fn start_stream(
+    config: CaptureConfig,
+    source_rect: ScreenRect,
+    mut on_frame: impl FnMut(Frame) + Send + 'static,
+    on_failure: impl FnOnce(StreamFailure) + Send + 'static,
+) -> anyhow::Result<CaptureStream> {
+    let cursor_hidden = is_cursor_hidden();
+    let config = config.with_show_cursor(!cursor_hidden);
+    status::update(|status| status.cursor_hidden = cursor_hidden);
+    let keep_planes = config::get().pixel_format.gpu_conversion;
+    let keep_10_bits = config::get().hdr.enabled;
+
+    let token = match CaptureStream::test_access(false) {
+        Some(token) => token,
+        None => CaptureStream::request_access(false).await.context(
+            "no screen recording permission, allow it in System Settings > Privacy & Security",
+        )?,
+    };
+
+    let mut on_failure = Some(on_failure);
+    CaptureStream::new(token, config, move |result| {
+        println!("result: {:?}", result);
+        if let Err(err) = &result {
+            // Losing the screen recording permission ends the stream with an error.
+            if let Some(on_failure) = on_failure.take() {
+                let permission_lost = CaptureStream::test_access(false).is_none();
+                let kind = if permission_lost { "permission_lost" } else { "capture_error" };
+                let error = format!("{err:?}");
+                let source = status::snapshot().source_name;
+                events::emit(events::Severity::Error, kind, &source, &error);
+                on_failure(StreamFailure { permission_lost, error });
+            }
+        }
+        if let Ok(StreamEvent::End) = &result {
+            if let Some(on_failure) = on_failure.take() {
+                on_failure(StreamFailure {
+                    permission_lost: CaptureStream::test_access(false).is_none(),
+                    error: "the stream ended".to_owned(),
+                });
+            }
+        }
+        if let Ok(StreamEvent::Video(frame)) = result {
+            let frame_id = frame.frame_id();
+
+            match frame.get_bitmap() {
+                Ok(bitmap) => {
+                    let ycbcr = keep_planes.then(|| pixel_format::ycbcr_planes(&bitmap));
+                    let hdr_data = keep_10_bits.then(|| pixel_format::to_rgb10a2(&bitmap));
+                    match pixel_format::to_bgra(bitmap) {
+                        Some(frame) => on_frame(Frame {
+                            frame_bitmap: frame,
+                            frame_id,
+                            capture_time_us: now_us(),
+                            source_rect,
+                            ycbcr: ycbcr.flatten(),
+                            hdr_data: hdr_data.flatten(),
+                        }),
+                        None => println!("format: RgbaF16x4 is not supported"),
+                    }
+                }
+                Err(e) => {
+                    println!("Bitmap error: {:?}", e);
+                }
+            }
+        }
+    })
+    .map_err(|err| anyhow::anyhow!("failed to start the capture stream: {err:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 4x2 pixel frame of an 8x4 point screen area at 100, 100, pixel values are x, y.
+    fn frame() -> Frame {
+        let data = (0..2u8).flat_map(|y| (0..4u8).map(move |x| [x, y, 0, 255])).collect();
+        Frame {
+            frame_bitmap: FrameBitmapBgraUnorm8x4 {
+                data,
+                width: 4,
+                height: 2,
+            },
+            frame_id: 1,
+            capture_time_us: 0,
+            source_rect: ScreenRect {
+                min: glam::vec2(100.0, 100.0),
+                size: glam::vec2(8.0, 4.0),
+            },
+            ycbcr: None,
+            hdr_data: None,
+        }
+    }
+
+    fn rect(x: f32, y: f32, width: f32, height: f32) -> ScreenRect {
+        ScreenRect {
+            min: glam::vec2(x, y),
+            size: glam::vec2(width, height),
+        }
+    }
+
+    #[test]
+    fn crop_to_rect_converts_points_to_pixels() {
+        let cropped = frame().crop_to_rect(rect(102.0, 102.0, 4.0, 2.0)).unwrap();
+        let bitmap = &cropped.frame_bitmap;
+        assert_eq!((bitmap.width, bitmap.height), (2, 1));
+        assert_eq!(&bitmap.data[..], &[[1, 1, 0, 255], [2, 1, 0, 255]]);
+        assert_eq!(cropped.source_rect, rect(102.0, 102.0, 4.0, 2.0));
+    }
+
+    #[test]
+    fn crop_to_rect_includes_partly_covered_pixels() {
+        let cropped = frame().crop_to_rect(rect(101.0, 101.0, 2.0, 1.0)).unwrap();
+        assert_eq!((cropped.frame_bitmap.width, cropped.frame_bitmap.height), (2, 1));
+        assert_eq!(cropped.source_rect, rect(100.0, 100.0, 4.0, 2.0));
+    }
+
+    #[test]
+    fn crop_to_rect_clamps_to_the_frame() {
+        let cropped = frame().crop_to_rect(rect(0.0, 0.0, 1000.0, 1000.0)).unwrap();
+        assert_eq!((cropped.frame_bitmap.width, cropped.frame_bitmap.height), (4, 2));
+        assert!(frame().crop_to_rect(rect(200.0, 100.0, 10.0, 10.0)).is_none());
+    }
+
+    #[test]
+    fn crop_to_rect_of_nothing_is_none() {
+        assert!(frame().crop_to_rect(rect(102.0, 102.0, 0.0, 2.0)).is_none());
+        let mut empty = frame();
+        empty.frame_bitmap = FrameBitmapBgraUnorm8x4 {
+            data: Vec::new().into(),
+            width: 0,
+            height: 0,
+        };
+        assert!(empty.crop_to_rect(rect(100.0, 100.0, 8.0, 4.0)).is_none());
+    }
+}
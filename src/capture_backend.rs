@@ -0,0 +1,370 @@
+//! Capture sources that can be swapped while running: the display capture, MCAP playback,
+//! video files, test patterns and a capture daemon connection. The key `C` (or `POST /api/backend`)
+//! switches between the configured ones, stopping the running backend before starting the
+//! next.
+//!
+//! The other sources (windows, A/B comparison, launch, application and multi display
+//! capture) are picked at startup and can't be switched away from.
+
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use anyhow::Context as _;
+use crossbeam_channel::Sender;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+use crate::capture::{self, Frame};
+use crate::config::Config;
+use crate::events::{self, Severity};
+use crate::{config, frame_link, playback, session_pause, status, test_pattern, video_file};
+
+pub type FrameCallback = Arc<dyn Fn(Frame) + Send + Sync>;
+
+pub trait CaptureBackend: Send {
+    /// Starts handing frames to `on_frame`, from threads of its own.
+    fn start(&mut self, runtime: &tokio::runtime::Handle, on_frame: FrameCallback);
+
+    /// Stops handing out frames and releases what the backend captures with, waiting for
+    /// its threads to exit.
+    fn stop(&mut self);
+}
+
+/// What a backend can do, unsupported options are rejected at startup and greyed out in
+/// the UI.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Capabilities {
+    /// Frames show the mouse cursor.
+    pub cursor: bool,
+    pub audio: bool,
+
+    /// Frames are in local screen coordinates, so they can be cropped to a UI element.
+    pub region_crop: bool,
+
+    /// Windows covered by others are captured as if they weren't.
+    pub occluded_windows: bool,
+
+    /// 10 bit per channel frames, see `--pixel-format argb2101010`.
+    pub hdr: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackendKind {
+    Display,
+    Playback,
+    Video,
+    TestPattern,
+    Daemon,
+}
+
+impl BackendKind {
+    pub const ALL: [Self; 5] =
+        [Self::Display, Self::Playback, Self::Video, Self::TestPattern, Self::Daemon];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Display => "display",
+            Self::Playback => "playback",
+            Self::Video => "video",
+            Self::TestPattern => "test_pattern",
+            Self::Daemon => "daemon",
+        }
+    }
+
+    /// The backend `config` starts with, `None` for sources that aren't a backend.
+    pub fn for_config(config: &Config) -> Option<Self> {
+        let is_fixed_source = (config.ab_compare.a.is_some() && config.ab_compare.b.is_some())
+            || config.window.is_set()
+            || config.launch.command.is_some()
+            || config.app_capture.application.is_some()
+            || config.multi_display.enabled;
+        // The capture daemon captures the display itself.
+        if config.frame_link.serve.is_some() {
+            Some(Self::Display)
+        } else if config.frame_link.connect.is_some() {
+            Some(Self::Daemon)
+        } else if config.playback.file.is_some() {
+            Some(Self::Playback)
+        } else if config.video.file.is_some() {
+            Some(Self::Video)
+        } else if config.test_pattern.kind.is_some() {
+            Some(Self::TestPattern)
+        } else if is_fixed_source {
+            None
+        } else {
+            Some(Self::Display)
+        }
+    }
+
+    pub fn capabilities(self) -> Capabilities {
+        match self {
+            Self::Display => Capabilities {
+                cursor: true,
+                audio: false,
+                region_crop: true,
+                occluded_windows: false,
+                hdr: true,
+            },
+            // Whatever was recorded, as 8 bit JPEG or PNG.
+            Self::Playback => Capabilities::default(),
+            // Decoded to 8 bit BGRA.
+            Self::Video => Capabilities::default(),
+            Self::TestPattern => Capabilities::default(),
+            // Frames are sent as 8 bit BGRA, in the daemon machine's screen coordinates.
+            Self::Daemon => Capabilities {
+                cursor: true,
+                ..Capabilities::default()
+            },
+        }
+    }
+
+    /// Whether the config has what the backend needs, a file to play or a daemon to
+    /// connect to.
+    fn is_configured(self) -> bool {
+        let config = config::get();
+        match self {
+            Self::Display => true,
+            Self::Playback => config.playback.file.is_some(),
+            Self::Video => config.video.file.is_some(),
+            Self::TestPattern => config.test_pattern.kind.is_some(),
+            Self::Daemon => config.frame_link.connect.is_some(),
+        }
+    }
+
+    fn create(self) -> anyhow::Result<Box<dyn CaptureBackend>> {
+        let config = config::get();
+        Ok(match self {
+            Self::Display => Box::new(DisplayBackend { thread: None }),
+            Self::Playback => Box::new(PlaybackBackend {
+                file: config.playback.file.clone().context("no file to play, see --play")?,
+                looping: config.playback.looping,
+                stop: None,
+                thread: None,
+            }),
+            Self::Video => Box::new(VideoBackend {
+                file: config.video.file.clone().context("no video to play, see --video")?,
+                looping: config.video.looping,
+                stop: None,
+                thread: None,
+            }),
+            Self::TestPattern => Box::new(TestPatternBackend {
+                kind: config.test_pattern.kind.context("no test pattern, see --test-pattern")?,
+                stop: None,
+                thread: None,
+            }),
+            Self::Daemon => Box::new(DaemonBackend {
+                endpoint: config.frame_link.connect.clone().context("no daemon, see --connect")?,
+                receiving: None,
+            }),
+        })
+    }
+}
+
+struct Active {
+    runtime: tokio::runtime::Handle,
+    on_frame: FrameCallback,
+    kind: BackendKind,
+    backend: Box<dyn CaptureBackend>,
+}
+
+/// The running backend, `None` when capturing from a source that can't be switched.
+static ACTIVE: Lazy<Mutex<Option<Active>>> = Lazy::new(Default::default);
+
+/// Starts capturing with `kind`, which can be switched with [`switch`] from then on.
+pub fn start(
+    runtime: tokio::runtime::Handle,
+    kind: BackendKind,
+    on_frame: impl Fn(Frame) + Send + Sync + 'static,
+) {
+    let on_frame: FrameCallback = Arc::new(on_frame);
+    let mut backend = kind.create().expect("Failed to create the capture backend");
+    backend.start(&runtime, on_frame.clone());
+    status::update(|status| status.capabilities = Some(kind.capabilities()));
+    *ACTIVE.lock().unwrap() = Some(Active {
+        runtime,
+        on_frame,
+        kind,
+        backend,
+    });
+}
+
+/// Stops the running backend and starts `kind` instead.
+pub fn switch(kind: BackendKind) -> anyhow::Result<()> {
+    let mut active = ACTIVE.lock().unwrap();
+    let active = active
+        .as_mut()
+        .context("the capture source can't be switched, it isn't a capture backend")?;
+    if active.kind == kind {
+        return Ok(());
+    }
+    let mut backend = kind.create()?;
+    active.backend.stop();
+    status::update(|status| {
+        status.display = None;
+        status.window_title = None;
+        status.capabilities = Some(kind.capabilities());
+    });
+    backend.start(&active.runtime, active.on_frame.clone());
+    active.backend = backend;
+    active.kind = kind;
+    events::emit(Severity::Info, "backend_switched", "capture", kind.name());
+    Ok(())
+}
+
+/// The running backend, `None` for sources that aren't a backend.
+pub fn kind() -> Option<BackendKind> {
+    ACTIVE.lock().unwrap().as_ref().map(|active| active.kind)
+}
+
+/// The configured backend after the running one, to cycle through them.
+pub fn next() -> anyhow::Result<BackendKind> {
+    let current =
+        kind().context("the capture source can't be switched, it isn't a capture backend")?;
+    let index = BackendKind::ALL.iter().position(|kind| *kind == current).unwrap();
+    (1..BackendKind::ALL.len())
+        .map(|offset| BackendKind::ALL[(index + offset) % BackendKind::ALL.len()])
+        .find(|kind| kind.is_configured())
+        .context("no other backend configured, see --play, --video, --test-pattern and --connect")
+}
+
+/// Capabilities of the running backend, `None` for sources that aren't a backend.
+pub fn capabilities() -> Option<Capabilities> {
+    ACTIVE.lock().unwrap().as_ref().map(|active| active.kind.capabilities())
+}
+
+/// Stops the running backend for good, when exiting.
+pub fn stop() {
+    if let Some(mut active) = ACTIVE.lock().unwrap().take() {
+        active.backend.stop();
+    }
+}
+
+struct DisplayBackend {
+    thread: Option<JoinHandle<()>>,
+}
+
+impl CaptureBackend for DisplayBackend {
+    fn start(&mut self, runtime: &tokio::runtime::Handle, on_frame: FrameCallback) {
+        let on_frame = move |frame| on_frame(frame);
+        self.thread = Some(capture::follow_display(runtime.clone(), on_frame));
+        if session_pause::is_paused() {
+            capture::set_display_paused(true);
+        }
+    }
+
+    fn stop(&mut self) {
+        capture::stop_following_display();
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+    }
+}
+
+struct PlaybackBackend {
+    file: std::path::PathBuf,
+    looping: bool,
+
+    /// Dropped to stop playing.
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl CaptureBackend for PlaybackBackend {
+    fn start(&mut self, _runtime: &tokio::runtime::Handle, on_frame: FrameCallback) {
+        let (stop, stopped) = crossbeam_channel::bounded(0);
+        let on_frame = move |frame| on_frame(frame);
+        self.stop = Some(stop);
+        self.thread = Some(playback::start(self.file.clone(), self.looping, stopped, on_frame));
+    }
+
+    fn stop(&mut self) {
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+    }
+}
+
+struct VideoBackend {
+    file: std::path::PathBuf,
+    looping: bool,
+
+    /// Dropped to stop playing.
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl CaptureBackend for VideoBackend {
+    fn start(&mut self, _runtime: &tokio::runtime::Handle, on_frame: FrameCallback) {
+        let (stop, stopped) = crossbeam_channel::bounded(0);
+        let on_frame = move |frame| on_frame(frame);
+        let ffmpeg_path = config::get().recording.ffmpeg_path.clone();
+        self.stop = Some(stop);
+        self.thread = Some(video_file::start(
+            self.file.clone(),
+            self.looping,
+            ffmpeg_path,
+            stopped,
+            on_frame,
+        ));
+    }
+
+    fn stop(&mut self) {
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+    }
+}
+
+struct TestPatternBackend {
+    kind: test_pattern::PatternKind,
+
+    /// Dropped to stop generating.
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl CaptureBackend for TestPatternBackend {
+    fn start(&mut self, _runtime: &tokio::runtime::Handle, on_frame: FrameCallback) {
+        let (stop, stopped) = crossbeam_channel::bounded(0);
+        let on_frame = move |frame| on_frame(frame);
+        let config = config::get().test_pattern.clone();
+        self.stop = Some(stop);
+        self.thread = Some(test_pattern::start(self.kind, config, stopped, on_frame));
+    }
+
+    fn stop(&mut self) {
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+    }
+}
+
+struct DaemonBackend {
+    endpoint: String,
+    receiving: Option<frame_link::Receiving>,
+}
+
+impl CaptureBackend for DaemonBackend {
+    fn start(&mut self, _runtime: &tokio::runtime::Handle, on_frame: FrameCallback) {
+        let endpoint = &self.endpoint;
+        status::update(|status| {
+            status.source = format!("capture daemon at {endpoint}");
+            status.source_name = "daemon".to_owned();
+        });
+        let on_frame = move |frame| on_frame(frame);
+        self.receiving = Some(frame_link::connect_in_background(endpoint, on_frame));
+    }
+
+    fn stop(&mut self) {
+        if let Some(mut receiving) = self.receiving.take() {
+            receiving.stop();
+        }
+    }
+}
@@ -0,0 +1,51 @@
+//! Restricts the capture to a rectangle of the screen: frames are cropped to it before
+//! they are shown, recorded or streamed, like [`crate::pinned_element`] but to a fixed
+//! region. The watches still get the whole frames. It's set by `[crop] rect` at startup,
+//! and interactively by pressing `T` and dragging out the region in the 2D view; `T` again
+//! goes back to the whole capture.
+
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+
+use crate::capture::Frame;
+use crate::focus::ScreenRect;
+use crate::status;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CropConfig {
+    /// Region to crop to at startup in global screen points: x, y, width and height.
+    pub rect: Option<[f32; 4]>,
+}
+
+static CROP: Lazy<Mutex<Option<ScreenRect>>> = Lazy::new(Default::default);
+
+/// Crops the frames to `rect` from now on, `None` captures the whole source again.
+pub fn set(rect: Option<ScreenRect>) {
+    *CROP.lock().unwrap() = rect;
+    match rect {
+        Some(rect) => println!(
+            "Cropping the capture to {}x{} at {}, {}",
+            rect.size.x, rect.size.y, rect.min.x, rect.min.y
+        ),
+        None => println!("Capturing the whole source again"),
+    }
+    status::update(|status| {
+        status.crop = rect.map(|rect| [rect.min.x, rect.min.y, rect.size.x, rect.size.y]);
+    });
+}
+
+pub fn get() -> Option<ScreenRect> {
+    *CROP.lock().unwrap()
+}
+
+/// Crops `frame` to the region, frames are passed through unchanged without one or if
+/// the region isn't in them.
+pub fn crop(frame: Frame) -> Frame {
+    let Some(rect) = get() else {
+        return frame;
+    };
+    frame.crop_to_rect(rect).unwrap_or(frame)
+}
@@ -0,0 +1,103 @@
+//! Ends capturing cleanly when the window closes: [`CaptureManager::stop`] tells every
+//! thread holding capture streams to stop them, so ScreenCaptureKit ends its sessions and
+//! the screen recording indicator goes away, and then shuts the tokio runtime down.
+//!
+//! Streams stay on the thread that started them. Those threads take an [`Owner`] and wait
+//! on it instead of sleeping; it tells them when to stop.
+
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
+use once_cell::sync::Lazy;
+
+/// How long stopping waits for the threads to stop their streams.
+const STOP_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How long stopping waits for the tasks still running on the runtime.
+const RUNTIME_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(1);
+
+struct Signal {
+    /// Dropped to stop, which disconnects every receiver at once.
+    sender: Mutex<Option<Sender<()>>>,
+    receiver: Receiver<()>,
+
+    /// Number of live [`Owner`]s.
+    owners: Mutex<usize>,
+    owner_dropped: Condvar,
+}
+
+static SIGNAL: Lazy<Signal> = Lazy::new(|| {
+    let (sender, receiver) = crossbeam_channel::bounded(0);
+    Signal {
+        sender: Mutex::new(Some(sender)),
+        receiver,
+        owners: Mutex::new(0),
+        owner_dropped: Condvar::new(),
+    }
+});
+
+/// Held by a thread owning capture streams, for as long as it owns any.
+pub struct Owner {
+    stop: Receiver<()>,
+}
+
+pub fn owner() -> Owner {
+    *SIGNAL.owners.lock().unwrap() += 1;
+    Owner {
+        stop: SIGNAL.receiver.clone(),
+    }
+}
+
+impl Owner {
+    /// Waits for `timeout`, returns false instead once capturing stops.
+    pub fn wait(&self, timeout: Duration) -> bool {
+        matches!(self.stop.recv_timeout(timeout), Err(RecvTimeoutError::Timeout))
+    }
+
+    pub fn wait_for_stop(&self) {
+        self.stop.recv().ok();
+    }
+
+    /// Disconnects once capturing stops, for selecting on it.
+    pub fn stop_signal(&self) -> &Receiver<()> {
+        &self.stop
+    }
+}
+
+impl Drop for Owner {
+    fn drop(&mut self) {
+        *SIGNAL.owners.lock().unwrap() -= 1;
+        SIGNAL.owner_dropped.notify_all();
+    }
+}
+
+/// Owns the runtime the capture sources run on.
+pub struct CaptureManager {
+    runtime: tokio::runtime::Runtime,
+}
+
+impl CaptureManager {
+    pub fn new(runtime: tokio::runtime::Runtime) -> Self {
+        Self { runtime }
+    }
+
+    pub fn handle(&self) -> tokio::runtime::Handle {
+        self.runtime.handle().clone()
+    }
+
+    /// Stops all capture streams, then the runtime.
+    pub fn stop(self) {
+        drop(SIGNAL.sender.lock().unwrap().take());
+        let owners = SIGNAL.owners.lock().unwrap();
+        let (owners, wait) = SIGNAL
+            .owner_dropped
+            .wait_timeout_while(owners, STOP_TIMEOUT, |owners| *owners > 0)
+            .unwrap();
+        if wait.timed_out() {
+            eprintln!("{} capture threads didn't stop their streams in time", *owners);
+        }
+        drop(owners);
+        self.runtime.shutdown_timeout(RUNTIME_SHUTDOWN_TIMEOUT);
+    }
+}
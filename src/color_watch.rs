@@ -0,0 +1,123 @@
+//! Color watch regions: an event fires when the average color of a region of the capture
+//! comes within a tolerance of a target color or leaves it again, e.g. a build status
+//! indicator turning red.
+//!
+//! Averaging a sampled region is cheap enough to do for every frame while capturing.
+
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+
+use crate::capture::Frame;
+use crate::events::{self, Delivery, Severity};
+
+/// Every n-th pixel of every n-th row of a region is averaged.
+const SAMPLE_STEP: usize = 2;
+
+/// Distance beyond the tolerance a color has to go before it counts as having left the
+/// range, so noise around the edge doesn't fire over and over.
+const HYSTERESIS: f32 = 4.0;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ColorWatchConfig {
+    pub regions: Vec<ColorRegion>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ColorRegion {
+    pub name: String,
+
+    /// `[x, y, width, height]` in capture pixels.
+    pub region: [u32; 4],
+
+    /// Target sRGB color.
+    pub color: [u8; 3],
+
+    /// Largest distance (per channel, 0–255) of the average color to the target that still
+    /// counts as matching.
+    pub tolerance: f32,
+
+    #[serde(flatten)]
+    pub delivery: Delivery,
+}
+
+impl Default for ColorRegion {
+    fn default() -> Self {
+        Self {
+            name: "color".to_owned(),
+            region: [0, 0, 0, 0],
+            color: [255, 0, 0],
+            tolerance: 24.0,
+            delivery: Delivery::default(),
+        }
+    }
+}
+
+/// Whether each region's color is in range, `None` before the first frame.
+static IN_RANGE: Lazy<Mutex<Vec<Option<bool>>>> = Lazy::new(Default::default);
+
+/// Checks every region against `frame` and fires events for those entering or leaving
+/// their range.
+pub fn observe(frame: &Frame, config: &ColorWatchConfig) {
+    let regions = &config.regions;
+    if regions.is_empty() {
+        return;
+    }
+    puffin::profile_function!();
+
+    let mut in_range = IN_RANGE.lock().unwrap();
+    in_range.resize(regions.len(), None);
+    for (region, was_in_range) in regions.iter().zip(in_range.iter_mut()) {
+        let Some(average) = average_color(frame, region.region) else {
+            continue;
+        };
+        let distance = average
+            .iter()
+            .zip(region.color)
+            .map(|(average, target)| (average - target as f32).abs())
+            .fold(0.0, f32::max);
+        let is_in_range = match *was_in_range {
+            Some(true) => distance <= region.tolerance + HYSTERESIS,
+            _ => distance <= region.tolerance,
+        };
+        let [r, g, b] = average.map(|channel| channel.round() as u8);
+        let message = format!("average color #{r:02x}{g:02x}{b:02x}");
+        match (*was_in_range, is_in_range) {
+            (Some(false) | None, true) => {
+                let event =
+                    events::emit(Severity::Warning, "color_entered", &region.name, &message);
+                events::deliver(&event, &region.delivery);
+            }
+            (Some(true), false) => {
+                let event = events::emit(Severity::Info, "color_left", &region.name, &message);
+                events::deliver(&event, &region.delivery);
+            }
+            _ => {}
+        }
+        *was_in_range = Some(is_in_range);
+    }
+}
+
+/// Average sRGB color of the part of `region` within the frame, `None` if that's empty.
+fn average_color(frame: &Frame, [x, y, width, height]: [u32; 4]) -> Option<[f32; 3]> {
+    let bitmap = &frame.frame_bitmap;
+    let [x, y, width, height] = [x, y, width, height].map(|value| value as usize);
+    let columns = x.min(bitmap.width)..(x + width).min(bitmap.width);
+    let rows = y.min(bitmap.height)..(y + height).min(bitmap.height);
+
+    let mut sum = [0u64; 3];
+    let mut count = 0u64;
+    for row in rows.step_by(SAMPLE_STEP) {
+        let pixels = &bitmap.data[row * bitmap.width..][columns.clone()];
+        for [b, g, r, _] in pixels.iter().step_by(SAMPLE_STEP) {
+            for (sum, channel) in sum.iter_mut().zip([r, g, b]) {
+                *sum += *channel as u64;
+            }
+            count += 1;
+        }
+    }
+    (count > 0).then(|| sum.map(|sum| sum as f32 / count as f32))
+}
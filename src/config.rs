@@ -0,0 +1,545 @@
+//! Runtime configuration.
+//!
+//! Settings are read from an optional TOML file (`--config <path>`, or
+//! `re_render_crabgrab.toml` in the working directory) and can be overridden with
+//! command line flags.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+
+use crate::ab_compare::AbCompareConfig;
+use crate::accessibility::Accessibility;
+use crate::app_capture::AppCaptureConfig;
+use crate::background::{BackgroundConfig, BackgroundKind};
+use crate::capture::WindowConfig;
+use crate::capture_backend::BackendKind;
+use crate::capture_crop::CropConfig;
+use crate::black_bars::BlackBarsConfig;
+use crate::color_watch::ColorWatchConfig;
+use crate::discovery::DiscoveryConfig;
+use crate::frame_history::HistoryConfig;
+use crate::frame_link::FrameLinkConfig;
+use crate::frame_style::FrameStyleConfig;
+use crate::framework::TimeSource;
+use crate::gamepad::GamepadConfig;
+use crate::hud::{HudConfig, Widget};
+use crate::keymap::{Action, Keymap};
+use crate::launch::LaunchConfig;
+use crate::live_stream::StreamConfig;
+use crate::mcap_export::McapConfig;
+use crate::mirror::MirrorConfig;
+use crate::multi_display::MultiDisplayConfig;
+use crate::notifications::NotificationsConfig;
+use crate::encoder::EncoderKind;
+use crate::exclusions::ExcludeConfig;
+use crate::output::OutputConfig;
+use crate::overlay_lod::OverlayBudgetConfig;
+use crate::pinned_element::PinnedElementConfig;
+use crate::pixel_format::{HdrConfig, PixelFormat, PixelFormatConfig};
+use crate::playback::PlaybackConfig;
+use crate::presets::Preset;
+use crate::profiles::{self, Profile};
+use crate::recorder::{RecordingConfig, RecordingSource};
+use crate::scene_change::SceneChangeConfig;
+use crate::status_server::StatusServerConfig;
+use crate::test_pattern::{PatternKind, TestPatternConfig};
+use crate::text_watch::TextWatchConfig;
+use crate::video_file::VideoConfig;
+use crate::watch_folder::WatchFolderConfig;
+use crate::webhook::WebhookConfig;
+
+const DEFAULT_CONFIG_PATH: &str = "re_render_crabgrab.toml";
+
+/// Every key with its default value and what it does, printed by `--print-default-config`.
+pub const DEFAULT_CONFIG: &str = include_str!("default_config.toml");
+
+static CONFIG: OnceCell<Config> = OnceCell::new();
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub accessibility: Accessibility,
+    pub background: BackgroundConfig,
+    pub frame_style: FrameStyleConfig,
+    pub pixel_format: PixelFormatConfig,
+
+    /// Preview 10 bit captures with all their bits, see [`HdrConfig`].
+    pub hdr: HdrConfig,
+
+    /// Frames captured faster than this are dropped before anything processes them.
+    pub max_capture_fps: Option<f32>,
+
+    /// Frames drawn ahead of the GPU at most, 2 if unset. Shows them in the 2D view when set.
+    pub max_frames_in_flight: Option<u32>,
+
+    /// Built-in bundle of settings applied on top of the others, see [`crate::profiles`].
+    pub profile: Option<Profile>,
+
+    /// Animation time, fixed-step or scripted for reproducible output.
+    pub time: TimeSource,
+
+    /// Highlight the focused UI element, needs the `focus-highlight` feature.
+    pub focus_highlight: bool,
+
+    /// Leave the mouse pointer out of captured displays and windows.
+    pub hide_cursor: bool,
+
+    /// Windows kept out of display captures, see [`crate::exclusions`].
+    pub exclude: ExcludeConfig,
+
+    pub pinned_element: PinnedElementConfig,
+
+    /// Region of the screen to capture, see [`crate::capture_crop`].
+    pub crop: CropConfig,
+    pub black_bars: BlackBarsConfig,
+    pub scene_changes: SceneChangeConfig,
+    pub text_watch: TextWatchConfig,
+    pub color_watch: ColorWatchConfig,
+
+    /// Where session events are reported, see [`crate::events`].
+    pub notifications: NotificationsConfig,
+    pub webhooks: Vec<WebhookConfig>,
+
+    pub overlay_budget: OverlayBudgetConfig,
+
+    /// Widgets in the corners of the 2D view, see [`crate::hud`].
+    pub hud: HudConfig,
+
+    /// Latest frames kept for scrubbing, see [`crate::frame_history`].
+    pub history: HistoryConfig,
+
+    /// Encode the views one after the other instead of on separate threads.
+    pub serial_view_encoding: bool,
+
+    pub status_server: StatusServerConfig,
+    pub discovery: DiscoveryConfig,
+    pub frame_link: FrameLinkConfig,
+    pub ab_compare: AbCompareConfig,
+    pub window: WindowConfig,
+    pub multi_display: MultiDisplayConfig,
+
+    /// Fullscreen mirror of the capture on another monitor, see [`crate::mirror`].
+    pub mirror: MirrorConfig,
+    pub launch: LaunchConfig,
+    pub app_capture: AppCaptureConfig,
+    pub playback: PlaybackConfig,
+    pub video: VideoConfig,
+    pub test_pattern: TestPatternConfig,
+    pub output: OutputConfig,
+    pub recording: RecordingConfig,
+    pub mcap: McapConfig,
+    pub stream: StreamConfig,
+    pub watch_folder: WatchFolderConfig,
+
+    /// Workspace presets, switched with Ctrl+1 to Ctrl+9.
+    pub presets: Vec<Preset>,
+
+    pub gamepad: GamepadConfig,
+
+    /// Key bindings replacing the default ones, see [`crate::keymap`].
+    pub keys: BTreeMap<Action, String>,
+
+    /// Start with the state the previous session ended in.
+    #[serde(skip)]
+    pub restore_session: bool,
+
+    /// Only list the capturable windows, then exit.
+    #[serde(skip)]
+    pub list_windows: bool,
+
+    /// Only list the monitors, then exit.
+    #[serde(skip)]
+    pub list_monitors: bool,
+
+    /// Only list sessions advertised on the network, then exit.
+    #[serde(skip)]
+    pub list_peers: bool,
+
+    /// Only check this lossless recording against its manifest, then exit.
+    #[serde(skip)]
+    pub verify_recording: Option<PathBuf>,
+
+    /// Only print [`DEFAULT_CONFIG`], then exit.
+    #[serde(skip)]
+    pub print_default_config: bool,
+}
+
+impl Config {
+    /// Loads the config file and applies the command line flags on top of it.
+    pub fn load() -> anyhow::Result<Self> {
+        let mut args = std::env::args().skip(1);
+
+        let mut config_path = None;
+        let mut flags = Vec::new();
+        while let Some(arg) = args.next() {
+            if arg == "--config" {
+                config_path = Some(PathBuf::from(
+                    args.next().context("--config expects a path")?,
+                ));
+            } else {
+                flags.push(arg);
+            }
+        }
+
+        // The config file may be what's broken, don't read it just to print the default one.
+        if flags.iter().any(|flag| flag == "--print-default-config") {
+            return Ok(Self {
+                print_default_config: true,
+                ..Self::default()
+            });
+        }
+
+        let mut config = match config_path {
+            Some(path) => Self::from_file(&path)?,
+            None if Path::new(DEFAULT_CONFIG_PATH).exists() => {
+                Self::from_file(Path::new(DEFAULT_CONFIG_PATH))?
+            }
+            None => Self::default(),
+        };
+
+        let mut flags = flags.into_iter();
+        while let Some(flag) = flags.next() {
+            config.apply_flag(&flag, &mut flags)?;
+        }
+        anyhow::ensure!(
+            config.crop.rect.iter().all(|[_, _, width, height]| *width > 0.0 && *height > 0.0),
+            "[crop] rect needs a positive width and height"
+        );
+        anyhow::ensure!(
+            config.app_capture.application.is_none()
+                || (config.crop.rect.is_none() && config.pinned_element.point.is_none()),
+            "the --capture-app grid isn't in screen coordinates, it can't be cropped or pinned"
+        );
+        if config.hdr.enabled {
+            let format = config.pixel_format.format.get_or_insert(PixelFormat::Argb2101010);
+            anyhow::ensure!(
+                *format == PixelFormat::Argb2101010,
+                "[hdr] needs the argb2101010 pixel format, not {}",
+                format.name()
+            );
+        }
+        anyhow::ensure!(config.hdr.exposure > 0.0, "[hdr] exposure has to be positive");
+        anyhow::ensure!(
+            config.recording.fps.is_finite() && config.recording.fps > 0.0,
+            "[recording] fps has to be positive"
+        );
+        anyhow::ensure!(
+            config.recording.queue_size >= 1,
+            "[recording] queue_size has to be at least 1"
+        );
+        anyhow::ensure!(
+            config.stream.fps.is_finite() && config.stream.fps > 0.0,
+            "[stream] fps has to be positive"
+        );
+        config.time.check()?;
+        config.window.matcher()?;
+        config.frame_link.check_endpoints()?;
+
+        match config.profile {
+            Some(profile) => profile.apply(&config).validate()?,
+            None => config.validate()?,
+        }
+        Keymap::new(&config.keys)?;
+        Ok(config)
+    }
+
+    /// Rejects options the capture backend doesn't support, rather than ignoring them
+    /// while capturing.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let Some(backend) = BackendKind::for_config(self) else {
+            return Ok(());
+        };
+        let capabilities = backend.capabilities();
+        let name = backend.name();
+        anyhow::ensure!(
+            self.pinned_element.point.is_none() || capabilities.region_crop,
+            "--pin-element needs frames in local screen coordinates, the {name} backend has none"
+        );
+        anyhow::ensure!(
+            !self.hide_cursor || capabilities.cursor,
+            "the {name} backend has no cursor in its frames, --hide-cursor can't apply"
+        );
+        anyhow::ensure!(
+            self.pixel_format.format != Some(PixelFormat::Argb2101010) || capabilities.hdr,
+            "the {name} backend has no 10 bit frames, --pixel-format argb2101010 can't apply"
+        );
+        Ok(())
+    }
+
+    /// Parses the config file, naming the key of the first invalid value. Unknown keys are
+    /// ignored with a warning, they're usually typos.
+    fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read config file {}", path.display()))?;
+        let mut unknown_keys = Vec::new();
+        let deserializer = serde_ignored::Deserializer::new(
+            toml::Deserializer::new(&text),
+            |key: serde_ignored::Path<'_>| unknown_keys.push(key.to_string()),
+        );
+        let config: Self = serde_path_to_error::deserialize(deserializer).map_err(|err| {
+            let file = path.display();
+            // Syntax errors have no key, their message has the line and column instead.
+            match err.path().to_string().as_str() {
+                "." => anyhow::anyhow!("failed to parse config file {file}: {}", err.inner()),
+                key => anyhow::anyhow!("invalid `{key}` in config file {file}: {}", err.inner()),
+            }
+        })?;
+        for key in unknown_keys {
+            eprintln!("Unknown key `{key}` in config file {} ignored", path.display());
+        }
+        Ok(config)
+    }
+
+    fn apply_flag(
+        &mut self,
+        flag: &str,
+        values: &mut impl Iterator<Item = String>,
+    ) -> anyhow::Result<()> {
+        match flag {
+            "--high-contrast" => self.accessibility.high_contrast = true,
+            "--reduced-motion" => self.accessibility.reduced_motion = true,
+            "--focus-highlight" => self.focus_highlight = true,
+            "--hide-cursor" => self.hide_cursor = true,
+            "--exclude-own-window" => self.exclude.own_window = true,
+            "--exclude-window" => {
+                let title = values.next().context("--exclude-window expects a title")?;
+                self.exclude.windows.push(title);
+            }
+            "--hdr" => self.hdr.enabled = true,
+            "--gamepad" => self.gamepad.enabled = true,
+            "--crop-black-bars" => self.black_bars.auto_crop = true,
+            "--detect-scene-changes" => self.scene_changes.detect = true,
+            "--pin-element" => {
+                let point = values.next().context("--pin-element expects a point x,y")?;
+                let (x, y) = point.split_once(',').context("--pin-element expects a point x,y")?;
+                self.pinned_element.point = Some([
+                    x.trim().parse().context("invalid --pin-element x")?,
+                    y.trim().parse().context("invalid --pin-element y")?,
+                ]);
+            }
+            "--crop" => {
+                let rect = values.next().context("--crop expects a region x,y,width,height")?;
+                let numbers = rect
+                    .split(',')
+                    .map(|number| number.trim().parse())
+                    .collect::<Result<Vec<f32>, _>>()
+                    .context("invalid --crop")?;
+                let rect = <[f32; 4]>::try_from(numbers)
+                    .ok()
+                    .context("--crop expects a region x,y,width,height")?;
+                self.crop.rect = Some(rect);
+            }
+            "--serial-view-encoding" => self.serial_view_encoding = true,
+            "--history-frames" => {
+                self.history.frames = values
+                    .next()
+                    .context("--history-frames expects a number of frames")?
+                    .parse()
+                    .context("invalid --history-frames")?;
+            }
+            "--hud" => {
+                let names = values.next().context("--hud expects widget names")?;
+                self.hud.widgets = names
+                    .split(',')
+                    .map(|name| {
+                        Widget::parse(name.trim())
+                            .with_context(|| format!("unknown HUD widget {name:?}"))
+                    })
+                    .collect::<anyhow::Result<_>>()?;
+            }
+            "--max-frames-in-flight" => {
+                self.max_frames_in_flight = Some(
+                    values
+                        .next()
+                        .context("--max-frames-in-flight expects a number of frames")?
+                        .parse()
+                        .context("invalid --max-frames-in-flight")?,
+                );
+            }
+            "--frame-budget" => {
+                self.overlay_budget.frame_ms = Some(
+                    values
+                        .next()
+                        .context("--frame-budget expects a frame time in ms")?
+                        .parse()
+                        .context("invalid --frame-budget")?,
+                );
+            }
+            "--encoder" => {
+                let name = values.next().context("--encoder expects a name")?;
+                self.recording.encoder = EncoderKind::parse(&name)
+                    .with_context(|| format!("unknown encoder {name:?}"))?;
+            }
+            "--record" => {
+                let name = values.next().context("--record expects a source")?;
+                self.recording.source = RecordingSource::parse(&name)
+                    .with_context(|| format!("unknown recording source {name:?}"))?;
+            }
+            "--captions-from-stdin" => self.recording.captions_from_stdin = true,
+            "--burn-in-captions" => self.recording.burn_in_captions = true,
+            "--mcap" => self.mcap.enabled = true,
+            "--rtmp" => {
+                self.stream.rtmp_url = Some(values.next().context("--rtmp expects a URL")?);
+            }
+            "--srt" => {
+                self.stream.srt_url = Some(values.next().context("--srt expects a URL")?);
+            }
+            "--hls" => {
+                self.stream.hls_directory = Some(PathBuf::from(
+                    values.next().context("--hls expects a directory")?,
+                ));
+            }
+            "--stream-bitrate" => {
+                self.stream.bitrate_kbps = values
+                    .next()
+                    .context("--stream-bitrate expects a bitrate in kbit/s")?
+                    .parse()
+                    .context("invalid --stream-bitrate")?;
+            }
+            "--watch-folder" => {
+                self.watch_folder.directory = Some(PathBuf::from(
+                    values.next().context("--watch-folder expects a directory")?,
+                ));
+            }
+            "--status-server" => {
+                self.status_server.address =
+                    Some(values.next().context("--status-server expects an address")?);
+            }
+            "--advertise" => {
+                self.discovery.name = Some(values.next().context("--advertise expects a name")?);
+            }
+            "--list-peers" => self.list_peers = true,
+            "--list-windows" => self.list_windows = true,
+            "--print-default-config" => self.print_default_config = true,
+            "--all-displays" => self.multi_display.enabled = true,
+            "--mirror" => {
+                self.mirror.monitor = Some(values.next().context("--mirror expects a monitor")?);
+            }
+            "--mirror-direct" => self.mirror.direct = true,
+            "--list-monitors" => self.list_monitors = true,
+            "--window" => {
+                self.window.title = Some(values.next().context("--window expects a title")?);
+            }
+            "--window-title" => {
+                self.window.title_pattern =
+                    Some(values.next().context("--window-title expects a regex")?);
+            }
+            "--app" => {
+                self.window.app = Some(values.next().context("--app expects a bundle id")?);
+            }
+            "--restore-session" => self.restore_session = true,
+            "--verify-recording" => {
+                self.verify_recording = Some(PathBuf::from(
+                    values.next().context("--verify-recording expects a path")?,
+                ));
+            }
+            "--output-dir" => {
+                self.output.directory =
+                    PathBuf::from(values.next().context("--output-dir expects a path")?);
+            }
+            "--capture-daemon" => {
+                self.frame_link.serve =
+                    Some(values.next().context("--capture-daemon expects an endpoint")?);
+            }
+            "--connect" => {
+                self.frame_link.connect =
+                    Some(values.next().context("--connect expects an endpoint")?);
+            }
+            "--compare" => {
+                self.ab_compare.a =
+                    Some(values.next().context("--compare expects two window titles")?);
+                self.ab_compare.b =
+                    Some(values.next().context("--compare expects two window titles")?);
+            }
+            "--launch" => {
+                self.launch.command = Some(values.next().context("--launch expects a command")?);
+            }
+            "--capture-app" => {
+                self.app_capture.application =
+                    Some(values.next().context("--capture-app expects an application name")?);
+            }
+            "--play" => {
+                let file = values.next().context("--play expects an MCAP file")?;
+                self.playback.file = Some(PathBuf::from(file));
+            }
+            "--video" => {
+                let file = values.next().context("--video expects a video file")?;
+                self.video.file = Some(PathBuf::from(file));
+            }
+            "--test-pattern" => {
+                let name = values.next().context("--test-pattern expects a pattern")?;
+                self.test_pattern.kind = Some(
+                    PatternKind::parse(&name)
+                        .with_context(|| format!("unknown test pattern {name:?}"))?,
+                );
+            }
+            "--loop" => {
+                self.playback.looping = true;
+                self.video.looping = true;
+            }
+            "--pixel-format" => {
+                let name = values.next().context("--pixel-format expects a format")?;
+                self.pixel_format.format = match name.as_str() {
+                    "auto" => None,
+                    name => Some(
+                        PixelFormat::parse(name)
+                            .with_context(|| format!("unknown pixel format {name:?}"))?,
+                    ),
+                };
+            }
+            "--benchmark-pixel-formats" => {
+                self.pixel_format.format = None;
+                self.pixel_format.rebenchmark = true;
+            }
+            "--fixed-step" => {
+                let fps: f32 = values
+                    .next()
+                    .context("--fixed-step expects a frame rate")?
+                    .parse()
+                    .context("invalid --fixed-step")?;
+                anyhow::ensure!(
+                    fps.is_finite() && fps > 0.0,
+                    "--fixed-step has to be a positive frame rate"
+                );
+                self.time = TimeSource::FixedStep { fps };
+            }
+            "--profile" => {
+                let name = values.next().context("--profile expects a name")?;
+                self.profile = Some(
+                    Profile::parse(&name).with_context(|| format!("unknown profile {name:?}"))?,
+                );
+            }
+            "--background" => {
+                let name = values.next().context("--background expects a kind")?;
+                self.background.kind = BackgroundKind::parse(&name)
+                    .with_context(|| format!("unknown background {name:?}"))?;
+            }
+            _ => anyhow::bail!("unknown flag {flag:?}"),
+        }
+        Ok(())
+    }
+}
+
+/// Makes `config` available to the rest of the application via [`get`].
+pub fn init(config: Config) {
+    let profile = config.profile;
+    CONFIG.set(config).expect("config initialized twice");
+    profiles::switch(profile).expect("the profile was validated when loading");
+}
+
+/// The config with the active profile applied, see [`crate::profiles`].
+pub fn get() -> &'static Config {
+    match profiles::active() {
+        Some(profile) => profiles::applied(profile),
+        None => base(),
+    }
+}
+
+/// The config file and command line flags, without a profile.
+pub fn base() -> &'static Config {
+    CONFIG.get().expect("config not initialized")
+}
@@ -0,0 +1,152 @@
+//! Right-click menu with actions for the object under the cursor, drawn with the text layer
+//! on top of the 2D view.
+
+use std::io::Write as _;
+
+use re_renderer::{renderer::TexturedRect, RenderContext};
+
+use crate::capture_backend;
+use crate::config;
+use crate::picking::Pick;
+use crate::text::TextRect;
+
+/// Scene units per font pixel of the menu items.
+const SCALE: f32 = 2.0;
+
+/// Height of an item: the 8 pixel glyphs plus 2 pixels of padding above and below.
+const ITEM_HEIGHT: f32 = 12.0 * SCALE;
+
+#[derive(Debug, Clone)]
+pub enum MenuAction {
+    CopyColor(Pick),
+    CopyPixelPosition(Pick),
+    Screenshot,
+    PinElement(Pick),
+    UnpinElement,
+    ClearAnnotations,
+}
+
+impl MenuAction {
+    fn label(&self) -> String {
+        match self {
+            Self::CopyColor(pick) => {
+                let [r, g, b] = pick.rgb;
+                format!("Copy color #{r:02x}{g:02x}{b:02x}")
+            }
+            Self::CopyPixelPosition(pick) => {
+                let [x, y] = pick.capture_pixel;
+                format!("Copy pixel position {x}, {y}")
+            }
+            Self::Screenshot => "Save screenshot".to_owned(),
+            Self::PinElement(_) => "Pin capture to this UI element".to_owned(),
+            Self::UnpinElement => "Unpin UI element".to_owned(),
+            Self::ClearAnnotations => "Clear annotations".to_owned(),
+        }
+    }
+
+    /// Whether the capture backend supports the action, unsupported ones are greyed out.
+    fn is_supported(&self) -> bool {
+        match (self, capture_backend::capabilities()) {
+            (Self::PinElement(_), Some(capabilities)) => capabilities.region_crop,
+            (Self::PinElement(_), None) => config::get().app_capture.application.is_none(),
+            _ => true,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ContextMenu {
+    /// Top left corner in 2D view scene units and the actions, if open.
+    open: Option<(glam::Vec2, Vec<MenuAction>)>,
+    items: Vec<TextRect>,
+}
+
+impl ContextMenu {
+    /// Opens the menu at `position` with `actions`, replacing one that's open.
+    pub fn open(&mut self, position: glam::Vec2, actions: Vec<MenuAction>) {
+        self.items.resize_with(actions.len(), TextRect::default);
+        self.open = Some((position, actions));
+    }
+
+    pub fn close(&mut self) {
+        self.open = None;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open.is_some()
+    }
+
+    /// Closes the menu, returns the action of the item at `position`, if any.
+    pub fn click(&mut self, position: glam::Vec2) -> Option<MenuAction> {
+        let (menu_position, actions) = self.open.take()?;
+        let index = self.item_at(menu_position, &actions, position)?;
+        Some(actions[index].clone()).filter(MenuAction::is_supported)
+    }
+
+    fn item_at(
+        &self,
+        menu_position: glam::Vec2,
+        actions: &[MenuAction],
+        position: glam::Vec2,
+    ) -> Option<usize> {
+        let offset = position - menu_position;
+        let width = labels(actions).first().map_or(0, |label| label.chars().count() + 1) as f32
+            * 8.0
+            * SCALE;
+        if offset.x < 0.0 || offset.y < 0.0 || offset.x > width {
+            return None;
+        }
+        let index = (offset.y / ITEM_HEIGHT) as usize;
+        (index < actions.len()).then_some(index)
+    }
+
+    /// Rects of the open menu, with the item under `cursor` highlighted.
+    pub fn rects(&mut self, re_ctx: &RenderContext, cursor: glam::Vec2) -> Vec<TexturedRect> {
+        let Some((menu_position, actions)) = &self.open else {
+            return Vec::new();
+        };
+        let hovered = self.item_at(*menu_position, actions, cursor);
+        let menu_position = *menu_position;
+        let labels = labels(actions);
+        labels
+            .iter()
+            .zip(&mut self.items)
+            .enumerate()
+            .map(|(i, (label, item))| {
+                let color = if !actions[i].is_supported() {
+                    [128, 128, 128, 255]
+                } else if hovered == Some(i) {
+                    [255, 200, 0, 255]
+                } else {
+                    [255, 255, 255, 255]
+                };
+                let position = menu_position + glam::vec2(0.0, i as f32 * ITEM_HEIGHT);
+                item.rect(re_ctx, label, color, position.extend(0.0), SCALE)
+            })
+            .collect()
+    }
+}
+
+/// Item labels, padded to the same length so the items line up.
+fn labels(actions: &[MenuAction]) -> Vec<String> {
+    let labels = actions.iter().map(MenuAction::label).collect::<Vec<_>>();
+    let width = labels.iter().map(|label| label.chars().count()).max().unwrap_or(0);
+    labels
+        .into_iter()
+        .map(|label| format!(" {label:<width$}"))
+        .collect()
+}
+
+/// Puts `text` on the system clipboard.
+pub fn copy_to_clipboard(text: &str) -> anyhow::Result<()> {
+    let mut child = std::process::Command::new("pbcopy")
+        .stdin(std::process::Stdio::piped())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(text.as_bytes())?;
+    anyhow::ensure!(child.wait()?.success(), "pbcopy failed");
+    Ok(())
+}
@@ -0,0 +1,85 @@
+//! Commands that can be sent to the running example from outside the render loop,
+//! e.g. by the status server.
+
+use crossbeam_channel::{Receiver, Sender};
+use once_cell::sync::Lazy;
+
+use crate::capture_backend::BackendKind;
+use crate::profiles::Profile;
+use crate::recorder::RecordingSource;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlCommand {
+    /// Save the latest captured frame as a PNG.
+    Screenshot,
+
+    StartRecording,
+    StopRecording,
+    ToggleRecording,
+
+    StartStreaming,
+    StopStreaming,
+    ToggleStreaming,
+
+    /// Switch what feeds the recorder and live stream, restarting them if running.
+    SetFeedSource(RecordingSource),
+    CycleFeedSource,
+
+    /// Mark the current point of the recording, optionally with a name.
+    Marker(Option<String>),
+
+    /// Show a caption line in the recording, an empty line clears it.
+    Caption(String),
+
+    /// Show text in the HUD's script widget, an empty text hides it.
+    HudText(String),
+
+    /// Switch to the workspace preset with this name or 1-based slot number.
+    ApplyPreset(String),
+
+    /// Save the current state as preset into this 1-based slot.
+    SavePreset(usize),
+
+    /// Undo or redo the latest interactive edit.
+    Undo,
+    Redo,
+
+    /// Pause on the current frame, or resume.
+    ToggleFrameStep,
+
+    /// While paused, show the next captured frame.
+    StepFrame,
+
+    /// Pause or resume the whole session: capture, recording and stats.
+    PauseSession,
+    ResumeSession,
+    TogglePause,
+
+    /// Capture the display with this 1-based index instead.
+    SwitchDisplay(usize),
+
+    /// Show or hide the mouse pointer in the display capture.
+    SetCursorHidden(bool),
+    ToggleCursor,
+
+    /// Switch to a built-in profile, or to none, see [`crate::profiles`].
+    SetProfile(Option<Profile>),
+    CycleProfile,
+
+    /// Switch the capture backend, see [`crate::capture_backend`].
+    SwitchBackend(BackendKind),
+    CycleBackend,
+}
+
+static CHANNEL: Lazy<(Sender<ControlCommand>, Receiver<ControlCommand>)> =
+    Lazy::new(crossbeam_channel::unbounded);
+
+pub fn send(command: ControlCommand) {
+    // The receiving end lives in the static as well, so this can't fail.
+    CHANNEL.0.send(command).ok();
+}
+
+/// Commands sent since the last call, oldest first.
+pub fn drain() -> impl Iterator<Item = ControlCommand> {
+    CHANNEL.1.try_iter()
+}
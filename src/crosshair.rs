@@ -0,0 +1,73 @@
+//! Crosshair at the content position under the pointer, shown on every rect showing the
+//! capture: hovering the capture rect in the 2D view, its quad in the 3D view or the B side
+//! of an A/B comparison puts it on the same spot of all of them.
+//!
+//! Positions in the content go 0–1 across a rect, the same in every rect, so the views
+//! only have to agree on where the rects are in the scene.
+
+use re_renderer::{Color32, LineDrawableBuilder, Size};
+
+/// The camera of the 3D view, looking at the 2D scene lying on the plane z = 0.
+#[derive(Debug, Clone, Copy)]
+pub struct Camera3d {
+    pub position: glam::Vec3,
+    pub target: glam::Vec3,
+
+    /// In radians.
+    pub vertical_fov: f32,
+
+    /// Width over height the projection was made for, which isn't always the view's.
+    pub aspect_ratio: f32,
+}
+
+impl Camera3d {
+    /// The scene position on the plane z = 0 that shows at `pixel` of a view with
+    /// `resolution`, if it looks at the plane there at all.
+    pub fn unproject(&self, pixel: glam::Vec2, resolution: glam::Vec2) -> Option<glam::Vec2> {
+        let ndc = (pixel / resolution * 2.0 - glam::Vec2::ONE) * glam::vec2(1.0, -1.0);
+        let half_height = (self.vertical_fov * 0.5).tan();
+        let direction_in_view =
+            glam::vec3(ndc.x * half_height * self.aspect_ratio, ndc.y * half_height, -1.0);
+        let world_from_view =
+            glam::Mat4::look_at_rh(self.position, self.target, glam::Vec3::Y).inverse();
+        let direction = world_from_view.transform_vector3(direction_in_view);
+        let distance = -self.position.z / direction.z;
+        (distance.is_finite() && distance > 0.0)
+            .then(|| (self.position + direction * distance).truncate())
+    }
+}
+
+/// Position in the content, 0–1 across the rect, of `scene_position` on the first of
+/// `rects` (min and size in scene units) it falls on.
+pub fn content_position(
+    rects: &[(glam::Vec2, glam::Vec2)],
+    scene_position: glam::Vec2,
+) -> Option<glam::Vec2> {
+    rects.iter().find_map(|&(min, size)| {
+        let position = (scene_position - min) / size;
+        let inside =
+            position.cmpge(glam::Vec2::ZERO).all() && position.cmplt(glam::Vec2::ONE).all();
+        inside.then_some(position)
+    })
+}
+
+/// Adds a crosshair through content `position` spanning each of `rects`.
+pub fn add_crosshairs(
+    builder: &mut LineDrawableBuilder<'_>,
+    rects: &[(glam::Vec2, glam::Vec2)],
+    position: glam::Vec2,
+    color: Color32,
+) {
+    let mut batch = builder.batch("crosshair").depth_offset(10);
+    for &(min, size) in rects {
+        let point = min + position * size;
+        batch
+            .add_segment_2d(glam::vec2(min.x, point.y), glam::vec2(min.x + size.x, point.y))
+            .radius(Size::new_points(1.0))
+            .color(color);
+        batch
+            .add_segment_2d(glam::vec2(point.x, min.y), glam::vec2(point.x, min.y + size.y))
+            .radius(Size::new_points(1.0))
+            .color(color);
+    }
+}
@@ -0,0 +1,134 @@
+//! mDNS/Bonjour advertisement of running sessions and capture daemons serving frames over
+//! TCP, so receivers on the LAN can find them by name instead of typing IP:port.
+
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use anyhow::Context as _;
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use serde::Deserialize;
+
+use crate::config;
+
+/// Service type all sessions are advertised under.
+const SERVICE_TYPE: &str = "_crabgrab._tcp.local.";
+
+/// Service type of capture daemons, see [`crate::frame_link`].
+const FRAME_LINK_SERVICE_TYPE: &str = "_crabgrab-frames._tcp.local.";
+
+/// How long `--list-peers` listens for announcements.
+const BROWSE_DURATION: Duration = Duration::from_secs(3);
+
+/// How long each browser is waited on before trying the other one.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct DiscoveryConfig {
+    /// Name the session is advertised as. Advertisement is disabled if unset.
+    pub name: Option<String>,
+}
+
+/// Advertises the status server (and its MJPEG endpoint) listening on `address` as `name`.
+///
+/// The advertisement is withdrawn when the returned daemon is dropped.
+pub fn advertise(name: &str, address: SocketAddr) -> anyhow::Result<ServiceDaemon> {
+    let properties = [("status", "/"), ("mjpeg", "/stream.mjpeg")];
+    register(SERVICE_TYPE, name, address, &properties)
+}
+
+/// Advertises the capture daemon serving frames on `address` as `name`, like
+/// [`advertise`].
+pub fn advertise_frame_link(name: &str, address: SocketAddr) -> anyhow::Result<ServiceDaemon> {
+    let token = if config::get().frame_link.token.is_some() {
+        "required"
+    } else {
+        "none"
+    };
+    register(FRAME_LINK_SERVICE_TYPE, name, address, &[("token", token)])
+}
+
+fn register(
+    service_type: &str,
+    name: &str,
+    address: SocketAddr,
+    properties: &[(&str, &str)],
+) -> anyhow::Result<ServiceDaemon> {
+    let daemon = ServiceDaemon::new().context("failed to start mDNS daemon")?;
+
+    let host_label = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect::<String>();
+    let ip = if address.ip().is_unspecified() {
+        String::new()
+    } else {
+        address.ip().to_string()
+    };
+    let mut service = ServiceInfo::new(
+        service_type,
+        name,
+        &format!("{host_label}.local."),
+        ip.as_str(),
+        address.port(),
+        properties,
+    )
+    .context("invalid mDNS service info")?;
+    if address.ip().is_unspecified() {
+        service = service.enable_addr_auto();
+    }
+
+    daemon
+        .register(service)
+        .context("failed to register mDNS service")?;
+    println!("Advertising {name:?} as {service_type} via mDNS");
+    Ok(daemon)
+}
+
+/// Prints all sessions and capture daemons that announce themselves within a few seconds.
+pub fn list_peers() -> anyhow::Result<()> {
+    let daemon = ServiceDaemon::new().context("failed to start mDNS daemon")?;
+    let sessions = daemon
+        .browse(SERVICE_TYPE)
+        .context("failed to browse for sessions")?;
+    let frame_links = daemon
+        .browse(FRAME_LINK_SERVICE_TYPE)
+        .context("failed to browse for capture daemons")?;
+
+    println!("Looking for sessions…");
+    let deadline = Instant::now() + BROWSE_DURATION;
+    while Instant::now() < deadline {
+        // Both browsers are polled in turn, mdns-sd's receivers can't be selected on.
+        let event = match sessions.recv_timeout(POLL_INTERVAL) {
+            Ok(event) => Ok((SERVICE_TYPE, event)),
+            Err(_) => frame_links
+                .recv_timeout(POLL_INTERVAL)
+                .map(|event| (FRAME_LINK_SERVICE_TYPE, event)),
+        };
+        let Ok((service_type, ServiceEvent::ServiceResolved(info))) = event else {
+            continue;
+        };
+        let name = info
+            .get_fullname()
+            .trim_end_matches(service_type)
+            .trim_end_matches('.');
+        for ip in info.get_addresses() {
+            let port = info.get_port();
+            if service_type == FRAME_LINK_SERVICE_TYPE {
+                println!(
+                    "{name}: capture daemon, --connect tcp://{ip}:{port} (token: {})",
+                    info.get_property_val_str("token").unwrap_or("none"),
+                );
+            } else {
+                println!(
+                    "{name}: http://{ip}:{port}{} (MJPEG: http://{ip}:{port}{})",
+                    info.get_property_val_str("status").unwrap_or("/"),
+                    info.get_property_val_str("mjpeg").unwrap_or("/stream.mjpeg"),
+                );
+            }
+        }
+    }
+
+    daemon.shutdown().ok();
+    Ok(())
+}
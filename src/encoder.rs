@@ -0,0 +1,183 @@
+//! Video encoders used by the recorder.
+//!
+//! The recorder only talks to the [`Encoder`] trait, adding a format means adding an
+//! implementation and an [`EncoderKind`].
+
+mod ffmpeg;
+mod gif;
+mod lossless;
+mod png_sequence;
+
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::recorder::RecordingConfig;
+
+pub use self::ffmpeg::{add_chapters, remux, FfmpegEncoder};
+pub use self::gif::GifEncoder;
+pub use self::lossless::{verify, LosslessEncoder};
+pub use self::png_sequence::PngSequenceEncoder;
+
+/// ffmpeg filter padding frames to even sizes, which yuv420p needs.
+const EVEN_SIZE: &str = "pad=ceil(iw/2)*2:ceil(ih/2)*2";
+
+/// Fragmented MP4 with a keyframe, and so a fragment, every two seconds: if the process dies
+/// while recording, everything up to the last fragment stays playable.
+const FRAGMENTED_MP4: &[&str] = &[
+    "-force_key_frames",
+    "expr:gte(t,n_forced*2)",
+    "-movflags",
+    "+frag_keyframe+empty_moov+default_base_moof",
+];
+
+/// Size and rate of the frames pushed to an encoder.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VideoFormat {
+    pub width: u32,
+    pub height: u32,
+    pub fps: f32,
+}
+
+pub struct EncoderFrame<'a> {
+    /// Tightly packed BGRA8 pixels.
+    pub bgra: &'a [u8],
+
+    /// Presentation time relative to the start of the recording.
+    pub timestamp: Duration,
+
+    /// When the frame was captured relative to the start of the recording. Differs from
+    /// [`Self::timestamp`] for repeated frames and by the capture jitter.
+    pub captured_at: Duration,
+
+    /// Wall clock capture time in µs since the UNIX epoch, synchronized with the capture
+    /// daemon's clock when rendering one of another machine.
+    pub capture_time_us: u64,
+}
+
+pub trait Encoder: Send {
+    /// Starts writing to `path`, which doesn't exist yet.
+    fn open(&mut self, path: &Path, format: VideoFormat) -> anyhow::Result<()>;
+
+    /// Frames arrive in order, at a constant rate matching [`VideoFormat::fps`].
+    fn push_frame(&mut self, frame: &EncoderFrame<'_>) -> anyhow::Result<()>;
+
+    /// Flushes everything and closes the output. No frames are pushed afterwards.
+    fn finish(&mut self) -> anyhow::Result<()>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EncoderKind {
+    /// One PNG per frame in a directory.
+    PngSequence,
+
+    /// H.264 MP4 through the `ffmpeg` command line tool.
+    Ffmpeg,
+
+    /// Hardware H.264 through VideoToolbox, driven by the `ffmpeg` command line tool.
+    VideoToolbox,
+
+    Gif,
+
+    /// AV1 WebM through rav1e, driven by the `ffmpeg` command line tool. Slow, but free of
+    /// patent-encumbered codecs.
+    Av1,
+
+    /// Pixel exact FFV1 Matroska with a manifest of frame hashes, see [`verify`].
+    Lossless,
+}
+
+impl EncoderKind {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "png_sequence" => Some(Self::PngSequence),
+            "ffmpeg" => Some(Self::Ffmpeg),
+            "video_toolbox" => Some(Self::VideoToolbox),
+            "gif" => Some(Self::Gif),
+            "av1" => Some(Self::Av1),
+            "lossless" => Some(Self::Lossless),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::PngSequence => "png_sequence",
+            Self::Ffmpeg => "ffmpeg",
+            Self::VideoToolbox => "video_toolbox",
+            Self::Gif => "gif",
+            Self::Av1 => "av1",
+            Self::Lossless => "lossless",
+        }
+    }
+
+    /// Extension of the output, empty for directories.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::PngSequence => "",
+            Self::Ffmpeg | Self::VideoToolbox => "mp4",
+            Self::Gif => "gif",
+            Self::Av1 => "webm",
+            Self::Lossless => "mkv",
+        }
+    }
+
+    /// Whether a recording cut off by a crash can be finalized by remuxing it, see
+    /// [`remux`].
+    pub fn is_recoverable(self) -> bool {
+        match self {
+            Self::Ffmpeg | Self::VideoToolbox | Self::Av1 | Self::Lossless => true,
+            Self::PngSequence | Self::Gif => false,
+        }
+    }
+
+    /// Whether the output container can hold chapters, see [`add_chapters`].
+    pub fn supports_chapters(self) -> bool {
+        match self {
+            Self::Ffmpeg | Self::VideoToolbox | Self::Av1 | Self::Lossless => true,
+            Self::PngSequence | Self::Gif => false,
+        }
+    }
+
+    pub fn create(self, config: &RecordingConfig) -> Box<dyn Encoder> {
+        let ffmpeg_path = &config.ffmpeg_path;
+        match self {
+            Self::PngSequence => Box::new(PngSequenceEncoder::default()),
+            Self::Ffmpeg => Box::new(FfmpegEncoder::new(
+                ffmpeg_path,
+                &[
+                    &["-vf", EVEN_SIZE, "-c:v", "libx264", "-preset", "veryfast", "-pix_fmt", "yuv420p"][..],
+                    FRAGMENTED_MP4,
+                ]
+                .concat(),
+            )),
+            Self::VideoToolbox => Box::new(FfmpegEncoder::new(
+                ffmpeg_path,
+                &[
+                    &["-vf", EVEN_SIZE, "-c:v", "h264_videotoolbox", "-b:v", "12M", "-pix_fmt", "yuv420p"][..],
+                    FRAGMENTED_MP4,
+                ]
+                .concat(),
+            )),
+            Self::Gif => Box::new(GifEncoder::default()),
+            Self::Av1 => Box::new(FfmpegEncoder::new(
+                ffmpeg_path,
+                &[
+                    "-vf",
+                    EVEN_SIZE,
+                    "-c:v",
+                    "librav1e",
+                    "-speed",
+                    &config.av1_speed.min(10).to_string(),
+                    "-qp",
+                    &config.av1_quantizer.to_string(),
+                    "-pix_fmt",
+                    "yuv420p",
+                ],
+            )),
+            Self::Lossless => Box::new(LosslessEncoder::new(ffmpeg_path)),
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use std::fmt::Write as _;
+use std::io::{BufRead as _, BufReader, Write as _};
+use std::path::Path;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context as _;
+
+use super::{Encoder, EncoderFrame, VideoFormat};
+
+/// Pipes raw frames into an `ffmpeg` child process.
+pub struct FfmpegEncoder {
+    ffmpeg_path: String,
+
+    /// Output options (codec, quality, container flags) passed after the input.
+    output_args: Vec<String>,
+
+    process: Option<(Child, ChildStdin)>,
+
+    on_progress: Option<Arc<dyn Fn(&str, &str) + Send + Sync>>,
+}
+
+impl FfmpegEncoder {
+    pub fn new(ffmpeg_path: &str, output_args: &[&str]) -> Self {
+        Self {
+            ffmpeg_path: ffmpeg_path.to_owned(),
+            output_args: output_args.iter().map(|arg| (*arg).to_owned()).collect(),
+            process: None,
+            on_progress: None,
+        }
+    }
+
+    /// Calls `on_progress` with every key and value of ffmpeg's `-progress` report, e.g.
+    /// `bitrate` and `speed`, roughly twice a second.
+    pub fn with_progress(mut self, on_progress: impl Fn(&str, &str) + Send + Sync + 'static) -> Self {
+        self.on_progress = Some(Arc::new(on_progress));
+        self
+    }
+}
+
+impl Encoder for FfmpegEncoder {
+    fn open(&mut self, path: &Path, format: VideoFormat) -> anyhow::Result<()> {
+        let mut command = Command::new(&self.ffmpeg_path);
+        if self.on_progress.is_some() {
+            command
+                .args(["-nostats", "-progress", "pipe:1", "-stats_period", "0.5"])
+                .stdout(Stdio::piped());
+        }
+        let mut child = command
+            // The output file exists already, reserved by `OutputManager::path_for`.
+            .arg("-y")
+            .args(["-hide_banner", "-loglevel", "error", "-f", "rawvideo", "-pix_fmt", "bgra"])
+            .args(["-s", &format!("{}x{}", format.width, format.height)])
+            .args(["-framerate", &format.fps.to_string()])
+            .args(["-i", "-"])
+            .args(&self.output_args)
+            .arg(path)
+            .stdin(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("failed to run {:?}, is ffmpeg installed?", self.ffmpeg_path))?;
+        let stdin = child.stdin.take().context("ffmpeg has no stdin")?;
+        if let (Some(on_progress), Some(stdout)) = (self.on_progress.clone(), child.stdout.take()) {
+            std::thread::spawn(move || {
+                for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                    if let Some((key, value)) = line.split_once('=') {
+                        on_progress(key, value.trim());
+                    }
+                }
+            });
+        }
+        self.process = Some((child, stdin));
+        Ok(())
+    }
+
+    fn push_frame(&mut self, frame: &EncoderFrame<'_>) -> anyhow::Result<()> {
+        let (_, stdin) = self.process.as_mut().context("encoder not open")?;
+        stdin.write_all(frame.bgra).context("ffmpeg stopped accepting frames")
+    }
+
+    fn finish(&mut self) -> anyhow::Result<()> {
+        let (mut child, stdin) = self.process.take().context("encoder not open")?;
+        // Closing stdin makes ffmpeg finalize the file.
+        drop(stdin);
+        let status = child.wait().context("failed to wait for ffmpeg")?;
+        anyhow::ensure!(status.success(), "ffmpeg exited with {status}");
+        Ok(())
+    }
+}
+
+/// Adds chapters starting at the given times to the finished recording at `path` by
+/// remuxing it, the last chapter ends at `duration`.
+pub fn add_chapters(
+    ffmpeg_path: &str,
+    path: &Path,
+    chapters: &[(&str, Duration)],
+    duration: Duration,
+) -> anyhow::Result<()> {
+    let mut metadata = String::from(";FFMETADATA1\n");
+    for (i, (title, start)) in chapters.iter().enumerate() {
+        let end = chapters.get(i + 1).map_or(duration, |(_, next)| *next).max(*start);
+        let title = title.chars().fold(String::new(), |mut escaped, c| {
+            if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+            escaped
+        });
+        write!(
+            metadata,
+            "[CHAPTER]\nTIMEBASE=1/1000\nSTART={}\nEND={}\ntitle={title}\n",
+            start.as_millis(),
+            end.as_millis()
+        )
+        .unwrap();
+    }
+
+    let metadata_path = path.with_extension("chapters.txt");
+    std::fs::write(&metadata_path, metadata)
+        .with_context(|| format!("failed to write {}", metadata_path.display()))?;
+    let extension = path.extension().unwrap_or_default().to_string_lossy();
+    let remuxed = path.with_extension(format!("chapters.{extension}"));
+    let status = Command::new(ffmpeg_path)
+        .args(["-hide_banner", "-loglevel", "error", "-i"])
+        .arg(path)
+        .arg("-i")
+        .arg(&metadata_path)
+        .args(["-map", "0", "-map_metadata", "1", "-map_chapters", "1", "-c", "copy"])
+        .arg(&remuxed)
+        .status()
+        .with_context(|| format!("failed to run {ffmpeg_path:?}"));
+    std::fs::remove_file(&metadata_path).ok();
+    let status = status?;
+    anyhow::ensure!(status.success(), "adding chapters failed, ffmpeg exited with {status}");
+    std::fs::rename(&remuxed, path)
+        .with_context(|| format!("failed to replace {}", path.display()))
+}
+
+/// Rewrites the recording at `path` without re-encoding, which finalizes the container of a
+/// recording that was cut off. Frames after the last complete fragment or cluster are lost.
+pub fn remux(ffmpeg_path: &str, path: &Path) -> anyhow::Result<()> {
+    let extension = path.extension().unwrap_or_default().to_string_lossy();
+    let remuxed = path.with_extension(format!("recovered.{extension}"));
+    let status = Command::new(ffmpeg_path)
+        .args(["-hide_banner", "-loglevel", "error", "-err_detect", "ignore_err", "-i"])
+        .arg(path)
+        .args(["-map", "0", "-c", "copy"])
+        .arg(&remuxed)
+        .status()
+        .with_context(|| format!("failed to run {ffmpeg_path:?}"))?;
+    if !status.success() {
+        std::fs::remove_file(&remuxed).ok();
+        anyhow::bail!("remuxing failed, ffmpeg exited with {status}");
+    }
+    std::fs::rename(&remuxed, path)
+        .with_context(|| format!("failed to replace {}", path.display()))
+}
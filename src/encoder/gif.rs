@@ -0,0 +1,63 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::Context as _;
+use image::codecs::gif::{GifEncoder as ImageGifEncoder, Repeat};
+
+use super::{Encoder, EncoderFrame, VideoFormat};
+
+/// Longest side of the GIF; quantizing full resolution frames is far too slow.
+const MAX_SIZE: u32 = 640;
+
+/// Highest frame rate of the GIF, pushed frames above it are skipped.
+const MAX_FPS: f32 = 15.0;
+
+/// Looping animated GIF, downscaled to [`MAX_SIZE`] at up to [`MAX_FPS`].
+#[derive(Default)]
+pub struct GifEncoder {
+    encoder: Option<(ImageGifEncoder<BufWriter<File>>, VideoFormat)>,
+    next_timestamp: Duration,
+}
+
+impl Encoder for GifEncoder {
+    fn open(&mut self, path: &Path, format: VideoFormat) -> anyhow::Result<()> {
+        let file =
+            File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+        let mut encoder = ImageGifEncoder::new(BufWriter::new(file));
+        encoder.set_repeat(Repeat::Infinite)?;
+        self.encoder = Some((encoder, format));
+        self.next_timestamp = Duration::ZERO;
+        Ok(())
+    }
+
+    fn push_frame(&mut self, frame: &EncoderFrame<'_>) -> anyhow::Result<()> {
+        let (encoder, format) = self.encoder.as_mut().context("encoder not open")?;
+        if frame.timestamp < self.next_timestamp {
+            return Ok(());
+        }
+        let frame_interval = Duration::from_secs_f32(1.0 / format.fps.min(MAX_FPS));
+        self.next_timestamp = frame.timestamp + frame_interval;
+
+        let step = format.width.max(format.height).div_ceil(MAX_SIZE).max(1);
+        let width = format.width.div_ceil(step);
+        let height = format.height.div_ceil(step);
+        let image = image::RgbaImage::from_fn(width, height, |x, y| {
+            let offset = (((y * step) * format.width + x * step) * 4) as usize;
+            let pixel = &frame.bgra[offset..offset + 4];
+            image::Rgba([pixel[2], pixel[1], pixel[0], 255])
+        });
+
+        let delay = image::Delay::from_saturating_duration(frame_interval);
+        encoder
+            .encode_frame(image::Frame::from_parts(image, 0, 0, delay))
+            .context("failed to encode GIF frame")
+    }
+
+    fn finish(&mut self) -> anyhow::Result<()> {
+        // The trailer is written when the encoder is dropped.
+        self.encoder.take().context("encoder not open")?;
+        Ok(())
+    }
+}
@@ -0,0 +1,141 @@
+use std::io::Read as _;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+
+use super::{Encoder, EncoderFrame, FfmpegEncoder, VideoFormat};
+
+/// Sidecar written next to every lossless recording.
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    width: u32,
+    height: u32,
+    fps: f32,
+    frames: Vec<ManifestFrame>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ManifestFrame {
+    /// Presentation time in the recording.
+    timestamp_us: u64,
+
+    /// When the frame was captured, both relative to the start of the recording.
+    captured_us: u64,
+
+    /// Wall clock capture time in µs since the UNIX epoch, for aligning recordings of
+    /// several machines.
+    capture_time_us: u64,
+
+    /// BLAKE3 of the BGRA pixels as they were captured.
+    blake3: String,
+}
+
+/// FFV1 in Matroska, pixel exact BGRA. Hashes every frame into a manifest that
+/// [`verify`] checks the decoded recording against.
+pub struct LosslessEncoder {
+    inner: FfmpegEncoder,
+    manifest: Option<(PathBuf, Manifest)>,
+}
+
+impl LosslessEncoder {
+    pub fn new(ffmpeg_path: &str) -> Self {
+        Self {
+            inner: FfmpegEncoder::new(
+                ffmpeg_path,
+                &["-c:v", "ffv1", "-level", "3", "-g", "1", "-pix_fmt", "bgra"],
+            ),
+            manifest: None,
+        }
+    }
+}
+
+impl Encoder for LosslessEncoder {
+    fn open(&mut self, path: &Path, format: VideoFormat) -> anyhow::Result<()> {
+        self.inner.open(path, format)?;
+        let manifest = Manifest {
+            width: format.width,
+            height: format.height,
+            fps: format.fps,
+            frames: Vec::new(),
+        };
+        self.manifest = Some((manifest_path(path), manifest));
+        Ok(())
+    }
+
+    fn push_frame(&mut self, frame: &EncoderFrame<'_>) -> anyhow::Result<()> {
+        let (_, manifest) = self.manifest.as_mut().context("encoder not open")?;
+        manifest.frames.push(ManifestFrame {
+            timestamp_us: frame.timestamp.as_micros() as u64,
+            captured_us: frame.captured_at.as_micros() as u64,
+            capture_time_us: frame.capture_time_us,
+            blake3: blake3::hash(frame.bgra).to_hex().to_string(),
+        });
+        self.inner.push_frame(frame)
+    }
+
+    fn finish(&mut self) -> anyhow::Result<()> {
+        self.inner.finish()?;
+        let (path, manifest) = self.manifest.take().context("encoder not open")?;
+        let json = serde_json::to_vec_pretty(&manifest)?;
+        std::fs::write(&path, json).with_context(|| format!("failed to write {}", path.display()))
+    }
+}
+
+/// `recording.mkv` → `recording.manifest.json`
+fn manifest_path(recording: &Path) -> PathBuf {
+    recording.with_extension("manifest.json")
+}
+
+/// Decodes the lossless recording at `path` and compares every frame with the hashes
+/// recorded in its manifest.
+pub fn verify(path: &Path, ffmpeg_path: &str) -> anyhow::Result<()> {
+    let manifest_path = manifest_path(path);
+    let manifest: Manifest = serde_json::from_slice(
+        &std::fs::read(&manifest_path)
+            .with_context(|| format!("failed to read {}", manifest_path.display()))?,
+    )
+    .with_context(|| format!("failed to parse {}", manifest_path.display()))?;
+
+    let mut child = Command::new(ffmpeg_path)
+        .args(["-hide_banner", "-loglevel", "error", "-i"])
+        .arg(path)
+        .args(["-f", "rawvideo", "-pix_fmt", "bgra", "-"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to run {ffmpeg_path:?}, is ffmpeg installed?"))?;
+    let mut stdout = child.stdout.take().context("ffmpeg has no stdout")?;
+
+    let mut pixels = vec![0u8; manifest.width as usize * manifest.height as usize * 4];
+    let mut decoded = 0;
+    let mut mismatches = 0;
+    while stdout.read_exact(&mut pixels).is_ok() {
+        match manifest.frames.get(decoded) {
+            Some(expected) if blake3::hash(&pixels).to_hex().as_str() == expected.blake3 => {}
+            Some(expected) => {
+                mismatches += 1;
+                eprintln!(
+                    "Frame {decoded} at {:.3}s doesn't match the captured frame",
+                    expected.timestamp_us as f64 / 1e6
+                );
+            }
+            None => {}
+        }
+        decoded += 1;
+    }
+    let status = child.wait().context("failed to wait for ffmpeg")?;
+    anyhow::ensure!(status.success(), "ffmpeg exited with {status}");
+
+    anyhow::ensure!(
+        decoded == manifest.frames.len(),
+        "recording has {decoded} frames, the manifest {}",
+        manifest.frames.len()
+    );
+    anyhow::ensure!(
+        mismatches == 0,
+        "{mismatches} of {decoded} frames don't match"
+    );
+    println!("All {decoded} frames of {} match", path.display());
+    Ok(())
+}
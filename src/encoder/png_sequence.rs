@@ -0,0 +1,42 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+
+use super::{Encoder, EncoderFrame, VideoFormat};
+
+/// Writes `frame_000001.png`, `frame_000002.png`, … into a directory.
+#[derive(Default)]
+pub struct PngSequenceEncoder {
+    directory: PathBuf,
+    format: Option<VideoFormat>,
+    frame_count: u64,
+}
+
+impl Encoder for PngSequenceEncoder {
+    fn open(&mut self, path: &Path, format: VideoFormat) -> anyhow::Result<()> {
+        std::fs::create_dir_all(path)
+            .with_context(|| format!("failed to create {}", path.display()))?;
+        self.directory = path.to_owned();
+        self.format = Some(format);
+        self.frame_count = 0;
+        Ok(())
+    }
+
+    fn push_frame(&mut self, frame: &EncoderFrame<'_>) -> anyhow::Result<()> {
+        let format = self.format.context("encoder not open")?;
+        self.frame_count += 1;
+        let rgba = frame
+            .bgra
+            .chunks_exact(4)
+            .flat_map(|pixel| [pixel[2], pixel[1], pixel[0], pixel[3]])
+            .collect::<Vec<_>>();
+        let path = self.directory.join(format!("frame_{:06}.png", self.frame_count));
+        image::save_buffer(&path, &rgba, format.width, format.height, image::ColorType::Rgba8)
+            .with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    fn finish(&mut self) -> anyhow::Result<()> {
+        self.format = None;
+        Ok(())
+    }
+}
@@ -0,0 +1,103 @@
+//! Log of noteworthy things happening in the session, e.g. a watch region triggering.
+//!
+//! Events are kept for the control API (`GET /api/events`) and handed to the configured
+//! [`notifications`] backends.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::notifications::{self, Notifier as _, SystemNotification};
+use crate::{capture, webhook};
+
+/// Events kept for the control API.
+const MAX_EVENTS: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Event {
+    /// Increasing, so clients can ask for the events after the last one they saw.
+    pub id: u64,
+
+    /// Wall clock time in µs since the UNIX epoch.
+    pub time_us: u64,
+    pub severity: Severity,
+
+    /// What happened, e.g. `text_changed`.
+    pub kind: &'static str,
+
+    /// What it happened to, e.g. the name of a watch region.
+    pub source: String,
+    pub message: String,
+}
+
+/// Where an event goes besides the configured backends, configured per event source.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Delivery {
+    /// Show a system notification.
+    pub notify: bool,
+
+    /// `http://` URL the event is POSTed to as JSON.
+    pub webhook: Option<String>,
+}
+
+#[derive(Default)]
+struct Log {
+    next_id: u64,
+    events: VecDeque<Event>,
+}
+
+static LOG: Lazy<Mutex<Log>> = Lazy::new(Default::default);
+
+/// Records an event, notifies about it and returns it, e.g. for a [`Delivery`].
+pub fn emit(severity: Severity, kind: &'static str, source: &str, message: &str) -> Event {
+    let mut log = LOG.lock().unwrap();
+    let event = Event {
+        id: log.next_id,
+        time_us: capture::now_us(),
+        severity,
+        kind,
+        source: source.to_owned(),
+        message: message.to_owned(),
+    };
+    log.next_id += 1;
+    if log.events.len() == MAX_EVENTS {
+        log.events.pop_front();
+    }
+    log.events.push_back(event.clone());
+    drop(log);
+    notifications::dispatch(&event);
+    event
+}
+
+/// Kept events with an id above `after`, oldest first.
+pub fn since(after: Option<u64>) -> Vec<Event> {
+    let first = after.map_or(0, |after| after + 1);
+    let log = LOG.lock().unwrap();
+    log.events
+        .iter()
+        .filter(|event| event.id >= first)
+        .cloned()
+        .collect()
+}
+
+/// Hands `event` on as configured in `delivery`.
+pub fn deliver(event: &Event, delivery: &Delivery) {
+    if delivery.notify {
+        // Explicitly asked for, whatever the severity.
+        SystemNotification { min_severity: Severity::Info }.notify(event);
+    }
+    if let Some(url) = &delivery.webhook {
+        webhook::post_in_background(url, event);
+    }
+}
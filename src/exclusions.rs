@@ -0,0 +1,101 @@
+//! Windows kept out of display captures: the example's own window, which would otherwise
+//! show itself over and over like a hall of mirrors, and windows with sensitive content.
+//!
+//! The own window is content protected, so the window server leaves it out and captures
+//! show what's behind it. crabgrab has no way to exclude other windows from a display
+//! stream, so those are found by title once a second and blacked out in every frame,
+//! covering whatever is in front of them as well.
+
+use std::sync::{Mutex, Once};
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+
+use crate::capture::{self, Frame};
+use crate::config;
+use crate::focus::ScreenRect;
+
+/// How often the excluded windows are looked up, they move and resize.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ExcludeConfig {
+    /// Keep the example's own window out of display captures.
+    pub own_window: bool,
+
+    /// Titles (or parts of them) of windows blacked out in display captures.
+    pub windows: Vec<String>,
+}
+
+/// Where the excluded windows are, in global screen points.
+static EXCLUDED: Lazy<Mutex<Vec<ScreenRect>>> = Lazy::new(Default::default);
+
+static WATCHING: Once = Once::new();
+
+/// Starts looking up the windows of `[exclude] windows` on a background thread, once.
+pub fn watch() {
+    let titles = config::get().exclude.windows.clone();
+    if titles.is_empty() {
+        return;
+    }
+    WATCHING.call_once(|| {
+        std::thread::Builder::new()
+            .name("excluded windows".to_owned())
+            .spawn(move || loop {
+                let matches = |window: &crabgrab::prelude::CapturableWindow| {
+                    let title = window.title();
+                    titles.iter().any(|part| title.contains(part.as_str()))
+                };
+                match pollster::block_on(capture::find_windows(matches)) {
+                    Ok(windows) => {
+                        *EXCLUDED.lock().unwrap() = windows
+                            .iter()
+                            .map(|window| {
+                                let rect = window.rect();
+                                ScreenRect {
+                                    min: glam::vec2(rect.origin.x as f32, rect.origin.y as f32),
+                                    size: glam::vec2(
+                                        rect.size.width as f32,
+                                        rect.size.height as f32,
+                                    ),
+                                }
+                            })
+                            .collect();
+                    }
+                    Err(err) => eprintln!("Looking up the excluded windows: {err:#}"),
+                }
+                std::thread::sleep(POLL_INTERVAL);
+            })
+            .expect("Failed to spawn the excluded windows thread");
+    });
+}
+
+/// Blacks out the excluded windows in `frame` of a display.
+pub fn mask(frame: &mut Frame) {
+    let excluded = EXCLUDED.lock().unwrap();
+    if excluded.is_empty() {
+        return;
+    }
+    let bitmap = &mut frame.frame_bitmap;
+    let (width, height) = (bitmap.width, bitmap.height);
+    let source = frame.source_rect;
+    let pixels_per_point = glam::vec2(width as f32, height as f32) / source.size;
+    for rect in excluded.iter() {
+        let min = ((rect.min - source.min) * pixels_per_point).floor().max(glam::Vec2::ZERO);
+        let max = ((rect.min + rect.size - source.min) * pixels_per_point)
+            .ceil()
+            .min(glam::vec2(width as f32, height as f32));
+        if min.cmpge(max).any() {
+            continue;
+        }
+        let (x0, x1) = (min.x as usize, max.x as usize);
+        for y in min.y as usize..max.y as usize {
+            bitmap.data[y * width + x0..y * width + x1].fill([0, 0, 0, 255]);
+            if let Some(hdr_data) = &mut frame.hdr_data {
+                hdr_data[y * width + x0..y * width + x1].fill(0b11 << 30);
+            }
+        }
+    }
+}
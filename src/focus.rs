@@ -0,0 +1,268 @@
+//! Tracks the bounds of the focused UI element through the platform accessibility API,
+//! so it can be highlighted on top of the capture. Also hit-tests elements for
+//! [`crate::pinned_element`].
+//!
+//! Only implemented for macOS behind the `focus-highlight` feature; everywhere else the
+//! tracker never reports a focused element.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+
+/// How often the accessibility tree is queried. Each query is an IPC round trip to the
+/// focused application, so this is kept off the render thread and well below frame rate.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Whether the accessibility API can be used, checked (and the missing access reported)
+/// once: hit tests run on every click.
+static IS_SUPPORTED: Lazy<bool> = Lazy::new(platform::is_supported);
+
+/// Rect in global screen coordinates (points, origin top left of the main display).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScreenRect {
+    pub min: glam::Vec2,
+    pub size: glam::Vec2,
+}
+
+/// A UI element found by [`element_at`]. It stays the same element while its window moves
+/// or resizes, [`Element::bounds`] follows it.
+pub use platform::Element;
+
+/// The UI element at `point` in global screen points, or its ancestor `levels_up` levels
+/// above it, e.g. to get from a label to the panel containing it.
+pub fn element_at(point: glam::Vec2, levels_up: u32) -> Option<Element> {
+    if !*IS_SUPPORTED {
+        return None;
+    }
+    platform::element_at(point, levels_up)
+}
+
+#[derive(Clone, Default)]
+pub struct FocusTracker {
+    focused: Arc<Mutex<Option<ScreenRect>>>,
+}
+
+impl FocusTracker {
+    /// Starts polling the focused element in the background.
+    pub fn start() -> Self {
+        let tracker = Self::default();
+        if !*IS_SUPPORTED {
+            eprintln!("Focus highlighting is not available in this build");
+            return tracker;
+        }
+
+        let focused = tracker.focused.clone();
+        std::thread::Builder::new()
+            .name("focus tracker".to_owned())
+            .spawn(move || loop {
+                *focused.lock().unwrap() = platform::focused_element_bounds();
+                std::thread::sleep(POLL_INTERVAL);
+            })
+            .expect("Failed to spawn focus tracker thread");
+        tracker
+    }
+
+    pub fn focused(&self) -> Option<ScreenRect> {
+        *self.focused.lock().unwrap()
+    }
+}
+
+#[cfg(all(feature = "focus-highlight", target_os = "macos"))]
+mod platform {
+    use std::ffi::{c_char, c_void, CStr};
+
+    use super::ScreenRect;
+
+    type CFTypeRef = *const c_void;
+    type CFStringRef = *const c_void;
+    type AXUIElementRef = *const c_void;
+
+    const K_CF_STRING_ENCODING_UTF8: u32 = 0x0800_0100;
+    const K_AX_ERROR_SUCCESS: i32 = 0;
+    const K_AX_VALUE_CG_POINT_TYPE: u32 = 1;
+    const K_AX_VALUE_CG_SIZE_TYPE: u32 = 2;
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct CGPoint {
+        x: f64,
+        y: f64,
+    }
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct CGSize {
+        width: f64,
+        height: f64,
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFStringCreateWithCString(
+            alloc: *const c_void,
+            c_str: *const c_char,
+            encoding: u32,
+        ) -> CFStringRef;
+        fn CFRelease(cf: CFTypeRef);
+    }
+
+    #[link(name = "ApplicationServices", kind = "framework")]
+    extern "C" {
+        fn AXIsProcessTrusted() -> u8;
+        fn AXUIElementCreateSystemWide() -> AXUIElementRef;
+        fn AXUIElementCopyElementAtPosition(
+            application: AXUIElementRef,
+            x: f32,
+            y: f32,
+            element: *mut AXUIElementRef,
+        ) -> i32;
+        fn AXUIElementCopyAttributeValue(
+            element: AXUIElementRef,
+            attribute: CFStringRef,
+            value: *mut CFTypeRef,
+        ) -> i32;
+        fn AXValueGetValue(value: CFTypeRef, value_type: u32, value_ptr: *mut c_void) -> u8;
+    }
+
+    pub fn is_supported() -> bool {
+        // SAFETY: no preconditions.
+        let trusted = unsafe { AXIsProcessTrusted() } != 0;
+        if !trusted {
+            eprintln!(
+                "Focus highlighting and element pinning need accessibility access, grant it in \
+                 System Settings > Privacy & Security > Accessibility"
+            );
+        }
+        trusted
+    }
+
+    pub fn focused_element_bounds() -> Option<ScreenRect> {
+        // SAFETY: every object we create or copy is released exactly once.
+        unsafe {
+            let system = AXUIElementCreateSystemWide();
+            let focused = copy_attribute(system, c"AXFocusedUIElement");
+            CFRelease(system);
+            let focused = focused?;
+            let bounds = bounds(focused);
+            CFRelease(focused);
+            bounds
+        }
+    }
+
+    pub struct Element(AXUIElementRef);
+
+    // SAFETY: accessibility elements are CoreFoundation objects, which may be queried and
+    // released from any thread.
+    unsafe impl Send for Element {}
+
+    impl Element {
+        pub fn bounds(&self) -> Option<ScreenRect> {
+            // SAFETY: `self.0` stays valid until dropped.
+            unsafe { bounds(self.0) }
+        }
+    }
+
+    impl Drop for Element {
+        fn drop(&mut self) {
+            // SAFETY: `Element` owns one reference.
+            unsafe { CFRelease(self.0) }
+        }
+    }
+
+    pub fn element_at(point: glam::Vec2, levels_up: u32) -> Option<Element> {
+        // SAFETY: every object we create or copy is released exactly once, the returned
+        // element owns the last one.
+        unsafe {
+            let system = AXUIElementCreateSystemWide();
+            let mut element: AXUIElementRef = std::ptr::null();
+            let error = AXUIElementCopyElementAtPosition(system, point.x, point.y, &mut element);
+            CFRelease(system);
+            if error != K_AX_ERROR_SUCCESS || element.is_null() {
+                return None;
+            }
+            for _ in 0..levels_up {
+                let Some(parent) = copy_attribute(element, c"AXParent") else {
+                    break;
+                };
+                CFRelease(element);
+                element = parent;
+            }
+            Some(Element(element))
+        }
+    }
+
+    /// Position and size of `element`, values are only read through `AXValueGetValue` with
+    /// the matching struct layout.
+    unsafe fn bounds(element: AXUIElementRef) -> Option<ScreenRect> {
+        let position = copy_attribute(element, c"AXPosition");
+        let size = copy_attribute(element, c"AXSize");
+
+        let mut point = CGPoint::default();
+        let mut extent = CGSize::default();
+        let valid = match (position, size) {
+            (Some(position), Some(size)) => {
+                let valid = AXValueGetValue(
+                    position,
+                    K_AX_VALUE_CG_POINT_TYPE,
+                    (&mut point as *mut CGPoint).cast(),
+                ) != 0
+                    && AXValueGetValue(
+                        size,
+                        K_AX_VALUE_CG_SIZE_TYPE,
+                        (&mut extent as *mut CGSize).cast(),
+                    ) != 0;
+                CFRelease(position);
+                CFRelease(size);
+                valid
+            }
+            (position, size) => {
+                position.into_iter().chain(size).for_each(|value| CFRelease(value));
+                false
+            }
+        };
+
+        valid.then(|| ScreenRect {
+            min: glam::vec2(point.x as f32, point.y as f32),
+            size: glam::vec2(extent.width as f32, extent.height as f32),
+        })
+    }
+
+    /// Copies `attribute` of `element`, the caller has to release the result.
+    unsafe fn copy_attribute(element: AXUIElementRef, attribute: &CStr) -> Option<CFTypeRef> {
+        let name = CFStringCreateWithCString(
+            std::ptr::null(),
+            attribute.as_ptr(),
+            K_CF_STRING_ENCODING_UTF8,
+        );
+        let mut value: CFTypeRef = std::ptr::null();
+        let error = AXUIElementCopyAttributeValue(element, name, &mut value);
+        CFRelease(name);
+        (error == K_AX_ERROR_SUCCESS && !value.is_null()).then_some(value)
+    }
+}
+
+#[cfg(not(all(feature = "focus-highlight", target_os = "macos")))]
+mod platform {
+    use super::ScreenRect;
+
+    pub fn is_supported() -> bool {
+        false
+    }
+
+    pub fn focused_element_bounds() -> Option<ScreenRect> {
+        None
+    }
+
+    pub enum Element {}
+
+    impl Element {
+        pub fn bounds(&self) -> Option<ScreenRect> {
+            match *self {}
+        }
+    }
+
+    pub fn element_at(_point: glam::Vec2, _levels_up: u32) -> Option<Element> {
+        None
+    }
+}
@@ -0,0 +1,56 @@
+//! Hands captured frames from a capture source to the renderer.
+//!
+//! Only the latest frame matters: a new frame replaces one the renderer hasn't picked up
+//! yet, so a slow draw never holds up capturing and never works on a stale frame. Every
+//! source gets its own channel, nothing is shared between them.
+
+use std::sync::Arc;
+
+use crossbeam_channel::{Receiver, Sender, TrySendError};
+
+use crate::capture::Frame;
+
+/// A channel holding at most one frame.
+pub fn channel() -> (FrameSender, FrameReceiver) {
+    let (sender, receiver) = crossbeam_channel::bounded(1);
+    let sender = FrameSender {
+        sender,
+        stale: receiver.clone(),
+    };
+    (sender, FrameReceiver { receiver })
+}
+
+#[derive(Clone)]
+pub struct FrameSender {
+    sender: Sender<Arc<Frame>>,
+
+    /// For taking out the frame the renderer didn't pick up in time.
+    stale: Receiver<Arc<Frame>>,
+}
+
+impl FrameSender {
+    /// Sends `frame`, replacing the one still waiting.
+    pub fn send(&self, frame: Frame) {
+        let mut frame = Arc::new(frame);
+        loop {
+            match self.sender.try_send(frame) {
+                Ok(()) | Err(TrySendError::Disconnected(_)) => return,
+                Err(TrySendError::Full(rejected)) => {
+                    self.stale.try_recv().ok();
+                    frame = rejected;
+                }
+            }
+        }
+    }
+}
+
+pub struct FrameReceiver {
+    receiver: Receiver<Arc<Frame>>,
+}
+
+impl FrameReceiver {
+    /// The frame sent since the last call, if any.
+    pub fn latest(&self) -> Option<Arc<Frame>> {
+        self.receiver.try_iter().last()
+    }
+}
@@ -0,0 +1,212 @@
+//! The latest captured frames, for scrubbing back through them with the arrow keys while
+//! capture goes on: `Left` shows the frame before the one shown, `Right` the one after,
+//! and stepping past the newest goes back to the live capture.
+//!
+//! Off by default. With `[history] frames` set, every frame the renderer picks up is kept
+//! as it was captured, until there are more than `frames` or they take more than `max_mb`
+//! together. The recorder and the live stream keep getting the live frames while scrubbing.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+use crate::capture::Frame;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct HistoryConfig {
+    /// Frames kept at most, none (the default) turns scrubbing off.
+    pub frames: usize,
+
+    /// Memory the kept frames may take together, in MB.
+    pub max_mb: f32,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            frames: 0,
+            max_mb: 256.0,
+        }
+    }
+}
+
+pub struct FrameHistory {
+    frames: VecDeque<Arc<Frame>>,
+    bytes: usize,
+    max_frames: usize,
+    max_bytes: usize,
+
+    /// Id of the frame shown while scrubbing, `None` while live.
+    scrubbed: Option<u64>,
+}
+
+impl FrameHistory {
+    pub fn new(config: &HistoryConfig) -> Self {
+        Self {
+            frames: VecDeque::new(),
+            bytes: 0,
+            max_frames: config.frames,
+            max_bytes: (config.max_mb.max(0.0) * 1e6) as usize,
+            scrubbed: None,
+        }
+    }
+
+    /// Keeps `frame` as the newest, dropping the oldest ones beyond the limits.
+    pub fn push(&mut self, frame: Arc<Frame>) {
+        if self.max_frames == 0 {
+            return;
+        }
+        // Another source starts over, its frame ids don't go on from the old ones.
+        if self.frames.back().is_some_and(|newest| frame.frame_id <= newest.frame_id) {
+            self.frames.clear();
+            self.bytes = 0;
+            self.scrubbed = None;
+        }
+        self.bytes += frame_bytes(&frame);
+        self.frames.push_back(frame);
+        while self.frames.len() > self.max_frames
+            || (self.bytes > self.max_bytes && self.frames.len() > 1)
+        {
+            let oldest = self.frames.pop_front().unwrap();
+            self.bytes -= frame_bytes(&oldest);
+        }
+    }
+
+    pub fn is_scrubbing(&self) -> bool {
+        self.scrubbed.is_some()
+    }
+
+    /// Steps `by` frames, backwards if negative, from the one shown, and returns the frame
+    /// to show. Stepping forward to the newest goes back to live. `None` without frames.
+    pub fn scrub(&mut self, by: isize) -> Option<Arc<Frame>> {
+        let newest = self.frames.len().checked_sub(1)?;
+        let index = self.shown_index().unwrap_or(newest);
+        let target = index.saturating_add_signed(by).min(newest);
+        let frame = self.frames[target].clone();
+        self.scrubbed = (by <= 0 || target < newest).then_some(frame.frame_id);
+        Some(frame)
+    }
+
+    /// How many frames the shown one is behind the newest and how many are kept, while
+    /// scrubbing.
+    pub fn position(&self) -> Option<(usize, usize)> {
+        let index = self.shown_index()?;
+        Some((self.frames.len() - 1 - index, self.frames.len()))
+    }
+
+    /// Index of the frame shown while scrubbing, the oldest if it was dropped since.
+    fn shown_index(&self) -> Option<usize> {
+        let scrubbed = self.scrubbed?;
+        Some(self.frames.iter().position(|frame| frame.frame_id >= scrubbed).unwrap_or(0))
+    }
+}
+
+fn frame_bytes(frame: &Frame) -> usize {
+    let pixels = frame.frame_bitmap.data.len();
+    let hdr_pixels = frame.hdr_data.as_ref().map_or(0, |hdr_data| hdr_data.len());
+    (pixels + hdr_pixels) * 4
+}
+
+#[cfg(test)]
+mod tests {
+    use crabgrab::prelude::FrameBitmapBgraUnorm8x4;
+
+    use super::*;
+    use crate::focus::ScreenRect;
+
+    /// A single pixel frame, 4 bytes.
+    fn frame(frame_id: u64) -> Arc<Frame> {
+        Arc::new(Frame {
+            frame_bitmap: FrameBitmapBgraUnorm8x4 {
+                data: vec![[0, 0, 0, 255]].into(),
+                width: 1,
+                height: 1,
+            },
+            frame_id,
+            capture_time_us: 0,
+            source_rect: ScreenRect {
+                min: glam::Vec2::ZERO,
+                size: glam::Vec2::ONE,
+            },
+            ycbcr: None,
+            hdr_data: None,
+        })
+    }
+
+    fn with_frames(frames: usize, max_mb: f32, ids: impl IntoIterator<Item = u64>) -> FrameHistory {
+        let mut history = FrameHistory::new(&HistoryConfig { frames, max_mb });
+        for id in ids {
+            history.push(frame(id));
+        }
+        history
+    }
+
+    fn scrub(history: &mut FrameHistory, by: isize) -> Option<u64> {
+        history.scrub(by).map(|frame| frame.frame_id)
+    }
+
+    #[test]
+    fn scrubbing_steps_through_the_kept_frames() {
+        let mut history = with_frames(10, 100.0, 1..=5);
+        assert_eq!(history.position(), None);
+        assert_eq!(scrub(&mut history, -1), Some(4));
+        assert_eq!(history.position(), Some((1, 5)));
+        assert_eq!(scrub(&mut history, -2), Some(2));
+        assert_eq!(history.position(), Some((3, 5)));
+        assert_eq!(scrub(&mut history, 1), Some(3));
+        assert!(history.is_scrubbing());
+    }
+
+    #[test]
+    fn stepping_past_the_newest_goes_live() {
+        let mut history = with_frames(10, 100.0, 1..=5);
+        scrub(&mut history, -2);
+        assert_eq!(scrub(&mut history, 5), Some(5));
+        assert!(!history.is_scrubbing());
+        assert_eq!(history.position(), None);
+    }
+
+    #[test]
+    fn scrubbing_stops_at_the_oldest_frame() {
+        let mut history = with_frames(10, 100.0, 1..=5);
+        assert_eq!(scrub(&mut history, -10), Some(1));
+        assert_eq!(history.position(), Some((4, 5)));
+    }
+
+    #[test]
+    fn frames_beyond_the_limits_are_dropped() {
+        let mut history = with_frames(3, 100.0, 1..=5);
+        assert_eq!(scrub(&mut history, -10), Some(3));
+
+        // 10 bytes fit two frames.
+        let mut history = with_frames(10, 0.00001, 1..=5);
+        assert_eq!(scrub(&mut history, -10), Some(4));
+    }
+
+    #[test]
+    fn a_dropped_shown_frame_moves_to_the_oldest() {
+        let mut history = with_frames(3, 100.0, 1..=3);
+        assert_eq!(scrub(&mut history, -2), Some(1));
+        history.push(frame(4));
+        assert_eq!(history.position(), Some((2, 3)));
+    }
+
+    #[test]
+    fn a_restarted_source_clears_the_history() {
+        let mut history = with_frames(10, 100.0, 1..=5);
+        scrub(&mut history, -1);
+        history.push(frame(1));
+        assert!(!history.is_scrubbing());
+        assert_eq!(scrub(&mut history, -1), Some(1));
+        assert_eq!(history.position(), Some((0, 1)));
+    }
+
+    #[test]
+    fn scrubbing_without_frames_shows_nothing() {
+        let mut history = with_frames(10, 100.0, []);
+        assert_eq!(scrub(&mut history, -1), None);
+        assert!(!history.is_scrubbing());
+    }
+}
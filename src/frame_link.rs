@@ -0,0 +1,579 @@
+//! Two-process mode: a capture daemon that owns the screen recording permission and the
+//! capture stream, and renderer clients that receive its frames over a UNIX socket, or TCP
+//! for renderers on other machines (`tcp://host:port` endpoints). Platforms without UNIX
+//! sockets only have the TCP endpoints.
+//!
+//! A renderer crashing or restarting doesn't end the capture session, and clients
+//! reconnect on their own when the daemon restarts.
+//!
+//! Clients start with a hello carrying `[frame_link] token`, and the daemon drops clients
+//! whose token doesn't match its own. Serving TCP on other than a loopback address needs a
+//! token. Frames aren't encrypted, across untrusted networks tunnel the endpoint (SSH,
+//! VPN).
+//!
+//! Clients estimate the offset between their clock and the daemon's NTP style: they
+//! periodically send a ping with their time, the daemon answers with its receive and send
+//! times, and the sample with the shortest round trip of the recent ones wins. Capture
+//! timestamps of received frames are converted to the client clock with it, so captures
+//! of several machines can be aligned in post.
+//!
+//! All messages are little endian. Frames are a header followed by the tightly packed BGRA
+//! pixels:
+//!
+//! | bytes | field                                          |
+//! |-------|------------------------------------------------|
+//! | 4     | magic `CGFR`                                   |
+//! | 8     | frame id                                       |
+//! | 8     | capture time, µs since the UNIX epoch          |
+//! | 4 + 4 | width, height in pixels                        |
+//! | 4 × 4 | source rect: min x, min y, width, height       |
+//!
+//! Clock sync messages are a magic followed by µs timestamps: `CGPI` + client send time
+//! from the client, `CGPO` + the client send time + daemon receive time + daemon send time
+//! in reply. The hello is `CGHI` + the token's length as 4 bytes + the token, UTF-8.
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs as _};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use anyhow::Context as _;
+use crabgrab::prelude::FrameBitmapBgraUnorm8x4;
+use crossbeam_channel::{RecvTimeoutError, Sender, TryRecvError, TrySendError};
+use serde::Deserialize;
+
+use crate::capture::{self, Frame};
+use crate::focus::ScreenRect;
+use crate::{config, status};
+
+const HELLO_MAGIC: [u8; 4] = *b"CGHI";
+const FRAME_MAGIC: [u8; 4] = *b"CGFR";
+const PING_MAGIC: [u8; 4] = *b"CGPI";
+const PONG_MAGIC: [u8; 4] = *b"CGPO";
+const HEADER_SIZE: usize = 4 + 8 + 8 + 4 + 4 + 4 * 4;
+
+/// Longest token accepted in a hello.
+const MAX_TOKEN_BYTES: usize = 1024;
+
+/// Longest time the daemon waits for the hello of a new client.
+const HELLO_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Largest width and height of a received frame, anything larger is a broken or hostile
+/// sender.
+const MAX_FRAME_SIDE: usize = 16384;
+
+/// Longest time a client waits before trying to reconnect to the daemon.
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+const PING_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Clock sync samples the offset is picked from.
+const CLOCK_SAMPLES: usize = 8;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct FrameLinkConfig {
+    /// Run as capture daemon, serving frames on this endpoint instead of opening a window.
+    pub serve: Option<String>,
+
+    /// Render the frames of the capture daemon on this endpoint instead of capturing.
+    pub connect: Option<String>,
+
+    /// Shared secret clients have to present to the daemon, required for serving TCP on
+    /// other than a loopback address.
+    pub token: Option<String>,
+}
+
+impl FrameLinkConfig {
+    /// Rejects endpoints this platform can't serve or connect to, and serving the network
+    /// without a token.
+    pub fn check_endpoints(&self) -> anyhow::Result<()> {
+        for endpoint in self.serve.iter().chain(&self.connect) {
+            Endpoint::parse(endpoint)?;
+        }
+        let serve = self.serve.as_deref().map(Endpoint::parse).transpose()?;
+        if let Some(Endpoint::Tcp(address)) = serve {
+            let is_loopback = address
+                .to_socket_addrs()
+                .with_context(|| format!("invalid address {address}"))?
+                .all(|address| address.ip().is_loopback());
+            anyhow::ensure!(
+                is_loopback || self.token.is_some(),
+                "serving frames on {address} needs a [frame_link] token, or a loopback address"
+            );
+        }
+        Ok(())
+    }
+}
+
+/// A UNIX socket path, or `tcp://host:port`.
+#[derive(Debug, Clone)]
+enum Endpoint {
+    #[cfg(unix)]
+    Unix(PathBuf),
+    Tcp(String),
+}
+
+impl Endpoint {
+    fn parse(endpoint: &str) -> anyhow::Result<Self> {
+        match endpoint.strip_prefix("tcp://") {
+            Some(address) => Ok(Self::Tcp(address.to_owned())),
+            #[cfg(unix)]
+            None => Ok(Self::Unix(PathBuf::from(endpoint))),
+            #[cfg(not(unix))]
+            None => anyhow::bail!("{endpoint}: UNIX sockets aren't available, use tcp://host:port"),
+        }
+    }
+
+    fn connect(&self) -> std::io::Result<Connection> {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(path) => UnixStream::connect(path).map(Connection::Unix),
+            Self::Tcp(address) => {
+                let stream = TcpStream::connect(address)?;
+                // Pings are tiny and have to go out right away for accurate round trips.
+                stream.set_nodelay(true)?;
+                Ok(Connection::Tcp(stream))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(path) => write!(f, "{}", path.display()),
+            Self::Tcp(address) => write!(f, "tcp://{address}"),
+        }
+    }
+}
+
+enum Connection {
+    #[cfg(unix)]
+    Unix(UnixStream),
+    Tcp(TcpStream),
+}
+
+impl Connection {
+    fn try_clone(&self) -> std::io::Result<Self> {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.try_clone().map(Self::Unix),
+            Self::Tcp(stream) => stream.try_clone().map(Self::Tcp),
+        }
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.set_read_timeout(timeout),
+            Self::Tcp(stream) => stream.set_read_timeout(timeout),
+        }
+    }
+
+    /// Ends the connection, also for the clones blocked reading from it.
+    fn shutdown(&self) {
+        let result = match self {
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.shutdown(std::net::Shutdown::Both),
+            Self::Tcp(stream) => stream.shutdown(std::net::Shutdown::Both),
+        };
+        result.ok();
+    }
+}
+
+impl Read for Connection {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.read(buf),
+            Self::Tcp(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Connection {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.write(buf),
+            Self::Tcp(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.flush(),
+            Self::Tcp(stream) => stream.flush(),
+        }
+    }
+}
+
+/// Listens on `endpoint` and returns a frame sink that forwards every frame to all
+/// connected clients, and the address listened on for TCP endpoints.
+///
+/// Each client gets at most one frame queued; clients that can't keep up skip frames
+/// instead of slowing down capture or the other clients.
+pub fn serve(
+    endpoint: &str,
+) -> anyhow::Result<(impl FnMut(Frame) + Send + 'static, Option<SocketAddr>)> {
+    let endpoint = Endpoint::parse(endpoint)?;
+    enum Listener {
+        #[cfg(unix)]
+        Unix(UnixListener),
+        Tcp(TcpListener),
+    }
+    let listener = match &endpoint {
+        #[cfg(unix)]
+        Endpoint::Unix(path) => {
+            use std::os::unix::fs::FileTypeExt as _;
+
+            // A socket file left behind by a previous daemon would make binding fail, but
+            // anything else at the path isn't ours to remove.
+            if let Ok(metadata) = std::fs::symlink_metadata(path) {
+                anyhow::ensure!(
+                    metadata.file_type().is_socket(),
+                    "{} exists and isn't a socket",
+                    path.display()
+                );
+                std::fs::remove_file(path)
+                    .with_context(|| format!("failed to remove stale socket {}", path.display()))?;
+            }
+            Listener::Unix(
+                UnixListener::bind(path)
+                    .with_context(|| format!("failed to listen on {}", path.display()))?,
+            )
+        }
+        Endpoint::Tcp(address) => Listener::Tcp(
+            TcpListener::bind(address).with_context(|| format!("failed to listen on {address}"))?,
+        ),
+    };
+    let tcp_address = match &listener {
+        Listener::Tcp(listener) => Some(listener.local_addr()?),
+        #[cfg(unix)]
+        Listener::Unix(_) => None,
+    };
+    println!("Serving frames on {endpoint}");
+
+    let clients = Arc::new(Mutex::new(Vec::<Sender<Arc<Vec<u8>>>>::new()));
+    {
+        let clients = clients.clone();
+        // Waiting for the hello happens off the listener thread, so a silent client doesn't
+        // hold up the others.
+        let add_client = move |connection: Connection| {
+            let clients = clients.clone();
+            std::thread::spawn(move || {
+                let result = check_hello(&connection).and_then(|()| serve_client(connection));
+                match result {
+                    Ok(sender) => {
+                        clients.lock().unwrap().push(sender);
+                        println!("Renderer client connected");
+                    }
+                    Err(err) => eprintln!("Failed to serve renderer client: {err:#}"),
+                }
+            });
+        };
+        std::thread::Builder::new()
+            .name("frame link listener".to_owned())
+            .spawn(move || match listener {
+                #[cfg(unix)]
+                Listener::Unix(listener) => {
+                    listener.incoming().flatten().map(Connection::Unix).for_each(add_client);
+                }
+                Listener::Tcp(listener) => {
+                    for stream in listener.incoming().flatten() {
+                        // Pongs are tiny and have to go out right away for accurate round trips.
+                        stream.set_nodelay(true).ok();
+                        add_client(Connection::Tcp(stream));
+                    }
+                }
+            })?;
+    }
+
+    let send_frame = move |frame: Frame| {
+        puffin::profile_scope!("frame link send");
+        let mut clients = clients.lock().unwrap();
+        if clients.is_empty() {
+            return;
+        }
+
+        let message = Arc::new(encode(&frame));
+        clients.retain(|client| match client.try_send(message.clone()) {
+            Ok(()) | Err(TrySendError::Full(_)) => true,
+            Err(TrySendError::Disconnected(_)) => false,
+        });
+    };
+    Ok((send_frame, tcp_address))
+}
+
+/// Reads the hello of a new client and checks its token against ours.
+fn check_hello(connection: &Connection) -> anyhow::Result<()> {
+    let mut reader = connection.try_clone()?;
+    reader.set_read_timeout(Some(HELLO_TIMEOUT))?;
+    let mut header = [0u8; 4 + 4];
+    reader.read_exact(&mut header).context("no hello")?;
+    anyhow::ensure!(header[0..4] == HELLO_MAGIC, "no hello");
+    let length = u32::from_le_bytes(header[4..8].try_into().unwrap()) as usize;
+    anyhow::ensure!(length <= MAX_TOKEN_BYTES, "token too long");
+    let mut token = vec![0u8; length];
+    reader.read_exact(&mut token).context("truncated hello")?;
+    reader.set_read_timeout(None)?;
+
+    let expected = config::get().frame_link.token.clone().unwrap_or_default();
+    // Compares every byte, so the time taken doesn't tell how much of the token matched.
+    let matches = token.len() == expected.len()
+        && token.iter().zip(expected.as_bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0;
+    anyhow::ensure!(matches, "wrong token");
+    Ok(())
+}
+
+fn hello() -> Vec<u8> {
+    let token = config::get().frame_link.token.clone().unwrap_or_default();
+    let mut hello = HELLO_MAGIC.to_vec();
+    hello.extend_from_slice(&(token.len() as u32).to_le_bytes());
+    hello.extend_from_slice(token.as_bytes());
+    hello
+}
+
+/// Starts the threads writing frames to and answering pings of a client, returns where
+/// to send its frames to.
+fn serve_client(connection: Connection) -> anyhow::Result<Sender<Arc<Vec<u8>>>> {
+    let mut reader = connection.try_clone()?;
+    let mut writer = connection;
+    let (frame_sender, frames) = crossbeam_channel::bounded::<Arc<Vec<u8>>>(1);
+    let (pong_sender, pongs) = crossbeam_channel::unbounded::<[u8; 4 + 3 * 8]>();
+
+    std::thread::spawn(move || {
+        let mut ping = [0u8; 4 + 8];
+        while reader.read_exact(&mut ping).is_ok() && ping[0..4] == PING_MAGIC {
+            let received_us = capture::now_us();
+            let mut pong = [0u8; 4 + 3 * 8];
+            pong[0..4].copy_from_slice(&PONG_MAGIC);
+            pong[4..12].copy_from_slice(&ping[4..12]);
+            pong[12..20].copy_from_slice(&received_us.to_le_bytes());
+            if pong_sender.send(pong).is_err() {
+                break;
+            }
+        }
+    });
+    std::thread::spawn(move || {
+        loop {
+            let result = crossbeam_channel::select! {
+                recv(frames) -> message => match message {
+                    Ok(message) => writer.write_all(&message),
+                    Err(_) => break,
+                },
+                recv(pongs) -> pong => match pong {
+                    Ok(mut pong) => {
+                        pong[20..28].copy_from_slice(&capture::now_us().to_le_bytes());
+                        writer.write_all(&pong)
+                    }
+                    Err(_) => break,
+                },
+            };
+            if result.is_err() {
+                break;
+            }
+        }
+        println!("Renderer client disconnected");
+    });
+    Ok(frame_sender)
+}
+
+/// Connects to the daemon on `endpoint` in the background and hands every received frame
+/// to `on_frame`, reconnecting with backoff whenever the connection fails, until
+/// [`Receiving::stop`].
+pub fn connect_in_background(
+    endpoint: &str,
+    mut on_frame: impl FnMut(Frame) + Send + 'static,
+) -> Receiving {
+    let endpoint = Endpoint::parse(endpoint).expect("Endpoint checked by Config::load");
+    let (stop, stopped) = crossbeam_channel::bounded::<()>(0);
+    let connection = Arc::new(Mutex::new(None::<Connection>));
+    let thread = {
+        let connection = connection.clone();
+        std::thread::Builder::new()
+            .name("frame link client".to_owned())
+            .spawn(move || {
+                let mut delay = Duration::from_millis(100);
+                loop {
+                    match endpoint.connect() {
+                        Ok(connected) => {
+                            println!("Connected to capture daemon at {endpoint}");
+                            delay = Duration::from_millis(100);
+                            *connection.lock().unwrap() = connected.try_clone().ok();
+                            // Stopped while connecting, before the connection could be shut down.
+                            if stopped.try_recv() != Err(TryRecvError::Empty) {
+                                return;
+                            }
+                            if let Err(err) = receive(connected, &mut on_frame) {
+                                eprintln!("Lost connection to capture daemon: {err:#}");
+                            }
+                        }
+                        Err(err) => {
+                            eprintln!("Failed to connect to capture daemon at {endpoint}: {err}");
+                        }
+                    }
+                    if stopped.recv_timeout(delay) != Err(RecvTimeoutError::Timeout) {
+                        return;
+                    }
+                    delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+                }
+            })
+            .expect("Failed to spawn frame link client thread")
+    };
+    Receiving {
+        stop: Some(stop),
+        connection,
+        thread: Some(thread),
+    }
+}
+
+/// The connection to a capture daemon of [`connect_in_background`].
+pub struct Receiving {
+    stop: Option<Sender<()>>,
+    connection: Arc<Mutex<Option<Connection>>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Receiving {
+    /// Disconnects and waits for the receiving thread to exit.
+    pub fn stop(&mut self) {
+        drop(self.stop.take());
+        if let Some(connection) = self.connection.lock().unwrap().take() {
+            connection.shutdown();
+        }
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+    }
+}
+
+fn encode(frame: &Frame) -> Vec<u8> {
+    let bitmap = &frame.frame_bitmap;
+    let mut message = Vec::with_capacity(HEADER_SIZE + bitmap.data.len() * 4);
+    message.extend_from_slice(&FRAME_MAGIC);
+    message.extend_from_slice(&frame.frame_id.to_le_bytes());
+    message.extend_from_slice(&frame.capture_time_us.to_le_bytes());
+    message.extend_from_slice(&(bitmap.width as u32).to_le_bytes());
+    message.extend_from_slice(&(bitmap.height as u32).to_le_bytes());
+    let rect = frame.source_rect;
+    for value in [rect.min.x, rect.min.y, rect.size.x, rect.size.y] {
+        message.extend_from_slice(&value.to_le_bytes());
+    }
+    message.extend(bitmap.data.iter().flatten());
+    message
+}
+
+/// Offset of the daemon clock relative to ours, estimated from ping round trips.
+#[derive(Default)]
+struct ClockSync {
+    /// Recent `(round trip, offset)` samples in µs, oldest first.
+    samples: Vec<(i64, i64)>,
+}
+
+impl ClockSync {
+    fn add_sample(&mut self, sent_us: u64, daemon_received_us: u64, daemon_sent_us: u64) {
+        let received_us = capture::now_us() as i64;
+        let [sent_us, daemon_received_us, daemon_sent_us] =
+            [sent_us, daemon_received_us, daemon_sent_us].map(|time| time as i64);
+        let round_trip = (received_us - sent_us) - (daemon_sent_us - daemon_received_us);
+        let offset = ((daemon_received_us - sent_us) + (daemon_sent_us - received_us)) / 2;
+        if self.samples.len() == CLOCK_SAMPLES {
+            self.samples.remove(0);
+        }
+        self.samples.push((round_trip, offset));
+    }
+
+    /// The offset of the sample with the shortest round trip, queueing delays only make
+    /// samples less accurate.
+    fn best(&self) -> Option<(i64, i64)> {
+        self.samples.iter().copied().min_by_key(|(round_trip, _)| *round_trip)
+    }
+}
+
+fn receive(connection: Connection, on_frame: &mut impl FnMut(Frame)) -> anyhow::Result<()> {
+    let mut pinger = connection.try_clone()?;
+    let mut stream = connection;
+    stream.write_all(&hello())?;
+    std::thread::spawn(move || loop {
+        let mut ping = PING_MAGIC.to_vec();
+        ping.extend_from_slice(&capture::now_us().to_le_bytes());
+        if pinger.write_all(&ping).is_err() {
+            break;
+        }
+        std::thread::sleep(PING_INTERVAL);
+    });
+
+    let mut clock = ClockSync::default();
+    let mut magic = [0u8; 4];
+    loop {
+        stream.read_exact(&mut magic)?;
+        if magic == PONG_MAGIC {
+            let mut times = [0u8; 3 * 8];
+            stream.read_exact(&mut times)?;
+            let time_at = |index: usize| u64::from_le_bytes(times[index * 8..][..8].try_into().unwrap());
+            clock.add_sample(time_at(0), time_at(1), time_at(2));
+            if let Some((round_trip, offset)) = clock.best() {
+                status::update(|status| {
+                    status.clock_offset_ms = Some(offset as f32 / 1000.0);
+                    status.clock_round_trip_ms = Some(round_trip as f32 / 1000.0);
+                });
+            }
+            continue;
+        }
+        anyhow::ensure!(magic == FRAME_MAGIC, "unexpected message");
+
+        let mut header = [0u8; HEADER_SIZE];
+        header[0..4].copy_from_slice(&magic);
+        stream.read_exact(&mut header[4..])?;
+        let u32_at = |offset: usize| u32::from_le_bytes(header[offset..offset + 4].try_into().unwrap());
+        let u64_at = |offset: usize| u64::from_le_bytes(header[offset..offset + 8].try_into().unwrap());
+        let f32_at = |offset: usize| f32::from_le_bytes(header[offset..offset + 4].try_into().unwrap());
+        let frame_id = u64_at(4);
+        let daemon_capture_time_us = u64_at(12);
+        let width = u32_at(20) as usize;
+        let height = u32_at(24) as usize;
+        let source_rect = ScreenRect {
+            min: glam::vec2(f32_at(28), f32_at(32)),
+            size: glam::vec2(f32_at(36), f32_at(40)),
+        };
+
+        anyhow::ensure!(
+            width <= MAX_FRAME_SIDE && height <= MAX_FRAME_SIDE,
+            "frame of {width}x{height} pixels is larger than {MAX_FRAME_SIDE}x{MAX_FRAME_SIDE}"
+        );
+        let size = width
+            .checked_mul(height)
+            .and_then(|pixels| pixels.checked_mul(4))
+            .context("frame size overflows")?;
+        let mut pixels = vec![0u8; size];
+        stream.read_exact(&mut pixels)?;
+        let data = pixels
+            .chunks_exact(4)
+            .map(|pixel| [pixel[0], pixel[1], pixel[2], pixel[3]])
+            .collect();
+
+        // Until the first pong the clocks are assumed to be in sync.
+        let offset = clock.best().map_or(0, |(_, offset)| offset);
+        on_frame(Frame {
+            frame_bitmap: FrameBitmapBgraUnorm8x4 {
+                data,
+                width,
+                height,
+            },
+            frame_id,
+            capture_time_us: (daemon_capture_time_us as i64 - offset) as u64,
+            source_rect,
+            ycbcr: None,
+            hdr_data: None,
+        });
+    }
+}
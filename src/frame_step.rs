@@ -0,0 +1,70 @@
+//! Frame-step debugging: while paused, captured frames are queued instead of shown and
+//! every step shows exactly the next one.
+//!
+//! Frames keep arriving while paused, the queue holds the latest [`MAX_QUEUED`] of them.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+use crate::capture::Frame;
+
+/// Captured frames kept for stepping, older ones are dropped.
+const MAX_QUEUED: usize = 120;
+
+#[derive(Default)]
+struct FrameStep {
+    paused: bool,
+    queue: VecDeque<Frame>,
+    frames_dropped: u64,
+}
+
+static FRAME_STEP: Lazy<Mutex<FrameStep>> = Lazy::new(Default::default);
+
+/// Per-step stats shown in the HUD.
+pub struct StepInfo {
+    pub queued: usize,
+    pub frames_dropped: u64,
+}
+
+/// Queues `frame` while paused, otherwise hands it back to be shown.
+pub fn intercept(frame: Frame) -> Option<Frame> {
+    let mut frame_step = FRAME_STEP.lock().unwrap();
+    if !frame_step.paused {
+        return Some(frame);
+    }
+    if frame_step.queue.len() == MAX_QUEUED {
+        frame_step.queue.pop_front();
+        frame_step.frames_dropped += 1;
+    }
+    frame_step.queue.push_back(frame);
+    None
+}
+
+pub fn is_paused() -> bool {
+    FRAME_STEP.lock().unwrap().paused
+}
+
+/// Pauses or resumes, returns the latest queued frame to show when resuming.
+pub fn set_paused(paused: bool) -> Option<Frame> {
+    let mut frame_step = FRAME_STEP.lock().unwrap();
+    frame_step.paused = paused;
+    frame_step.frames_dropped = 0;
+    let latest = frame_step.queue.pop_back();
+    frame_step.queue.clear();
+    latest.filter(|_| !paused)
+}
+
+/// The next captured frame, if one arrived since the last step.
+pub fn step() -> Option<Frame> {
+    FRAME_STEP.lock().unwrap().queue.pop_front()
+}
+
+pub fn info() -> StepInfo {
+    let frame_step = FRAME_STEP.lock().unwrap();
+    StepInfo {
+        queued: frame_step.queue.len(),
+        frames_dropped: frame_step.frames_dropped,
+    }
+}
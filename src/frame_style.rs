@@ -0,0 +1,279 @@
+//! Monitor-mockup styling for the capture rect: rounded corners, a border and a drop shadow.
+//!
+//! The rectangle renderer only knows axis aligned, fully textured quads, so corners are
+//! rounded by clearing the alpha of the uploaded pixels, the border is a line strip and
+//! the shadow is a separately generated, linearly magnified texture.
+
+use std::f32::consts::FRAC_PI_2;
+
+use re_renderer::{
+    renderer::{ColormappedTexture, RectangleOptions, TextureFilterMag, TextureFilterMin, TexturedRect},
+    resource_managers::{GpuTexture2D, Texture2DCreationDesc},
+    Color32, LineDrawableBuilder, RenderContext, Rgba, Size,
+};
+use serde::Deserialize;
+
+/// Resolution of the generated shadow texture along its longer side.
+const SHADOW_TEXTURE_SIZE: f32 = 128.0;
+
+/// Line segments used per rounded corner of the border.
+const CORNER_SEGMENTS: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct FrameStyleConfig {
+    /// Corner radius in scene units.
+    pub corner_radius: f32,
+
+    /// Border width in scene units, `0` disables the border.
+    pub border_width: f32,
+
+    /// Border color as unmultiplied sRGBA.
+    pub border_color: [u8; 4],
+
+    pub shadow: bool,
+
+    /// Offset of the shadow relative to the capture rect in scene units.
+    pub shadow_offset: [f32; 2],
+
+    /// Distance over which the shadow fades out, in scene units.
+    pub shadow_blur: f32,
+
+    pub shadow_opacity: f32,
+}
+
+impl Default for FrameStyleConfig {
+    fn default() -> Self {
+        Self {
+            corner_radius: 24.0,
+            border_width: 6.0,
+            border_color: [40, 40, 40, 255],
+            shadow: true,
+            shadow_offset: [12.0, 16.0],
+            shadow_blur: 32.0,
+            shadow_opacity: 0.6,
+        }
+    }
+}
+
+pub struct FrameStyle {
+    pub config: FrameStyleConfig,
+
+    /// Shadow texture together with the rect size and config it was generated for.
+    shadow_texture: Option<(glam::Vec2, FrameStyleConfig, GpuTexture2D)>,
+}
+
+impl FrameStyle {
+    pub fn new(config: FrameStyleConfig) -> Self {
+        Self {
+            config,
+            shadow_texture: None,
+        }
+    }
+
+    /// The corner radius of a `width` by `height` pixel image shown at `rect_size` scene
+    /// units, per axis in pixels.
+    pub fn corner_radius_in_pixels(
+        &self,
+        width: usize,
+        height: usize,
+        rect_size: glam::Vec2,
+    ) -> glam::Vec2 {
+        // The image may be stretched onto the rect, so the radius differs per axis in pixels.
+        let size_in_pixels = glam::vec2(width as f32, height as f32);
+        (self.config.corner_radius * size_in_pixels / rect_size).min(size_in_pixels * 0.5)
+    }
+
+    /// Clears the alpha outside of the rounded corners of a tightly packed 4 byte per
+    /// pixel image with alpha in the last channel, shown at `rect_size` scene units.
+    pub fn round_corners(&self, data: &mut [u8], width: usize, height: usize, rect_size: glam::Vec2) {
+        puffin::profile_function!();
+        self.for_each_corner_pixel(width, height, rect_size, |index, coverage| {
+            let alpha = &mut data[index * 4 + 3];
+            *alpha = (*alpha as f32 * coverage) as u8;
+        });
+    }
+
+    /// [`Self::round_corners`] for pixels packed like `wgpu::TextureFormat::Rgb10a2Unorm`,
+    /// whose 2 alpha bits only leave a coarse edge.
+    pub fn round_corners_10_bit(
+        &self,
+        data: &mut [u32],
+        width: usize,
+        height: usize,
+        rect_size: glam::Vec2,
+    ) {
+        puffin::profile_function!();
+        self.for_each_corner_pixel(width, height, rect_size, |index, coverage| {
+            let pixel = &mut data[index];
+            let alpha = ((*pixel >> 30) as f32 * coverage).round() as u32;
+            *pixel = *pixel & 0x3fff_ffff | alpha << 30;
+        });
+    }
+
+    /// Calls `apply` with the index and coverage of every pixel of a `width` x `height`
+    /// image that the rounded corners cover partially or not at all.
+    fn for_each_corner_pixel(
+        &self,
+        width: usize,
+        height: usize,
+        rect_size: glam::Vec2,
+        mut apply: impl FnMut(usize, f32),
+    ) {
+        let radius = self.corner_radius_in_pixels(width, height, rect_size);
+        if radius.min_element() < 1.0 {
+            return;
+        }
+
+        for y in 0..radius.y.ceil() as usize {
+            for x in 0..radius.x.ceil() as usize {
+                // Offset of the pixel center from the corner's arc center, with the arc
+                // normalized to a unit circle.
+                let offset = (radius - glam::vec2(x as f32 + 0.5, y as f32 + 0.5)) / radius;
+                let distance_in_pixels = (offset.length() - 1.0) * radius.min_element();
+                let coverage = (0.5 - distance_in_pixels).clamp(0.0, 1.0);
+                if coverage >= 1.0 {
+                    continue;
+                }
+
+                for (px, py) in [
+                    (x, y),
+                    (width - 1 - x, y),
+                    (x, height - 1 - y),
+                    (width - 1 - x, height - 1 - y),
+                ] {
+                    apply(py * width + px, coverage);
+                }
+            }
+        }
+    }
+
+    /// Adds the border around the rect at `min` with `size` to `line_builder`.
+    pub fn add_border(&self, line_builder: &mut LineDrawableBuilder<'_>, min: glam::Vec2, size: glam::Vec2) {
+        if self.config.border_width <= 0.0 {
+            return;
+        }
+
+        // The line is centered on its points, so move it outwards by half its width.
+        let half_width = self.config.border_width * 0.5;
+        let points = rounded_rect_outline(
+            min - glam::Vec2::splat(half_width),
+            size + glam::Vec2::splat(self.config.border_width),
+            self.config.corner_radius + half_width,
+        );
+
+        let [r, g, b, a] = self.config.border_color;
+        line_builder
+            .batch("capture border")
+            .add_strip_2d(points.into_iter())
+            .radius(Size::new_scene(half_width))
+            .color(Color32::from_rgba_unmultiplied(r, g, b, a));
+    }
+
+    /// Rect drawing the drop shadow of the rect at `min` with `size`, if enabled.
+    pub fn shadow_rect(
+        &mut self,
+        re_ctx: &RenderContext,
+        min: glam::Vec2,
+        size: glam::Vec2,
+    ) -> Option<TexturedRect> {
+        if !self.config.shadow {
+            return None;
+        }
+
+        let texture = match &self.shadow_texture {
+            Some((cached_size, cached_config, texture))
+                if *cached_size == size && *cached_config == self.config =>
+            {
+                texture.clone()
+            }
+            _ => {
+                let texture = self.create_shadow_texture(re_ctx, size);
+                self.shadow_texture = Some((size, self.config, texture.clone()));
+                texture
+            }
+        };
+
+        let margin = glam::Vec2::splat(self.config.shadow_blur);
+        let top_left = min - margin + glam::Vec2::from(self.config.shadow_offset);
+        let extent = size + margin * 2.0;
+        Some(TexturedRect {
+            // Between the capture rect and the background.
+            top_left_corner_position: top_left.extend(-0.075),
+            extent_u: extent.x * glam::Vec3::X,
+            extent_v: extent.y * glam::Vec3::Y,
+            colormapped_texture: ColormappedTexture::from_unorm_rgba(texture),
+            options: RectangleOptions {
+                texture_filter_magnification: TextureFilterMag::Linear,
+                texture_filter_minification: TextureFilterMin::Linear,
+                multiplicative_tint: Rgba::from_white_alpha(self.config.shadow_opacity),
+                depth_offset: -1,
+                ..Default::default()
+            },
+        })
+    }
+
+    fn create_shadow_texture(&self, re_ctx: &RenderContext, size: glam::Vec2) -> GpuTexture2D {
+        puffin::profile_function!();
+
+        let blur = self.config.shadow_blur.max(1.0);
+        let extent = size + glam::Vec2::splat(blur * 2.0);
+        let units_per_texel = extent.max_element() / SHADOW_TEXTURE_SIZE;
+        let width = (extent.x / units_per_texel).ceil() as u32;
+        let height = (extent.y / units_per_texel).ceil() as u32;
+
+        let half_size = size * 0.5;
+        let radius = self.config.corner_radius.min(half_size.min_element());
+        let data = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .flat_map(|(x, y)| {
+                let position = (glam::vec2(x as f32, y as f32) + 0.5) * units_per_texel;
+                // Signed distance to the rounded rect, relative to its center.
+                let q = (position - extent * 0.5).abs() - half_size + radius;
+                let distance = q.max(glam::Vec2::ZERO).length() + q.max_element().min(0.0) - radius;
+                let falloff = 1.0 - (distance / blur).clamp(0.0, 1.0);
+                let alpha = falloff * falloff * (3.0 - 2.0 * falloff);
+                [0, 0, 0, (alpha * 255.0) as u8]
+            })
+            .collect::<Vec<u8>>();
+
+        re_ctx
+            .texture_manager_2d
+            .create(
+                &re_ctx.gpu_resources.textures,
+                &Texture2DCreationDesc {
+                    label: "capture shadow".into(),
+                    data: data.into(),
+                    format: wgpu::TextureFormat::Rgba8Unorm,
+                    width,
+                    height,
+                },
+            )
+            .expect("Failed to create shadow texture")
+    }
+}
+
+/// Closed polyline along a rounded rect, starting and ending at the top left corner.
+fn rounded_rect_outline(min: glam::Vec2, size: glam::Vec2, radius: f32) -> Vec<glam::Vec2> {
+    let radius = radius.min(size.min_element() * 0.5).max(0.0);
+    let max = min + size;
+    // Arc centers in clockwise order (y points down), with the angle each arc starts at.
+    let corners = [
+        (glam::vec2(min.x + radius, min.y + radius), 2.0 * FRAC_PI_2),
+        (glam::vec2(max.x - radius, min.y + radius), 3.0 * FRAC_PI_2),
+        (glam::vec2(max.x - radius, max.y - radius), 0.0),
+        (glam::vec2(min.x + radius, max.y - radius), FRAC_PI_2),
+    ];
+
+    let mut points = corners
+        .iter()
+        .flat_map(|&(center, start_angle)| {
+            (0..=CORNER_SEGMENTS).map(move |i| {
+                let angle = start_angle + FRAC_PI_2 * i as f32 / CORNER_SEGMENTS as f32;
+                center + radius * glam::vec2(angle.cos(), angle.sin())
+            })
+        })
+        .collect::<Vec<_>>();
+    points.push(points[0]);
+    points
+}
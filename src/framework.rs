@@ -1,20 +1,24 @@
 //! Example framework
 
-use std::sync::Arc;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
 
 use anyhow::Context as _;
+use serde::Deserialize;
 use web_time::Instant;
 
 use re_renderer::{
     config::{supported_backends, DeviceCaps, RenderContextConfig},
+    resource_managers::GpuTexture2D,
     view_builder::ViewBuilder,
     RenderContext,
 };
 
 use winit::{
     event::{Event, WindowEvent},
-    event_loop::{ControlFlow, EventLoop},
-    window::Window,
+    event_loop::{EventLoop, EventLoopProxy, EventLoopWindowTarget},
+    monitor::MonitorHandle,
+    window::{Fullscreen, Window},
 };
 
 pub struct ViewDrawResult {
@@ -23,10 +27,46 @@ pub struct ViewDrawResult {
     pub target_location: glam::Vec2,
 }
 
+/// Part of a texture drawn straight into a swapchain texture, skipping the view builder and
+/// its passes.
+pub struct Blit {
+    pub texture: GpuTexture2D,
+
+    /// Min and max of the part of the texture, 0–1 across it.
+    pub uv: (glam::Vec2, glam::Vec2),
+
+    /// Min and size in pixels of where it goes, within the swapchain texture.
+    pub target: (glam::Vec2, glam::Vec2),
+}
+
+pub enum MirrorDraw {
+    View(ViewDrawResult),
+    Blit(Blit),
+}
+
 pub trait Example {
+    /// Handed to [`Example::new`], e.g. the receiving end of a data source.
+    type Init;
+
     fn title() -> &'static str;
 
-    fn new(re_ctx: &RenderContext) -> Self;
+    /// Keep the window out of screen captures, including the example's own.
+    fn content_protected() -> bool {
+        false
+    }
+
+    /// Frames the CPU may be ahead of the GPU, submitted but not finished yet. More keep
+    /// the GPU busy, fewer get frames on the screen sooner after they're drawn.
+    fn max_frames_in_flight() -> u32 {
+        2
+    }
+
+    /// Monitor for a borderless fullscreen window drawn with [`Example::draw_mirror`].
+    fn mirror_monitor(_event_loop: &EventLoopWindowTarget<()>) -> Option<MonitorHandle> {
+        None
+    }
+
+    fn new(re_ctx: &RenderContext, init: Self::Init) -> Self;
 
     fn draw(
         &mut self,
@@ -36,9 +76,50 @@ pub trait Example {
         pixels_from_point: f32,
     ) -> Vec<ViewDrawResult>;
 
+    /// What fills the mirror window, drawn right after [`Example::draw`].
+    fn draw_mirror(
+        &mut self,
+        _re_ctx: &RenderContext,
+        _resolution: [u32; 2],
+        _pixels_from_point: f32,
+    ) -> Option<MirrorDraw> {
+        None
+    }
+
+    /// The main window's latest frame went to the window server.
+    fn on_presented(&mut self) {}
+
+    /// The mirror window's latest frame went to the window server.
+    fn on_mirror_presented(&mut self) {}
+
     fn on_key_event(&mut self, _event: winit::event::KeyEvent) {}
 
+    fn on_modifiers_changed(&mut self, _modifiers: winit::keyboard::ModifiersState) {}
+
     fn on_cursor_moved(&mut self, _position_in_pixel: glam::UVec2) {}
+
+    fn on_mouse_input(
+        &mut self,
+        _state: winit::event::ElementState,
+        _button: winit::event::MouseButton,
+    ) {
+    }
+
+    fn on_mouse_wheel(
+        &mut self,
+        _delta: winit::event::MouseScrollDelta,
+        _phase: winit::event::TouchPhase,
+    ) {
+    }
+
+    /// Trackpad pinch, `delta` is the change of the magnification, e.g. 0.1 for 10 % larger.
+    fn on_touchpad_magnify(&mut self, _delta: f64, _phase: winit::event::TouchPhase) {}
+
+    /// Trackpad rotation, `delta` in degrees, counterclockwise positive.
+    fn on_touchpad_rotate(&mut self, _delta: f32, _phase: winit::event::TouchPhase) {}
+
+    /// A finger on a touchscreen, with its location in window pixels.
+    fn on_touch(&mut self, _touch: winit::event::Touch) {}
 }
 
 #[allow(dead_code)]
@@ -70,22 +151,458 @@ pub fn split_resolution(
         })
 }
 
+/// Where the animation time of [`Time::seconds`] comes from.
+///
+/// Everything but [`TimeSource::Real`] only depends on the number of frames drawn, so
+/// animations come out identical across runs.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TimeSource {
+    /// Wall clock time since startup.
+    #[default]
+    Real,
+
+    /// Advances by `1 / fps` every frame, no matter how long frames take.
+    FixedStep { fps: f32 },
+
+    /// `[frame, seconds]` keyframes, linearly interpolated and held after the last one.
+    Scripted { keyframes: Vec<[f32; 2]> },
+}
+
+impl TimeSource {
+    /// Rejects frame rates [`Time::seconds`] would divide by and keyframes out of order.
+    pub fn check(&self) -> anyhow::Result<()> {
+        match self {
+            Self::Real => {}
+            Self::FixedStep { fps } => {
+                anyhow::ensure!(fps.is_finite() && *fps > 0.0, "[time] fps has to be positive");
+            }
+            Self::Scripted { keyframes } => {
+                anyhow::ensure!(
+                    keyframes.iter().flatten().all(|value| value.is_finite()),
+                    "[time] keyframes have to be finite"
+                );
+                anyhow::ensure!(
+                    keyframes.windows(2).all(|pair| pair[0][0] <= pair[1][0]),
+                    "[time] keyframes have to be in order of their frames"
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
 pub struct Time {
     start_time: Instant,
     last_draw_time: Instant,
     pub last_frame_duration: web_time::Duration,
+
+    source: TimeSource,
+
+    /// Frames drawn so far.
+    pub frame_index: u64,
+
+    /// Frames submitted to the GPU that it hasn't finished, when this one started.
+    pub frames_in_flight: usize,
+
+    /// From submitting the latest frame the GPU finished to it finishing.
+    pub gpu_latency: web_time::Duration,
 }
 
 impl Time {
+    fn new(source: TimeSource) -> Self {
+        Self {
+            start_time: Instant::now(),
+            last_draw_time: Instant::now(),
+            last_frame_duration: web_time::Duration::from_secs(0),
+            source,
+            frame_index: 0,
+            frames_in_flight: 0,
+            gpu_latency: web_time::Duration::from_secs(0),
+        }
+    }
+
+    /// Wall clock time, regardless of the [`TimeSource`].
     pub fn seconds_since_startup(&self) -> f32 {
         self.start_time.elapsed().as_secs_f32()
     }
+
+    /// Animation time according to the [`TimeSource`].
+    pub fn seconds(&self) -> f32 {
+        let frame = self.frame_index as f32;
+        match &self.source {
+            TimeSource::Real => self.seconds_since_startup(),
+            TimeSource::FixedStep { fps } => frame / fps,
+            TimeSource::Scripted { keyframes } => {
+                let next = keyframes.iter().position(|[keyframe, _]| *keyframe > frame);
+                match next {
+                    None => keyframes.last().map_or(0.0, |[_, seconds]| *seconds),
+                    Some(0) => keyframes[0][1],
+                    Some(next) => {
+                        let [from_frame, from_seconds] = keyframes[next - 1];
+                        let [to_frame, to_seconds] = keyframes[next];
+                        let t = (frame - from_frame) / (to_frame - from_frame);
+                        from_seconds + (to_seconds - from_seconds) * t
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Values a [`Tween`] can animate between.
+pub trait Lerp: Copy {
+    /// `self` at `t` = 0, `to` at `t` = 1.
+    fn lerp(self, to: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Lerp for glam::Vec2 {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        glam::Vec2::lerp(self, to, t)
+    }
+}
+
+/// Animates from one value to another in wall clock time, easing in and out, e.g. for
+/// camera moves. The clock starts when the value is first asked for.
+#[derive(Debug, Clone, Copy)]
+pub struct Tween<T> {
+    from: T,
+    to: T,
+    duration: web_time::Duration,
+    start_seconds: Option<f32>,
+}
+
+impl<T: Lerp> Tween<T> {
+    /// At `to` right away without a `duration`.
+    pub fn new(from: T, to: T, duration: web_time::Duration) -> Self {
+        Self {
+            from,
+            to,
+            duration,
+            start_seconds: None,
+        }
+    }
+
+    pub fn value(&mut self, time: &Time) -> T {
+        let progress = self.progress(time);
+        self.from.lerp(self.to, ease_in_out(progress))
+    }
+
+    pub fn is_done(&mut self, time: &Time) -> bool {
+        self.progress(time) >= 1.0
+    }
+
+    /// From 0 to 1 over the duration.
+    fn progress(&mut self, time: &Time) -> f32 {
+        let now = time.seconds_since_startup();
+        let elapsed = now - *self.start_seconds.get_or_insert(now);
+        let duration = self.duration.as_secs_f32();
+        if duration > 0.0 {
+            (elapsed / duration).min(1.0)
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Cubic easing of `t` from 0 to 1, slow at both ends.
+pub fn ease_in_out(t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    if t < 0.5 {
+        4.0 * t * t * t
+    } else {
+        1.0 - (2.0 - 2.0 * t).powi(3) * 0.5
+    }
+}
+
+/// Borderless fullscreen window on another monitor, see [`Example::draw_mirror`].
+struct Mirror {
+    window: WindowSurface,
+    blitter: Blitter,
+}
+
+/// Frames submitted to the GPU, waiting for the oldest before drawing another once there
+/// are `max` of them, so the CPU doesn't get further ahead.
+struct FramesInFlight {
+    max: usize,
+    submissions: VecDeque<wgpu::SubmissionIndex>,
+    submitted: u64,
+
+    /// Frames the GPU finished, and how long after its submission the latest one finished.
+    finished: Arc<Mutex<(u64, web_time::Duration)>>,
+}
+
+impl FramesInFlight {
+    fn new(max: u32) -> Self {
+        Self {
+            max: max.max(1) as usize,
+            submissions: VecDeque::new(),
+            submitted: 0,
+            finished: Default::default(),
+        }
+    }
+
+    /// Waits until a frame more may be submitted, then updates `time` with the frames in
+    /// flight.
+    fn wait(&mut self, device: &wgpu::Device, time: &mut Time) {
+        while self.submissions.len() >= self.max {
+            let oldest = self.submissions.pop_front().unwrap();
+            device.poll(wgpu::Maintain::WaitForSubmissionIndex(oldest));
+        }
+        // Runs the callbacks of the ones finished since.
+        device.poll(wgpu::Maintain::Poll);
+        let (finished, gpu_latency) = *self.finished.lock().unwrap();
+        time.frames_in_flight = self.submitted.saturating_sub(finished) as usize;
+        time.gpu_latency = gpu_latency;
+    }
+
+    fn submitted(&mut self, queue: &wgpu::Queue, submission: wgpu::SubmissionIndex) {
+        self.submissions.push_back(submission);
+        self.submitted += 1;
+        let finished = self.finished.clone();
+        let submitted_at = Instant::now();
+        queue.on_submitted_work_done(move || {
+            let mut finished = finished.lock().unwrap();
+            *finished = (finished.0 + 1, submitted_at.elapsed());
+        });
+    }
+}
+
+const BLIT_SHADER: &str = r#"
+struct Uniforms {
+    uv_min: vec2<f32>,
+    uv_max: vec2<f32>,
+};
+
+@group(0) @binding(0) var<uniform> uniforms: Uniforms;
+@group(0) @binding(1) var source: texture_2d<f32>;
+@group(0) @binding(2) var source_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    // A triangle covering the viewport, 0–1 across it.
+    let corner = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    out.position = vec4<f32>(corner.x * 2.0 - 1.0, 1.0 - corner.y * 2.0, 0.0, 1.0);
+    out.uv = mix(uniforms.uv_min, uniforms.uv_max, corner);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(source, source_sampler, in.uv);
+}
+"#;
+
+/// Draws [`Blit`]s, with a pipeline of its own, re_renderer has none that skips its passes.
+struct Blitter {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    uniforms: wgpu::Buffer,
+}
+
+impl Blitter {
+    fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("blit"),
+            source: wgpu::ShaderSource::Wgsl(BLIT_SHADER.into()),
+        });
+        let fragment_entry = |binding, ty| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty,
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("blit"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                fragment_entry(
+                    1,
+                    wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                ),
+                fragment_entry(2, wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering)),
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("blit"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("blit"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(format.into())],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("blit"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let uniforms = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("blit uniforms"),
+            size: 16,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        Self {
+            pipeline,
+            bind_group_layout,
+            sampler,
+            uniforms,
+        }
+    }
+
+    /// Clears `view` and draws `blit` into it.
+    fn blit(
+        &self,
+        re_ctx: &RenderContext,
+        view: &wgpu::TextureView,
+        blit: &Blit,
+    ) -> wgpu::CommandBuffer {
+        let (uv_min, uv_max) = blit.uv;
+        let uniforms = [uv_min.x, uv_min.y, uv_max.x, uv_max.y]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect::<Vec<_>>();
+        re_ctx.queue.write_buffer(&self.uniforms, 0, &uniforms);
+        let bind_group = re_ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("blit"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: self.uniforms.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&blit.texture.default_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+            ],
+        });
+
+        let mut encoder = re_ctx.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: "blit_encoder".into(),
+        });
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            let (min, size) = blit.target;
+            pass.set_viewport(min.x, min.y, size.x, size.y, 0.0, 1.0);
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.draw(0..3, 0..1);
+        }
+        encoder.finish()
+    }
+}
+
+/// What the event loop tells the render thread, see [`start`].
+enum Message {
+    Window(WindowEvent),
+    Mirror(WindowEvent),
+    Exit,
+}
+
+/// A window's surface, with the size and scale factor the render thread heard of last.
+/// Asking the window itself from the render thread would wait for the event loop.
+struct WindowSurface {
+    surface: wgpu::Surface<'static>,
+    size: winit::dpi::PhysicalSize<u32>,
+    scale_factor: f64,
+}
+
+impl WindowSurface {
+    fn new(instance: &wgpu::Instance, window: &Arc<Window>) -> Self {
+        Self {
+            surface: instance.create_surface(window.clone()).unwrap(),
+            size: window.inner_size(),
+            scale_factor: window.scale_factor(),
+        }
+    }
+
+    /// Follows the window's size and scale factor, `true` if the size changed.
+    fn on_event(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::Resized(size) => {
+                self.size = *size;
+                true
+            }
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                self.scale_factor = *scale_factor;
+                false
+            }
+            _ => false,
+        }
+    }
+
+    fn is_minimized(&self) -> bool {
+        self.size.width == 0 || self.size.height == 0
+    }
 }
 
 struct Application<E> {
-    window: Arc<Window>,
+    window: WindowSurface,
     adapter: wgpu::Adapter,
-    surface: wgpu::Surface<'static>,
+
+    /// Until the mirror window is closed.
+    mirror: Option<Mirror>,
+    frames_in_flight: FramesInFlight,
     time: Time,
 
     example: E,
@@ -93,6 +610,32 @@ struct Application<E> {
     re_ctx: RenderContext,
 }
 
+/// Configures `surface` for a window of `size`, unless the window is minimized.
+fn configure_surface(
+    surface: &wgpu::Surface<'static>,
+    adapter: &wgpu::Adapter,
+    re_ctx: &RenderContext,
+    size: winit::dpi::PhysicalSize<u32>,
+    present_mode: wgpu::PresentMode,
+    max_frames_in_flight: u32,
+) -> bool {
+    if size.width == 0 || size.height == 0 {
+        return false;
+    }
+
+    let surface_config = wgpu::SurfaceConfiguration {
+        present_mode,
+        desired_maximum_frame_latency: max_frames_in_flight,
+        format: re_ctx.config.output_format_color,
+        view_formats: vec![re_ctx.config.output_format_color],
+        ..surface
+            .get_default_config(adapter, size.width, size.height)
+            .expect("The surface isn't supported by this adapter")
+    };
+    surface.configure(&re_ctx.device, &surface_config);
+    true
+}
+
 // Same as egui_wgpu::preferred_framebuffer_format
 fn preferred_framebuffer_format(formats: &[wgpu::TextureFormat]) -> wgpu::TextureFormat {
     for &format in formats {
@@ -107,20 +650,18 @@ fn preferred_framebuffer_format(formats: &[wgpu::TextureFormat]) -> wgpu::Textur
 }
 
 impl<E: Example + 'static> Application<E> {
-    async fn new(window: Window) -> anyhow::Result<Self> {
-        let window = Arc::new(window);
-        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            backends: supported_backends(),
-            flags: wgpu::InstanceFlags::default(),
-            dx12_shader_compiler: wgpu::Dx12Compiler::Fxc,
-            gles_minor_version: wgpu::Gles3MinorVersion::Automatic,
-        });
-        let surface = instance.create_surface(window.clone()).unwrap();
+    async fn new(
+        instance: wgpu::Instance,
+        window: WindowSurface,
+        mirror_window: Option<WindowSurface>,
+        time_source: TimeSource,
+        init: E::Init,
+    ) -> anyhow::Result<Self> {
         let adapter = instance
             .request_adapter(&wgpu::RequestAdapterOptions {
                 power_preference: wgpu::PowerPreference::HighPerformance,
                 force_fallback_adapter: false,
-                compatible_surface: Some(&surface),
+                compatible_surface: Some(&window.surface),
             })
             .await
             .context("failed to find an appropriate adapter")?;
@@ -142,7 +683,7 @@ impl<E: Example + 'static> Application<E> {
         let queue = Arc::new(queue);
 
         let output_format_color =
-            preferred_framebuffer_format(&surface.get_capabilities(&adapter).formats);
+            preferred_framebuffer_format(&window.surface.get_capabilities(&adapter).formats);
 
         let re_ctx = RenderContext::new(
             &adapter,
@@ -154,233 +695,461 @@ impl<E: Example + 'static> Application<E> {
             },
         );
 
-        let example = E::new(&re_ctx);
+        let mirror = mirror_window.map(|window| Mirror {
+            window,
+            blitter: Blitter::new(&re_ctx.device, output_format_color),
+        });
+
+        let example = E::new(&re_ctx, init);
 
         Ok(Self {
             window,
             adapter,
-            surface,
+            mirror,
             re_ctx,
-            time: Time {
-                start_time: Instant::now(),
-                last_draw_time: Instant::now(),
-                last_frame_duration: web_time::Duration::from_secs(0),
-            },
+            frames_in_flight: FramesInFlight::new(E::max_frames_in_flight()),
+            time: Time::new(time_source),
 
             example,
         })
     }
 
-    fn configure_surface(&mut self, size: winit::dpi::PhysicalSize<u32>) {
-        if size.width == 0 || size.height == 0 {
+    fn configure_surface(&self) {
+        // Not the best setting in general, but nice for quick & easy performance checking.
+        // TODO(andreas): It seems at least on Metal M1 this still does not discard command buffers that come in too fast (even when using `Immediate` explicitly).
+        //                  Quick look into wgpu looks like it does it correctly there. OS limitation? iOS has this limitation, so wouldn't be surprising!
+        let present_mode = wgpu::PresentMode::AutoNoVsync;
+        let window = &self.window;
+        configure_surface(
+            &window.surface,
+            &self.adapter,
+            &self.re_ctx,
+            window.size,
+            present_mode,
+            E::max_frames_in_flight(),
+        );
+    }
+
+    fn configure_mirror_surface(&self) {
+        let Some(mirror) = &self.mirror else {
             return;
+        };
+        // As little latency as possible, tearing is the lesser evil for a mirror.
+        let surface = &mirror.window.surface;
+        let present_modes = surface.get_capabilities(&self.adapter).present_modes;
+        let present_mode = if present_modes.contains(&wgpu::PresentMode::Immediate) {
+            wgpu::PresentMode::Immediate
+        } else {
+            wgpu::PresentMode::AutoNoVsync
+        };
+        configure_surface(
+            surface,
+            &self.adapter,
+            &self.re_ctx,
+            mirror.window.size,
+            present_mode,
+            E::max_frames_in_flight(),
+        );
+    }
+
+    fn on_window_event(&mut self, event: WindowEvent) {
+        if self.window.on_event(&event) {
+            self.configure_surface();
         }
+        match event {
+            WindowEvent::KeyboardInput { event, .. } => self.example.on_key_event(event),
+
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.example.on_modifiers_changed(modifiers.state());
+            }
+
+            WindowEvent::CursorMoved { position, .. } => self
+                .example
+                // Don't round the position: The entire range from 0 to excluding 1 should fall into pixel coordinate 0!
+                .on_cursor_moved(glam::uvec2(position.x as u32, position.y as u32)),
+
+            WindowEvent::MouseInput { state, button, .. } => {
+                self.example.on_mouse_input(state, button);
+            }
 
-        let surface_config = wgpu::SurfaceConfiguration {
-            // Not the best setting in general, but nice for quick & easy performance checking.
-            // TODO(andreas): It seems at least on Metal M1 this still does not discard command buffers that come in too fast (even when using `Immediate` explicitly).
-            //                  Quick look into wgpu looks like it does it correctly there. OS limitation? iOS has this limitation, so wouldn't be surprising!
-            present_mode: wgpu::PresentMode::AutoNoVsync,
-            format: self.re_ctx.config.output_format_color,
-            view_formats: vec![self.re_ctx.config.output_format_color],
-            ..self
-                .surface
-                .get_default_config(&self.adapter, size.width, size.height)
-                .expect("The surface isn't supported by this adapter")
+            WindowEvent::MouseWheel { delta, phase, .. } => {
+                self.example.on_mouse_wheel(delta, phase);
+            }
+
+            WindowEvent::TouchpadMagnify { delta, phase, .. } => {
+                self.example.on_touchpad_magnify(delta, phase);
+            }
+
+            WindowEvent::TouchpadRotate { delta, phase, .. } => {
+                self.example.on_touchpad_rotate(delta, phase);
+            }
+
+            WindowEvent::Touch(touch) => self.example.on_touch(touch),
+
+            _ => {}
+        }
+    }
+
+    /// Only keys get through to the example, positions in the mirror mean nothing to it.
+    fn on_mirror_event(&mut self, event: WindowEvent) {
+        let Some(mirror) = &mut self.mirror else {
+            return;
         };
-        self.surface.configure(&self.re_ctx.device, &surface_config);
-        self.window.request_redraw();
+        if mirror.window.on_event(&event) {
+            self.configure_mirror_surface();
+        }
+        match event {
+            WindowEvent::KeyboardInput { event, .. } => self.example.on_key_event(event),
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.example.on_modifiers_changed(modifiers.state());
+            }
+            // The control window keeps running without it.
+            WindowEvent::CloseRequested => self.mirror = None,
+            _ => {}
+        }
     }
 
-    fn run(mut self, event_loop: EventLoop<()>) {
-        event_loop
-            .run(move |event, event_loop_window_target| {
-                // Keep our example busy.
-                // Not how one should generally do it, but great for animated content and
-                // checking on perf.
-                event_loop_window_target.set_control_flow(ControlFlow::Poll);
+    /// The mirror's next swapchain texture and the scale factor of its monitor.
+    fn mirror_frame(&self) -> Option<(wgpu::SurfaceTexture, f32)> {
+        let mirror = self.mirror.as_ref().filter(|mirror| !mirror.window.is_minimized())?;
+        match mirror.window.surface.get_current_texture() {
+            Ok(frame) => Some((frame, mirror.window.scale_factor as f32)),
+            Err(wgpu::SurfaceError::Timeout | wgpu::SurfaceError::Outdated) => {
+                self.configure_mirror_surface();
+                None
+            }
+            Err(_) => None,
+        }
+    }
 
-                match event {
-                    Event::NewEvents(winit::event::StartCause::Init) => {
-                        self.configure_surface(self.window.inner_size());
-                    }
+    fn composite(
+        &self,
+        view: &wgpu::TextureView,
+        draw_results: &[ViewDrawResult],
+    ) -> wgpu::CommandBuffer {
+        let mut composite_cmd_encoder =
+            self.re_ctx.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: "composite_encoder".into(),
+            });
 
-                    Event::WindowEvent {
-                        event: WindowEvent::Resized(size),
-                        ..
-                    } => {
-                        self.configure_surface(size);
-                    }
+        {
+            // Lock render pipelines for the lifetime of the composite pass.
+            let render_pipelines = self.re_ctx.gpu_resources.render_pipelines.resources();
 
-                    Event::WindowEvent {
-                        event: WindowEvent::KeyboardInput { event, .. },
-                        ..
-                    } => self.example.on_key_event(event),
-
-                    Event::WindowEvent {
-                        event: WindowEvent::CursorMoved { position, .. },
-                        ..
-                    } => self
-                        .example
-                        // Don't round the position: The entire range from 0 to excluding 1 should fall into pixel coordinate 0!
-                        .on_cursor_moved(glam::uvec2(position.x as u32, position.y as u32)),
-
-                    winit::event::Event::WindowEvent {
-                        event: winit::event::WindowEvent::RedrawRequested,
-                        ..
-                    } => {
-                        self.re_ctx.begin_frame();
-
-                        // native debug build
-                        #[cfg(all(not(target_arch = "wasm32"), debug_assertions))]
-                            let frame = match self.surface.get_current_texture() {
-                            Ok(frame) => frame,
-                            Err(wgpu::SurfaceError::Timeout | wgpu::SurfaceError::Outdated) => {
-                                // We haven't been able to present anything to the swapchain for
-                                // a while, because the pipeline is poisoned.
-                                // Recreate a sane surface to restart the cycle and see if the
-                                // user has fixed the issue.
-                                self.configure_surface(self.window.inner_size());
-                                return;
-                            }
-                            Err(err) => {
-                                return;
-                            }
-                        };
-                        #[cfg(not(all(not(target_arch = "wasm32"), debug_assertions)))] // otherwise
-                            let frame = self
-                            .surface
-                            .get_current_texture()
-                            .expect("failed to acquire next swap chain texture");
-
-                        let view = frame
-                            .texture
-                            .create_view(&wgpu::TextureViewDescriptor::default());
-
-                        let draw_results = self.example.draw(
-                            &self.re_ctx,
-                            [frame.texture.width(), frame.texture.height()],
-                            &self.time,
-                            self.window.scale_factor() as f32,
-                        );
-
-                        let mut composite_cmd_encoder = self.re_ctx.device.create_command_encoder(
-                            &wgpu::CommandEncoderDescriptor {
-                                label: "composite_encoder".into(),
-                            },
-                        );
-
-                        {
-                            // Lock render pipelines for the lifetime of the composite pass.
-                            let render_pipelines =
-                                self.re_ctx.gpu_resources.render_pipelines.resources();
-
-                            let mut composite_pass = composite_cmd_encoder.begin_render_pass(
-                                &wgpu::RenderPassDescriptor {
-                                    label: None,
-                                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                                        view: &view,
-                                        resolve_target: None,
-                                        ops: wgpu::Operations {
-                                            load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                                            store: wgpu::StoreOp::Store,
-                                        },
-                                    })],
-                                    depth_stencil_attachment: None,
-                                    timestamp_writes: None,
-                                    occlusion_query_set: None,
-                                },
-                            );
-
-                            for draw_result in &draw_results {
-                                composite_pass.set_viewport(
-                                    draw_result.target_location.x,
-                                    draw_result.target_location.y,
-                                    draw_result.view_builder.resolution_in_pixel()[0] as f32,
-                                    draw_result.view_builder.resolution_in_pixel()[1] as f32,
-                                    0.0,
-                                    1.0,
-                                );
-                                draw_result.view_builder.composite(
-                                    &self.re_ctx,
-                                    &render_pipelines,
-                                    &mut composite_pass,
-                                );
-                            }
-                        };
-
-                        self.re_ctx.before_submit();
-                        self.re_ctx.queue.submit(
-                            draw_results
-                                .into_iter()
-                                .map(|d| d.command_buffer)
-                                .chain(std::iter::once(composite_cmd_encoder.finish())),
-                        );
-                        frame.present();
-
-                        // Note that this measures time spent on CPU, not GPU
-                        // However, iff we're GPU bound (likely for this sample) and GPU times are somewhat stable,
-                        // we eventually end up waiting for GPU in `get_current_texture`
-                        // (wgpu has a swap chain with a limited amount of buffers, the exact count is dependent on `present_mode` and backend!).
-                        // It's important to keep in mind that depending on the `present_mode`, the GPU might be waiting on the screen in turn.
-                        let current_time = Instant::now();
-                        let time_passed = current_time - self.time.last_draw_time;
-                        self.time.last_draw_time = current_time;
-                        self.time.last_frame_duration = time_passed;
-
-                        // TODO(andreas): Display a median over n frames and while we're on it also stddev thereof.
-                        // Do it only every second.
-                        let time_until_next_report =
-                            1.0 - self.time.seconds_since_startup().fract();
-                        if time_until_next_report - time_passed.as_secs_f32() < 0.0 {
-                            let time_info_str = format!(
-                                "{:.2} ms ({:.2} fps)",
-                                time_passed.as_secs_f32() * 1000.0,
-                                1.0 / time_passed.as_secs_f32()
-                            );
-                        }
+            let mut composite_pass =
+                composite_cmd_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: None,
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                    timestamp_writes: None,
+                    occlusion_query_set: None,
+                });
 
-                        self.window.request_redraw(); // Busy-painting
-                    }
+            for draw_result in draw_results {
+                composite_pass.set_viewport(
+                    draw_result.target_location.x,
+                    draw_result.target_location.y,
+                    draw_result.view_builder.resolution_in_pixel()[0] as f32,
+                    draw_result.view_builder.resolution_in_pixel()[1] as f32,
+                    0.0,
+                    1.0,
+                );
+                draw_result.view_builder.composite(
+                    &self.re_ctx,
+                    &render_pipelines,
+                    &mut composite_pass,
+                );
+            }
+        };
 
-                    Event::WindowEvent {
-                        event: WindowEvent::CloseRequested,
-                        ..
-                    } => {
-                        event_loop_window_target.exit();
+        composite_cmd_encoder.finish()
+    }
+
+    /// Draws frames as fast as it can, handling the messages of the event loop in between,
+    /// until it's told to exit.
+    fn run(mut self, messages: crossbeam_channel::Receiver<Message>) {
+        self.configure_surface();
+        self.configure_mirror_surface();
+        loop {
+            // Nothing to draw while minimized, until the window is back.
+            let message = if self.window.is_minimized() {
+                messages.recv().ok()
+            } else {
+                match messages.try_recv() {
+                    Ok(message) => Some(message),
+                    Err(crossbeam_channel::TryRecvError::Empty) => {
+                        self.draw_frame();
+                        continue;
                     }
+                    Err(crossbeam_channel::TryRecvError::Disconnected) => None,
+                }
+            };
+            match message {
+                Some(Message::Window(event)) => self.on_window_event(event),
+                Some(Message::Mirror(event)) => self.on_mirror_event(event),
+                Some(Message::Exit) | None => return,
+            }
+        }
+    }
+
+    fn draw_frame(&mut self) {
+        self.frames_in_flight.wait(&self.re_ctx.device, &mut self.time);
+        self.re_ctx.begin_frame();
+
+        // native debug build
+        #[cfg(all(not(target_arch = "wasm32"), debug_assertions))]
+            let frame = match self.window.surface.get_current_texture() {
+            Ok(frame) => frame,
+            Err(wgpu::SurfaceError::Timeout | wgpu::SurfaceError::Outdated) => {
+                // We haven't been able to present anything to the swapchain for
+                // a while, because the pipeline is poisoned.
+                // Recreate a sane surface to restart the cycle and see if the
+                // user has fixed the issue.
+                self.configure_surface();
+                return;
+            }
+            Err(err) => {
+                return;
+            }
+        };
+        #[cfg(not(all(not(target_arch = "wasm32"), debug_assertions)))] // otherwise
+            let frame = self
+            .window
+            .surface
+            .get_current_texture()
+            .expect("failed to acquire next swap chain texture");
+
+        let view = frame
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        let draw_results = self.example.draw(
+            &self.re_ctx,
+            [frame.texture.width(), frame.texture.height()],
+            &self.time,
+            self.window.scale_factor as f32,
+        );
+
+        let mirror_frame = self.mirror_frame();
+        let mirror_draw = mirror_frame.as_ref().and_then(|(frame, pixels_from_point)| {
+            self.example.draw_mirror(
+                &self.re_ctx,
+                [frame.texture.width(), frame.texture.height()],
+                *pixels_from_point,
+            )
+        });
+        let (mirror_results, mirror_blit) = match mirror_draw {
+            Some(MirrorDraw::View(draw_result)) => (vec![draw_result], None),
+            Some(MirrorDraw::Blit(blit)) => (Vec::new(), Some(blit)),
+            None => (Vec::new(), None),
+        };
 
-                    _ => {}
+        let composite_cmd_buffer = self.composite(&view, &draw_results);
+        let mirror_composite_cmd_buffer = mirror_frame
+            .as_ref()
+            .zip(self.mirror.as_ref())
+            .map(|((mirror_frame, _), mirror)| {
+                let view = mirror_frame
+                    .texture
+                    .create_view(&wgpu::TextureViewDescriptor::default());
+                match &mirror_blit {
+                    Some(blit) => mirror.blitter.blit(&self.re_ctx, &view, blit),
+                    None => self.composite(&view, &mirror_results),
                 }
-            })
-            .unwrap();
+            });
+
+        self.re_ctx.before_submit();
+        let submission = self.re_ctx.queue.submit(
+            draw_results
+                .into_iter()
+                .chain(mirror_results)
+                .map(|d| d.command_buffer)
+                .chain(std::iter::once(composite_cmd_buffer))
+                .chain(mirror_composite_cmd_buffer),
+        );
+        self.frames_in_flight.submitted(&self.re_ctx.queue, submission);
+        // The mirror first, it's the one waited for.
+        if let Some((mirror_frame, _)) = mirror_frame {
+            mirror_frame.present();
+            self.example.on_mirror_presented();
+        }
+        frame.present();
+        self.example.on_presented();
+
+        // Note that this measures time spent on CPU, not GPU
+        // However, iff we're GPU bound (likely for this sample) and GPU times are somewhat stable,
+        // we eventually end up waiting for GPU in `get_current_texture`
+        // (wgpu has a swap chain with a limited amount of buffers, the exact count is dependent on `present_mode` and backend!).
+        // It's important to keep in mind that depending on the `present_mode`, the GPU might be waiting on the screen in turn.
+        let current_time = Instant::now();
+        let time_passed = current_time - self.time.last_draw_time;
+        self.time.last_draw_time = current_time;
+        self.time.last_frame_duration = time_passed;
+        self.time.frame_index += 1;
+
+        // TODO(andreas): Display a median over n frames and while we're on it also stddev thereof.
+        // Do it only every second.
+        let time_until_next_report =
+            1.0 - self.time.seconds_since_startup().fract();
+        if time_until_next_report - time_passed.as_secs_f32() < 0.0 {
+            let time_info_str = format!(
+                "{:.2} ms ({:.2} fps)",
+                time_passed.as_secs_f32() * 1000.0,
+                1.0 / time_passed.as_secs_f32()
+            );
+        }
     }
 }
 
+/// Ends the event loop once the render thread is done, also when it panicked.
+struct ExitOnDrop(EventLoopProxy<()>);
 
-async fn run<E: Example + 'static>(event_loop: EventLoop<()>, window: Window) {
-    let app = Application::<E>::new(window).await.unwrap();
-    app.run(event_loop);
+impl Drop for ExitOnDrop {
+    fn drop(&mut self) {
+        self.0.send_event(()).ok();
+    }
 }
 
-pub fn start<E: Example + 'static>() {
+/// Opens the windows and draws `E` into them on a render thread of its own. The event loop
+/// only hands the window events over, so moving and resizing windows or menus holding it
+/// up on macOS and Windows don't hold up drawing.
+pub fn start<E: Example + 'static>(time_source: TimeSource, init: E::Init)
+where
+    E::Init: Send + 'static,
+{
     let event_loop = EventLoop::new().unwrap();
-    let window = winit::window::WindowBuilder::new()
+    let mirror_monitor = E::mirror_monitor(&event_loop);
+    let mut window_builder = winit::window::WindowBuilder::new()
         .with_title(format!("re_renderer sample - {}", E::title()))
         .with_inner_size(winit::dpi::PhysicalSize {
             width: 1920,
             height: 1080,
+        });
+    // Out of the mirror's way, also when it's on the monitor the window would open on.
+    if let Some(primary) = mirror_monitor.as_ref().and_then(|_| event_loop.primary_monitor()) {
+        window_builder = window_builder.with_position(primary.position());
+    }
+    let window = Arc::new(window_builder.build(&event_loop).unwrap());
+    window.set_content_protected(E::content_protected());
+    let mirror_window = mirror_monitor.map(|monitor| {
+        let mirror_window = winit::window::WindowBuilder::new()
+            .with_title(format!("re_renderer sample - {} mirror", E::title()))
+            .with_decorations(false)
+            .with_fullscreen(Some(Fullscreen::Borderless(Some(monitor))))
+            .build(&event_loop)
+            .unwrap();
+        mirror_window.set_content_protected(E::content_protected());
+        Arc::new(mirror_window)
+    });
+
+    // The surfaces are made here, on the main thread, as AppKit wants.
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: supported_backends(),
+        flags: wgpu::InstanceFlags::default(),
+        dx12_shader_compiler: wgpu::Dx12Compiler::Fxc,
+        gles_minor_version: wgpu::Gles3MinorVersion::Automatic,
+    });
+    let window_surface = WindowSurface::new(&instance, &window);
+    let mirror_surface = mirror_window.as_ref().map(|window| WindowSurface::new(&instance, window));
+
+    let (sender, receiver) = crossbeam_channel::unbounded();
+    let exit_on_drop = ExitOnDrop(event_loop.create_proxy());
+    let render_thread = std::thread::Builder::new()
+        .name("render".to_owned())
+        .spawn(move || {
+            let _exit_on_drop = exit_on_drop;
+            let app = pollster::block_on(Application::<E>::new(
+                instance,
+                window_surface,
+                mirror_surface,
+                time_source,
+                init,
+            ))
+            .unwrap();
+            app.run(receiver);
         })
-        .build(&event_loop)
-        .unwrap();
+        .expect("Failed to spawn the render thread");
 
-    #[cfg(not(target_arch = "wasm32"))]
-    {
-        pollster::block_on(run::<E>(event_loop, window));
-    }
+    let mirror_id = mirror_window.as_ref().map(|window| window.id());
+    let hidden_mirror = mirror_window.clone();
+    event_loop
+        .run(move |event, event_loop_window_target| match event {
+            Event::WindowEvent { window_id, event } => {
+                let is_mirror = mirror_id == Some(window_id);
+                match event {
+                    // The render thread draws all the time, without being asked to.
+                    WindowEvent::RedrawRequested => {}
+                    WindowEvent::CloseRequested if !is_mirror => {
+                        sender.send(Message::Exit).ok();
+                        event_loop_window_target.exit();
+                    }
+                    event if is_mirror => {
+                        if matches!(event, WindowEvent::CloseRequested) {
+                            if let Some(mirror_window) = &hidden_mirror {
+                                mirror_window.set_visible(false);
+                            }
+                        }
+                        sender.send(Message::Mirror(event)).ok();
+                    }
+                    event => {
+                        sender.send(Message::Window(event)).ok();
+                    }
+                }
+            }
+            Event::UserEvent(()) => event_loop_window_target.exit(),
+            _ => {}
+        })
+        .unwrap();
+    // The windows go last, dropping them on the render thread would wait for the event
+    // loop.
+    render_thread.join().ok();
+    drop(mirror_window);
+    drop(window);
 }
 
 // This allows treating the framework as a standalone example,
 // thus avoiding listing the example names in `Cargo.toml`.
 #[allow(dead_code)]
 fn main() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seconds_at(source: &TimeSource, frame_index: u64) -> f32 {
+        let mut time = Time::new(source.clone());
+        time.frame_index = frame_index;
+        time.seconds()
+    }
+
+    #[test]
+    fn fixed_step_needs_a_positive_frame_rate() {
+        assert!(TimeSource::FixedStep { fps: 60.0 }.check().is_ok());
+        for fps in [0.0, -30.0, f32::INFINITY, f32::NAN] {
+            assert!(TimeSource::FixedStep { fps }.check().is_err());
+        }
+    }
+
+    #[test]
+    fn scripted_keyframes_have_to_be_in_order() {
+        let ordered = TimeSource::Scripted { keyframes: vec![[0.0, 0.0], [10.0, 1.0]] };
+        assert!(ordered.check().is_ok());
+        let unordered = TimeSource::Scripted { keyframes: vec![[10.0, 1.0], [0.0, 0.0]] };
+        assert!(unordered.check().is_err());
+    }
+
+    #[test]
+    fn scripted_time_is_interpolated_and_held() {
+        let source = TimeSource::Scripted { keyframes: vec![[10.0, 1.0], [20.0, 3.0]] };
+        assert_eq!(seconds_at(&source, 0), 1.0);
+        assert_eq!(seconds_at(&source, 15), 2.0);
+        assert_eq!(seconds_at(&source, 30), 3.0);
+    }
+}
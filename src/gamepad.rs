@@ -0,0 +1,134 @@
+//! Gamepad control for demo setups without keyboard and mouse, enabled with `--gamepad`.
+//!
+//! The left stick moves a picking cursor over the 2D view, the right stick orbits the 3D
+//! camera and the triggers zoom the capture rect. South (A on Xbox pads) picks the pixel
+//! under the cursor like a left click, West opens the context menu there and East closes
+//! it, Start pauses the session and Select resets the camera and zoom.
+
+use gilrs::{Axis, Button, EventType, Gilrs};
+use serde::Deserialize;
+
+/// Radians (yaw) and camera heights (pitch) per second at full stick deflection.
+const ORBIT_SPEED: glam::Vec2 = glam::vec2(2.0, 1.0);
+
+/// Zoom factor per second with a trigger fully pressed.
+const ZOOM_SPEED: f32 = 2.0;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct GamepadConfig {
+    pub enabled: bool,
+
+    /// Stick deflection (0–1) below which sticks count as centered, worn sticks drift.
+    pub deadzone: f32,
+
+    /// Picking cursor speed in pixels per second at full stick deflection.
+    pub cursor_speed: f32,
+}
+
+impl Default for GamepadConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            deadzone: 0.15,
+            cursor_speed: 900.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Press {
+    Pick,
+    OpenMenu,
+    CloseMenu,
+    TogglePause,
+    ResetView,
+}
+
+/// What the gamepads did since the last [`Gamepads::poll`].
+#[derive(Debug, Default)]
+pub struct Input {
+    /// Picking cursor movement in pixels.
+    pub cursor: glam::Vec2,
+
+    /// Change of the 3D camera's yaw in radians and height in camera distances.
+    pub orbit: glam::Vec2,
+
+    /// Factor to zoom the capture rect by.
+    pub zoom: f32,
+    pub presses: Vec<Press>,
+}
+
+pub struct Gamepads {
+    gilrs: Gilrs,
+    config: GamepadConfig,
+}
+
+impl Gamepads {
+    /// `None` if the platform has no gamepad support.
+    pub fn new(config: &GamepadConfig) -> Option<Self> {
+        match Gilrs::new() {
+            Ok(gilrs) => {
+                for (_, gamepad) in gilrs.gamepads() {
+                    println!("Gamepad {:?} connected", gamepad.name());
+                }
+                Some(Self {
+                    gilrs,
+                    config: config.clone(),
+                })
+            }
+            Err(err) => {
+                eprintln!("No gamepad support: {err}");
+                None
+            }
+        }
+    }
+
+    /// Collects button presses and stick positions, held for `seconds`.
+    pub fn poll(&mut self, seconds: f32) -> Input {
+        let mut input = Input {
+            zoom: 1.0,
+            ..Input::default()
+        };
+        while let Some(gilrs::Event { id, event, .. }) = self.gilrs.next_event() {
+            let press = match event {
+                EventType::ButtonPressed(Button::South, _) => Press::Pick,
+                EventType::ButtonPressed(Button::West, _) => Press::OpenMenu,
+                EventType::ButtonPressed(Button::East, _) => Press::CloseMenu,
+                EventType::ButtonPressed(Button::Start, _) => Press::TogglePause,
+                EventType::ButtonPressed(Button::Select, _) => Press::ResetView,
+                EventType::Connected => {
+                    println!("Gamepad {:?} connected", self.gilrs.gamepad(id).name());
+                    continue;
+                }
+                EventType::Disconnected => {
+                    println!("Gamepad {:?} disconnected", self.gilrs.gamepad(id).name());
+                    continue;
+                }
+                _ => continue,
+            };
+            input.presses.push(press);
+        }
+
+        let deadzone = self.config.deadzone;
+        for (_, gamepad) in self.gilrs.gamepads() {
+            // Sticks point up for positive y, the screen's y points down.
+            let stick = |x, y| {
+                let deflection = glam::vec2(gamepad.value(x), gamepad.value(y));
+                if deflection.length() < deadzone {
+                    glam::Vec2::ZERO
+                } else {
+                    deflection
+                }
+            };
+            let left = stick(Axis::LeftStickX, Axis::LeftStickY);
+            input.cursor += glam::vec2(left.x, -left.y) * self.config.cursor_speed * seconds;
+            input.orbit += stick(Axis::RightStickX, Axis::RightStickY) * ORBIT_SPEED * seconds;
+
+            let trigger = |button| gamepad.button_data(button).map_or(0.0, |data| data.value());
+            let zoom = trigger(Button::RightTrigger2) - trigger(Button::LeftTrigger2);
+            input.zoom *= ZOOM_SPEED.powf(zoom * seconds);
+        }
+        input
+    }
+}
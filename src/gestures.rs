@@ -0,0 +1,135 @@
+//! Trackpad gestures: pinching zooms the capture rect around the fingers, scrolling with
+//! two fingers pans it and rotating turns the 3D scene with the fingers.
+//!
+//! macOS keeps scrolling after the fingers lift by itself, with momentum scroll events, so
+//! panning just follows those. Pinching and rotating have no momentum of their own,
+//! [`Momentum`] adds it so they come to rest the same way. [`ZoomPan`] is where they leave
+//! a rect.
+
+use winit::event::TouchPhase;
+
+use crate::framework::Lerp;
+
+/// Fraction of the speed left after a second without fingers on the trackpad.
+const DECAY_PER_SECOND: f32 = 0.02;
+
+/// Speed in units per second below which the gesture is at rest.
+const REST_SPEED: f32 = 0.01;
+
+/// Carries on a gesture after the fingers lift, at the speed it had, slowing down.
+#[derive(Debug, Default)]
+pub struct Momentum {
+    /// Units per second, measured while the gesture runs.
+    velocity: f32,
+
+    /// Units moved since the last [`Self::step`].
+    moved: f32,
+    active: bool,
+}
+
+impl Momentum {
+    /// Records a gesture event moving by `delta` units, which the caller applies itself.
+    pub fn update(&mut self, delta: f32, phase: TouchPhase) {
+        match phase {
+            TouchPhase::Started => *self = Self { active: true, ..Self::default() },
+            TouchPhase::Moved => self.moved += delta,
+            TouchPhase::Ended => {
+                self.moved += delta;
+                self.active = false;
+            }
+            TouchPhase::Cancelled => *self = Self::default(),
+        }
+    }
+
+    /// Units to move by after `seconds` more, zero while the fingers are still down.
+    pub fn step(&mut self, seconds: f32) -> f32 {
+        if seconds <= 0.0 {
+            return 0.0;
+        }
+        if self.active || self.moved != 0.0 {
+            // Smoothed, a single short frame would otherwise fling the view.
+            self.velocity = (self.velocity + self.moved / seconds) * 0.5;
+            self.moved = 0.0;
+            return 0.0;
+        }
+        self.velocity *= DECAY_PER_SECOND.powf(seconds);
+        if self.velocity.abs() < REST_SPEED {
+            self.velocity = 0.0;
+        }
+        self.velocity * seconds
+    }
+
+    pub fn stop(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Zoom of a rect around its center, and then a pan, as gestures leave them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ZoomPan {
+    pub zoom: f32,
+    pub pan: glam::Vec2,
+}
+
+impl Default for ZoomPan {
+    fn default() -> Self {
+        Self {
+            zoom: 1.0,
+            pan: glam::Vec2::ZERO,
+        }
+    }
+}
+
+impl ZoomPan {
+    /// Min and size of the `unzoomed` rect after zooming and panning.
+    pub fn apply(self, (min, size): (glam::Vec2, glam::Vec2)) -> (glam::Vec2, glam::Vec2) {
+        (min + size * (1.0 - self.zoom) * 0.5 + self.pan, size * self.zoom)
+    }
+
+    /// Zooms by `factor`, keeping what's at `point` there, for the rect at `unzoomed`
+    /// otherwise. Works out where the rect is from the zoom and pan, several gesture events
+    /// can come in before it's drawn again.
+    pub fn zoom_around(
+        &mut self,
+        unzoomed: (glam::Vec2, glam::Vec2),
+        factor: f32,
+        point: glam::Vec2,
+    ) {
+        let zoom = (self.zoom * factor).clamp(0.25, 4.0);
+        let (min, _) = self.apply(unzoomed);
+        let zoomed_min = point + (min - point) * (zoom / self.zoom);
+        // Zooming by itself already moves the min, around the center of the unzoomed rect.
+        self.pan += zoomed_min - min + unzoomed.1 * (zoom - self.zoom) * 0.5;
+        self.zoom = zoom;
+    }
+
+    /// The zoom and pan that fit `region` of the view, as it's shown now, into the middle
+    /// of a view of `view_size`, for the rect at `unzoomed` otherwise.
+    pub fn framing(
+        self,
+        unzoomed: (glam::Vec2, glam::Vec2),
+        region: (glam::Vec2, glam::Vec2),
+        view_size: glam::Vec2,
+    ) -> Self {
+        let (min, size) = self.apply(unzoomed);
+        // The region relative to the rect, which keeps it when zooming and panning.
+        let region_min = (region.0 - min) / size;
+        let region_size = region.1 / size;
+        let zoom = (view_size / (region_size * unzoomed.1)).min_element().clamp(0.25, 4.0);
+        let zoomed_size = unzoomed.1 * zoom;
+        let zoomed_min = view_size * 0.5 - (region_min + region_size * 0.5) * zoomed_size;
+        Self {
+            zoom,
+            pan: zoomed_min - unzoomed.0 - unzoomed.1 * (1.0 - zoom) * 0.5,
+        }
+    }
+}
+
+impl Lerp for ZoomPan {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        Self {
+            zoom: Lerp::lerp(self.zoom, to.zoom, t),
+            pan: Lerp::lerp(self.pan, to.pan, t),
+        }
+    }
+}
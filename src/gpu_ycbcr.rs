@@ -0,0 +1,279 @@
+//! Converts 4:2:0 captures to RGB on the GPU for the preview.
+//!
+//! The luma and chroma planes are uploaded as `R8Unorm` and `Rg8Unorm` textures and a
+//! fullscreen triangle renders them into an RGBA texture, with BT.709 like
+//! [`crate::pixel_format`]'s CPU conversion. The chroma is sampled bilinearly instead of per
+//! block, and the frame style's rounded corners are applied in the same pass.
+
+use re_renderer::{
+    resource_managers::GpuTexture2D, wgpu_resources::TextureDesc, RenderContext,
+};
+
+use crate::pixel_format::YCbCrPlanes;
+use crate::texture_pool::TexturePool;
+
+const OUTPUT_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+const SHADER: &str = r#"
+struct Params {
+    // Corner radius in output pixels per axis, zero for square corners.
+    radius: vec2<f32>,
+    full_range: u32,
+};
+
+@group(0) @binding(0) var luma: texture_2d<f32>;
+@group(0) @binding(1) var chroma: texture_2d<f32>;
+@group(0) @binding(2) var planes: sampler;
+@group(0) @binding(3) var<uniform> params: Params;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    out.position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+fn corner_coverage(pixel: vec2<f32>, size: vec2<f32>) -> f32 {
+    let radius = params.radius;
+    if min(radius.x, radius.y) < 1.0 {
+        return 1.0;
+    }
+    let from_edges = min(pixel, size - pixel);
+    if from_edges.x >= radius.x || from_edges.y >= radius.y {
+        return 1.0;
+    }
+    let offset = (radius - from_edges) / radius;
+    let distance_in_pixels = (length(offset) - 1.0) * min(radius.x, radius.y);
+    return clamp(0.5 - distance_in_pixels, 0.0, 1.0);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    var y = textureSample(luma, planes, in.uv).r * 255.0;
+    var c = textureSample(chroma, planes, in.uv).rg * 255.0 - 128.0;
+    if params.full_range != 0u {
+        y = y / 255.0;
+        c = c / 255.0;
+    } else {
+        y = (y - 16.0) / 219.0;
+        c = c / 224.0;
+    }
+    let rgb = vec3<f32>(
+        y + 1.5748 * c.y,
+        y - 0.1873 * c.x - 0.4681 * c.y,
+        y + 1.8556 * c.x,
+    );
+    let size = vec2<f32>(textureDimensions(luma));
+    let alpha = corner_coverage(in.position.xy, size);
+    return vec4<f32>(clamp(rgb, vec3<f32>(0.0), vec3<f32>(1.0)), alpha);
+}
+"#;
+
+pub struct YCbCrConverter {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+}
+
+impl YCbCrConverter {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("ycbcr conversion"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("ycbcr conversion"),
+            entries: &[
+                texture_entry(0),
+                texture_entry(1),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("ycbcr conversion"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("ycbcr conversion"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: OUTPUT_FORMAT,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("ycbcr planes"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        Self {
+            pipeline,
+            bind_group_layout,
+            sampler,
+        }
+    }
+
+    /// Uploads `planes` and renders them to an RGBA texture of the luma size, rounding its
+    /// corners with `corner_radius` in pixels per axis.
+    pub fn convert(
+        &self,
+        re_ctx: &RenderContext,
+        texture_pool: &mut TexturePool,
+        planes: &YCbCrPlanes,
+        corner_radius: glam::Vec2,
+    ) -> GpuTexture2D {
+        puffin::profile_function!();
+        let ([luma_width, luma_height], [chroma_width, chroma_height]) =
+            (planes.luma_size, planes.chroma_size);
+        let luma = texture_pool.upload(
+            re_ctx,
+            "luma plane",
+            &planes.luma,
+            wgpu::TextureFormat::R8Unorm,
+            luma_width as u32,
+            luma_height as u32,
+        );
+        let chroma_data = planes.chroma.iter().flatten().copied().collect::<Vec<_>>();
+        let chroma = texture_pool.upload(
+            re_ctx,
+            "chroma plane",
+            &chroma_data,
+            wgpu::TextureFormat::Rg8Unorm,
+            chroma_width as u32,
+            chroma_height as u32,
+        );
+
+        // Freed output textures of the same size are recycled by re_renderer's pool.
+        let output = re_ctx.gpu_resources.textures.alloc(
+            &re_ctx.device,
+            &TextureDesc {
+                label: "converted ycbcr texture".into(),
+                size: wgpu::Extent3d {
+                    width: luma_width as u32,
+                    height: luma_height as u32,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: OUTPUT_FORMAT,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING
+                    | wgpu::TextureUsages::RENDER_ATTACHMENT,
+            },
+        );
+
+        let mut params = Vec::with_capacity(16);
+        params.extend_from_slice(&corner_radius.x.to_le_bytes());
+        params.extend_from_slice(&corner_radius.y.to_le_bytes());
+        params.extend_from_slice(&u32::from(planes.full_range).to_le_bytes());
+        // Uniform buffers are sized in multiples of 16 bytes.
+        params.extend_from_slice(&[0; 4]);
+        let params_buffer = re_ctx.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("ycbcr conversion params"),
+            size: params.len() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        re_ctx.queue.write_buffer(&params_buffer, 0, &params);
+
+        let bind_group = re_ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("ycbcr conversion"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&luma.default_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&chroma.default_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: params_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut encoder = re_ctx.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("ycbcr conversion"),
+        });
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("ycbcr conversion"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &output.default_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.draw(0..3, 0..1);
+        }
+        // Submitted right away, so the texture is ready before the frame's draw data is.
+        re_ctx.queue.submit([encoder.finish()]);
+
+        GpuTexture2D::new(output).expect("Converted ycbcr texture is 2D")
+    }
+}
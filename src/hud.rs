@@ -0,0 +1,284 @@
+//! The 2D view's HUD: one line text widgets, stacked in the top left and top right corner
+//! in the order of [`Widget::ALL`]. Parts of the example show their widget every frame
+//! they have something to say, [`Hud::rects`] lays out the ones shown since the last call.
+//! `[hud] widgets` picks the ones that are shown at all.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::path::Path;
+
+use re_renderer::{renderer::TexturedRect, RenderContext};
+use serde::Deserialize;
+
+use crate::text;
+use crate::texture_pool::PoolStats;
+
+/// Distance of the widgets from the view's edges and between them, in points.
+const MARGIN: f32 = 16.0;
+
+/// Points per font pixel.
+const SCALE: f32 = 2.0;
+
+/// Frames the dedup ratio looks back on.
+const HISTORY: usize = 120;
+
+/// Bars of the latency graph, one per new frame.
+const GRAPH_BARS: usize = 32;
+
+/// Block elements from low to full, for the latency graph.
+const BAR_GLYPHS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+const WHITE: [u8; 4] = [255, 255, 255, 255];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Widget {
+    /// Live stream health, while streaming.
+    Stream,
+
+    /// Frame step state, while paused on a frame.
+    FrameStep,
+
+    /// Session pause banner.
+    Pause,
+
+    /// File being recorded to.
+    Recording,
+
+    /// Region the capture is cropped to, or selecting one.
+    Crop,
+
+    /// Position in the frame history, while scrubbing.
+    History,
+
+    /// Text sent to `POST /api/hud`, e.g. by scripts.
+    Script,
+
+    /// Overlay work skipped to stay within the frame budget.
+    OverlayLod,
+
+    /// What the active profile changes.
+    Profile,
+
+    /// Frames in flight, with `--max-frames-in-flight`.
+    FramesInFlight,
+    Fps,
+
+    /// Preview latency of the latest new frames, as bar graph.
+    Latency,
+
+    /// GPU memory of the pooled textures.
+    Memory,
+
+    /// Share of the latest frames drawn without uploading the capture again.
+    Dedup,
+
+    /// Frames produced, drawn and dropped, latency and upload time, with a bar graph of
+    /// the latest frames, see [`crate::pipeline_stats`].
+    Pipeline,
+}
+
+impl Widget {
+    pub const ALL: [Self; 15] = [
+        Self::Stream,
+        Self::FrameStep,
+        Self::Pause,
+        Self::Recording,
+        Self::Crop,
+        Self::History,
+        Self::Script,
+        Self::OverlayLod,
+        Self::Profile,
+        Self::FramesInFlight,
+        Self::Fps,
+        Self::Latency,
+        Self::Memory,
+        Self::Dedup,
+        Self::Pipeline,
+    ];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|widget| widget.name() == name)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Stream => "stream",
+            Self::FrameStep => "frame_step",
+            Self::Pause => "pause",
+            Self::Recording => "recording",
+            Self::Crop => "crop",
+            Self::History => "history",
+            Self::Script => "script",
+            Self::OverlayLod => "overlay_lod",
+            Self::Profile => "profile",
+            Self::FramesInFlight => "frames_in_flight",
+            Self::Fps => "fps",
+            Self::Latency => "latency",
+            Self::Memory => "memory",
+            Self::Dedup => "dedup",
+            Self::Pipeline => "pipeline",
+        }
+    }
+
+    /// Session state first, at the left, numbers at the right.
+    fn is_left(self) -> bool {
+        self <= Self::Script
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct HudConfig {
+    /// Widgets shown in the 2D view, in any order.
+    pub widgets: Vec<Widget>,
+}
+
+impl Default for HudConfig {
+    fn default() -> Self {
+        Self {
+            widgets: vec![
+                Widget::Stream,
+                Widget::FrameStep,
+                Widget::Pause,
+                Widget::Crop,
+                Widget::History,
+                Widget::Script,
+                Widget::OverlayLod,
+                Widget::Profile,
+                Widget::FramesInFlight,
+            ],
+        }
+    }
+}
+
+/// What the statistics widgets show, gathered once per frame.
+pub struct FrameStats<'a> {
+    pub fps: f32,
+    pub frame_ms: f32,
+
+    /// Frame id and preview latency of the latest new frame.
+    pub preview_latency: Option<(u64, f32)>,
+    pub texture_pool: PoolStats,
+    pub recording: Option<&'a Path>,
+}
+
+pub struct Hud {
+    widgets: Vec<Widget>,
+    shown: Vec<(Widget, String, [u8; 4])>,
+    rects: BTreeMap<Widget, text::TextRect>,
+
+    /// Latency of the latest new frames, oldest first, with the id of the latest.
+    latencies: VecDeque<f32>,
+    latency_frame_id: Option<u64>,
+
+    /// Whether the latest frames kept the capture texture, oldest first.
+    kept_frames: VecDeque<bool>,
+    script_text: Option<String>,
+}
+
+impl Hud {
+    pub fn new(config: &HudConfig) -> Self {
+        Self {
+            widgets: config.widgets.clone(),
+            shown: Vec::new(),
+            rects: BTreeMap::new(),
+            latencies: VecDeque::with_capacity(GRAPH_BARS),
+            latency_frame_id: None,
+            kept_frames: VecDeque::with_capacity(HISTORY),
+            script_text: None,
+        }
+    }
+
+    pub fn is_enabled(&self, widget: Widget) -> bool {
+        self.widgets.contains(&widget)
+    }
+
+    /// Shows `widget` with `text` this frame, unless it's turned off.
+    pub fn show(&mut self, widget: Widget, text: impl Into<String>, color: [u8; 4]) {
+        if self.is_enabled(widget) {
+            self.shown.push((widget, text.into(), color));
+        }
+    }
+
+    /// Text of the script widget, an empty one hides it.
+    pub fn set_script_text(&mut self, text: String) {
+        self.script_text = (!text.trim().is_empty()).then_some(text);
+    }
+
+    /// Shows the recording, script and statistics widgets.
+    pub fn show_stats(&mut self, stats: &FrameStats) {
+        if let Some(path) = stats.recording {
+            let name = path.file_name().unwrap_or(path.as_os_str()).to_string_lossy();
+            self.show(Widget::Recording, format!("REC {name}"), [255, 80, 80, 255]);
+        }
+        if let Some(text) = self.script_text.clone() {
+            self.show(Widget::Script, text, WHITE);
+        }
+        let fps_text = format!("{:.0} fps, {:.2} ms", stats.fps, stats.frame_ms);
+        self.show(Widget::Fps, fps_text, WHITE);
+
+        if let Some((frame_id, latency_ms)) = stats.preview_latency {
+            if self.latency_frame_id != Some(frame_id) {
+                self.latency_frame_id = Some(frame_id);
+                if self.latencies.len() == GRAPH_BARS {
+                    self.latencies.pop_front();
+                }
+                self.latencies.push_back(latency_ms);
+            }
+        }
+        if let Some(&latest) = self.latencies.back() {
+            let text = format!("latency {} {latest:.1} ms", self.latency_graph());
+            self.show(Widget::Latency, text, WHITE);
+        }
+
+        let pool = stats.texture_pool;
+        let memory_mb = pool.bytes as f64 / 1e6;
+        let memory_text = format!("{memory_mb:.1} MB in {} pooled textures", pool.textures);
+        self.show(Widget::Memory, memory_text, WHITE);
+
+        if self.kept_frames.len() == HISTORY {
+            self.kept_frames.pop_front();
+        }
+        self.kept_frames.push_back(pool.kept_this_frame > 0);
+        let kept = self.kept_frames.iter().filter(|kept| **kept).count();
+        let percent = kept as f32 * 100.0 / self.kept_frames.len() as f32;
+        let frames = self.kept_frames.len();
+        let dedup_text = format!("dedup {percent:.0} % of {frames} frames without an upload");
+        self.show(Widget::Dedup, dedup_text, WHITE);
+    }
+
+    /// One bar per latency, scaled to the highest.
+    fn latency_graph(&self) -> String {
+        let max = self.latencies.iter().copied().fold(f32::EPSILON, f32::max);
+        self.latencies
+            .iter()
+            .map(|latency| {
+                let level = (latency / max * (BAR_GLYPHS.len() - 1) as f32).round() as usize;
+                BAR_GLYPHS[level.min(BAR_GLYPHS.len() - 1)]
+            })
+            .collect()
+    }
+
+    /// Rects of the widgets shown since the last call, stacked in the corners of a view
+    /// of `screen_size`.
+    pub fn rects(&mut self, re_ctx: &RenderContext, screen_size: glam::Vec2) -> Vec<TexturedRect> {
+        let mut shown = std::mem::take(&mut self.shown);
+        shown.sort_by_key(|(widget, ..)| *widget);
+        let (mut left_y, mut right_y) = (MARGIN, MARGIN);
+        shown
+            .into_iter()
+            .map(|(widget, text, color)| {
+                let (x, y) = if widget.is_left() {
+                    (MARGIN, &mut left_y)
+                } else {
+                    (screen_size.x * 0.5 + MARGIN, &mut right_y)
+                };
+                let position = glam::vec3(x, *y, 0.0);
+                let text_rect = self.rects.entry(widget).or_default();
+                let rect = text_rect.rect(re_ctx, &text, color, position, SCALE);
+                *y += rect.extent_v.y + MARGIN;
+                rect
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,124 @@
+//! Debug view showing one of the intermediate textures a frame is drawn from, e.g. the
+//! styled capture or the blurred background on their own.
+//!
+//! Textures are shown fitted into the 2D view, optionally with their values stretched from
+//! the measured range to the full range so dark or low contrast content becomes visible.
+
+use re_renderer::{
+    renderer::{ColormappedTexture, RectangleOptions, TextureFilterMag, TextureFilterMin, TexturedRect},
+    resource_managers::GpuTexture2D,
+    RenderContext,
+};
+
+use crate::text::TextRect;
+
+/// Space between the inspected texture and the view border, in scene units.
+const MARGIN: f32 = 40.0;
+
+/// An intermediate texture of the current frame.
+pub struct Stage {
+    pub name: &'static str,
+    pub texture: GpuTexture2D,
+
+    /// Smallest and largest color value the texture contains, if known.
+    pub value_range: Option<[f32; 2]>,
+}
+
+#[derive(Default)]
+pub struct Inspector {
+    /// Index of the inspected stage, hidden if `None`.
+    selected: Option<usize>,
+    normalize: bool,
+    label: TextRect,
+}
+
+impl Inspector {
+    pub fn is_enabled(&self) -> bool {
+        self.selected.is_some()
+    }
+
+    /// Selects the next stage, hides the inspector after the last one.
+    pub fn cycle(&mut self) {
+        self.selected = Some(self.selected.map_or(0, |selected| selected + 1));
+    }
+
+    pub fn toggle_normalize(&mut self) {
+        self.normalize = !self.normalize;
+    }
+
+    /// Rects showing the selected stage with its label and `details` below it, in a view of
+    /// `view_size`.
+    pub fn rects(
+        &mut self,
+        re_ctx: &RenderContext,
+        stages: &[Stage],
+        details: &str,
+        view_size: glam::Vec2,
+    ) -> Vec<TexturedRect> {
+        let Some(selected) = self.selected else {
+            return Vec::new();
+        };
+        let Some(stage) = stages.get(selected) else {
+            self.selected = None;
+            return Vec::new();
+        };
+
+        let range = match stage.value_range {
+            Some([min, max]) if self.normalize && max > min => [min, max],
+            _ => [0.0, 1.0],
+        };
+        let label = format!(
+            "{} ({}/{})  {}x{} {:?}  range [{:.3}, {:.3}]{}  I: next  O: normalize\n{details}",
+            stage.name,
+            selected + 1,
+            stages.len(),
+            stage.texture.width(),
+            stage.texture.height(),
+            stage.texture.format(),
+            range[0],
+            range[1],
+            if self.normalize { " normalized" } else { "" },
+        );
+
+        let texture_size = glam::vec2(stage.texture.width() as f32, stage.texture.height() as f32);
+        let available = (view_size - 2.0 * MARGIN).max(glam::Vec2::ONE);
+        let size = texture_size * (available / texture_size).min_element();
+        let min = (view_size - size) * 0.5;
+        let mut colormapped_texture = ColormappedTexture::from_unorm_rgba(stage.texture.clone());
+        colormapped_texture.range = range;
+        // Show the color channels as they are, alpha is often zero outside of the shape.
+        colormapped_texture.multiply_rgb_with_alpha = false;
+
+        vec![
+            TexturedRect {
+                top_left_corner_position: min.extend(0.0),
+                extent_u: size.x * glam::Vec3::X,
+                extent_v: size.y * glam::Vec3::Y,
+                colormapped_texture,
+                options: RectangleOptions {
+                    texture_filter_magnification: TextureFilterMag::Nearest,
+                    texture_filter_minification: TextureFilterMin::Linear,
+                    ..Default::default()
+                },
+            },
+            self.label.rect(
+                re_ctx,
+                &label,
+                [255, 255, 255, 255],
+                glam::vec3(MARGIN, view_size.y - MARGIN, 0.0),
+                2.0,
+            ),
+        ]
+    }
+}
+
+/// Smallest and largest color channel value of `pixels`, sampled.
+pub fn value_range(pixels: &[[u8; 4]]) -> [f32; 2] {
+    puffin::profile_function!();
+    let (min, max) = pixels
+        .iter()
+        .step_by(7)
+        .flat_map(|pixel| &pixel[..3])
+        .fold((u8::MAX, u8::MIN), |(min, max), &value| (min.min(value), max.max(value)));
+    [min as f32 / 255.0, max as f32 / 255.0]
+}
@@ -0,0 +1,129 @@
+//! Worker pool for the disk writes that happen next to capturing: finalizing recordings,
+//! screenshots and small bookkeeping files. Jobs queue per [`Priority`] and the workers
+//! always take the most important one, so a slow disk delays the bookkeeping before it
+//! delays a recording.
+//!
+//! The queues are bounded: when one is full, screenshots and bookkeeping are dropped with
+//! an `io_job_dropped` event. Recordings are never dropped, an overflowing one gets a thread
+//! of its own instead. Streaming writes (encoders, the MCAP export) keep their own threads.
+
+use std::collections::VecDeque;
+use std::sync::{Condvar, Mutex};
+
+use once_cell::sync::Lazy;
+
+use crate::events::{self, Severity};
+use crate::status;
+
+const WORKERS: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    /// Small files like the session state or benchmark cache, rewritten when they change.
+    Stats,
+    Screenshot,
+
+    /// Finalizing a recording or its sidecars.
+    Recording,
+}
+
+impl Priority {
+    const ALL: [Priority; 3] = [Priority::Recording, Priority::Screenshot, Priority::Stats];
+
+    /// Jobs queued at most before new ones overflow.
+    fn capacity(self) -> usize {
+        match self {
+            Priority::Stats => 4,
+            Priority::Screenshot => 8,
+            Priority::Recording => 4,
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+#[derive(Default)]
+struct Queues {
+    /// Queue per priority, indexed by [`Priority::index`].
+    jobs: [VecDeque<(&'static str, Job)>; 3],
+}
+
+impl Queues {
+    fn next(&mut self) -> Option<(&'static str, Job)> {
+        Priority::ALL
+            .iter()
+            .find_map(|priority| self.jobs[priority.index()].pop_front())
+    }
+
+    fn len(&self) -> usize {
+        self.jobs.iter().map(VecDeque::len).sum()
+    }
+}
+
+struct Pool {
+    queues: Mutex<Queues>,
+    job_queued: Condvar,
+}
+
+static POOL: Lazy<&'static Pool> = Lazy::new(|| {
+    let pool: &'static Pool = Box::leak(Box::new(Pool {
+        queues: Mutex::default(),
+        job_queued: Condvar::new(),
+    }));
+    for index in 0..WORKERS {
+        std::thread::Builder::new()
+            .name(format!("io worker {index}"))
+            .spawn(move || pool.work())
+            .expect("Failed to spawn an I/O worker thread");
+    }
+    pool
+});
+
+impl Pool {
+    fn work(&self) {
+        loop {
+            let mut queues = self.queues.lock().unwrap();
+            let (name, job) = loop {
+                match queues.next() {
+                    Some(next) => break next,
+                    None => queues = self.job_queued.wait(queues).unwrap(),
+                }
+            };
+            let queued = queues.len();
+            drop(queues);
+            status::update(|status| status.io_jobs_queued = queued);
+            puffin::profile_scope!("io job", name);
+            job();
+        }
+    }
+}
+
+/// Runs `job`, named `name` for reporting, on a worker once no more important job waits.
+pub fn submit(priority: Priority, name: &'static str, job: impl FnOnce() + Send + 'static) {
+    let mut queues = POOL.queues.lock().unwrap();
+    let queue = &mut queues.jobs[priority.index()];
+    if queue.len() < priority.capacity() {
+        queue.push_back((name, Box::new(job)));
+        let queued = queues.len();
+        drop(queues);
+        POOL.job_queued.notify_one();
+        status::update(|status| status.io_jobs_queued = queued);
+        return;
+    }
+    drop(queues);
+
+    if priority == Priority::Recording {
+        std::thread::Builder::new()
+            .name(name.to_owned())
+            .spawn(job)
+            .expect("Failed to spawn an overflow I/O thread");
+    } else {
+        status::update(|status| status.io_jobs_dropped += 1);
+        let message = format!("{name}: the {priority:?} queue is full, the disk is too slow");
+        events::emit(Severity::Warning, "io_job_dropped", "io_pool", &message);
+    }
+}
@@ -0,0 +1,375 @@
+//! Key bindings of the window, remappable in the `[keys]` section of the config:
+//!
+//! ```toml
+//! [keys]
+//! screenshot = "f12"
+//! toggle_recording = "ctrl+r"
+//! ```
+//!
+//! Bindings match the character a key types on the active layout, without modifiers, so
+//! `toggle_recording = "r"` is the key labeled R wherever it is. Layouts without Latin
+//! letters, e.g. Cyrillic or Greek, fall back to the key at that letter's position on a US
+//! layout. The digit keys, which capture a display and switch presets, go by position on
+//! every layout, since some type digits only with Shift.
+
+use std::collections::BTreeMap;
+
+use anyhow::Context as _;
+use serde::Deserialize;
+use winit::keyboard::{Key, KeyCode, ModifiersState, NamedKey, PhysicalKey};
+use winit::platform::modifier_supplement::KeyEventExtModifierSupplement as _;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Undo,
+    Redo,
+    ToggleHighContrast,
+    ToggleReducedMotion,
+    CycleBackground,
+    Screenshot,
+    ToggleRecording,
+    Marker,
+    ToggleStreaming,
+    CycleFeedSource,
+    InspectNextTexture,
+    ToggleNormalize,
+    ToggleFrameStep,
+    StepFrame,
+    CycleBackend,
+    ToggleCursor,
+    CycleProfile,
+    TogglePause,
+    ToggleViewLink,
+    CloseMenu,
+    Crop,
+    ScrubBack,
+    ScrubForward,
+}
+
+impl Action {
+    const ALL: [Self; 23] = [
+        Self::Undo,
+        Self::Redo,
+        Self::ToggleHighContrast,
+        Self::ToggleReducedMotion,
+        Self::CycleBackground,
+        Self::Screenshot,
+        Self::ToggleRecording,
+        Self::Marker,
+        Self::ToggleStreaming,
+        Self::CycleFeedSource,
+        Self::InspectNextTexture,
+        Self::ToggleNormalize,
+        Self::ToggleFrameStep,
+        Self::StepFrame,
+        Self::CycleBackend,
+        Self::ToggleCursor,
+        Self::CycleProfile,
+        Self::TogglePause,
+        Self::ToggleViewLink,
+        Self::CloseMenu,
+        Self::Crop,
+        Self::ScrubBack,
+        Self::ScrubForward,
+    ];
+
+    /// Name in the `[keys]` section.
+    fn name(self) -> &'static str {
+        match self {
+            Self::Undo => "undo",
+            Self::Redo => "redo",
+            Self::ToggleHighContrast => "toggle_high_contrast",
+            Self::ToggleReducedMotion => "toggle_reduced_motion",
+            Self::CycleBackground => "cycle_background",
+            Self::Screenshot => "screenshot",
+            Self::ToggleRecording => "toggle_recording",
+            Self::Marker => "marker",
+            Self::ToggleStreaming => "toggle_streaming",
+            Self::CycleFeedSource => "cycle_feed_source",
+            Self::InspectNextTexture => "inspect_next_texture",
+            Self::ToggleNormalize => "toggle_normalize",
+            Self::ToggleFrameStep => "toggle_frame_step",
+            Self::StepFrame => "step_frame",
+            Self::CycleBackend => "cycle_backend",
+            Self::ToggleCursor => "toggle_cursor",
+            Self::CycleProfile => "cycle_profile",
+            Self::TogglePause => "toggle_pause",
+            Self::ToggleViewLink => "toggle_view_link",
+            Self::CloseMenu => "close_menu",
+            Self::Crop => "crop",
+            Self::ScrubBack => "scrub_back",
+            Self::ScrubForward => "scrub_forward",
+        }
+    }
+
+    fn default_binding(self) -> &'static str {
+        match self {
+            Self::Undo => "ctrl+z",
+            Self::Redo => "shift+ctrl+z",
+            Self::ToggleHighContrast => "h",
+            Self::ToggleReducedMotion => "m",
+            Self::CycleBackground => "b",
+            Self::Screenshot => "s",
+            Self::ToggleRecording => "r",
+            Self::Marker => "k",
+            Self::ToggleStreaming => "l",
+            Self::CycleFeedSource => "v",
+            Self::InspectNextTexture => "i",
+            Self::ToggleNormalize => "o",
+            Self::ToggleFrameStep => "p",
+            Self::StepFrame => "n",
+            Self::CycleBackend => "c",
+            Self::ToggleCursor => "x",
+            Self::CycleProfile => "f",
+            Self::TogglePause => "space",
+            Self::ToggleViewLink => "u",
+            Self::CloseMenu => "escape",
+            Self::Crop => "t",
+            Self::ScrubBack => "left",
+            Self::ScrubForward => "right",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum BindingKey {
+    /// Lowercase character typed by the key.
+    Character(String),
+    Named(NamedKey),
+}
+
+/// A key with the modifiers that have to be held, no more and no less.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Binding {
+    key: BindingKey,
+    ctrl: bool,
+    shift: bool,
+    alt: bool,
+}
+
+impl Binding {
+    /// Parses e.g. `s`, `space`, `f5` or `shift+ctrl+z`.
+    fn parse(text: &str) -> anyhow::Result<Self> {
+        let mut parts = text.split('+').map(str::trim).collect::<Vec<_>>();
+        let key = parts.pop().filter(|key| !key.is_empty()).context("no key")?.to_lowercase();
+        let key = match named_key(&key) {
+            Some(named) => BindingKey::Named(named),
+            None if key.chars().count() == 1 => {
+                anyhow::ensure!(
+                    !key.chars().all(|c| c.is_ascii_digit()),
+                    "the digit keys are taken by display and preset switching"
+                );
+                BindingKey::Character(key)
+            }
+            None => anyhow::bail!("unknown key {key:?}"),
+        };
+        let (mut ctrl, mut shift, mut alt) = (false, false, false);
+        for modifier in parts {
+            match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => ctrl = true,
+                "shift" => shift = true,
+                "alt" | "option" => alt = true,
+                _ => anyhow::bail!("unknown modifier {modifier:?}"),
+            }
+        }
+        Ok(Self { key, ctrl, shift, alt })
+    }
+
+    fn matches_key(&self, key: &Key, physical_key: PhysicalKey) -> bool {
+        match (&self.key, key) {
+            (BindingKey::Named(named), Key::Named(pressed)) => named == pressed,
+            (BindingKey::Character(c), Key::Character(pressed)) => {
+                let pressed = pressed.to_lowercase();
+                if pressed.is_ascii() {
+                    return *c == pressed;
+                }
+                // Not a Latin letter, e.g. on a Cyrillic layout, go by position instead.
+                us_layout_position(c).is_some_and(|code| physical_key == PhysicalKey::Code(code))
+            }
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for Binding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (held, name) in [(self.shift, "shift"), (self.ctrl, "ctrl"), (self.alt, "alt")] {
+            if held {
+                write!(f, "{name}+")?;
+            }
+        }
+        match &self.key {
+            BindingKey::Character(c) => write!(f, "{c}"),
+            BindingKey::Named(named) => write!(f, "{named:?}"),
+        }
+    }
+}
+
+fn named_key(name: &str) -> Option<NamedKey> {
+    Some(match name {
+        "space" => NamedKey::Space,
+        "escape" | "esc" => NamedKey::Escape,
+        "tab" => NamedKey::Tab,
+        "enter" | "return" => NamedKey::Enter,
+        "backspace" => NamedKey::Backspace,
+        "delete" => NamedKey::Delete,
+        "home" => NamedKey::Home,
+        "end" => NamedKey::End,
+        "pageup" => NamedKey::PageUp,
+        "pagedown" => NamedKey::PageDown,
+        "left" => NamedKey::ArrowLeft,
+        "right" => NamedKey::ArrowRight,
+        "up" => NamedKey::ArrowUp,
+        "down" => NamedKey::ArrowDown,
+        "f1" => NamedKey::F1,
+        "f2" => NamedKey::F2,
+        "f3" => NamedKey::F3,
+        "f4" => NamedKey::F4,
+        "f5" => NamedKey::F5,
+        "f6" => NamedKey::F6,
+        "f7" => NamedKey::F7,
+        "f8" => NamedKey::F8,
+        "f9" => NamedKey::F9,
+        "f10" => NamedKey::F10,
+        "f11" => NamedKey::F11,
+        "f12" => NamedKey::F12,
+        _ => return None,
+    })
+}
+
+/// Position of a Latin letter on a US layout.
+fn us_layout_position(letter: &str) -> Option<KeyCode> {
+    Some(match letter {
+        "a" => KeyCode::KeyA,
+        "b" => KeyCode::KeyB,
+        "c" => KeyCode::KeyC,
+        "d" => KeyCode::KeyD,
+        "e" => KeyCode::KeyE,
+        "f" => KeyCode::KeyF,
+        "g" => KeyCode::KeyG,
+        "h" => KeyCode::KeyH,
+        "i" => KeyCode::KeyI,
+        "j" => KeyCode::KeyJ,
+        "k" => KeyCode::KeyK,
+        "l" => KeyCode::KeyL,
+        "m" => KeyCode::KeyM,
+        "n" => KeyCode::KeyN,
+        "o" => KeyCode::KeyO,
+        "p" => KeyCode::KeyP,
+        "q" => KeyCode::KeyQ,
+        "r" => KeyCode::KeyR,
+        "s" => KeyCode::KeyS,
+        "t" => KeyCode::KeyT,
+        "u" => KeyCode::KeyU,
+        "v" => KeyCode::KeyV,
+        "w" => KeyCode::KeyW,
+        "x" => KeyCode::KeyX,
+        "y" => KeyCode::KeyY,
+        "z" => KeyCode::KeyZ,
+        _ => return None,
+    })
+}
+
+pub struct Keymap {
+    bindings: Vec<(Binding, Action)>,
+}
+
+impl Keymap {
+    /// The default bindings with `remapped` ones replacing theirs. Fails if a binding
+    /// doesn't parse or two actions end up on the same key.
+    pub fn new(remapped: &BTreeMap<Action, String>) -> anyhow::Result<Self> {
+        let mut bindings: Vec<(Binding, Action)> = Vec::new();
+        for action in Action::ALL {
+            let text = remapped.get(&action).map_or(action.default_binding(), String::as_str);
+            let name = action.name();
+            let binding = Binding::parse(text)
+                .with_context(|| format!("invalid key binding {text:?} for `keys.{name}`"))?;
+            if let Some((_, other)) = bindings.iter().find(|(bound, _)| *bound == binding) {
+                let other = other.name();
+                anyhow::bail!("{binding} is bound to both `keys.{other}` and `keys.{name}`");
+            }
+            bindings.push((binding, action));
+        }
+        Ok(Self { bindings })
+    }
+
+    /// The action bound to the key of `event` with `modifiers` held.
+    pub fn action(
+        &self,
+        event: &winit::event::KeyEvent,
+        modifiers: ModifiersState,
+    ) -> Option<Action> {
+        let key = event.key_without_modifiers();
+        self.bindings
+            .iter()
+            .find(|(binding, _)| {
+                binding.ctrl == modifiers.control_key()
+                    && binding.shift == modifiers.shift_key()
+                    && binding.alt == modifiers.alt_key()
+                    && binding.matches_key(&key, event.physical_key)
+            })
+            .map(|(_, action)| *action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binding(key: BindingKey, [ctrl, shift, alt]: [bool; 3]) -> Binding {
+        Binding { key, ctrl, shift, alt }
+    }
+
+    #[test]
+    fn bindings_parse_keys_and_modifiers() {
+        let character = |c: &str| BindingKey::Character(c.to_owned());
+        assert_eq!(Binding::parse("s").unwrap(), binding(character("s"), [false; 3]));
+        assert_eq!(
+            Binding::parse("Shift + Ctrl+Z").unwrap(),
+            binding(character("z"), [true, true, false])
+        );
+        assert_eq!(
+            Binding::parse("option+f5").unwrap(),
+            binding(BindingKey::Named(NamedKey::F5), [false, false, true])
+        );
+        assert_eq!(
+            Binding::parse("space").unwrap(),
+            binding(BindingKey::Named(NamedKey::Space), [false; 3])
+        );
+    }
+
+    #[test]
+    fn invalid_bindings_are_rejected() {
+        for text in ["", "ctrl+", "1", "shift+5", "meta+s", "nokey"] {
+            assert!(Binding::parse(text).is_err(), "{text:?} parsed");
+        }
+    }
+
+    #[test]
+    fn bindings_display_like_they_parse() {
+        let binding = Binding::parse("ctrl+shift+z").unwrap();
+        assert_eq!(binding.to_string(), "shift+ctrl+z");
+        assert_eq!(Binding::parse(&binding.to_string()).unwrap(), binding);
+    }
+
+    #[test]
+    fn characters_fall_back_to_the_us_layout_position() {
+        let binding = Binding::parse("r").unwrap();
+        let (r_key, t_key) = (PhysicalKey::Code(KeyCode::KeyR), PhysicalKey::Code(KeyCode::KeyT));
+        assert!(binding.matches_key(&Key::Character("R".into()), r_key));
+        // Cyrillic ka is on the R key of a Russian layout.
+        assert!(binding.matches_key(&Key::Character("к".into()), r_key));
+        assert!(!binding.matches_key(&Key::Character("t".into()), r_key));
+        assert!(!binding.matches_key(&Key::Character("к".into()), t_key));
+    }
+
+    #[test]
+    fn remapping_onto_a_taken_key_fails() {
+        assert!(Keymap::new(&BTreeMap::new()).is_ok());
+        let remapped = BTreeMap::from([(Action::Screenshot, "f12".to_owned())]);
+        assert!(Keymap::new(&remapped).is_ok());
+        let remapped = BTreeMap::from([(Action::Screenshot, "h".to_owned())]);
+        assert!(Keymap::new(&remapped).is_err());
+    }
+}
@@ -0,0 +1,149 @@
+//! `--launch`: starts the application to capture, waits for its first window, captures
+//! it and stops capturing (and recording) once the application exits. Meant for recording
+//! automated UI tests.
+//!
+//! Windows are matched by process id, so the command has to run the application binary
+//! itself, e.g. `/Applications/Foo.app/Contents/MacOS/Foo`, not `open -a Foo`, which
+//! returns right away.
+
+use std::process::{Child, Command};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Context as _;
+use serde::Deserialize;
+
+use crate::capture::{self, Frame};
+use crate::control::{self, ControlCommand};
+use crate::{capture_manager, status};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LaunchConfig {
+    /// Shell command starting the application to capture.
+    pub command: Option<String>,
+
+    /// How long to wait for the application's first window.
+    pub window_timeout_seconds: f32,
+
+    /// Record from the moment the window shows up until the application exits.
+    pub record: bool,
+}
+
+impl Default for LaunchConfig {
+    fn default() -> Self {
+        Self {
+            command: None,
+            window_timeout_seconds: 30.0,
+            record: false,
+        }
+    }
+}
+
+/// Launches `config.command` and captures its window on a background thread.
+pub fn start(
+    runtime: tokio::runtime::Handle,
+    config: LaunchConfig,
+    on_frame: impl FnMut(Frame) + Clone + Send + 'static,
+) {
+    let Some(command) = config.command.clone() else {
+        return;
+    };
+    status::update(|status| {
+        status.source = format!("launched {command:?}");
+        status.source_name = "launch".to_owned();
+    });
+    std::thread::Builder::new()
+        .name("launch".to_owned())
+        .spawn(move || {
+            if let Err(err) = run(&runtime, &command, &config, on_frame) {
+                eprintln!("Launching {command:?}: {err:#}");
+            }
+        })
+        .expect("Failed to spawn the launch thread");
+}
+
+fn run(
+    runtime: &tokio::runtime::Handle,
+    command: &str,
+    config: &LaunchConfig,
+    mut on_frame: impl FnMut(Frame) + Clone + Send + 'static,
+) -> anyhow::Result<()> {
+    let timeout = Duration::try_from_secs_f32(config.window_timeout_seconds)
+        .context("invalid [launch] window_timeout_seconds")?;
+    let owner = capture_manager::owner();
+    // `exec` keeps the process id of the shell, which is what windows are matched by.
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(format!("exec {command}"))
+        .spawn()
+        .context("failed to start")?;
+    let pid = child.id() as i32;
+    println!("Launched {command:?} as process {pid}");
+
+    if let Err(err) = wait_for_window(runtime, &mut child, timeout) {
+        // Nobody would stop an application that isn't captured.
+        child.kill().ok();
+        child.wait().ok();
+        return Err(err);
+    }
+    // Recording needs the frame size, so it starts with the first frame. The stream is
+    // restarted with a clone of `on_frame` when the window is resized, hence the flag.
+    let start_recording = Arc::new(AtomicBool::new(config.record));
+    let on_frame = move |frame| {
+        on_frame(frame);
+        if start_recording.swap(false, Ordering::Relaxed) {
+            control::send(ControlCommand::StartRecording);
+        }
+    };
+
+    let mut exited = Ok(None);
+    capture::keep_following(
+        runtime,
+        &format!("window of {command:?}"),
+        |window| window.application().pid() == pid,
+        on_frame,
+        || {
+            exited = child.try_wait();
+            matches!(exited, Ok(None)) && owner.wait(POLL_INTERVAL)
+        },
+    );
+    let Some(exit_status) = exited.context("failed to wait for the process")? else {
+        // Closing the window leaves the application running.
+        return Ok(());
+    };
+    println!("{command:?} exited ({exit_status}), stopping capture");
+    control::send(ControlCommand::StopRecording);
+    status::update(|status| status.source = format!("{command:?} exited ({exit_status})"));
+    Ok(())
+}
+
+/// Waits up to `timeout` for the first window of `child`.
+fn wait_for_window(
+    runtime: &tokio::runtime::Handle,
+    child: &mut Child,
+    timeout: Duration,
+) -> anyhow::Result<()> {
+    let pid = child.id() as i32;
+    let deadline = Instant::now() + timeout;
+    loop {
+        let window = runtime
+            .block_on(capture::find_window(|window| window.application().pid() == pid))?;
+        if window.is_some() {
+            return Ok(());
+        }
+        if let Some(exit_status) = child.try_wait()? {
+            anyhow::bail!("exited ({exit_status}) before opening a window");
+        }
+        if Instant::now() > deadline {
+            anyhow::bail!(
+                "no window after {} s, is the command running the application binary?",
+                timeout.as_secs_f32(),
+            );
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
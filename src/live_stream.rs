@@ -0,0 +1,437 @@
+//! Live streaming of the capture through ffmpeg, to an RTMP ingest (e.g. Twitch or YouTube)
+//! and/or an SRT listener for lower latency, loss tolerant contribution feeds, and/or HLS
+//! segments in a directory for watching in a browser. With several outputs configured, a
+//! single encoder feeds them all through ffmpeg's `tee` muxer.
+//!
+//! Frames are sent at a constant rate, repeating the latest frame while capture is slower.
+//! When the connection drops ffmpeg gets restarted with backoff; frames are dropped in the
+//! meantime, so the render loop never waits for the network.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use anyhow::Context as _;
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
+use serde::{Deserialize, Serialize};
+
+use crate::capture::Frame;
+use crate::encoder::{Encoder as _, EncoderFrame, FfmpegEncoder, VideoFormat};
+
+/// Longest time between two reconnection attempts.
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct StreamConfig {
+    /// Ingest URL including the stream key, e.g. `rtmp://live.twitch.tv/app/<key>`.
+    pub rtmp_url: Option<String>,
+
+    /// SRT destination, e.g. `srt://ingest.example.com:9000`.
+    pub srt_url: Option<String>,
+
+    /// SRT receiver buffer, larger values tolerate more packet loss at the cost of delay.
+    pub srt_latency_ms: u32,
+
+    /// Encrypts the SRT stream, 10 to 79 characters.
+    pub srt_passphrase: Option<String>,
+
+    /// Writes an HLS playlist (`stream.m3u8`) with fMP4 segments into this directory,
+    /// served by the status server under `/hls/` if it runs.
+    pub hls_directory: Option<PathBuf>,
+    pub hls_segment_seconds: f32,
+
+    /// Segments kept in the playlist, older ones get deleted.
+    pub hls_playlist_size: u32,
+
+    pub bitrate_kbps: u32,
+    pub fps: f32,
+
+    /// Encode with VideoToolbox instead of libx264.
+    pub hardware_encoder: bool,
+}
+
+impl Default for StreamConfig {
+    fn default() -> Self {
+        Self {
+            rtmp_url: None,
+            srt_url: None,
+            srt_latency_ms: 120,
+            srt_passphrase: None,
+            hls_directory: None,
+            hls_segment_seconds: 2.0,
+            hls_playlist_size: 6,
+            bitrate_kbps: 6000,
+            fps: 30.0,
+            hardware_encoder: cfg!(target_os = "macos"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StreamHealth {
+    /// ffmpeg is running and reports progress.
+    pub connected: bool,
+
+    /// Output bitrate reported by ffmpeg.
+    pub bitrate_kbps: f32,
+
+    /// Encoding speed relative to real time, the stream falls behind below 1.
+    pub speed: f32,
+    pub reconnects: u32,
+    pub frames_dropped: u64,
+}
+
+impl StreamHealth {
+    /// One line summary for the HUD, and its color: green while healthy, yellow when
+    /// falling behind and red while disconnected.
+    pub fn summary(&self) -> (String, [u8; 4]) {
+        let text = if self.connected {
+            format!(
+                "LIVE {:.0} kbit/s  {:.2}x  {} dropped",
+                self.bitrate_kbps, self.speed, self.frames_dropped
+            )
+        } else {
+            format!("LIVE reconnecting ({} attempts)", self.reconnects)
+        };
+        let color = if !self.connected {
+            [255, 80, 80, 255]
+        } else if self.speed < 0.95 {
+            [255, 210, 60, 255]
+        } else {
+            [90, 230, 90, 255]
+        };
+        (text, color)
+    }
+}
+
+/// Name of the HLS playlist in [`StreamConfig::hls_directory`].
+pub const HLS_PLAYLIST: &str = "stream.m3u8";
+
+/// One destination of the stream.
+struct Output {
+    muxer: &'static str,
+
+    /// Muxer options, without the leading `-`.
+    options: Vec<(&'static str, String)>,
+    target: String,
+
+    /// Shown in logs, without secrets.
+    description: String,
+}
+
+pub struct LiveStream {
+    sender: Option<Sender<Vec<u8>>>,
+    worker: Option<JoinHandle<()>>,
+    health: Arc<Mutex<StreamHealth>>,
+    size: [u32; 2],
+}
+
+impl LiveStream {
+    /// Starts streaming `width` x `height` frames, fails if no output is configured.
+    pub fn start(
+        config: &StreamConfig,
+        ffmpeg_path: &str,
+        width: u32,
+        height: u32,
+    ) -> anyhow::Result<Self> {
+        let mut outputs = Vec::new();
+        if let Some(url) = &config.rtmp_url {
+            outputs.push(Output {
+                muxer: "flv",
+                options: Vec::new(),
+                target: url.clone(),
+                description: redact(url),
+            });
+        }
+        if let Some(url) = &config.srt_url {
+            outputs.push(Output {
+                muxer: "mpegts",
+                options: Vec::new(),
+                target: srt_url(url, config)?,
+                description: redact(url),
+            });
+        }
+        if let Some(directory) = &config.hls_directory {
+            std::fs::create_dir_all(directory)
+                .with_context(|| format!("failed to create {}", directory.display()))?;
+            let playlist = directory.join(HLS_PLAYLIST);
+            outputs.push(Output {
+                muxer: "hls",
+                options: vec![
+                    ("hls_time", config.hls_segment_seconds.to_string()),
+                    ("hls_list_size", config.hls_playlist_size.to_string()),
+                    ("hls_segment_type", "fmp4".to_owned()),
+                    ("hls_flags", "delete_segments+independent_segments".to_owned()),
+                ],
+                target: playlist.to_string_lossy().into_owned(),
+                description: playlist.display().to_string(),
+            });
+        }
+
+        let (muxer_args, target) = match outputs.as_slice() {
+            [] => anyhow::bail!("no stream output configured"),
+            [output] => {
+                let mut args = vec!["-f".to_owned(), output.muxer.to_owned()];
+                for (key, value) in &output.options {
+                    args.extend([format!("-{key}"), value.clone()]);
+                }
+                (args, output.target.clone())
+            }
+            // Encoders only put the codec headers where flv and fmp4 want them with a global
+            // header, which the single muxers request but tee can't.
+            outputs => (
+                ["-flags", "+global_header", "-f", "tee"].map(str::to_owned).to_vec(),
+                outputs
+                    .iter()
+                    .map(|output| {
+                        let options = output
+                            .options
+                            .iter()
+                            .map(|(key, value)| format!(":{key}={value}"))
+                            .collect::<String>();
+                        format!("[f={}{options}]{}", output.muxer, escape_tee(&output.target))
+                    })
+                    .collect::<Vec<_>>()
+                    .join("|"),
+            ),
+        };
+        let destinations = outputs
+            .iter()
+            .map(|output| format!("{} ({})", output.description, output.muxer))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let format = VideoFormat {
+            width,
+            height,
+            fps: config.fps,
+        };
+        let bitrate = format!("{}k", config.bitrate_kbps);
+        let buffer_size = format!("{}k", config.bitrate_kbps * 2);
+        let keyframe_interval = ((config.fps * 2.0).round() as u32).to_string();
+        let codec = if config.hardware_encoder {
+            "h264_videotoolbox"
+        } else {
+            "libx264"
+        };
+        let mut output_args = [
+            "-vf", "pad=ceil(iw/2)*2:ceil(ih/2)*2",
+            "-c:v", codec,
+            "-b:v", bitrate.as_str(),
+            "-maxrate", bitrate.as_str(),
+            "-bufsize", buffer_size.as_str(),
+            "-g", keyframe_interval.as_str(),
+            "-pix_fmt", "yuv420p",
+            // The tee muxer needs explicit stream mapping.
+            "-map", "0:v",
+        ]
+        .map(str::to_owned)
+        .to_vec();
+        output_args.extend(muxer_args);
+
+        let health = Arc::new(Mutex::new(StreamHealth::default()));
+        let (sender, receiver) = crossbeam_channel::bounded(1);
+        let worker = {
+            let ffmpeg_path = ffmpeg_path.to_owned();
+            let health = health.clone();
+            std::thread::Builder::new()
+                .name("live stream".to_owned())
+                .spawn(move || {
+                    stream_frames(&ffmpeg_path, &output_args, &target, format, receiver, &health);
+                })?
+        };
+
+        println!("Streaming to {destinations}");
+        Ok(Self {
+            sender: Some(sender),
+            worker: Some(worker),
+            health,
+            size: [width, height],
+        })
+    }
+
+    /// Hands a new frame to the stream, frames of a different size are ignored.
+    pub fn push(&self, frame: &Frame) {
+        puffin::profile_function!();
+
+        let bitmap = &frame.frame_bitmap;
+        if [bitmap.width as u32, bitmap.height as u32] != self.size {
+            return;
+        }
+        if let Some(sender) = &self.sender {
+            // If the worker hasn't picked up the previous frame yet, it isn't needed anymore
+            // either; the worker repeats whatever frame it has.
+            sender.try_send(bitmap.data.iter().flatten().copied().collect()).ok();
+        }
+    }
+
+    pub fn health(&self) -> StreamHealth {
+        self.health.lock().unwrap().clone()
+    }
+
+    pub fn stop(mut self) {
+        drop(self.sender.take());
+        if let Some(worker) = self.worker.take() {
+            worker.join().ok();
+        }
+        println!("Stopped streaming");
+    }
+}
+
+fn stream_frames(
+    ffmpeg_path: &str,
+    output_args: &[String],
+    url: &str,
+    format: VideoFormat,
+    receiver: Receiver<Vec<u8>>,
+    health: &Arc<Mutex<StreamHealth>>,
+) {
+    let frame_interval = Duration::from_secs_f64(1.0 / format.fps as f64);
+    let mut latest = None;
+    let mut frames_sent = 0u32;
+    let mut next_tick = Instant::now();
+
+    let mut encoder: Option<FfmpegEncoder> = None;
+    let mut reconnect_delay = Duration::from_secs(1);
+    let mut reconnect_at = Instant::now();
+
+    loop {
+        match receiver.recv_deadline(next_tick) {
+            Ok(frame) => {
+                latest = Some(frame);
+                continue;
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        // Don't try to catch up after stalls, that only bursts frames at the ingest.
+        next_tick = (next_tick + frame_interval).max(Instant::now());
+        let Some(frame) = &latest else {
+            continue;
+        };
+
+        if encoder.is_none() && Instant::now() >= reconnect_at {
+            let progress_health = health.clone();
+            let mut new_encoder = FfmpegEncoder::new(
+                ffmpeg_path,
+                &output_args.iter().map(String::as_str).collect::<Vec<_>>(),
+            )
+            .with_progress(move |key, value| {
+                let mut health = progress_health.lock().unwrap();
+                match key {
+                    "bitrate" => {
+                        health.bitrate_kbps = value
+                            .trim_end_matches("kbits/s")
+                            .parse()
+                            .unwrap_or(health.bitrate_kbps);
+                    }
+                    "speed" => {
+                        health.speed = value.trim_end_matches('x').parse().unwrap_or(health.speed);
+                    }
+                    "progress" => health.connected = value == "continue",
+                    _ => {}
+                }
+            });
+            match new_encoder.open(Path::new(url), format) {
+                Ok(()) => encoder = Some(new_encoder),
+                Err(err) => {
+                    eprintln!("Failed to start streaming: {err:#}");
+                    health.lock().unwrap().reconnects += 1;
+                    reconnect_at = Instant::now() + reconnect_delay;
+                    reconnect_delay = (reconnect_delay * 2).min(MAX_RECONNECT_DELAY);
+                }
+            }
+        }
+
+        let Some(active) = &mut encoder else {
+            health.lock().unwrap().frames_dropped += 1;
+            continue;
+        };
+        let timestamp = frame_interval * frames_sent;
+        let result = active.push_frame(&EncoderFrame {
+            bgra: frame,
+            timestamp,
+            captured_at: timestamp,
+            capture_time_us: crate::capture::now_us(),
+        });
+        frames_sent += 1;
+        match result {
+            Ok(()) => reconnect_delay = Duration::from_secs(1),
+            Err(err) => {
+                eprintln!("Lost stream connection, reconnecting: {err:#}");
+                if let Err(err) = active.finish() {
+                    eprintln!("{err:#}");
+                }
+                encoder = None;
+                let mut health = health.lock().unwrap();
+                health.connected = false;
+                health.reconnects += 1;
+                health.frames_dropped += 1;
+                reconnect_at = Instant::now() + reconnect_delay;
+            }
+        }
+    }
+
+    if let Some(mut encoder) = encoder {
+        if let Err(err) = encoder.finish() {
+            eprintln!("{err:#}");
+        }
+    }
+}
+
+/// Adds the latency and passphrase options to an SRT URL.
+fn srt_url(url: &str, config: &StreamConfig) -> anyhow::Result<String> {
+    anyhow::ensure!(url.starts_with("srt://"), "SRT URL {url:?} doesn't start with srt://");
+    // ffmpeg takes the latency in microseconds.
+    let mut url = format!(
+        "{url}{}latency={}",
+        if url.contains('?') { '&' } else { '?' },
+        config.srt_latency_ms * 1000
+    );
+    if let Some(passphrase) = &config.srt_passphrase {
+        anyhow::ensure!(
+            (10..=79).contains(&passphrase.len()),
+            "the SRT passphrase must be 10 to 79 characters long"
+        );
+        url += &format!("&passphrase={}&pbkeylen=16", percent_encode(passphrase));
+    }
+    Ok(url)
+}
+
+/// Percent-encodes everything but unreserved characters, for URL query values.
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char);
+            }
+            byte => encoded += &format!("%{byte:02X}"),
+        }
+    }
+    encoded
+}
+
+/// Escapes the characters the tee muxer splits outputs and options at.
+fn escape_tee(target: &str) -> String {
+    let mut escaped = String::with_capacity(target.len());
+    for c in target.chars() {
+        if matches!(c, '\\' | '|' | ':' | '[' | ']') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Hides the stream key (the last path segment of RTMP URLs) and SRT options from logs.
+fn redact(url: &str) -> String {
+    if let Some((base, _)) = url.split_once('?') {
+        return format!("{base}?…");
+    }
+    match url.rsplit_once('/') {
+        Some((base, _)) => format!("{base}/…"),
+        None => url.to_owned(),
+    }
+}
@@ -2,59 +2,1280 @@
 //!
 //! On the left is a 2D view, on the right a 3D view of the same scene.
 
-use std::borrow::Cow;
-use std::mem::ManuallyDrop;
-use std::sync::{Arc, Mutex};
-use std::time::Duration;
-use crabgrab::prelude::{CapturableContent, CapturableContentFilter, CaptureConfig, CapturePixelFormat, CaptureStream, FrameBitmap, FrameBitmapBgraUnorm8x4, MetalVideoFrameExt, MetalVideoFramePlaneTexture, StreamEvent, VideoFrameBitmap, WgpuCaptureConfigExt, WgpuVideoFrameExt, WgpuVideoFramePlaneTexture};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use anyhow::Context as _;
 use itertools::Itertools as _;
-use re_renderer::Hsva;
-
-#[allow(unused)]
-struct Gfx {
-    device: wgpu::Device,
-    queue: wgpu::Queue,
-}
-
-impl AsRef<wgpu::Device> for Gfx {
-    fn as_ref(&self) -> &wgpu::Device {
-        &self.device
-    }
-}
 
 use re_renderer::{
     renderer::{
-        ColormappedTexture, LineStripFlags, RectangleDrawData, RectangleOptions, TextureFilterMag,
-        TextureFilterMin, TexturedRect,
+        ColormappedTexture, LineDrawData, LineStripFlags, PointCloudDrawData, RectangleDrawData,
+        RectangleOptions, TextureFilterMag, TextureFilterMin, TexturedRect,
     },
     resource_managers::{GpuTexture2D, Texture2DCreationDesc},
     view_builder::{self, Projection, TargetConfiguration, ViewBuilder},
-    Color32, LineDrawableBuilder, PointCloudBuilder, Size,
+    LineDrawableBuilder, PointCloudBuilder, ScreenshotProcessor, Size,
 };
-use wgpu::Texture;
-use once_cell::sync::Lazy;
 
+mod ab_compare;
+mod accessibility;
+mod app_capture;
+mod background;
+mod black_bars;
+mod capture;
+mod capture_backend;
+mod capture_crop;
+mod capture_manager;
+mod captions;
+mod color_watch;
+mod config;
+mod context_menu;
+mod control;
+mod crosshair;
+mod discovery;
+mod encoder;
+mod events;
+mod exclusions;
+mod focus;
+mod frame_channel;
+mod frame_history;
+mod frame_link;
+mod frame_style;
+mod frame_step;
 mod framework;
+mod gamepad;
+mod gestures;
+mod gpu_ycbcr;
+mod hud;
+mod inspector;
+mod io_pool;
+mod keymap;
+mod launch;
+mod live_stream;
+mod mcap_export;
+mod minimap;
+mod mirror;
+mod multi_display;
+mod notifications;
+mod output;
+mod overlay_lod;
+mod perf_stats;
+mod picking;
+mod pinned_element;
+mod pipeline_stats;
+mod presets;
+mod pixel_format;
+mod playback;
+mod profiles;
+mod recorder;
+mod scene_change;
+mod screenshot;
+mod session_pause;
+mod status;
+mod status_server;
+mod test_pattern;
+mod text;
+mod text_watch;
+mod texture_pool;
+mod toasts;
+mod touch;
+mod undo;
+mod video_file;
+mod watch_folder;
+mod webhook;
+
+use capture::Frame;
+use events::Severity;
+use overlay_lod::Degradation;
+
+/// Readbacks of the 2D and 3D view feeding the recorder and live stream.
+const VIEW_READBACKS: [re_renderer::GpuReadbackIdentifier; 2] = [1, 2];
+
+/// Time without a new captured frame after which the capture counts as stalled. Displays
+/// only deliver frames when their content changes, so long enough for a still screen.
+const CAPTURE_STALL_US: u64 = 30_000_000;
+
+/// How long zooming to a region or back to fit takes.
+const ZOOM_ANIMATION: web_time::Duration = web_time::Duration::from_millis(200);
+
+/// Longest time between the clicks of a double click.
+const DOUBLE_CLICK: web_time::Duration = web_time::Duration::from_millis(300);
 
-struct Frame {
-    frame_bitmap: FrameBitmapBgraUnorm8x4,
-    frame_id: u64,
+/// Pixels the pointer has to move for a drag, rather than a click.
+const DRAG_THRESHOLD: f32 = 8.0;
+
+/// Turns a view read back from the GPU into a frame for the recorder.
+fn view_frame(rgba: &[u8], extent: glam::UVec2) -> Frame {
+    Frame {
+        frame_bitmap: crabgrab::prelude::FrameBitmapBgraUnorm8x4 {
+            data: rgba
+                .chunks_exact(4)
+                .map(|pixel| [pixel[2], pixel[1], pixel[0], pixel[3]])
+                .collect(),
+            width: extent.x as usize,
+            height: extent.y as usize,
+        },
+        frame_id: 0,
+        capture_time_us: capture::now_us(),
+        source_rect: focus::ScreenRect {
+            min: glam::Vec2::ZERO,
+            size: extent.as_vec2(),
+        },
+        ycbcr: None,
+        hdr_data: None,
+    }
+}
+
+/// Banner text for a failed display capture, with a countdown to the next attempt.
+fn capture_failure_text(failure: &capture::CaptureFailure) -> String {
+    let seconds = failure.retry_at_us.saturating_sub(capture::now_us()).div_ceil(1_000_000);
+    let retry = format!("Trying again in {seconds} s, attempt {}", failure.attempts + 1);
+    if failure.permission_lost {
+        format!(
+            "Screen recording permission lost\n\
+             Allow it in System Settings > Privacy & Security\n{retry}"
+        )
+    } else {
+        // The 8x8 font gets wide, long errors are cut.
+        let error = failure.error.chars().take(60).collect::<String>();
+        format!("Capture failed: {error}\n{retry}")
+    }
 }
 
-static SCREEN_TEXTURE: Lazy<Arc<Mutex<Option<Frame>>>> = Lazy::new(|| Arc::new(Mutex::new(None)));
+/// Places two frames next to each other, top aligned.
+fn side_by_side(left: &Frame, right: &Frame) -> Frame {
+    let (left_bitmap, right_bitmap) = (&left.frame_bitmap, &right.frame_bitmap);
+    let width = left_bitmap.width + right_bitmap.width;
+    let height = left_bitmap.height.max(right_bitmap.height);
+    let mut data = vec![[0, 0, 0, 255]; width * height];
+    for (bitmap, x0) in [(left_bitmap, 0), (right_bitmap, left_bitmap.width)] {
+        for y in 0..bitmap.height {
+            data[y * width + x0..][..bitmap.width]
+                .copy_from_slice(&bitmap.data[y * bitmap.width..][..bitmap.width]);
+        }
+    }
+    Frame {
+        frame_bitmap: crabgrab::prelude::FrameBitmapBgraUnorm8x4 {
+            data: data.into(),
+            width,
+            height,
+        },
+        frame_id: right.frame_id,
+        capture_time_us: right.capture_time_us,
+        source_rect: focus::ScreenRect {
+            min: glam::Vec2::ZERO,
+            size: glam::vec2(width as f32, height as f32),
+        },
+        ycbcr: None,
+        hdr_data: None,
+    }
+}
+
+/// Everything the overlay lines and points depend on, they're rebuilt when it changes.
+#[derive(Clone, PartialEq)]
+struct OverlayInputs {
+    screen_size: glam::Vec2,
+    capture_rect: (glam::Vec2, glam::Vec2),
+    top_line: i16,
+    high_contrast: bool,
+    frame_style: frame_style::FrameStyleConfig,
+
+    /// Focused UI element and the captured screen area.
+    focus_highlight: Option<(focus::ScreenRect, focus::ScreenRect)>,
+
+    /// Changes with every annotation point drawn or cleared.
+    annotations_revision: u64,
+}
+
+/// 1 to 9 for the digit keys.
+fn digit(key: &winit::keyboard::PhysicalKey) -> Option<usize> {
+    use winit::keyboard::{KeyCode, PhysicalKey};
+    let PhysicalKey::Code(code) = key else {
+        return None;
+    };
+    [
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+        KeyCode::Digit7,
+        KeyCode::Digit8,
+        KeyCode::Digit9,
+    ]
+    .iter()
+    .position(|digit| digit == code)
+    .map(|index| index + 1)
+}
 
 struct Render2D {
     rerun_logo_texture: GpuTexture2D,
     rerun_logo_texture_width: u32,
     rerun_logo_texture_height: u32,
+
+    /// Captured frames, of which the latest gets shown.
+    frames: frame_channel::FrameReceiver,
+    screen_frame: Option<Arc<Frame>>,
+
+    accessibility: accessibility::Accessibility,
+    background: background::Background,
+    frame_style: frame_style::FrameStyle,
+    focus_tracker: Option<focus::FocusTracker>,
+    output: output::OutputManager,
+
+    /// Time and frame id the capture rate was last measured at.
+    capture_fps_sample: (web_time::Instant, Option<u64>),
+    capture_stalled: bool,
+    toasts: toasts::Toasts,
+    perf_stats: perf_stats::PerfStats,
+
+    recorder: Option<recorder::Recorder>,
+    mcap_export: Option<mcap_export::McapExport>,
+    live_stream: Option<live_stream::LiveStream>,
+    ab_delay_text: text::TextRect,
+    inspector: inspector::Inspector,
+    texture_pool: texture_pool::TexturePool,
+    ycbcr_converter: gpu_ycbcr::YCbCrConverter,
+
+    /// Texture of the frame in the capture rect, with the frame id, rect size and style it
+    /// was uploaded for; drawn again as long as no new frame arrives.
+    screen_texture: Option<(u64, glam::Vec2, frame_style::FrameStyleConfig, GpuTexture2D)>,
+    overlay_cache: Option<(OverlayInputs, LineDrawData, PointCloudDrawData)>,
+    overlay_lod: overlay_lod::OverlayLod,
+
+    /// Text widgets in the corners of the 2D view, see [`hud`].
+    hud: hud::Hud,
+    pipeline_stats: pipeline_stats::PipelineStats,
+
+    /// CPU time the latest upload of the capture took, `None` while it's kept.
+    upload_ms: Option<f32>,
+    frame_history: frame_history::FrameHistory,
+
+    /// What the active profile changes, see [`profiles::summary`].
+    profile_summary: Option<String>,
+
+    /// Frame id and estimated time from capturing it to it being on the screen, of the
+    /// latest new frame drawn.
+    preview_latency: Option<(u64, f32)>,
+
+    /// Red banner while the display capture failed and waits to retry.
+    capture_failure_hud: text::TextRect,
+
+    /// Cursor position in window pixels, and where the 2D view and the capture rect in it
+    /// were last drawn, for picking.
+    cursor_position: glam::Vec2,
+
+    /// Gamepad input, with the camera orbit and capture rect zoom and pan it controls along
+    /// with trackpad gestures. The cursor is drawn while a gamepad moved it last, there's no
+    /// mouse pointer where it is.
+    gamepads: Option<gamepad::Gamepads>,
+    camera_orbit: glam::Vec2,
+    zoom_pan_2d: gestures::ZoomPan,
+
+    /// Zoom and pan of each pane of a comparison on top of [`Self::zoom_pan_2d`], the
+    /// capture rect first, then the B side or the other displays. Only gestures over a pane
+    /// while unlinked change its own, relinking puts the panes back in line.
+    panes_linked: bool,
+    pane_zoom_pans: Vec<gestures::ZoomPan>,
+
+    /// The pane rects as last drawn, and before their own zoom and pan.
+    pane_rects: Vec<(glam::Vec2, glam::Vec2)>,
+    unzoomed_pane_rects: Vec<(glam::Vec2, glam::Vec2)>,
+
+    /// Momentum of pinching, in log zoom around where the pinch was, and of rotating, in
+    /// radians.
+    pinch_momentum: gestures::Momentum,
+    pinch_centroid: glam::Vec2,
+    rotate_momentum: gestures::Momentum,
+
+    /// Touchscreen fingers, and the strokes drawn with them in capture rect coordinates
+    /// from 0 to 1, so they stay on what they mark while zooming and panning.
+    touches: touch::Touches,
+    annotations: Vec<Vec<glam::Vec2>>,
+    annotations_revision: u64,
+    gamepad_cursor_shown: bool,
+    gamepad_cursor_hud: text::TextRect,
+    context_menu: context_menu::ContextMenu,
+    minimap: minimap::Minimap,
+
+    /// The capture rect as drawn in the 2D view last, for the mirror window.
+    mirror_capture: Option<TexturedRect>,
+
+    /// Id and capture time of the frame in the mirror waiting to be presented, and the id
+    /// of the one presented last.
+    mirror_pending: Option<(u64, u64)>,
+    mirror_presented: Option<u64>,
+
+    /// Zooming to a region or back to fit, which animates [`Self::zoom_pan_2d`].
+    zoom_tween: Option<framework::Tween<gestures::ZoomPan>>,
+
+    /// Where dragging out a region to zoom to started and is at, in the 2D view, and when
+    /// and where the last click was, for double clicks.
+    region_drag: Option<(glam::Vec2, glam::Vec2)>,
+
+    /// Whether the next region dragged out crops the capture instead of zooming to it.
+    crop_selecting: bool,
+    last_click: Option<(web_time::Instant, glam::Vec2)>,
+    view_2d_location: glam::Vec2,
+    capture_rect: (glam::Vec2, glam::Vec2),
+
+    /// Min and size of the capture rect before zooming and panning.
+    unzoomed_capture_rect: (glam::Vec2, glam::Vec2),
+
+    /// Animation time of the latest frame, and the one frozen while frame stepping.
+    animation_seconds: f32,
+    paused_animation_seconds: Option<f32>,
+
+    /// Id and capture time of the frame shown before the latest step.
+    previous_step: Option<(u64, u64)>,
+
+    /// Latest frame handed to the recorder and live stream.
+    last_pushed_frame_id: Option<u64>,
+
+    /// Resolutions of the 2D and 3D view in the last frame.
+    view_resolutions: [[u32; 2]; 2],
+
+    /// Frames read back from the fed views so far, used as their frame ids.
+    view_frames_read: u64,
+
+    /// What feeds the recorder and live stream.
+    feed_source: recorder::RecordingSource,
+
+    undo: undo::UndoStack,
+    presets: presets::Presets,
+    keymap: keymap::Keymap,
+    modifiers: winit::keyboard::ModifiersState,
+
+    /// Read back 2D view of a composite, waiting for the 3D view drawn in the same frame.
+    pending_composite: Option<(u64, Frame)>,
+    draw_count: u64,
+}
+
+impl Render2D {
+    fn handle_command(&mut self, command: control::ControlCommand) {
+        match command {
+            control::ControlCommand::Screenshot => {
+                let Some(frame) = self.screen_frame.clone() else {
+                    let message = "no frame captured yet";
+                    events::emit(Severity::Warning, "command_ignored", "screenshot", message);
+                    return;
+                };
+                let mut metadata = output::Metadata::from(&status::snapshot());
+                metadata.frame_id = Some(frame.frame_id);
+                match self.output.create_file(output::OutputKind::Screenshot, &metadata, "png") {
+                    Ok((path, file)) => {
+                        screenshot::save_in_background(&frame.frame_bitmap, path, file);
+                    }
+                    Err(err) => {
+                        let message = format!("{err:#}");
+                        events::emit(Severity::Error, "screenshot_failed", "screenshot", &message);
+                    }
+                }
+            }
+            control::ControlCommand::StartRecording => {
+                if self.recorder.is_none() {
+                    if let Err(err) = self.start_recording() {
+                        let message = format!("{err:#}");
+                        events::emit(Severity::Error, "recording_failed", "recorder", &message);
+                    }
+                }
+            }
+            control::ControlCommand::StopRecording => self.stop_recording(),
+            control::ControlCommand::ToggleRecording => {
+                let command = if self.recorder.is_some() {
+                    control::ControlCommand::StopRecording
+                } else {
+                    control::ControlCommand::StartRecording
+                };
+                self.handle_command(command);
+            }
+            control::ControlCommand::StartStreaming => {
+                if self.live_stream.is_none() {
+                    if let Err(err) = self.start_streaming() {
+                        let message = format!("{err:#}");
+                        events::emit(Severity::Error, "streaming_failed", "live_stream", &message);
+                    }
+                }
+            }
+            control::ControlCommand::StopStreaming => {
+                if let Some(live_stream) = self.live_stream.take() {
+                    status::update(|status| status.streaming = None);
+                    std::thread::spawn(move || live_stream.stop());
+                }
+            }
+            control::ControlCommand::ToggleStreaming => {
+                let command = if self.live_stream.is_some() {
+                    control::ControlCommand::StopStreaming
+                } else {
+                    control::ControlCommand::StartStreaming
+                };
+                self.handle_command(command);
+            }
+            control::ControlCommand::SetFeedSource(source) => {
+                if source != self.feed_source {
+                    self.edit(undo::Edit::FeedSource { from: self.feed_source, to: source });
+                }
+            }
+            control::ControlCommand::CycleFeedSource => {
+                self.handle_command(control::ControlCommand::SetFeedSource(self.feed_source.next()));
+            }
+            control::ControlCommand::Marker(name) => match &mut self.recorder {
+                Some(recorder) => recorder.add_marker(name),
+                None => {
+                    let message = "not recording, no marker added";
+                    events::emit(Severity::Warning, "command_ignored", "recorder", message);
+                }
+            },
+            control::ControlCommand::Caption(text) => match &mut self.recorder {
+                Some(recorder) => recorder.add_caption(&text),
+                None => {
+                    let message = format!("not recording, caption {text:?} ignored");
+                    events::emit(Severity::Warning, "command_ignored", "recorder", &message);
+                }
+            },
+            control::ControlCommand::HudText(text) => self.hud.set_script_text(text),
+            control::ControlCommand::ToggleFrameStep => {
+                let paused = !frame_step::is_paused();
+                self.paused_animation_seconds = paused.then_some(self.animation_seconds);
+                self.previous_step = None;
+                if let Some(frame) = frame_step::set_paused(paused) {
+                    self.show_frame(Arc::new(frame));
+                }
+                let kind = if paused { "frame_step_paused" } else { "frame_step_resumed" };
+                events::emit(Severity::Info, kind, "frame_step", "N steps, P resumes");
+            }
+            control::ControlCommand::ApplyPreset(name) => match self.presets.find(&name) {
+                Some(preset) => {
+                    let preset = preset.clone();
+                    events::emit(Severity::Info, "preset_applied", "presets", &preset.name);
+                    self.apply_preset(&preset);
+                }
+                None => {
+                    let message = format!("no preset {name:?}");
+                    events::emit(Severity::Warning, "command_ignored", "presets", &message);
+                }
+            },
+            control::ControlCommand::SavePreset(slot) => {
+                let preset = presets::Preset {
+                    name: format!("Preset {slot}"),
+                    background: Some(self.background.config.kind),
+                    feed_source: Some(self.feed_source),
+                    high_contrast: Some(self.accessibility.high_contrast),
+                    reduced_motion: Some(self.accessibility.reduced_motion),
+                };
+                match self.presets.save(slot, preset) {
+                    Ok(()) => {
+                        let message = format!("Preset {slot}");
+                        events::emit(Severity::Info, "preset_saved", "presets", &message);
+                    }
+                    Err(err) => {
+                        let message = format!("preset {slot}: {err:#}");
+                        events::emit(Severity::Error, "preset_failed", "presets", &message);
+                    }
+                }
+            }
+            control::ControlCommand::Undo => match self.undo.undo() {
+                Some(edit) => {
+                    events::emit(Severity::Info, "undone", "undo", &edit.describe());
+                    self.try_apply_edit(&edit);
+                    self.save_session();
+                }
+                None => {
+                    events::emit(Severity::Warning, "command_ignored", "undo", "nothing to undo");
+                }
+            },
+            control::ControlCommand::Redo => match self.undo.redo() {
+                Some(edit) => {
+                    events::emit(Severity::Info, "redone", "undo", &edit.describe());
+                    self.try_apply_edit(&edit);
+                    self.save_session();
+                }
+                None => {
+                    events::emit(Severity::Warning, "command_ignored", "undo", "nothing to redo");
+                }
+            },
+            control::ControlCommand::StepFrame => {
+                if !frame_step::is_paused() {
+                    let message = "not paused, frame step ignored";
+                    events::emit(Severity::Warning, "command_ignored", "frame_step", message);
+                    return;
+                }
+                let Some(frame) = frame_step::step() else {
+                    let message = "no new frame captured yet";
+                    events::emit(Severity::Warning, "command_ignored", "frame_step", message);
+                    return;
+                };
+                self.previous_step = self
+                    .screen_frame
+                    .as_ref()
+                    .map(|previous| (previous.frame_id, previous.capture_time_us));
+                self.show_frame(Arc::new(frame));
+            }
+            control::ControlCommand::PauseSession => self.set_session_paused(true),
+            control::ControlCommand::ResumeSession => self.set_session_paused(false),
+            control::ControlCommand::TogglePause => {
+                self.set_session_paused(!session_pause::is_paused());
+            }
+            control::ControlCommand::SwitchDisplay(to) => match status::snapshot().display {
+                Some(from) if from == to => {}
+                Some(from) => self.edit(undo::Edit::Display { from, to }),
+                None => {
+                    if let Err(err) = capture::switch_display(to) {
+                        let message = format!("{err:#}");
+                        events::emit(Severity::Warning, "command_ignored", "capture", &message);
+                    }
+                }
+            },
+            control::ControlCommand::SetCursorHidden(hidden) => {
+                if let Err(err) = capture::set_cursor_hidden(hidden) {
+                    let message = format!("{err:#}");
+                    events::emit(Severity::Warning, "command_ignored", "capture", &message);
+                }
+            }
+            control::ControlCommand::ToggleCursor => {
+                self.handle_command(control::ControlCommand::SetCursorHidden(
+                    !capture::is_cursor_hidden(),
+                ));
+            }
+            control::ControlCommand::SetProfile(profile) => {
+                if let Err(err) = self.set_profile(profile) {
+                    let message = format!("{err:#}");
+                    events::emit(Severity::Warning, "command_ignored", "profiles", &message);
+                }
+            }
+            control::ControlCommand::CycleProfile => {
+                self.handle_command(control::ControlCommand::SetProfile(profiles::next()));
+            }
+            control::ControlCommand::SwitchBackend(to) => match capture_backend::kind() {
+                Some(from) if from == to => {}
+                Some(from) => self.edit(undo::Edit::Backend { from, to }),
+                None => {
+                    if let Err(err) = capture_backend::switch(to) {
+                        let message = format!("{err:#}");
+                        events::emit(Severity::Warning, "command_ignored", "capture", &message);
+                    }
+                }
+            },
+            control::ControlCommand::CycleBackend => match capture_backend::next() {
+                Ok(kind) => self.handle_command(control::ControlCommand::SwitchBackend(kind)),
+                Err(err) => {
+                    let message = format!("{err:#}");
+                    events::emit(Severity::Warning, "command_ignored", "capture", &message);
+                }
+            },
+        }
+    }
+
+    /// Applies an undoable edit and records it, unless it failed.
+    fn edit(&mut self, edit: undo::Edit) {
+        if self.try_apply_edit(&edit) {
+            self.undo.push(edit);
+            self.save_session();
+        }
+    }
+
+    /// Applies `edit`, reports whether that worked. Only switching displays and backends
+    /// can fail.
+    fn try_apply_edit(&mut self, edit: &undo::Edit) -> bool {
+        let Err(err) = self.apply_edit(edit) else {
+            return true;
+        };
+        let message = format!("{err:#}");
+        events::emit(Severity::Warning, "command_ignored", "capture", &message);
+        false
+    }
+
+    fn apply_edit(&mut self, edit: &undo::Edit) -> anyhow::Result<()> {
+        match edit {
+            undo::Edit::Background { to, .. } => {
+                self.background.config.kind = *to;
+                println!("background: {}", to.name());
+            }
+            undo::Edit::FeedSource { to, .. } => self.set_feed_source(*to),
+            undo::Edit::HighContrast { to } => self.accessibility.high_contrast = *to,
+            undo::Edit::ReducedMotion { to } => self.accessibility.reduced_motion = *to,
+            undo::Edit::Annotations { to, .. } => {
+                self.annotations = to.clone();
+                self.annotations_revision += 1;
+            }
+            undo::Edit::Display { to, .. } => capture::switch_display(*to)?,
+            undo::Edit::Backend { to, .. } => capture_backend::switch(*to)?,
+            undo::Edit::Crop { to, .. } => {
+                capture_crop::set(*to);
+                self.animate_zoom_pan(gestures::ZoomPan::default());
+            }
+        }
+        Ok(())
+    }
+
+    /// Switches to the settings of `preset`, as undoable edits.
+    fn apply_preset(&mut self, preset: &presets::Preset) {
+        if let Some(to) = preset.background.filter(|to| *to != self.background.config.kind) {
+            self.edit(undo::Edit::Background { from: self.background.config.kind, to });
+        }
+        if let Some(to) = preset.feed_source.filter(|to| *to != self.feed_source) {
+            self.edit(undo::Edit::FeedSource { from: self.feed_source, to });
+        }
+        if let Some(to) = preset.high_contrast.filter(|to| *to != self.accessibility.high_contrast) {
+            self.edit(undo::Edit::HighContrast { to });
+        }
+        if let Some(to) = preset.reduced_motion.filter(|to| *to != self.accessibility.reduced_motion)
+        {
+            self.edit(undo::Edit::ReducedMotion { to });
+        }
+    }
+
+    fn set_feed_source(&mut self, source: recorder::RecordingSource) {
+        println!("Recording and streaming {}", source.name());
+        self.feed_source = source;
+        self.pending_composite = None;
+        status::update(|status| status.feed_source = source.name().to_owned());
+        // The frame size changes with the source, running sinks start over.
+        if self.recorder.is_some() {
+            self.handle_command(control::ControlCommand::StopRecording);
+            self.handle_command(control::ControlCommand::StartRecording);
+        }
+        if self.live_stream.is_some() {
+            self.handle_command(control::ControlCommand::StopStreaming);
+            self.handle_command(control::ControlCommand::StartStreaming);
+        }
+    }
+
+    fn save_session(&self) {
+        undo::SessionState {
+            background: self.background.config.kind.name().to_owned(),
+            feed_source: self.feed_source.name().to_owned(),
+            high_contrast: self.accessibility.high_contrast,
+            reduced_motion: self.accessibility.reduced_motion,
+        }
+        .save();
+    }
+
+    /// Applies the state saved by the previous session, without recording edits.
+    fn restore_session(&mut self) {
+        let Some(state) = undo::SessionState::load() else {
+            return;
+        };
+        println!("Restoring the previous session");
+        if let Some(kind) = background::BackgroundKind::parse(&state.background) {
+            self.background.config.kind = kind;
+        }
+        if let Some(source) = recorder::RecordingSource::parse(&state.feed_source) {
+            self.feed_source = source;
+            status::update(|status| status.feed_source = source.name().to_owned());
+        }
+        self.accessibility.high_contrast = state.high_contrast;
+        self.accessibility.reduced_motion = state.reduced_motion;
+    }
+
+    fn run_menu_action(&mut self, action: context_menu::MenuAction) {
+        let text = match action {
+            context_menu::MenuAction::CopyColor(pick) => {
+                let [r, g, b] = pick.rgb;
+                format!("#{r:02x}{g:02x}{b:02x}")
+            }
+            context_menu::MenuAction::CopyPixelPosition(pick) => {
+                let [x, y] = pick.capture_pixel;
+                format!("{x}, {y}")
+            }
+            context_menu::MenuAction::Screenshot => {
+                self.handle_command(control::ControlCommand::Screenshot);
+                return;
+            }
+            context_menu::MenuAction::PinElement(pick) => {
+                let levels_up = config::get().pinned_element.levels_up;
+                if let Err(err) = pinned_element::pin(pick.screen_position.into(), levels_up) {
+                    eprintln!("Failed to pin a UI element: {err:#}");
+                }
+                return;
+            }
+            context_menu::MenuAction::UnpinElement => {
+                pinned_element::unpin();
+                return;
+            }
+            context_menu::MenuAction::ClearAnnotations => {
+                let from = std::mem::take(&mut self.annotations);
+                self.edit(undo::Edit::Annotations { from, to: Vec::new() });
+                return;
+            }
+        };
+        match context_menu::copy_to_clipboard(&text) {
+            Ok(()) => {
+                events::emit(Severity::Info, "copied", "clipboard", &text);
+            }
+            Err(err) => {
+                let message = format!("{text:?}: {err:#}");
+                events::emit(Severity::Error, "copy_failed", "clipboard", &message);
+            }
+        }
+    }
+
+    /// Crops the capture to `region` of the 2D view, min and size, and zooms back to fit
+    /// the cropped capture, see [`capture_crop`].
+    fn crop_to_region(&mut self, (min, size): (glam::Vec2, glam::Vec2)) {
+        self.crop_selecting = false;
+        let Some(source_rect) = self.screen_frame.as_deref().map(|frame| frame.source_rect) else {
+            return;
+        };
+        let (rect_min, rect_size) = self.capture_rect;
+        let min_in_view = min.max(rect_min);
+        let max_in_view = (min + size).min(rect_min + rect_size);
+        if max_in_view.cmple(min_in_view).any() {
+            return;
+        }
+        let points_per_view_unit = source_rect.size / rect_size;
+        let to = Some(focus::ScreenRect {
+            min: source_rect.min + (min_in_view - rect_min) * points_per_view_unit,
+            size: (max_in_view - min_in_view) * points_per_view_unit,
+        });
+        self.edit(undo::Edit::Crop { from: capture_crop::get(), to });
+    }
+
+    /// Starts selecting a region to crop the capture to, cancels that, or goes back to the
+    /// whole capture while cropped.
+    fn toggle_crop(&mut self) {
+        if config::get().app_capture.application.is_some() {
+            eprintln!("The application window grid can't be cropped");
+        } else if self.crop_selecting {
+            self.crop_selecting = false;
+        } else if let Some(from) = capture_crop::get() {
+            self.edit(undo::Edit::Crop { from: Some(from), to: None });
+        } else {
+            self.crop_selecting = true;
+        }
+    }
+
+    /// HUD text while frame stepping: the shown frame and its stats.
+    fn frame_step_text(&self, time: &framework::Time) -> String {
+        let info = frame_step::info();
+        let status = status::snapshot();
+        let mut lines = vec![format!(
+            "PAUSED  N: next frame  P: resume  ({} queued, {} dropped)",
+            info.queued, info.frames_dropped
+        )];
+        if let Some(frame) = &self.screen_frame {
+            lines.push(format!(
+                "frame {}  {}x{}  captured at {} us",
+                frame.frame_id,
+                frame.frame_bitmap.width,
+                frame.frame_bitmap.height,
+                frame.capture_time_us,
+            ));
+            if let Some((previous_id, previous_time_us)) = self.previous_step {
+                lines.push(format!(
+                    "+{} frame ids, +{:.1} ms since the previous frame",
+                    frame.frame_id.saturating_sub(previous_id),
+                    frame.capture_time_us.saturating_sub(previous_time_us) as f32 / 1000.0,
+                ));
+            }
+        }
+        lines.push(format!(
+            "render {:.2} ms  capture {:.1} fps",
+            time.last_frame_duration.as_secs_f32() * 1000.0,
+            status.capture_fps,
+        ));
+        if let Some(offset) = status.clock_offset_ms {
+            lines.push(format!("clock offset {offset:.2} ms"));
+        }
+        lines.push(self.texture_pool.stats().summary());
+        lines.join("\n")
+    }
+
+    /// Uploads `frame` as texture for a rect of `rect_size`, with the frame style applied.
+    /// Frames with their 4:2:0 planes are converted on the GPU, frames with 10 bit pixels
+    /// are uploaded with all of them.
+    fn frame_texture(
+        &mut self,
+        re_ctx: &re_renderer::RenderContext,
+        frame: &Frame,
+        rect_size: glam::Vec2,
+    ) -> GpuTexture2D {
+        puffin::profile_function!();
+        let Frame { frame_bitmap, ycbcr, hdr_data, .. } = frame;
+        if let Some(planes) = ycbcr {
+            let corner_radius = self.frame_style.corner_radius_in_pixels(
+                frame_bitmap.width,
+                frame_bitmap.height,
+                rect_size,
+            );
+            return self.ycbcr_converter.convert(
+                re_ctx,
+                &mut self.texture_pool,
+                planes,
+                corner_radius,
+            );
+        }
+        if let Some(hdr_data) = hdr_data {
+            let mut pixels = hdr_data.to_vec();
+            self.frame_style.round_corners_10_bit(
+                &mut pixels,
+                frame_bitmap.width,
+                frame_bitmap.height,
+                rect_size,
+            );
+            let data = pixels.iter().flat_map(|pixel| pixel.to_le_bytes()).collect::<Vec<_>>();
+            return self.texture_pool.upload(
+                re_ctx,
+                "screen texture",
+                &data,
+                wgpu::TextureFormat::Rgb10a2Unorm,
+                frame_bitmap.width as u32,
+                frame_bitmap.height as u32,
+            );
+        }
+        let mut data = frame_bitmap.data.iter().flatten().copied().collect::<Vec<_>>();
+        self.frame_style.round_corners(
+            &mut data,
+            frame_bitmap.width,
+            frame_bitmap.height,
+            rect_size,
+        );
+        self.texture_pool.upload(
+            re_ctx,
+            "screen texture",
+            &data,
+            wgpu::TextureFormat::Bgra8Unorm,
+            frame_bitmap.width as u32,
+            frame_bitmap.height as u32,
+        )
+    }
+
+    /// Like [`Self::frame_texture`], but skips the upload while `frame` is the one uploaded
+    /// last, e.g. while the captured screen is static.
+    fn screen_texture(
+        &mut self,
+        re_ctx: &re_renderer::RenderContext,
+        frame: &Frame,
+        rect_size: glam::Vec2,
+    ) -> GpuTexture2D {
+        let style = self.frame_style.config;
+        if let Some((frame_id, cached_size, cached_style, texture)) = &self.screen_texture {
+            let is_current =
+                *frame_id == frame.frame_id && *cached_size == rect_size && *cached_style == style;
+            // Converted 4:2:0 textures aren't pooled, the cached handle keeps them alive.
+            if is_current && (frame.ycbcr.is_some() || self.texture_pool.keep(texture)) {
+                self.upload_ms = None;
+                return texture.clone();
+            }
+        }
+        let upload_started = web_time::Instant::now();
+        let texture = self.frame_texture(re_ctx, frame, rect_size);
+        self.upload_ms = Some(upload_started.elapsed().as_secs_f32() * 1000.0);
+        self.screen_texture = Some((frame.frame_id, rect_size, style, texture.clone()));
+        texture
+    }
+
+    /// Size of the frames the current feed source produces.
+    fn feed_size(&self) -> Option<[u32; 2]> {
+        let [view_2d, view_3d] = self.view_resolutions;
+        match self.feed_source {
+            recorder::RecordingSource::Capture => self.capture_size(),
+            recorder::RecordingSource::View2d => Some(view_2d),
+            recorder::RecordingSource::View3d => Some(view_3d),
+            recorder::RecordingSource::Composite => {
+                Some([view_2d[0] + view_3d[0], view_2d[1].max(view_3d[1])])
+            }
+        }
+    }
+
+    /// Shows `frame` in the capture rect, and on the status page.
+    fn show_frame(&mut self, frame: Arc<Frame>) {
+        status_server::publish_frame(frame.clone());
+        self.screen_frame = Some(frame);
+    }
+
+    /// Size of the shown captured frame in pixels.
+    fn capture_size(&self) -> Option<[u32; 2]> {
+        self.screen_frame
+            .as_ref()
+            .map(|frame| [frame.frame_bitmap.width as u32, frame.frame_bitmap.height as u32])
+    }
+
+    /// Applies what the gamepads did over the last `seconds`, see [`gamepad`].
+    fn poll_gamepads(&mut self, seconds: f32) {
+        use framework::Example as _;
+        use winit::event::{ElementState, MouseButton};
+
+        let Some(input) = self.gamepads.as_mut().map(|gamepads| gamepads.poll(seconds)) else {
+            return;
+        };
+        if input.cursor != glam::Vec2::ZERO {
+            let [width, height] = self.view_resolutions[0];
+            let view_min = self.view_2d_location;
+            let view_max = view_min + glam::vec2(width as f32, height as f32);
+            self.cursor_position = (self.cursor_position + input.cursor).clamp(view_min, view_max);
+            self.gamepad_cursor_shown = true;
+        }
+        self.camera_orbit += input.orbit;
+        // From just above the floor to well above the scene.
+        self.camera_orbit.y = self.camera_orbit.y.clamp(-0.45, 2.0);
+        if input.zoom != 1.0 {
+            self.zoom_tween = None;
+            let zoom = &mut self.zoom_pan_2d.zoom;
+            *zoom = (*zoom * input.zoom).clamp(0.25, 4.0);
+        }
+
+        for press in input.presses {
+            match press {
+                gamepad::Press::Pick => {
+                    self.on_mouse_input(ElementState::Pressed, MouseButton::Left);
+                    self.on_mouse_input(ElementState::Released, MouseButton::Left);
+                }
+                gamepad::Press::OpenMenu => {
+                    self.on_mouse_input(ElementState::Pressed, MouseButton::Right);
+                }
+                gamepad::Press::CloseMenu => self.context_menu.close(),
+                gamepad::Press::TogglePause => {
+                    self.handle_command(control::ControlCommand::TogglePause);
+                }
+                gamepad::Press::ResetView => {
+                    self.camera_orbit = glam::Vec2::ZERO;
+                    self.zoom_pan_2d = gestures::ZoomPan::default();
+                    self.pane_zoom_pans.clear();
+                    self.pinch_momentum.stop();
+                    self.rotate_momentum.stop();
+                }
+            }
+        }
+    }
+
+    /// Cursor position in the 2D view, `None` outside of it.
+    fn cursor_in_view_2d(&self) -> Option<glam::Vec2> {
+        let [width, height] = self.view_resolutions[0];
+        let position = self.cursor_position - self.view_2d_location;
+        let inside = position.cmpge(glam::Vec2::ZERO).all()
+            && position.cmplt(glam::vec2(width as f32, height as f32)).all();
+        inside.then_some(position)
+    }
+
+    /// Zooms the capture rect by `factor`, keeping what's at `point` in the 2D view there,
+    /// along with the other panes of a comparison unless they're unlinked.
+    fn zoom_2d_around(&mut self, factor: f32, point: glam::Vec2) {
+        self.zoom_tween = None;
+        match self.unlinked_pane_at(point) {
+            Some(pane) => {
+                let unzoomed = self.unzoomed_pane_rects[pane];
+                self.pane_zoom_pans[pane].zoom_around(unzoomed, factor, point);
+            }
+            None => self.zoom_pan_2d.zoom_around(self.unzoomed_capture_rect, factor, point),
+        }
+    }
+
+    /// Pans the capture rect by `delta`, or only the pane at `point` while unlinked, by at
+    /// most the view size in total.
+    fn pan_2d_by(&mut self, delta: glam::Vec2, point: glam::Vec2) {
+        self.zoom_tween = None;
+        let [width, height] = self.view_resolutions[0];
+        let view_size = glam::vec2(width as f32, height as f32);
+        let zoom_pan = match self.unlinked_pane_at(point) {
+            Some(pane) => &mut self.pane_zoom_pans[pane],
+            None => &mut self.zoom_pan_2d,
+        };
+        zoom_pan.pan = (zoom_pan.pan + delta).clamp(-view_size, view_size);
+    }
+
+    /// Animates the zoom and pan of the 2D view to `to`, right away with reduced motion.
+    fn animate_zoom_pan(&mut self, to: gestures::ZoomPan) {
+        let duration = if self.accessibility.reduced_motion {
+            web_time::Duration::ZERO
+        } else {
+            ZOOM_ANIMATION
+        };
+        self.zoom_tween = Some(framework::Tween::new(self.zoom_pan_2d, to, duration));
+    }
+
+    /// Animates the 2D view to fill it with `region` of it, min and size.
+    fn zoom_to_region(&mut self, region: (glam::Vec2, glam::Vec2)) {
+        let [width, height] = self.view_resolutions[0];
+        let view_size = glam::vec2(width as f32, height as f32);
+        let to = self.zoom_pan_2d.framing(self.unzoomed_capture_rect, region, view_size);
+        self.animate_zoom_pan(to);
+    }
+
+    /// The comparison pane at `point` in the 2D view, if the panes are unlinked.
+    fn unlinked_pane_at(&self, point: glam::Vec2) -> Option<usize> {
+        if self.panes_linked || self.pane_rects.len() < 2 {
+            return None;
+        }
+        self.pane_rects.iter().position(|&(min, size)| {
+            point.cmpge(min).all() && point.cmplt(min + size).all()
+        })
+    }
+
+    /// Shows the frame `by` frames after the shown one in the frame history, before it if
+    /// negative, see [`frame_history`].
+    fn scrub(&mut self, by: isize) {
+        match self.frame_history.scrub(by) {
+            Some(frame) => self.show_frame(frame),
+            None => eprintln!("No frames in the history to scrub through"),
+        }
+    }
+
+    /// Unlinks the zoom and pan of the comparison panes, or puts them back in line.
+    fn toggle_view_link(&mut self) {
+        self.panes_linked = !self.panes_linked;
+        if self.panes_linked {
+            self.pane_zoom_pans.clear();
+        }
+        if self.panes_linked {
+            println!("Comparison views linked");
+        } else {
+            println!("Comparison views unlinked, gestures move the one under the pointer");
+        }
+    }
+
+    /// Acts on what the fingers on a touchscreen did, see [`touch`].
+    fn on_touch_gesture(&mut self, gesture: touch::Gesture) {
+        use framework::Example as _;
+        use winit::event::{ElementState, MouseButton};
+
+        let (rect_min, rect_size) = self.capture_rect;
+        let in_rect = |position: glam::Vec2| (position - rect_min) / rect_size;
+        match gesture {
+            touch::Gesture::Tap(position) => {
+                self.cursor_position = position;
+                self.on_mouse_input(ElementState::Pressed, MouseButton::Left);
+                self.on_mouse_input(ElementState::Released, MouseButton::Left);
+            }
+            touch::Gesture::LongPress(position) => {
+                self.cursor_position = position;
+                self.on_mouse_input(ElementState::Pressed, MouseButton::Right);
+            }
+            touch::Gesture::StrokeStart(position) => {
+                let point = in_rect(position - self.view_2d_location);
+                let from = self.annotations.clone();
+                let mut to = from.clone();
+                to.push(vec![point]);
+                self.edit(undo::Edit::Annotations { from, to });
+            }
+            touch::Gesture::StrokeTo(position) => {
+                let point = in_rect(position - self.view_2d_location);
+                if let Some(stroke) = self.annotations.last_mut() {
+                    stroke.push(point);
+                    self.annotations_revision += 1;
+                    // The stroke goes on, and so does the edit that started it.
+                    if let Some(undo::Edit::Annotations { to, .. }) = self.undo.latest_mut() {
+                        if let Some(stroke) = to.last_mut() {
+                            stroke.push(point);
+                        }
+                    }
+                }
+            }
+            touch::Gesture::PanZoom { pan, zoom, center } => {
+                let center = center - self.view_2d_location;
+                self.zoom_2d_around(zoom, center);
+                self.pan_2d_by(pan, center);
+            }
+        }
+    }
+
+    /// Keeps pinching and rotating for a while after the fingers lift, none with reduced
+    /// motion.
+    fn apply_gesture_momentum(&mut self, seconds: f32) {
+        if self.accessibility.reduced_motion {
+            self.pinch_momentum.stop();
+            self.rotate_momentum.stop();
+            return;
+        }
+        let zoom = self.pinch_momentum.step(seconds);
+        if zoom != 0.0 {
+            self.zoom_2d_around(zoom.exp(), self.pinch_centroid);
+        }
+        self.camera_orbit.x += self.rotate_momentum.step(seconds);
+    }
+
+    /// Switches to `profile`, or to no profile. Running recordings and streams keep their
+    /// settings, the display capture restarts if the pixel format changes.
+    fn set_profile(&mut self, profile: Option<profiles::Profile>) -> anyhow::Result<()> {
+        let before = &config::get().pixel_format;
+        profiles::switch(profile)?;
+        let after = &config::get().pixel_format;
+        if before.format != after.format
+            || before.allow_chroma_subsampling != after.allow_chroma_subsampling
+        {
+            capture::restart_display();
+        }
+        self.overlay_lod = overlay_lod::OverlayLod::new(config::get().overlay_budget);
+        self.profile_summary = profiles::summary();
+        let name = profile.map_or("none", profiles::Profile::name);
+        status::update(|status| status.profile = profile.map(|profile| profile.name().to_owned()));
+        events::emit(Severity::Info, "profile_switched", "profiles", name);
+        Ok(())
+    }
+
+    fn set_session_paused(&mut self, paused: bool) {
+        if !session_pause::set_paused(paused) {
+            return;
+        }
+        // Other sources keep capturing, their frames are dropped while paused.
+        capture::set_display_paused(paused);
+        if !paused {
+            // No frames arrived while paused, measure the capture rate from now on.
+            self.capture_fps_sample.0 = web_time::Instant::now();
+        }
+        status::update(|status| status.paused = paused);
+        let kind = if paused { "session_paused" } else { "session_resumed" };
+        events::emit(Severity::Info, kind, "session", "");
+    }
+
+    fn push_to_sinks(&mut self, frame: &Frame) {
+        // Paused time is cut from the recording and the live stream holds the last frame.
+        if session_pause::is_paused() {
+            return;
+        }
+        if let Some(recorder) = &mut self.recorder {
+            recorder.push(frame);
+        }
+        if let Some(mcap_export) = &mut self.mcap_export {
+            mcap_export.push(frame);
+        }
+        if let Some(live_stream) = &self.live_stream {
+            live_stream.push(frame);
+        }
+    }
+
+    /// Hands views read back from the GPU to the sinks, they arrive a few frames after
+    /// they were drawn.
+    fn push_view_readbacks(&mut self, re_ctx: &re_renderer::RenderContext) {
+        let mut readbacks = Vec::new();
+        for (view, identifier) in VIEW_READBACKS.into_iter().enumerate() {
+            while ScreenshotProcessor::next_readback_result(
+                re_ctx,
+                identifier,
+                |rgba: &[u8], extent: glam::UVec2, draw: u64| {
+                    readbacks.push((view, draw, view_frame(rgba, extent)));
+                },
+            )
+            .is_some()
+            {}
+        }
+        readbacks.sort_by_key(|(view, draw, _)| (*draw, *view));
+
+        for (view, draw, frame) in readbacks {
+            let mut frame = match (self.feed_source, view) {
+                (recorder::RecordingSource::Composite, 0) => {
+                    self.pending_composite = Some((draw, frame));
+                    continue;
+                }
+                (recorder::RecordingSource::Composite, _) => match self.pending_composite.take() {
+                    Some((left_draw, left)) if left_draw == draw => side_by_side(&left, &frame),
+                    _ => continue,
+                },
+                _ => frame,
+            };
+            self.view_frames_read += 1;
+            frame.frame_id = self.view_frames_read;
+            self.push_to_sinks(&frame);
+        }
+    }
+
+    fn start_recording(&mut self) -> anyhow::Result<()> {
+        let config = &config::get().recording;
+        let [width, height] = self.feed_size().context("No frame captured yet, not recording")?;
+
+        let path = self.output.path_for(
+            output::OutputKind::Recording,
+            &output::Metadata::from(&status::snapshot()),
+            config.encoder.extension(),
+        )?;
+        let recorder = recorder::Recorder::start(config, path, width, height)?;
+        let mcap_config = &config::get().mcap;
+        if mcap_config.enabled {
+            match mcap_export::McapExport::start(mcap_config, &recorder.path) {
+                Ok(mcap_export) => self.mcap_export = Some(mcap_export),
+                Err(err) => {
+                    let message = format!("{err:#}");
+                    events::emit(Severity::Error, "mcap_export_failed", "mcap_export", &message);
+                }
+            }
+        }
+        let path = recorder.path.display().to_string();
+        events::emit(Severity::Info, "recording_started", "recorder", &path);
+        status::update(|status| status.recording = Some(path));
+        self.recorder = Some(recorder);
+        self.last_pushed_frame_id = None;
+        Ok(())
+    }
+
+    fn start_streaming(&mut self) -> anyhow::Result<()> {
+        let [width, height] = self.feed_size().context("No frame captured yet, not streaming")?;
+        let config = config::get();
+        self.live_stream = Some(live_stream::LiveStream::start(
+            &config.stream,
+            &config.recording.ffmpeg_path,
+            width,
+            height,
+        )?);
+        Ok(())
+    }
+
+    fn stop_recording(&mut self) {
+        let Some(recorder) = self.recorder.take() else {
+            return;
+        };
+        status::update(|status| status.recording = None);
+        let path = recorder.path.display().to_string();
+        events::emit(Severity::Info, "recording_stopped", "recorder", &path);
+        // Finalizing can take a while, e.g. waiting for ffmpeg to flush.
+        let priority = io_pool::Priority::Recording;
+        io_pool::submit(priority, "recording", move || match recorder.stop() {
+            Ok(path) => {
+                let path = path.display().to_string();
+                events::emit(Severity::Info, "recording_saved", "recorder", &path);
+            }
+            Err(err) => {
+                let message = format!("{err:#}");
+                events::emit(Severity::Error, "recording_failed", "recorder", &message);
+            }
+        });
+        if let Some(mcap_export) = self.mcap_export.take() {
+            io_pool::submit(priority, "mcap export", move || match mcap_export.stop() {
+                Ok(path) => {
+                    let path = path.display().to_string();
+                    events::emit(Severity::Info, "mcap_export_saved", "mcap_export", &path);
+                }
+                Err(err) => {
+                    let message = format!("{err:#}");
+                    events::emit(Severity::Error, "mcap_export_failed", "mcap_export", &message);
+                }
+            });
+        }
+    }
 }
 
 impl framework::Example for Render2D {
+    type Init = frame_channel::FrameReceiver;
+
     fn title() -> &'static str {
         "2D Rendering"
     }
 
-    fn new(re_ctx: &re_renderer::RenderContext) -> Self {
+    fn content_protected() -> bool {
+        config::get().exclude.own_window
+    }
+
+    fn max_frames_in_flight() -> u32 {
+        config::get().max_frames_in_flight.unwrap_or(2)
+    }
+
+    fn mirror_monitor(
+        event_loop: &winit::event_loop::EventLoopWindowTarget<()>,
+    ) -> Option<winit::monitor::MonitorHandle> {
+        let spec = config::get().mirror.monitor.as_ref()?;
+        let monitor = mirror::find_monitor(event_loop.available_monitors(), spec);
+        if monitor.is_none() {
+            eprintln!("No monitor {spec:?} to mirror the capture on, see --list-monitors");
+        }
+        monitor
+    }
+
+    fn new(re_ctx: &re_renderer::RenderContext, frames: frame_channel::FrameReceiver) -> Self {
+        let feed_source = config::get().recording.source;
+        status::update(|status| {
+            status.feed_source = feed_source.name().to_owned();
+            status.profile = profiles::active().map(|profile| profile.name().to_owned());
+        });
         let rerun_logo =
             image::load_from_memory(include_bytes!("logo_dark_mode.png")).unwrap();
 
@@ -73,12 +1294,95 @@ impl framework::Example for Render2D {
                 },
             )
             .expect("Failed to create texture for rerun logo");
-        Render2D {
+        let mut render = Render2D {
             rerun_logo_texture,
 
             rerun_logo_texture_width: rerun_logo.width(),
             rerun_logo_texture_height: rerun_logo.height(),
+
+            frames,
+            screen_frame: None,
+
+            accessibility: config::get().accessibility,
+            background: background::Background::new(config::get().background),
+            frame_style: frame_style::FrameStyle::new(config::get().frame_style),
+            focus_tracker: config::get()
+                .focus_highlight
+                .then(focus::FocusTracker::start),
+            output: output::OutputManager::new(config::get().output.clone())
+                .expect("Invalid output config"),
+            capture_fps_sample: (web_time::Instant::now(), None),
+            capture_stalled: false,
+            toasts: toasts::Toasts::default(),
+            perf_stats: perf_stats::PerfStats::new(),
+            recorder: None,
+            mcap_export: None,
+            live_stream: None,
+            ab_delay_text: text::TextRect::default(),
+            inspector: inspector::Inspector::default(),
+            texture_pool: texture_pool::TexturePool::default(),
+            ycbcr_converter: gpu_ycbcr::YCbCrConverter::new(&re_ctx.device),
+            screen_texture: None,
+            overlay_cache: None,
+            overlay_lod: overlay_lod::OverlayLod::new(config::get().overlay_budget),
+            hud: hud::Hud::new(&config::get().hud),
+            pipeline_stats: pipeline_stats::PipelineStats::default(),
+            upload_ms: None,
+            frame_history: frame_history::FrameHistory::new(&config::get().history),
+            profile_summary: profiles::summary(),
+            preview_latency: None,
+            capture_failure_hud: text::TextRect::default(),
+            cursor_position: glam::Vec2::ZERO,
+            gamepads: config::get()
+                .gamepad
+                .enabled
+                .then(|| gamepad::Gamepads::new(&config::get().gamepad))
+                .flatten(),
+            camera_orbit: glam::Vec2::ZERO,
+            zoom_pan_2d: gestures::ZoomPan::default(),
+            panes_linked: true,
+            pane_zoom_pans: Vec::new(),
+            pane_rects: Vec::new(),
+            unzoomed_pane_rects: Vec::new(),
+            minimap: minimap::Minimap::default(),
+            mirror_capture: None,
+            mirror_pending: None,
+            mirror_presented: None,
+            zoom_tween: None,
+            region_drag: None,
+            crop_selecting: false,
+            last_click: None,
+            pinch_momentum: gestures::Momentum::default(),
+            pinch_centroid: glam::Vec2::ZERO,
+            rotate_momentum: gestures::Momentum::default(),
+            touches: touch::Touches::default(),
+            annotations: Vec::new(),
+            annotations_revision: 0,
+            gamepad_cursor_shown: false,
+            gamepad_cursor_hud: text::TextRect::default(),
+            context_menu: context_menu::ContextMenu::default(),
+            view_2d_location: glam::Vec2::ZERO,
+            capture_rect: (glam::Vec2::ZERO, glam::Vec2::ZERO),
+            unzoomed_capture_rect: (glam::Vec2::ZERO, glam::Vec2::ONE),
+            animation_seconds: 0.0,
+            paused_animation_seconds: None,
+            previous_step: None,
+            last_pushed_frame_id: None,
+            view_resolutions: [[0, 0]; 2],
+            view_frames_read: 0,
+            feed_source,
+            pending_composite: None,
+            draw_count: 0,
+            undo: undo::UndoStack::default(),
+            presets: presets::Presets::load(&config::get().presets),
+            keymap: keymap::Keymap::new(&config::get().keys)
+                .expect("key bindings were checked when loading the config"),
+            modifiers: winit::keyboard::ModifiersState::empty(),
+        };
+        if config::get().restore_session {
+            render.restore_session();
         }
+        render
     }
 
     fn draw(
@@ -90,228 +1394,727 @@ impl framework::Example for Render2D {
     ) -> Vec<framework::ViewDrawResult> {
         puffin::GlobalProfiler::lock().new_frame();
         puffin::profile_function!();
+        // Frames first, commands may refer to the frame they were sent after.
+        if let Some(frame) = self.frames.latest() {
+            self.frame_history.push(frame.clone());
+            if self.frame_history.is_scrubbing() {
+                // The recorder and the live stream go on with the live capture.
+                if self.feed_source == recorder::RecordingSource::Capture {
+                    self.push_to_sinks(&frame);
+                }
+                self.last_pushed_frame_id = Some(frame.frame_id);
+            } else {
+                self.show_frame(frame);
+            }
+        }
+        for command in control::drain() {
+            self.handle_command(command);
+        }
+
+        self.poll_gamepads(time.last_frame_duration.as_secs_f32());
+        self.apply_gesture_momentum(time.last_frame_duration.as_secs_f32());
+        if let Some(gesture) = self.touches.poll() {
+            self.on_touch_gesture(gesture);
+        }
+
         let splits = framework::split_resolution(resolution, 1, 2).collect::<Vec<_>>();
+        self.view_resolutions = [splits[0].resolution_in_pixel, splits[1].resolution_in_pixel];
+        self.draw_count += 1;
+        self.texture_pool.begin_frame();
+        let feeding = self.recorder.is_some() || self.live_stream.is_some();
+        let fed_views = if feeding { self.feed_source.views() } else { &[] };
+        self.push_view_readbacks(re_ctx);
+        let palette = self.accessibility.palette();
+        let animation_seconds = self
+            .paused_animation_seconds
+            .unwrap_or_else(|| self.accessibility.animation_seconds(time, self.animation_seconds));
+        self.animation_seconds = animation_seconds;
+        let gradient = if palette.line_gradients {
+            LineStripFlags::FLAG_COLOR_GRADIENT
+        } else {
+            LineStripFlags::empty()
+        };
 
         let screen_size = glam::vec2(
             splits[0].resolution_in_pixel[0] as f32,
             splits[0].resolution_in_pixel[1] as f32,
         );
 
-        let mut line_strip_builder = LineDrawableBuilder::new(re_ctx);
-        line_strip_builder.reserve_strips(128).unwrap();
-        line_strip_builder.reserve_vertices(2048).unwrap();
+        let image_scale = 4.0;
+        let capture_rect_min = glam::vec2(500.0, 120.0);
+        let mut capture_rect_size = glam::vec2(
+            self.rerun_logo_texture_width as f32 * image_scale,
+            self.rerun_logo_texture_height as f32 * image_scale,
+        );
+        // Windows come in any shape and change it when resized, keep their aspect ratio.
+        if config::get().window.is_set() {
+            if let Some([width, height]) = self.capture_size() {
+                let frame_size = glam::vec2(width as f32, height as f32);
+                capture_rect_size = frame_size * (capture_rect_size / frame_size).min_element();
+            }
+        }
+        if let Some(tween) = &mut self.zoom_tween {
+            self.zoom_pan_2d = tween.value(time);
+            if tween.is_done(time) {
+                self.zoom_tween = None;
+            }
+        }
+        // Clicking or dragging in the minimap centers the capture rect as last drawn there,
+        // moving all panes.
+        if let Some(target) = self.minimap.take_target() {
+            self.zoom_tween = None;
+            let (min, size) = self.capture_rect;
+            self.zoom_pan_2d.pan += screen_size * 0.5 - (min + target * size);
+        }
+        self.unzoomed_capture_rect = (capture_rect_min, capture_rect_size);
+        // Zoomed around the center, and panned, with the gamepad and gestures.
+        let (capture_rect_min, capture_rect_size) =
+            self.zoom_pan_2d.apply(self.unzoomed_capture_rect);
+        // Comparisons show their sides in panes, the capture rect first. With all displays
+        // captured, the first one takes the first cell of a grid filling the capture rect,
+        // the others the following cells. In A/B comparison mode B is next to A.
+        let ab_pair = ab_compare::pair();
+        let is_multi_display = config::get().multi_display.enabled && ab_pair.is_none();
+        let unzoomed_pane_rects = if ab_pair.is_some() {
+            let b_rect_min = capture_rect_min + glam::vec2(capture_rect_size.x + 40.0, 0.0);
+            vec![(capture_rect_min, capture_rect_size), (b_rect_min, capture_rect_size)]
+        } else if is_multi_display {
+            let count = multi_display::other_frames().len() + 1;
+            multi_display::grid(count, capture_rect_min, capture_rect_size)
+        } else {
+            vec![(capture_rect_min, capture_rect_size)]
+        };
+        self.pane_zoom_pans.resize(unzoomed_pane_rects.len(), gestures::ZoomPan::default());
+        let pane_rects = unzoomed_pane_rects
+            .iter()
+            .zip(&self.pane_zoom_pans)
+            .map(|(&rect, zoom_pan)| zoom_pan.apply(rect))
+            .collect_vec();
+        self.unzoomed_pane_rects = unzoomed_pane_rects;
+        self.pane_rects = pane_rects.clone();
+        let (capture_rect_min, capture_rect_size) = pane_rects[0];
+        self.capture_rect = (capture_rect_min, capture_rect_size);
+        self.view_2d_location = splits[0].target_location;
 
-        // Blue rect outline around the bottom right quarter.
-        {
-            let mut line_batch = line_strip_builder.batch("quads");
-            let line_radius = 10.0;
-            let blue_rect_position = screen_size * 0.5 - glam::vec2(line_radius, line_radius);
-            line_batch
-                .add_rectangle_outline_2d(
-                    blue_rect_position,
-                    glam::vec2(screen_size.x * 0.5, 0.0),
-                    glam::vec2(0.0, screen_size.y * 0.5),
-                )
-                .radius(Size::new_scene(line_radius))
-                .color(Color32::BLUE);
-
-            // .. within, a orange rectangle
-            line_batch
-                .add_rectangle_outline_2d(
-                    blue_rect_position + screen_size * 0.125,
-                    glam::vec2(screen_size.x * 0.25, 0.0),
-                    glam::vec2(0.0, screen_size.y * 0.25),
-                )
-                .radius(Size::new_scene(5.0))
-                .color(Color32::from_rgb(255, 100, 1));
-        }
-
-        // All variations of line caps
-        {
-            let mut line_batch = line_strip_builder.batch("line cap variations");
-            for (i, flags) in [
-                LineStripFlags::empty(),
-                LineStripFlags::FLAG_CAP_START_ROUND,
-                LineStripFlags::FLAG_CAP_END_ROUND,
-                LineStripFlags::FLAG_CAP_START_TRIANGLE,
-                LineStripFlags::FLAG_CAP_END_TRIANGLE,
-                LineStripFlags::FLAG_CAP_START_ROUND | LineStripFlags::FLAG_CAP_END_ROUND,
-                LineStripFlags::FLAG_CAP_START_ROUND | LineStripFlags::FLAG_CAP_END_TRIANGLE,
-                LineStripFlags::FLAG_CAP_START_TRIANGLE | LineStripFlags::FLAG_CAP_END_ROUND,
-                LineStripFlags::FLAG_CAP_START_TRIANGLE | LineStripFlags::FLAG_CAP_END_TRIANGLE,
-            ]
-                .iter()
-                .enumerate()
+        // The overlays only change 6 times a second with the overlap demo, or on input, so
+        // their draw data gets reused until then. re_renderer has no way to update parts of
+        // existing draw data, any change rebuilds everything.
+        let num_lines = 20_i16;
+        // Cycle through which line is on top.
+        let top_line = match &self.overlay_cache {
+            Some((inputs, ..)) if self.overlay_lod.is_active(Degradation::FreezeOverlapDemo) => {
+                inputs.top_line
+            }
+            _ => ((animation_seconds * 6.0) as i16 % (num_lines * 2 - 1) - num_lines).abs(),
+        };
+        let focused = self.focus_tracker.as_ref().and_then(focus::FocusTracker::focused);
+        let source_rect = self.screen_frame.as_ref().map(|frame| frame.source_rect);
+        let inputs = OverlayInputs {
+            screen_size,
+            capture_rect: self.capture_rect,
+            top_line,
+            high_contrast: self.accessibility.high_contrast,
+            frame_style: self.frame_style.config,
+            focus_highlight: focused.zip(source_rect),
+            annotations_revision: self.annotations_revision,
+        };
+        let cached = self
+            .overlay_cache
+            .as_ref()
+            .filter(|(cached_inputs, ..)| *cached_inputs == inputs)
+            .map(|(_, lines, points)| (lines.clone(), points.clone()));
+        let (line_strip_draw_data, point_draw_data) = if let Some(cached) = cached {
+            cached
+        } else {
+            let mut line_strip_builder = LineDrawableBuilder::new(re_ctx);
+            line_strip_builder.reserve_strips(128).unwrap();
+            line_strip_builder.reserve_vertices(2048).unwrap();
+
+            // Blue rect outline around the bottom right quarter.
             {
-                let y = (i + 1) as f32 * 70.0;
+                let mut line_batch = line_strip_builder.batch("quads");
+                let line_radius = 10.0;
+                let blue_rect_position = screen_size * 0.5 - glam::vec2(line_radius, line_radius);
+                line_batch
+                    .add_rectangle_outline_2d(
+                        blue_rect_position,
+                        glam::vec2(screen_size.x * 0.5, 0.0),
+                        glam::vec2(0.0, screen_size.y * 0.5),
+                    )
+                    .radius(Size::new_scene(line_radius))
+                    .color(palette.outer_rect);
+
+                // .. within, a orange rectangle
                 line_batch
-                    .add_segment_2d(glam::vec2(70.0, y), glam::vec2(400.0, y))
-                    .radius(Size::new_scene(15.0))
-                    .flags(*flags | LineStripFlags::FLAG_COLOR_GRADIENT);
+                    .add_rectangle_outline_2d(
+                        blue_rect_position + screen_size * 0.125,
+                        glam::vec2(screen_size.x * 0.25, 0.0),
+                        glam::vec2(0.0, screen_size.y * 0.25),
+                    )
+                    .radius(Size::new_scene(5.0))
+                    .color(palette.inner_rect);
             }
-        }
 
-        // Lines with non-default arrow heads - long thin arrows.
-        {
-            let mut line_batch = line_strip_builder
-                .batch("larger arrowheads")
-                .triangle_cap_length_factor(15.0)
-                .triangle_cap_width_factor(3.0);
-            for (i, flags) in [
-                LineStripFlags::FLAG_CAP_START_TRIANGLE | LineStripFlags::FLAG_CAP_END_ROUND,
-                LineStripFlags::FLAG_CAP_START_ROUND | LineStripFlags::FLAG_CAP_END_TRIANGLE,
-                LineStripFlags::FLAG_CAP_START_TRIANGLE | LineStripFlags::FLAG_CAP_END_TRIANGLE,
-            ]
-                .iter()
-                .enumerate()
+            // All variations of line caps
+            {
+                let mut line_batch = line_strip_builder.batch("line cap variations");
+                for (i, flags) in [
+                    LineStripFlags::empty(),
+                    LineStripFlags::FLAG_CAP_START_ROUND,
+                    LineStripFlags::FLAG_CAP_END_ROUND,
+                    LineStripFlags::FLAG_CAP_START_TRIANGLE,
+                    LineStripFlags::FLAG_CAP_END_TRIANGLE,
+                    LineStripFlags::FLAG_CAP_START_ROUND | LineStripFlags::FLAG_CAP_END_ROUND,
+                    LineStripFlags::FLAG_CAP_START_ROUND | LineStripFlags::FLAG_CAP_END_TRIANGLE,
+                    LineStripFlags::FLAG_CAP_START_TRIANGLE | LineStripFlags::FLAG_CAP_END_ROUND,
+                    LineStripFlags::FLAG_CAP_START_TRIANGLE | LineStripFlags::FLAG_CAP_END_TRIANGLE,
+                ]
+                    .iter()
+                    .enumerate()
+                {
+                    let y = (i + 1) as f32 * 70.0;
+                    line_batch
+                        .add_segment_2d(glam::vec2(70.0, y), glam::vec2(400.0, y))
+                        .radius(Size::new_scene(15.0))
+                        .flags(*flags | gradient);
+                }
+            }
+
+            // Lines with non-default arrow heads - long thin arrows.
+            {
+                let mut line_batch = line_strip_builder
+                    .batch("larger arrowheads")
+                    .triangle_cap_length_factor(15.0)
+                    .triangle_cap_width_factor(3.0);
+                for (i, flags) in [
+                    LineStripFlags::FLAG_CAP_START_TRIANGLE | LineStripFlags::FLAG_CAP_END_ROUND,
+                    LineStripFlags::FLAG_CAP_START_ROUND | LineStripFlags::FLAG_CAP_END_TRIANGLE,
+                    LineStripFlags::FLAG_CAP_START_TRIANGLE | LineStripFlags::FLAG_CAP_END_TRIANGLE,
+                ]
+                    .iter()
+                    .enumerate()
+                {
+                    let y = (i + 1) as f32 * 40.0 + 650.0;
+                    line_batch
+                        .add_segment_2d(glam::vec2(70.0, y), glam::vec2(400.0, y))
+                        .radius(Size::new_scene(5.0))
+                        .flags(*flags);
+                }
+            }
+
+            // Lines with different kinds of radius
+            // The first two lines are the same thickness if there no (!) scaling.
+            // Moving the windows to a high dpi screen makes the second one bigger.
+            // Also, it looks different under perspective projection.
+            // The third line is automatic thickness which is determined by the line renderer implementation.
             {
-                let y = (i + 1) as f32 * 40.0 + 650.0;
+                let mut line_batch = line_strip_builder.batch("radius variations");
                 line_batch
-                    .add_segment_2d(glam::vec2(70.0, y), glam::vec2(400.0, y))
-                    .radius(Size::new_scene(5.0))
-                    .flags(*flags);
+                    .add_segment_2d(glam::vec2(500.0, 10.0), glam::vec2(1000.0, 10.0))
+                    .radius(Size::new_scene(4.0))
+                    .color(palette.radius_variations);
+                line_batch
+                    .add_segment_2d(glam::vec2(500.0, 30.0), glam::vec2(1000.0, 30.0))
+                    .radius(Size::new_points(4.0))
+                    .color(palette.radius_variations);
+                line_batch
+                    .add_segment_2d(glam::vec2(500.0, 60.0), glam::vec2(1000.0, 60.0))
+                    .radius(Size::AUTO)
+                    .color(palette.radius_variations);
+                line_batch
+                    .add_segment_2d(glam::vec2(500.0, 90.0), glam::vec2(1000.0, 90.0))
+                    .radius(Size::AUTO_LARGE)
+                    .color(palette.radius_variations);
             }
-        }
 
-        // Lines with different kinds of radius
-        // The first two lines are the same thickness if there no (!) scaling.
-        // Moving the windows to a high dpi screen makes the second one bigger.
-        // Also, it looks different under perspective projection.
-        // The third line is automatic thickness which is determined by the line renderer implementation.
-        {
-            let mut line_batch = line_strip_builder.batch("radius variations");
-            line_batch
-                .add_segment_2d(glam::vec2(500.0, 10.0), glam::vec2(1000.0, 10.0))
-                .radius(Size::new_scene(4.0))
-                .color(Color32::from_rgb(255, 180, 1));
-            line_batch
-                .add_segment_2d(glam::vec2(500.0, 30.0), glam::vec2(1000.0, 30.0))
-                .radius(Size::new_points(4.0))
-                .color(Color32::from_rgb(255, 180, 1));
-            line_batch
-                .add_segment_2d(glam::vec2(500.0, 60.0), glam::vec2(1000.0, 60.0))
-                .radius(Size::AUTO)
-                .color(Color32::from_rgb(255, 180, 1));
-            line_batch
-                .add_segment_2d(glam::vec2(500.0, 90.0), glam::vec2(1000.0, 90.0))
-                .radius(Size::AUTO_LARGE)
-                .color(Color32::from_rgb(255, 180, 1));
-        }
-
-        // Points with different kinds of radius
-        // The first two points are the same thickness if there no (!) scaling.
-        // Moving the windows to a high dpi screen makes the second one bigger.
-        // Also, it looks different under perspective projection.
-        // The third point is automatic thickness which is determined by the point renderer implementation.
-        let mut point_cloud_builder = PointCloudBuilder::new(re_ctx);
-        point_cloud_builder.reserve(128).unwrap();
-        point_cloud_builder.batch("points").add_points_2d(
-            &[
-                glam::vec3(500.0, 120.0, 0.0),
-                glam::vec3(520.0, 120.0, 0.0),
-                glam::vec3(540.0, 120.0, 0.0),
-                glam::vec3(560.0, 120.0, 0.0),
-            ],
-            &[
-                Size::new_scene(4.0),
-                Size::new_points(4.0),
-                Size::AUTO,
-                Size::AUTO_LARGE,
-            ],
-            &[Color32::from_rgb(55, 180, 1); 4],
-            &[re_renderer::PickingLayerInstanceId::default(); 4],
-        );
+            // Points with different kinds of radius
+            // The first two points are the same thickness if there no (!) scaling.
+            // Moving the windows to a high dpi screen makes the second one bigger.
+            // Also, it looks different under perspective projection.
+            // The third point is automatic thickness which is determined by the point renderer implementation.
+            let mut point_cloud_builder = PointCloudBuilder::new(re_ctx);
+            point_cloud_builder.reserve(128).unwrap();
+            point_cloud_builder.batch("points").add_points_2d(
+                &[
+                    glam::vec3(500.0, 120.0, 0.0),
+                    glam::vec3(520.0, 120.0, 0.0),
+                    glam::vec3(540.0, 120.0, 0.0),
+                    glam::vec3(560.0, 120.0, 0.0),
+                ],
+                &[
+                    Size::new_scene(4.0),
+                    Size::new_points(4.0),
+                    Size::AUTO,
+                    Size::AUTO_LARGE,
+                ],
+                &[palette.points; 4],
+                &[re_renderer::PickingLayerInstanceId::default(); 4],
+            );
 
-        // Pile stuff to test for overlap handling.
-        // Do in individual batches to test depth offset.
-        {
-            let num_lines = 20_i16;
-            let y_range = 800.0..880.0;
-
-            // Cycle through which line is on top.
-            let top_line = ((time.seconds_since_startup() * 6.0) as i16 % (num_lines * 2 - 1)
-                - num_lines)
-                .abs();
-            for i in 0..num_lines {
-                let depth_offset = if i < top_line { i } else { top_line * 2 - i };
-                let mut batch = line_strip_builder
-                    .batch(format!("overlapping objects {i}"))
-                    .depth_offset(depth_offset);
-
-                let x = 15.0 * i as f32 + 20.0;
-                batch
-                    .add_segment_2d(glam::vec2(x, y_range.start), glam::vec2(x, y_range.end))
-                    .color(Hsva::new(0.25 / num_lines as f32 * i as f32, 1.0, 0.5, 1.0).into())
-                    .radius(Size::new_points(10.0))
-                    .flags(LineStripFlags::FLAG_COLOR_GRADIENT);
-            }
-
-            let num_points = 8;
-            let size = Size::new_points(3.0);
-
-            let positions = (0..num_points)
-                .map(|i| {
-                    glam::vec3(
-                        30.0 * i as f32 + 20.0,
-                        y_range.start
-                            + (y_range.end - y_range.start) / num_points as f32 * i as f32,
-                        0.0,
-                    )
-                })
-                .collect_vec();
+            // Pile stuff to test for overlap handling.
+            // Do in individual batches to test depth offset.
+            {
+                let num_lines = 20_i16;
+                let y_range = 800.0..880.0;
 
-            let sizes = vec![size; num_points];
+                let top_line = inputs.top_line;
+                for i in 0..num_lines {
+                    let depth_offset = if i < top_line { i } else { top_line * 2 - i };
+                    let mut batch = line_strip_builder
+                        .batch(format!("overlapping objects {i}"))
+                        .depth_offset(depth_offset);
 
-            let colors = vec![Color32::WHITE; num_points];
+                    let x = 15.0 * i as f32 + 20.0;
+                    batch
+                        .add_segment_2d(glam::vec2(x, y_range.start), glam::vec2(x, y_range.end))
+                        .color(self.accessibility.overlap_line_color(i, num_lines))
+                        .radius(Size::new_points(10.0))
+                        .flags(gradient);
+                }
 
-            let picking_ids = vec![re_renderer::PickingLayerInstanceId::default(); num_points];
+                let num_points = 8;
+                let size = Size::new_points(3.0);
 
-            point_cloud_builder
-                .batch("points overlapping with lines")
-                .depth_offset(5)
-                .add_points_2d(&positions, &sizes, &colors, &picking_ids);
-        }
+                let positions = (0..num_points)
+                    .map(|i| {
+                        glam::vec3(
+                            30.0 * i as f32 + 20.0,
+                            y_range.start
+                                + (y_range.end - y_range.start) / num_points as f32 * i as f32,
+                            0.0,
+                        )
+                    })
+                    .collect_vec();
 
-        let line_strip_draw_data = line_strip_builder.into_draw_data().unwrap();
-        let point_draw_data = point_cloud_builder.into_draw_data().unwrap();
+                let sizes = vec![size; num_points];
 
-        let image_scale = 4.0;
+                let colors = vec![palette.overlap_points; num_points];
 
-        let texture = if let Some(texture) = SCREEN_TEXTURE.lock().unwrap().as_ref() {
-            puffin::profile_scope!("screen texture");
-            let Frame { frame_bitmap, .. } = texture;
-            let screen_texture = re_ctx.texture_manager_2d.create(
-                &re_ctx.gpu_resources.textures,
-                &Texture2DCreationDesc {
-                    label: "screen texture".into(),
-                    data: Cow::Owned(frame_bitmap.data.iter().flatten().copied().collect::<Vec<_>>()),
-                    format: wgpu::TextureFormat::Bgra8Unorm,
-                    width: frame_bitmap.width as u32,
-                    height: frame_bitmap.height as u32,
-                },
-            ).unwrap();
-            screen_texture
+                let picking_ids = vec![re_renderer::PickingLayerInstanceId::default(); num_points];
+
+                point_cloud_builder
+                    .batch("points overlapping with lines")
+                    .depth_offset(5)
+                    .add_points_2d(&positions, &sizes, &colors, &picking_ids);
+            }
+
+            self.frame_style
+                .add_border(&mut line_strip_builder, capture_rect_min, capture_rect_size);
+
+            // Strokes drawn on a touchscreen.
+            {
+                let mut batch = line_strip_builder.batch("annotations");
+                for stroke in self.annotations.iter().filter(|stroke| stroke.len() > 1) {
+                    let points =
+                        stroke.iter().map(|point| capture_rect_min + *point * capture_rect_size);
+                    batch
+                        .add_strip_2d(points)
+                        .radius(Size::new_points(3.0))
+                        .color(palette.annotation);
+                }
+            }
+
+            // Highlight of the focused UI element, mapped from screen points onto the capture.
+            if let Some((focused, source_rect)) = inputs.focus_highlight {
+                let scale = capture_rect_size / source_rect.size;
+                let top_left = capture_rect_min + (focused.min - source_rect.min) * scale;
+                let extent = focused.size * scale;
+                line_strip_builder
+                    .batch("focus highlight")
+                    .add_rectangle_outline_2d(
+                        top_left,
+                        glam::vec2(extent.x, 0.0),
+                        glam::vec2(0.0, extent.y),
+                    )
+                    .radius(Size::new_points(2.0))
+                    .color(palette.highlight);
+            }
+
+            let line_strip_draw_data = line_strip_builder.into_draw_data().unwrap();
+            let point_draw_data = point_cloud_builder.into_draw_data().unwrap();
+            self.overlay_cache = Some((inputs, line_strip_draw_data.clone(), point_draw_data.clone()));
+            (line_strip_draw_data, point_draw_data)
+        };
+
+        let screen_frame = self.screen_frame.clone();
+        // In A/B comparison mode the capture rect shows the A frame matching B instead.
+        let shown_frame = ab_pair.as_ref().map(|pair| &*pair.a).or(screen_frame.as_deref());
+        let texture = if let Some(frame) = shown_frame {
+            self.screen_texture(re_ctx, frame, capture_rect_size)
         } else {
             self.rerun_logo_texture.clone()
         };
+        // Frames scrubbed to are neither new nor late.
+        let is_scrubbing = self.frame_history.is_scrubbing();
+        if let Some(frame) = screen_frame.as_ref().filter(|_| !is_scrubbing) {
+            let (frame_id, capture_time_us) = (frame.frame_id, frame.capture_time_us);
+            self.pipeline_stats.frame_drawn(frame_id, capture_time_us, self.upload_ms);
+        }
+        let mut inspector_stages = Vec::new();
+        if self.inspector.is_enabled() {
+            inspector_stages.push(inspector::Stage {
+                name: "capture (styled)",
+                texture: texture.clone(),
+                value_range: shown_frame.map(|frame| inspector::value_range(&frame.frame_bitmap.data)),
+            });
+        }
+        let mut ab_rects = Vec::new();
+        if let Some(pair) = &ab_pair {
+            let (b_rect_min, b_rect_size) = pane_rects[1];
+            ab_rects.push(TexturedRect {
+                top_left_corner_position: b_rect_min.extend(-0.05),
+                extent_u: b_rect_size.x * glam::Vec3::X,
+                extent_v: b_rect_size.y * glam::Vec3::Y,
+                colormapped_texture: ColormappedTexture::from_unorm_rgba(
+                    self.frame_texture(re_ctx, &pair.b, b_rect_size),
+                ),
+                options: RectangleOptions {
+                    texture_filter_magnification: TextureFilterMag::Nearest,
+                    texture_filter_minification: TextureFilterMin::Linear,
+                    ..Default::default()
+                },
+            });
+            let text = match pair.delay_ms {
+                Some(delay_ms) => format!("B lags A by {delay_ms:.0} ms"),
+                None => "B lags A by: waiting for changing content".to_owned(),
+            };
+            ab_rects.push(self.ab_delay_text.rect(
+                re_ctx,
+                &text,
+                [255, 255, 255, 255],
+                (b_rect_min - glam::vec2(0.0, 40.0)).extend(-0.05),
+                3.0,
+            ));
+        }
+        let mut display_rects = Vec::new();
+        let other_frames = multi_display::other_frames();
+        let display_cells: &[_] = if is_multi_display { &pane_rects[1..] } else { &[] };
+        for (frame, &(min, size)) in other_frames.iter().zip(display_cells) {
+            let Some(frame) = frame else {
+                continue;
+            };
+            display_rects.push(TexturedRect {
+                top_left_corner_position: min.extend(-0.05),
+                extent_u: size.x * glam::Vec3::X,
+                extent_v: size.y * glam::Vec3::Y,
+                colormapped_texture: ColormappedTexture::from_unorm_rgba(
+                    self.frame_texture(re_ctx, frame, size),
+                ),
+                options: RectangleOptions {
+                    texture_filter_magnification: TextureFilterMag::Nearest,
+                    texture_filter_minification: TextureFilterMin::Linear,
+                    ..Default::default()
+                },
+            });
+        }
+        drop(other_frames);
+
+        // A crosshair on every rect showing the capture, at the content under the pointer
+        // in either view.
+        let camera_3d = {
+            let camera_rotation_center = screen_size.extend(0.0) * 0.5;
+            let yaw = animation_seconds + self.camera_orbit.x;
+            crosshair::Camera3d {
+                position: glam::vec3(yaw.sin(), 0.5 + self.camera_orbit.y, yaw.cos())
+                    * screen_size.x.max(screen_size.y)
+                    + camera_rotation_center,
+                target: camera_rotation_center,
+                vertical_fov: 70.0 * std::f32::consts::TAU / 360.0,
+                aspect_ratio: resolution[0] as f32 / resolution[1] as f32,
+            }
+        };
+        // The other displays show other content.
+        let content_rects = if is_multi_display { &pane_rects[..1] } else { &pane_rects[..] };
+        let scene_position = match self.cursor_in_view_2d() {
+            Some(position) => Some(position),
+            None => {
+                let [width, height] = splits[1].resolution_in_pixel;
+                let view_3d_size = glam::vec2(width as f32, height as f32);
+                let position = self.cursor_position - splits[1].target_location;
+                let inside = position.cmpge(glam::Vec2::ZERO).all()
+                    && position.cmplt(view_3d_size).all();
+                inside.then(|| camera_3d.unproject(position, view_3d_size)).flatten()
+            }
+        };
+        let crosshair_draw_data = scene_position
+            .and_then(|position| crosshair::content_position(content_rects, position))
+            .map(|position| {
+                let mut builder = LineDrawableBuilder::new(re_ctx);
+                builder.reserve_strips(content_rects.len() * 2).unwrap();
+                builder.reserve_vertices(content_rects.len() * 4).unwrap();
+                let color = palette.crosshair;
+                crosshair::add_crosshairs(&mut builder, content_rects, position, color);
+                builder.into_draw_data().unwrap()
+            });
+        self.background.freeze_blur = self.overlay_lod.is_active(Degradation::FreezeBackgroundBlur);
+        let background_rect = self.background.rect(
+            re_ctx,
+            screen_size,
+            screen_frame
+                .as_ref()
+                .map(|frame| (&frame.frame_bitmap, frame.frame_id)),
+        );
+        if let Some(rect) = background_rect.as_ref().filter(|_| self.inspector.is_enabled()) {
+            inspector_stages.push(inspector::Stage {
+                name: "background",
+                texture: rect.colormapped_texture.texture.clone(),
+                value_range: None,
+            });
+        }
+        let latest_frame_id = screen_frame.as_ref().map(|frame| frame.frame_id);
+        let is_holding_frame =
+            frame_step::is_paused() || session_pause::is_paused() || is_scrubbing;
+        if let Some(frame) = screen_frame.as_ref().filter(|_| !is_holding_frame) {
+            let is_stalled =
+                capture::now_us().saturating_sub(frame.capture_time_us) > CAPTURE_STALL_US;
+            if is_stalled != self.capture_stalled {
+                self.capture_stalled = is_stalled;
+                let (severity, kind, message) = if is_stalled {
+                    let message = format!("no frame since frame {}", frame.frame_id);
+                    (Severity::Warning, "capture_stalled", message)
+                } else {
+                    (Severity::Info, "capture_resumed", format!("frame {}", frame.frame_id))
+                };
+                events::emit(severity, kind, &status::snapshot().source_name, &message);
+            }
+        }
+        let capture_fps = {
+            let (sample_time, sample_frame_id) = &mut self.capture_fps_sample;
+            let elapsed = sample_time.elapsed().as_secs_f32();
+            (elapsed >= 1.0).then(|| {
+                let fps = match (*sample_frame_id, latest_frame_id) {
+                    (Some(from), Some(to)) => to.saturating_sub(from) as f32 / elapsed,
+                    _ => 0.0,
+                };
+                *sample_time = web_time::Instant::now();
+                *sample_frame_id = latest_frame_id;
+                fps
+            })
+        };
+        let is_session_paused = session_pause::is_paused();
+        if !is_session_paused {
+            self.perf_stats.record_frame(re_ctx, time.last_frame_duration);
+        }
+        if self.perf_stats.is_warmed_up() {
+            self.overlay_lod.update(time.last_frame_duration.as_secs_f32() * 1000.0);
+        }
+        let overlay_degradations = self
+            .overlay_lod
+            .active()
+            .iter()
+            .map(|degradation| degradation.name().to_owned())
+            .collect();
+        let performance = self.perf_stats.summary();
+        let texture_pool_stats = self.texture_pool.stats();
+        if let Some(frame) = screen_frame.as_ref().filter(|_| !is_scrubbing) {
+            if self.preview_latency.map(|(frame_id, _)| frame_id) != Some(frame.frame_id) {
+                // The frame's age now, plus as long as the GPU took for the latest frame it
+                // finished; this one waits for the frames in flight ahead of it the same way.
+                let age_us = capture::now_us().saturating_sub(frame.capture_time_us);
+                let age_ms = age_us as f32 / 1000.0;
+                let gpu_ms = time.gpu_latency.as_secs_f32() * 1000.0;
+                self.preview_latency = Some((frame.frame_id, age_ms + gpu_ms));
+            }
+        }
+        let preview_latency_ms = self.preview_latency.map(|(_, latency_ms)| latency_ms);
+        let pipeline_summary = self.pipeline_stats.summary();
+        let scrubbed_frames_back = self.frame_history.position().map(|(back, _)| back);
+        status::update(|status| {
+            if !is_session_paused {
+                status.frames_rendered += 1;
+            }
+            status.performance = performance;
+            status.texture_pool = texture_pool_stats;
+            status.overlay_degradations = overlay_degradations;
+            if let Some(capture_fps) = capture_fps {
+                status.capture_fps = capture_fps;
+            }
+            status.render_fps = 1.0 / time.last_frame_duration.as_secs_f32().max(f32::EPSILON);
+            status.frames_in_flight = time.frames_in_flight;
+            status.preview_latency_ms = preview_latency_ms;
+            status.pipeline = pipeline_summary;
+            status.scrubbed_frames_back = scrubbed_frames_back;
+            status.uptime_seconds = time.seconds_since_startup();
+            if let Some(frame) = screen_frame.as_ref() {
+                status.latest_frame_id = Some(frame.frame_id);
+                status.capture_size = Some([
+                    frame.frame_bitmap.width as u32,
+                    frame.frame_bitmap.height as u32,
+                ]);
+            }
+        });
+        if let Some(frame) = screen_frame.as_ref().filter(|_| !is_scrubbing) {
+            if self.last_pushed_frame_id != Some(frame.frame_id) {
+                if self.feed_source == recorder::RecordingSource::Capture {
+                    self.push_to_sinks(frame);
+                }
+                self.last_pushed_frame_id = Some(frame.frame_id);
+            }
+        }
+        drop(screen_frame);
+
+        let shadow_rect = if self.overlay_lod.is_active(Degradation::NoShadow) {
+            None
+        } else {
+            self.frame_style
+                .shadow_rect(re_ctx, capture_rect_min, capture_rect_size)
+        };
+        if let Some(rect) = shadow_rect.as_ref().filter(|_| self.inspector.is_enabled()) {
+            inspector_stages.push(inspector::Stage {
+                name: "shadow",
+                texture: rect.colormapped_texture.texture.clone(),
+                value_range: None,
+            });
+        }
 
+        // Overlays only shown in the 2D view.
+        if let Some(live_stream) = &self.live_stream {
+            let health = live_stream.health();
+            let (text, color) = health.summary();
+            self.hud.show(hud::Widget::Stream, text, color);
+            status::update(|status| status.streaming = Some(health));
+        }
+        if frame_step::is_paused() {
+            let text = self.frame_step_text(time);
+            self.hud.show(hud::Widget::FrameStep, text, [255, 255, 255, 255]);
+        }
+        if self.crop_selecting {
+            let text = "Drag out the region to crop the capture to, T cancels";
+            self.hud.show(hud::Widget::Crop, text, [120, 200, 255, 255]);
+        } else if let Some(rect) = capture_crop::get() {
+            let ([x, y], [width, height]) = (rect.min.to_array(), rect.size.to_array());
+            let text = format!("Cropped to {width}x{height} at {x}, {y}, T shows all of it");
+            self.hud.show(hud::Widget::Crop, text, [120, 200, 255, 255]);
+        }
+        if let Some((frames_back, frames_kept)) = self.frame_history.position() {
+            let text = format!("{frames_back} of {frames_kept} frames back, Right goes forward");
+            self.hud.show(hud::Widget::History, text, [120, 200, 255, 255]);
+        }
+        if is_session_paused {
+            let text = "Session paused, press Space to resume";
+            self.hud.show(hud::Widget::Pause, text, [255, 210, 60, 255]);
+        }
+        if let Some(text) = self.overlay_lod.summary() {
+            self.hud.show(hud::Widget::OverlayLod, text, [255, 200, 0, 255]);
+        }
+        if let Some(text) = &self.profile_summary {
+            self.hud.show(hud::Widget::Profile, text.clone(), [160, 220, 255, 255]);
+        }
+        let fps = 1.0 / time.last_frame_duration.as_secs_f32().max(f32::EPSILON);
+        if let Some(max_frames_in_flight) = config::get().max_frames_in_flight {
+            let latency = preview_latency_ms.map_or("-".to_owned(), |ms| format!("{ms:.1} ms"));
+            let in_flight = format!("{}/{max_frames_in_flight}", time.frames_in_flight);
+            let text = format!("{in_flight} frames in flight, {fps:.0} fps, {latency} latency");
+            self.hud.show(hud::Widget::FramesInFlight, text, [255, 255, 255, 255]);
+        }
+        self.hud.show(hud::Widget::Pipeline, self.pipeline_stats.text(), [255, 255, 255, 255]);
+        self.hud.show_stats(&hud::FrameStats {
+            fps,
+            frame_ms: time.last_frame_duration.as_secs_f32() * 1000.0,
+            preview_latency: self.preview_latency,
+            texture_pool: texture_pool_stats,
+            recording: self.recorder.as_ref().map(|recorder| recorder.path.as_path()),
+        });
+        let mut hud_rects = self.hud.rects(re_ctx, screen_size);
+        if self.gamepad_cursor_shown {
+            // Centered on the cursor, the "+" is 12 font pixels wide with the padding.
+            let position = self.cursor_position - self.view_2d_location - glam::Vec2::splat(12.0);
+            hud_rects.push(self.gamepad_cursor_hud.rect(
+                re_ctx,
+                "+",
+                [255, 255, 255, 255],
+                position.extend(0.0),
+                2.0,
+            ));
+        }
+        if let Some(failure) = capture::failure() {
+            let mut rect = self.capture_failure_hud.rect_on(
+                re_ctx,
+                &capture_failure_text(&failure),
+                [255, 255, 255, 255],
+                [190, 30, 30, 230],
+                glam::vec3(16.0, 0.0, 0.0),
+                2.0,
+            );
+            rect.top_left_corner_position.y = screen_size.y - 16.0 - rect.extent_v.y;
+            hud_rects.push(rect);
+        }
+        let minimap_layout = minimap::Layout::new(screen_size, self.capture_rect);
+        self.minimap.set_layout(minimap_layout);
+        if let Some(layout) = minimap_layout {
+            let (min, size) = layout.rect;
+            hud_rects.push(TexturedRect {
+                top_left_corner_position: min.extend(0.0),
+                extent_u: size.x * glam::Vec3::X,
+                extent_v: size.y * glam::Vec3::Y,
+                colormapped_texture: ColormappedTexture::from_unorm_rgba(texture.clone()),
+                options: RectangleOptions {
+                    texture_filter_minification: TextureFilterMin::Linear,
+                    ..Default::default()
+                },
+            });
+        }
+        let selected_region = self
+            .region_drag
+            .map(|(start, end)| (start.min(end), (end - start).abs()))
+            .filter(|(_, size)| size.min_element() >= DRAG_THRESHOLD);
+        let show_pipeline_graph = self.hud.is_enabled(hud::Widget::Pipeline);
+        let has_hud_lines =
+            minimap_layout.is_some() || selected_region.is_some() || show_pipeline_graph;
+        let hud_line_draw_data = has_hud_lines.then(|| {
+            let (graph_strips, graph_vertices) = pipeline_stats::PipelineStats::graph_size();
+            let mut builder = LineDrawableBuilder::new(re_ctx);
+            builder.reserve_strips(3 + graph_strips).unwrap();
+            builder.reserve_vertices(15 + graph_vertices).unwrap();
+            self.minimap.add_outlines(&mut builder, palette.highlight);
+            if show_pipeline_graph {
+                let minimap_top = minimap_layout.map(|layout| layout.rect.0.y);
+                self.pipeline_stats.add_graph(&mut builder, screen_size, minimap_top);
+            }
+            if let Some((min, size)) = selected_region {
+                builder
+                    .batch("region selection")
+                    .add_rectangle_outline_2d(min, glam::vec2(size.x, 0.0), glam::vec2(0.0, size.y))
+                    .radius(Size::new_points(1.5))
+                    .color(palette.highlight);
+            }
+            builder.into_draw_data().unwrap()
+        });
+        hud_rects.extend(self.inspector.rects(
+            re_ctx,
+            &inspector_stages,
+            &self.texture_pool.stats().summary(),
+            screen_size,
+        ));
+        let cursor = self.cursor_position - self.view_2d_location;
+        hud_rects.extend(self.toasts.rects(re_ctx, screen_size));
+        hud_rects.extend(self.context_menu.rects(re_ctx, cursor));
+        let hud_draw_data = RectangleDrawData::new(re_ctx, &hud_rects).unwrap();
 
+        let hdr = &config::get().hdr;
+        let capture_textured_rect = TexturedRect {
+            top_left_corner_position: capture_rect_min.extend(-0.05),
+            extent_u: capture_rect_size.x * glam::Vec3::X,
+            extent_v: capture_rect_size.y * glam::Vec3::Y,
+            colormapped_texture: ColormappedTexture {
+                // Narrowing the range mapped to 0–1 scales the colors by the exposure.
+                range: [0.0, 1.0 / hdr.exposure],
+                gamma: hdr.gamma,
+                ..ColormappedTexture::from_unorm_rgba(texture)
+            },
+            options: RectangleOptions {
+                texture_filter_magnification: TextureFilterMag::Nearest,
+                texture_filter_minification: TextureFilterMin::Linear,
+                ..Default::default()
+            },
+        };
+        if config::get().mirror.monitor.is_some() {
+            self.mirror_capture = Some(capture_textured_rect.clone());
+        }
         let rectangle_draw_data = RectangleDrawData::new(
             re_ctx,
-            &[
-                TexturedRect {
-                    top_left_corner_position: glam::vec3(500.0, 120.0, -0.05),
-                    extent_u: self.rerun_logo_texture_width as f32 * image_scale * glam::Vec3::X,
-                    extent_v: self.rerun_logo_texture_height as f32 * image_scale * glam::Vec3::Y,
-                    colormapped_texture: ColormappedTexture::from_unorm_rgba(
-                        texture
-                    ),
-                    options: RectangleOptions {
-                        texture_filter_magnification: TextureFilterMag::Nearest,
-                        texture_filter_minification: TextureFilterMin::Linear,
-                        ..Default::default()
-                    },
-                },
+            &background_rect.into_iter().chain(shadow_rect).chain(ab_rects).chain(display_rects).chain([
+                capture_textured_rect,
                 TexturedRect {
                     top_left_corner_position: glam::vec3(
                         500.0,
@@ -331,166 +2134,564 @@ impl framework::Example for Render2D {
                         ..Default::default()
                     },
                 },
-            ],
+            ]).collect_vec(),
         )
             .unwrap();
 
-        vec![
-            // 2D view to the left
-            {
-                let mut view_builder = ViewBuilder::new(
-                    re_ctx,
-                    TargetConfiguration {
-                        name: "2D".into(),
-                        resolution_in_pixel: splits[0].resolution_in_pixel,
-                        view_from_world: macaw::IsoTransform::IDENTITY,
-                        projection_from_view: Projection::Orthographic {
-                            camera_mode:
-                            view_builder::OrthographicCameraMode::TopLeftCornerAndExtendZ,
-                            vertical_world_size: splits[0].resolution_in_pixel[1] as f32,
-                            far_plane_distance: 1000.0,
-                        },
-                        pixels_from_point,
-                        ..Default::default()
+        let draw_count = self.draw_count;
+        let clear_color = self.background.clear_color();
+        // 2D view to the left
+        let draw_2d = || {
+            puffin::profile_scope!("2D view");
+            let mut view_builder = ViewBuilder::new(
+                re_ctx,
+                TargetConfiguration {
+                    name: "2D".into(),
+                    resolution_in_pixel: splits[0].resolution_in_pixel,
+                    view_from_world: macaw::IsoTransform::IDENTITY,
+                    projection_from_view: Projection::Orthographic {
+                        camera_mode:
+                        view_builder::OrthographicCameraMode::TopLeftCornerAndExtendZ,
+                        vertical_world_size: splits[0].resolution_in_pixel[1] as f32,
+                        far_plane_distance: 1000.0,
                     },
-                );
-                view_builder.queue_draw(line_strip_draw_data.clone());
-                view_builder.queue_draw(point_draw_data.clone());
-                view_builder.queue_draw(rectangle_draw_data.clone());
-                let command_buffer = view_builder
-                    .draw(re_ctx, re_renderer::Rgba::TRANSPARENT)
+                    pixels_from_point,
+                    ..Default::default()
+                },
+            );
+            view_builder.queue_draw(line_strip_draw_data.clone());
+            view_builder.queue_draw(point_draw_data.clone());
+            view_builder.queue_draw(rectangle_draw_data.clone());
+            if let Some(draw_data) = &crosshair_draw_data {
+                view_builder.queue_draw(draw_data.clone());
+            }
+            if let Some(draw_data) = hud_line_draw_data {
+                view_builder.queue_draw(draw_data);
+            }
+            view_builder.queue_draw(hud_draw_data);
+            if fed_views.contains(&0) {
+                view_builder
+                    .schedule_screenshot(re_ctx, VIEW_READBACKS[0], draw_count)
                     .unwrap();
-                framework::ViewDrawResult {
-                    view_builder,
-                    command_buffer,
-                    target_location: splits[0].target_location,
-                }
-            },
-            // and 3D view of the same scene to the right
-            {
-                let seconds_since_startup = time.seconds_since_startup();
-                let camera_rotation_center = screen_size.extend(0.0) * 0.5;
-                let camera_position = glam::vec3(
-                    seconds_since_startup.sin(),
-                    0.5,
-                    seconds_since_startup.cos(),
-                ) * screen_size.x.max(screen_size.y)
-                    + camera_rotation_center;
-                let mut view_builder = ViewBuilder::new(
-                    re_ctx,
-                    view_builder::TargetConfiguration {
-                        name: "3D".into(),
-                        resolution_in_pixel: splits[1].resolution_in_pixel,
-                        view_from_world: macaw::IsoTransform::look_at_rh(
-                            camera_position,
-                            camera_rotation_center,
-                            glam::Vec3::Y,
-                        )
-                            .unwrap(),
-                        projection_from_view: Projection::Perspective {
-                            vertical_fov: 70.0 * std::f32::consts::TAU / 360.0,
-                            near_plane_distance: 0.01,
-                            aspect_ratio: resolution[0] as f32 / resolution[1] as f32,
-                        },
-                        pixels_from_point,
-                        ..Default::default()
+            }
+            let command_buffer = view_builder
+                .draw(re_ctx, clear_color)
+                .unwrap();
+            framework::ViewDrawResult {
+                view_builder,
+                command_buffer,
+                target_location: splits[0].target_location,
+            }
+        };
+        // and 3D view of the same scene to the right
+        let draw_3d = || {
+            puffin::profile_scope!("3D view");
+            let mut view_builder = ViewBuilder::new(
+                re_ctx,
+                view_builder::TargetConfiguration {
+                    name: "3D".into(),
+                    resolution_in_pixel: splits[1].resolution_in_pixel,
+                    view_from_world: macaw::IsoTransform::look_at_rh(
+                        camera_3d.position,
+                        camera_3d.target,
+                        glam::Vec3::Y,
+                    )
+                        .unwrap(),
+                    projection_from_view: Projection::Perspective {
+                        vertical_fov: camera_3d.vertical_fov,
+                        near_plane_distance: 0.01,
+                        aspect_ratio: camera_3d.aspect_ratio,
                     },
-                );
-                let command_buffer = view_builder
-                    .queue_draw(line_strip_draw_data)
-                    .queue_draw(point_draw_data)
-                    .queue_draw(rectangle_draw_data)
-                    .draw(re_ctx, re_renderer::Rgba::TRANSPARENT)
+                    pixels_from_point,
+                    ..Default::default()
+                },
+            );
+            view_builder
+                .queue_draw(line_strip_draw_data.clone())
+                .queue_draw(point_draw_data.clone())
+                .queue_draw(rectangle_draw_data.clone());
+            if let Some(draw_data) = &crosshair_draw_data {
+                view_builder.queue_draw(draw_data.clone());
+            }
+            if fed_views.contains(&1) {
+                view_builder
+                    .schedule_screenshot(re_ctx, VIEW_READBACKS[1], draw_count)
                     .unwrap();
-                framework::ViewDrawResult {
-                    view_builder,
-                    command_buffer,
-                    target_location: splits[1].target_location,
+            }
+            let command_buffer = view_builder
+                .draw(re_ctx, clear_color)
+                .unwrap();
+            framework::ViewDrawResult {
+                view_builder,
+                command_buffer,
+                target_location: splits[1].target_location,
+            }
+        };
+
+        // The draw data is shared between the views, so their command buffers can be
+        // encoded in parallel.
+        let encoding_started = web_time::Instant::now();
+        let views = if config::get().serial_view_encoding {
+            vec![draw_2d(), draw_3d()]
+        } else {
+            std::thread::scope(|scope| {
+                let view_3d = scope.spawn(draw_3d);
+                let view_2d = draw_2d();
+                vec![view_2d, view_3d.join().expect("3D view encoding panicked")]
+            })
+        };
+        let encoding_ms = encoding_started.elapsed().as_secs_f32() * 1000.0;
+        status::update(|status| status.view_encoding_ms = encoding_ms);
+        views
+    }
+
+    fn draw_mirror(
+        &mut self,
+        re_ctx: &re_renderer::RenderContext,
+        resolution: [u32; 2],
+        pixels_from_point: f32,
+    ) -> Option<framework::MirrorDraw> {
+        let capture = self.mirror_capture.clone()?;
+        let view_size = glam::UVec2::from(self.view_resolutions[0]).as_vec2();
+        let mirror_size = glam::UVec2::from(resolution).as_vec2();
+        let (min, size) = mirror::layout(self.capture_rect, view_size, mirror_size)?;
+        if let Some(frame) = &self.screen_frame {
+            if self.mirror_presented != Some(frame.frame_id) {
+                self.mirror_pending = Some((frame.frame_id, frame.capture_time_us));
+            }
+        }
+        if config::get().mirror.direct {
+            let (uv, target) = mirror::blit_rects((min, size), mirror_size)?;
+            return Some(framework::MirrorDraw::Blit(framework::Blit {
+                texture: capture.colormapped_texture.texture,
+                uv,
+                target,
+            }));
+        }
+        let draw_data = RectangleDrawData::new(
+            re_ctx,
+            &[TexturedRect {
+                top_left_corner_position: min.extend(-0.05),
+                extent_u: size.x * glam::Vec3::X,
+                extent_v: size.y * glam::Vec3::Y,
+                ..capture
+            }],
+        )
+            .unwrap();
+        let mut view_builder = ViewBuilder::new(
+            re_ctx,
+            TargetConfiguration {
+                name: "mirror".into(),
+                resolution_in_pixel: resolution,
+                view_from_world: macaw::IsoTransform::IDENTITY,
+                projection_from_view: Projection::Orthographic {
+                    camera_mode: view_builder::OrthographicCameraMode::TopLeftCornerAndExtendZ,
+                    vertical_world_size: resolution[1] as f32,
+                    far_plane_distance: 1000.0,
+                },
+                pixels_from_point,
+                ..Default::default()
+            },
+        );
+        view_builder.queue_draw(draw_data);
+        let command_buffer = view_builder.draw(re_ctx, re_renderer::Rgba::BLACK).unwrap();
+        Some(framework::MirrorDraw::View(framework::ViewDrawResult {
+            view_builder,
+            command_buffer,
+            target_location: glam::Vec2::ZERO,
+        }))
+    }
+
+    fn on_presented(&mut self) {
+        self.pipeline_stats.presented(capture::now_us());
+    }
+
+    fn on_mirror_presented(&mut self) {
+        let Some((frame_id, capture_time_us)) = self.mirror_pending.take() else {
+            return;
+        };
+        self.mirror_presented = Some(frame_id);
+        let latency_ms = capture::now_us().saturating_sub(capture_time_us) as f32 / 1000.0;
+        status::update(|status| status.mirror_latency_ms = Some(latency_ms));
+    }
+
+    fn on_cursor_moved(&mut self, position_in_pixel: glam::UVec2) {
+        self.cursor_position = position_in_pixel.as_vec2();
+        self.gamepad_cursor_shown = false;
+        let position = self.cursor_position - self.view_2d_location;
+        self.minimap.drag(position);
+        if let Some((_, end)) = &mut self.region_drag {
+            *end = position;
+        }
+    }
+
+    fn on_mouse_input(
+        &mut self,
+        state: winit::event::ElementState,
+        button: winit::event::MouseButton,
+    ) {
+        if state == winit::event::ElementState::Released
+            && button == winit::event::MouseButton::Left
+        {
+            self.minimap.release();
+            // Dragging out a region zooms to it, a click doesn't.
+            let region = self
+                .region_drag
+                .take()
+                .map(|(start, end)| (start.min(end), (end - start).abs()))
+                .filter(|(_, size)| size.min_element() >= DRAG_THRESHOLD);
+            match region {
+                Some(region) if self.crop_selecting => self.crop_to_region(region),
+                Some(region) => self.zoom_to_region(region),
+                None => {}
+            }
+        }
+        if state != winit::event::ElementState::Pressed {
+            return;
+        }
+        // The 2D view maps scene units 1:1 to pixels.
+        let position = self.cursor_position - self.view_2d_location;
+        if button == winit::event::MouseButton::Left && self.toasts.click(position) {
+            return;
+        }
+        if button == winit::event::MouseButton::Left && self.context_menu.is_open() {
+            if let Some(action) = self.context_menu.click(position) {
+                self.run_menu_action(action);
+            }
+            return;
+        }
+        if button == winit::event::MouseButton::Left && self.minimap.press(position) {
+            return;
+        }
+        if button == winit::event::MouseButton::Left {
+            let is_double_click = self.last_click.is_some_and(|(at, place)| {
+                at.elapsed() < DOUBLE_CLICK && place.distance(position) < DRAG_THRESHOLD
+            });
+            self.last_click = (!is_double_click).then(|| (web_time::Instant::now(), position));
+            let (rect_min, rect_size) = self.capture_rect;
+            if is_double_click {
+                // Twice as close, around the point.
+                let [width, height] = self.view_resolutions[0];
+                let view_size = glam::vec2(width as f32, height as f32);
+                self.zoom_to_region((position - view_size * 0.25, view_size * 0.5));
+            } else if position.cmpge(rect_min).all() && position.cmplt(rect_min + rect_size).all()
+            {
+                self.region_drag = Some((position, position));
+            }
+        }
+
+        let (rect_min, rect_size) = self.capture_rect;
+        let pick = self
+            .screen_frame
+            .as_deref()
+            .and_then(|frame| picking::pick_frame(frame, rect_min, rect_size, position));
+        match button {
+            winit::event::MouseButton::Left => {
+                if let Some(pick) = &pick {
+                    let [x, y] = pick.capture_pixel;
+                    let [r, g, b] = pick.rgb;
+                    println!("Picked capture pixel {x}, {y} of frame {}: #{r:02x}{g:02x}{b:02x}", pick.frame_id);
                 }
+                status::update(|status| status.last_pick = pick);
+            }
+            winit::event::MouseButton::Right => match pick {
+                Some(pick) => {
+                    let mut actions = vec![
+                        context_menu::MenuAction::CopyColor(pick.clone()),
+                        context_menu::MenuAction::CopyPixelPosition(pick.clone()),
+                        context_menu::MenuAction::Screenshot,
+                    ];
+                    actions.push(if pinned_element::is_pinned() {
+                        context_menu::MenuAction::UnpinElement
+                    } else {
+                        context_menu::MenuAction::PinElement(pick)
+                    });
+                    if !self.annotations.is_empty() {
+                        actions.push(context_menu::MenuAction::ClearAnnotations);
+                    }
+                    self.context_menu.open(position, actions);
+                }
+                None => self.context_menu.close(),
             },
-        ]
+            _ => {}
+        }
     }
 
-    fn on_key_event(&mut self, _input: winit::event::KeyEvent) {}
+    fn on_mouse_wheel(
+        &mut self,
+        delta: winit::event::MouseScrollDelta,
+        _phase: winit::event::TouchPhase,
+    ) {
+        let Some(position) = self.cursor_in_view_2d() else {
+            return;
+        };
+        match delta {
+            // Scrolling on a trackpad, including the momentum scrolling after lifting the
+            // fingers, pans with the fingers.
+            winit::event::MouseScrollDelta::PixelDelta(delta) => {
+                self.pan_2d_by(glam::vec2(delta.x as f32, delta.y as f32), position);
+            }
+            // A mouse wheel zooms around the pointer instead.
+            winit::event::MouseScrollDelta::LineDelta(_, lines) => {
+                self.zoom_2d_around(1.1_f32.powf(lines), position);
+            }
+        }
+    }
+
+    fn on_touchpad_magnify(&mut self, delta: f64, phase: winit::event::TouchPhase) {
+        let Some(centroid) = self.cursor_in_view_2d() else {
+            return;
+        };
+        // Fingers on a trackpad have no place on screen, like macOS apps do the pinch
+        // centers on the pointer.
+        let zoom = (1.0 + delta as f32).max(0.01).ln();
+        self.pinch_momentum.update(zoom, phase);
+        self.pinch_centroid = centroid;
+        self.zoom_2d_around(zoom.exp(), centroid);
+    }
+
+    fn on_touch(&mut self, touch: winit::event::Touch) {
+        if let Some(gesture) = self.touches.update(&touch) {
+            self.on_touch_gesture(gesture);
+        }
+    }
+
+    fn on_touchpad_rotate(&mut self, delta: f32, phase: winit::event::TouchPhase) {
+        // The camera turns the other way for the scene to turn with the fingers.
+        let yaw = -delta.to_radians();
+        self.rotate_momentum.update(yaw, phase);
+        self.camera_orbit.x += yaw;
+    }
+
+    fn on_modifiers_changed(&mut self, modifiers: winit::keyboard::ModifiersState) {
+        self.modifiers = modifiers;
+    }
+
+    fn on_key_event(&mut self, input: winit::event::KeyEvent) {
+        use keymap::Action;
+
+        if input.state != winit::event::ElementState::Pressed || input.repeat {
+            return;
+        }
+
+        let zero = winit::keyboard::PhysicalKey::Code(winit::keyboard::KeyCode::Digit0);
+        if !self.modifiers.control_key() && input.physical_key == zero {
+            self.pane_zoom_pans.clear();
+            self.animate_zoom_pan(gestures::ZoomPan::default());
+            return;
+        }
+        if let (true, Some(slot)) = (self.modifiers.control_key(), digit(&input.physical_key)) {
+            let command = if self.modifiers.shift_key() {
+                control::ControlCommand::SavePreset(slot)
+            } else {
+                control::ControlCommand::ApplyPreset(slot.to_string())
+            };
+            self.handle_command(command);
+            return;
+        }
+        if let (false, Some(index)) = (self.modifiers.control_key(), digit(&input.physical_key)) {
+            self.handle_command(control::ControlCommand::SwitchDisplay(index));
+            return;
+        }
+
+        let Some(action) = self.keymap.action(&input, self.modifiers) else {
+            return;
+        };
+        match action {
+            Action::Undo => self.handle_command(control::ControlCommand::Undo),
+            Action::Redo => self.handle_command(control::ControlCommand::Redo),
+            Action::ToggleHighContrast => {
+                self.edit(undo::Edit::HighContrast { to: !self.accessibility.high_contrast });
+            }
+            Action::ToggleReducedMotion => {
+                self.edit(undo::Edit::ReducedMotion { to: !self.accessibility.reduced_motion });
+            }
+            Action::CycleBackground => {
+                let from = self.background.config.kind;
+                self.edit(undo::Edit::Background { from, to: from.next() });
+            }
+            Action::Screenshot => self.handle_command(control::ControlCommand::Screenshot),
+            Action::ToggleRecording => {
+                self.handle_command(control::ControlCommand::ToggleRecording);
+            }
+            Action::Marker => self.handle_command(control::ControlCommand::Marker(None)),
+            Action::ToggleStreaming => {
+                self.handle_command(control::ControlCommand::ToggleStreaming);
+            }
+            Action::CycleFeedSource => {
+                self.handle_command(control::ControlCommand::CycleFeedSource);
+            }
+            Action::InspectNextTexture => self.inspector.cycle(),
+            Action::ToggleNormalize => self.inspector.toggle_normalize(),
+            Action::ToggleFrameStep => {
+                self.handle_command(control::ControlCommand::ToggleFrameStep);
+            }
+            Action::StepFrame => self.handle_command(control::ControlCommand::StepFrame),
+            Action::CycleBackend => self.handle_command(control::ControlCommand::CycleBackend),
+            Action::ToggleCursor => self.handle_command(control::ControlCommand::ToggleCursor),
+            Action::CycleProfile => self.handle_command(control::ControlCommand::CycleProfile),
+            Action::TogglePause => self.handle_command(control::ControlCommand::TogglePause),
+            Action::ToggleViewLink => self.toggle_view_link(),
+            Action::CloseMenu => self.context_menu.close(),
+            Action::Crop => self.toggle_crop(),
+            Action::ScrubBack => self.scrub(-1),
+            Action::ScrubForward => self.scrub(1),
+        }
+    }
 }
 
 fn main() {
+    config::init(config::Config::load().expect("Failed to load config"));
+    if config::get().print_default_config {
+        print!("{}", config::DEFAULT_CONFIG);
+        return;
+    }
+    if config::get().list_peers {
+        discovery::list_peers().expect("Failed to list peers");
+        return;
+    }
+    if config::get().list_windows {
+        pollster::block_on(capture::list_windows()).expect("Failed to list windows");
+        return;
+    }
+    if config::get().list_monitors {
+        let event_loop = winit::event_loop::EventLoop::new().expect("Failed to list monitors");
+        mirror::list_monitors(event_loop.available_monitors(), event_loop.primary_monitor());
+        return;
+    }
+    if let Some(path) = &config::get().verify_recording {
+        if let Err(err) = encoder::verify(path, &config::get().recording.ffmpeg_path) {
+            eprintln!("Verification failed: {err:#}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    std::thread::spawn(|| {
+        let config = config::get();
+        recorder::recover_interrupted(&config.output.directory, &config.recording.ffmpeg_path);
+    });
+
     let server_addr = format!("127.0.0.1:{}", puffin_http::DEFAULT_PORT);
     let _puffin_server = puffin_http::Server::new(&server_addr).unwrap();
     eprintln!("Run this to view profiling data:  puffin_viewer {server_addr}");
     puffin::set_scopes_on(true);
+    let status_server_addr = config::get()
+        .status_server
+        .address
+        .as_ref()
+        .map(|address| status_server::start(address).expect("Failed to start status server"));
+    let _discovery = match (&config::get().discovery.name, status_server_addr) {
+        (Some(name), Some(address)) => Some(
+            discovery::advertise(name, address).expect("Failed to advertise session"),
+        ),
+        // Capture daemons advertise their frame link instead.
+        (Some(_), None) if config::get().frame_link.serve.is_none() => {
+            eprintln!("Not advertising the session, the status server is disabled");
+            None
+        }
+        _ => None,
+    };
+    notifications::start(&config::get().notifications, &config::get().webhooks)
+        .expect("Failed to start the notifications");
+    text_watch::start(&config::get().text_watch).expect("Failed to start the text watches");
+    if let Some(directory) = &config::get().watch_folder.directory {
+        watch_folder::start(directory).expect("Failed to watch the trigger folder");
+    }
+    if config::get().recording.captions_from_stdin {
+        captions::read_stdin_in_background();
+    }
     let runtime = tokio::runtime::Builder::new_multi_thread().worker_threads(1).enable_all().build().unwrap();
+    let capture_manager = capture_manager::CaptureManager::new(runtime);
+    let runtime = capture_manager.handle();
 
-    runtime.spawn(async {
-        let token = match CaptureStream::test_access(false) {
-            Some(token) => token,
-            None => CaptureStream::request_access(false).await.expect("Expected capture access")
+    if let Some([x, y, width, height]) = config::get().crop.rect {
+        let (min, size) = (glam::vec2(x, y), glam::vec2(width, height));
+        capture_crop::set(Some(focus::ScreenRect { min, size }));
+    }
+    if let Some(point) = config::get().pinned_element.point {
+        let levels_up = config::get().pinned_element.levels_up;
+        if let Err(err) = pinned_element::pin(point.into(), levels_up) {
+            eprintln!("Failed to pin a UI element: {err:#}");
+        }
+    }
+    let (frame_sender, frame_receiver) = frame_channel::channel();
+    // Capture time of the latest frame let through `max_capture_fps`.
+    let last_frame_us = Arc::new(AtomicU64::new(0));
+    let store_frame = move |frame: Frame| {
+        if session_pause::is_paused() {
+            return;
+        }
+        if let Some(fps) = config::get().max_capture_fps {
+            let interval_us = (1_000_000.0 / fps.max(0.1)) as u64;
+            let last_us = last_frame_us.load(Ordering::Relaxed);
+            if frame.capture_time_us.saturating_sub(last_us) < interval_us {
+                return;
+            }
+            last_frame_us.store(frame.capture_time_us, Ordering::Relaxed);
+        }
+        let frame = pinned_element::crop(frame);
+        let frame = black_bars::process(frame, &config::get().black_bars);
+        scene_change::observe(&frame, &config::get().scene_changes);
+        text_watch::observe(&frame);
+        color_watch::observe(&frame, &config::get().color_watch);
+        // After the watches, their regions are in pixels of the whole capture.
+        let frame = capture_crop::crop(frame);
+        if let Some(frame) = frame_step::intercept(frame) {
+            frame_sender.send(frame);
+        }
+    };
+    let frame_link = &config::get().frame_link;
+    if let Some(endpoint) = &frame_link.serve {
+        let (send_frame, tcp_address) =
+            frame_link::serve(endpoint).expect("Failed to start capture daemon");
+        let _frame_link_discovery = match (&config::get().discovery.name, tcp_address) {
+            (Some(name), Some(address)) => Some(
+                discovery::advertise_frame_link(name, address)
+                    .expect("Failed to advertise the capture daemon"),
+            ),
+            _ => None,
         };
-
-        let wgpu_instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            #[cfg(target_os = "windows")]
-            backends: wgpu::Backends::DX12,
-            #[cfg(target_os = "macos")]
-            backends: wgpu::Backends::METAL,
-            flags: wgpu::InstanceFlags::default(),
-            dx12_shader_compiler: wgpu::Dx12Compiler::default(),
-            gles_minor_version: wgpu::Gles3MinorVersion::default(),
+        runtime.block_on(capture::run(send_frame));
+        // The daemon has no window, just keep capturing until killed.
+        loop {
+            std::thread::park();
+        }
+    } else if frame_link.connect.is_some() {
+        capture_backend::start(runtime, capture_backend::BackendKind::Daemon, store_frame);
+    } else if let (Some(a), Some(b)) = (&config::get().ab_compare.a, &config::get().ab_compare.b) {
+        status::update(|status| {
+            status.source = format!("A/B comparison of {a:?} and {b:?}");
+            status.source_name = "ab_compare".to_owned();
         });
-        let wgpu_adapter = wgpu_instance.request_adapter(&wgpu::RequestAdapterOptions {
-            power_preference: wgpu::PowerPreference::None,
-            force_fallback_adapter: false,
-            compatible_surface: None,
-        }).await.expect("Expected wgpu adapter");
-        let (wgpu_device, wgpu_queue) = wgpu_adapter.request_device(&wgpu::DeviceDescriptor {
-            label: Some("wgpu adapter"),
-            required_features: wgpu::Features::default(),
-            required_limits: wgpu::Limits::default(),
-        }, None).await.expect("Expected wgpu device");
-        let gfx = Arc::new(Gfx {
-            device: wgpu_device,
-            queue: wgpu_queue,
+        ab_compare::start(runtime, a.clone(), b.clone());
+    } else if config::get().playback.file.is_some() {
+        capture_backend::start(runtime, capture_backend::BackendKind::Playback, store_frame);
+    } else if config::get().video.file.is_some() {
+        capture_backend::start(runtime, capture_backend::BackendKind::Video, store_frame);
+    } else if config::get().test_pattern.kind.is_some() {
+        capture_backend::start(runtime, capture_backend::BackendKind::TestPattern, store_frame);
+    } else if config::get().window.is_set() {
+        let target = config::get()
+            .window
+            .matcher()
+            .expect("the window filter was checked when loading the config");
+        status::update(|status| {
+            status.source = target.to_string();
+            status.source_name = "window".to_owned();
         });
+        capture::follow_window(runtime, target, store_frame);
+    } else if config::get().launch.command.is_some() {
+        launch::start(runtime, config::get().launch.clone(), store_frame);
+    } else if let Some(application) = &config::get().app_capture.application {
+        app_capture::start(runtime, application.clone(), store_frame);
+    } else if config::get().multi_display.enabled {
+        multi_display::start(runtime, store_frame);
+    } else {
+        capture_backend::start(runtime, capture_backend::BackendKind::Display, store_frame);
+    }
 
-        let filter = CapturableContentFilter { windows: None, displays: true };
-        let content = CapturableContent::new(filter).await.unwrap();
-        let display = content.displays().next()
-            .expect("Expected at least one capturable display");
-        let config = CaptureConfig::with_display(display, CapturePixelFormat::Bgra8888)
-            .with_wgpu_device(gfx.clone())
-            .expect("Expected config with wgpu device");
-
-        let mut stream = CaptureStream::new(token, config, |result| {
-            println!("result: {:?}", result);
-            if let Ok(StreamEvent::Video(frame)) = result {
-                let frame_id = frame.frame_id();
-
-                match frame.get_bitmap() {
-                    Ok(bitmap) => {
-                        match bitmap {
-                            crabgrab::feature::bitmap::FrameBitmap::BgraUnorm8x4(frame) => {
-                                println!("format: BgraUnorm8x4");
-                                SCREEN_TEXTURE.lock().unwrap().replace(Frame {
-                                    frame_bitmap: frame,
-                                    frame_id,
-                                });
-                            }
-                            crabgrab::feature::bitmap::FrameBitmap::RgbaUnormPacked1010102(_) => println!("format: RgbaUnormPacked1010102"),
-                            crabgrab::feature::bitmap::FrameBitmap::RgbaF16x4(_) => println!("format: RgbaF16x4"),
-                            crabgrab::feature::bitmap::FrameBitmap::YCbCr(_) => println!("format: YCbCr"),
-                        }
-                    }
-                    Err(e) => {
-                        println!("Bitmap error: {:?}", e);
-                    }
-                }
-            }
-        }).unwrap();
-        let _ = ManuallyDrop::new(stream);
-
-        // tokio::time::sleep(Duration::from_millis(20000000)).await;
-        //
-        // stream.stop().unwrap();
-    });
-
-    framework::start::<Render2D>();
+    framework::start::<Render2D>(config::get().time.clone(), frame_receiver);
+    capture_backend::stop();
+    capture_manager.stop();
 }
@@ -0,0 +1,299 @@
+//! MCAP export of recordings: next to every recording, a `<name>.mcap` with the recorded
+//! frames as JPEG, the session status once a second and every event, for Foxglove and
+//! other MCAP tooling.
+//!
+//! Messages are JSON with JSON schemas; frames use the `foxglove.CompressedImage` schema so
+//! viewers show them as images. The file has no summary section, readers scan it instead.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use anyhow::Context as _;
+use base64::Engine as _;
+use crossbeam_channel::{RecvTimeoutError, Sender};
+use serde::{Deserialize, Serialize};
+
+use crate::capture::{self, Frame};
+use crate::{events, screenshot, status};
+
+pub const MAGIC: &[u8] = b"\x89MCAP0\r\n";
+
+const OP_HEADER: u8 = 0x01;
+pub const OP_FOOTER: u8 = 0x02;
+pub const OP_SCHEMA: u8 = 0x03;
+pub const OP_CHANNEL: u8 = 0x04;
+pub const OP_MESSAGE: u8 = 0x05;
+pub const OP_CHUNK: u8 = 0x06;
+const OP_DATA_END: u8 = 0x0f;
+
+const FRAMES_CHANNEL: u16 = 1;
+const STATUS_CHANNEL: u16 = 2;
+const EVENTS_CHANNEL: u16 = 3;
+
+/// How often the session status is written.
+const STATUS_INTERVAL: Duration = Duration::from_secs(1);
+
+const COMPRESSED_IMAGE_SCHEMA: &str = r#"{
+  "title": "foxglove.CompressedImage",
+  "type": "object",
+  "properties": {
+    "timestamp": {
+      "type": "object",
+      "properties": { "sec": { "type": "integer" }, "nsec": { "type": "integer" } }
+    },
+    "frame_id": { "type": "string" },
+    "data": { "type": "string", "contentEncoding": "base64" },
+    "format": { "type": "string" }
+  }
+}"#;
+
+const STATUS_SCHEMA: &str = r#"{ "title": "re_render_crabgrab.SessionStatus", "type": "object" }"#;
+
+const EVENT_SCHEMA: &str = r#"{
+  "title": "re_render_crabgrab.Event",
+  "type": "object",
+  "properties": {
+    "id": { "type": "integer" },
+    "time_us": { "type": "integer" },
+    "severity": { "type": "string", "enum": ["info", "warning", "error"] },
+    "kind": { "type": "string" },
+    "source": { "type": "string" },
+    "message": { "type": "string" }
+  }
+}"#;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct McapConfig {
+    /// Export every recording to an MCAP file as well.
+    pub enabled: bool,
+
+    /// Frames per second written, at most; JPEG encoding every captured frame is slow.
+    pub fps: f32,
+    pub jpeg_quality: u8,
+}
+
+impl Default for McapConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            fps: 10.0,
+            jpeg_quality: 80,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Timestamp {
+    sec: u64,
+    nsec: u32,
+}
+
+#[derive(Serialize)]
+struct CompressedImage<'a> {
+    timestamp: Timestamp,
+    frame_id: &'a str,
+    data: String,
+    format: &'static str,
+}
+
+/// An MCAP file being written on a worker thread.
+pub struct McapExport {
+    pub path: PathBuf,
+    sender: Option<Sender<Frame>>,
+    worker: Option<JoinHandle<anyhow::Result<()>>>,
+    frame_interval_us: u64,
+    last_frame_us: Option<u64>,
+}
+
+impl McapExport {
+    /// Starts exporting to the MCAP sidecar of the recording at `recording_path`.
+    pub fn start(config: &McapConfig, recording_path: &Path) -> anyhow::Result<Self> {
+        let path = recording_path.with_extension("mcap");
+        let file = File::create(&path)
+            .with_context(|| format!("failed to create {}", path.display()))?;
+        let mut writer = McapWriter::new(BufWriter::new(file))?;
+        let channels = [
+            (FRAMES_CHANNEL, "/frames", "foxglove.CompressedImage", COMPRESSED_IMAGE_SCHEMA),
+            (STATUS_CHANNEL, "/status", "re_render_crabgrab.SessionStatus", STATUS_SCHEMA),
+            (EVENTS_CHANNEL, "/events", "re_render_crabgrab.Event", EVENT_SCHEMA),
+        ];
+        for (id, topic, schema_name, schema) in channels {
+            writer.channel(id, topic, schema_name, schema)?;
+        }
+
+        // Events emitted before the export started aren't part of it.
+        let last_event = events::since(None).last().map(|event| event.id);
+        let (sender, receiver) = crossbeam_channel::bounded::<Frame>(2);
+        let jpeg_quality = config.jpeg_quality;
+        let worker = std::thread::Builder::new()
+            .name("mcap export".to_owned())
+            .spawn(move || {
+                let mut last_event = last_event;
+                let mut next_status = Instant::now();
+                loop {
+                    let frame = match receiver.recv_timeout(STATUS_INTERVAL) {
+                        Ok(frame) => Some(frame),
+                        Err(RecvTimeoutError::Timeout) => None,
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    };
+                    if let Some(frame) = frame {
+                        write_frame(&mut writer, &frame, jpeg_quality)?;
+                    }
+                    for event in events::since(last_event) {
+                        let json = serde_json::to_vec(&event)?;
+                        writer.message(EVENTS_CHANNEL, event.time_us, &json)?;
+                        last_event = Some(event.id);
+                    }
+                    if Instant::now() >= next_status {
+                        let status = serde_json::to_vec(&status::snapshot())?;
+                        writer.message(STATUS_CHANNEL, capture::now_us(), &status)?;
+                        next_status = Instant::now() + STATUS_INTERVAL;
+                    }
+                }
+                writer.finish()
+            })?;
+        Ok(Self {
+            path,
+            sender: Some(sender),
+            worker: Some(worker),
+            frame_interval_us: (1_000_000.0 / config.fps.max(0.1)) as u64,
+            last_frame_us: None,
+        })
+    }
+
+    /// Queues `frame` unless the previous one was too recent or the worker is busy.
+    pub fn push(&mut self, frame: &Frame) {
+        let is_due = match self.last_frame_us {
+            Some(last) => frame.capture_time_us.saturating_sub(last) >= self.frame_interval_us,
+            None => true,
+        };
+        let Some(sender) = self.sender.as_ref().filter(|_| is_due) else {
+            return;
+        };
+        // Cropping to the whole frame is the way to copy one.
+        let bitmap = &frame.frame_bitmap;
+        let copy = frame.crop([0, 0], [bitmap.width, bitmap.height]);
+        if sender.try_send(copy).is_ok() {
+            self.last_frame_us = Some(frame.capture_time_us);
+        }
+    }
+
+    /// Writes what's queued and finalizes the file, returns its path.
+    pub fn stop(mut self) -> anyhow::Result<PathBuf> {
+        drop(self.sender.take());
+        if let Some(worker) = self.worker.take() {
+            worker
+                .join()
+                .map_err(|_| anyhow::anyhow!("MCAP export thread panicked"))??;
+        }
+        Ok(self.path.clone())
+    }
+}
+
+fn write_frame(
+    writer: &mut McapWriter<BufWriter<File>>,
+    frame: &Frame,
+    jpeg_quality: u8,
+) -> anyhow::Result<()> {
+    let (rgba, width, height) = screenshot::to_rgba(&frame.frame_bitmap, usize::MAX);
+    let jpeg = screenshot::encode_jpeg(&rgba, width, height, jpeg_quality)?;
+    let image = CompressedImage {
+        timestamp: Timestamp {
+            sec: frame.capture_time_us / 1_000_000,
+            nsec: (frame.capture_time_us % 1_000_000) as u32 * 1000,
+        },
+        frame_id: "capture",
+        data: base64::engine::general_purpose::STANDARD.encode(jpeg),
+        format: "jpeg",
+    };
+    writer.message(FRAMES_CHANNEL, frame.capture_time_us, &serde_json::to_vec(&image)?)
+}
+
+/// Minimal MCAP writer: header, one schema per channel, messages, footer; no chunks or
+/// summary.
+struct McapWriter<W: std::io::Write> {
+    out: W,
+    sequence: u32,
+}
+
+impl<W: std::io::Write> McapWriter<W> {
+    fn new(mut out: W) -> anyhow::Result<Self> {
+        out.write_all(MAGIC)?;
+        let mut header = Vec::new();
+        put_string(&mut header, "");
+        put_string(&mut header, "re_render_crabgrab");
+        write_record(&mut out, OP_HEADER, &header)?;
+        Ok(Self { out, sequence: 0 })
+    }
+
+    /// Adds a JSON channel on `topic` with a JSON schema of the same id.
+    fn channel(
+        &mut self,
+        id: u16,
+        topic: &str,
+        schema_name: &str,
+        schema: &str,
+    ) -> anyhow::Result<()> {
+        let mut record = Vec::new();
+        record.extend_from_slice(&id.to_le_bytes());
+        put_string(&mut record, schema_name);
+        put_string(&mut record, "jsonschema");
+        put_bytes(&mut record, schema.as_bytes());
+        write_record(&mut self.out, OP_SCHEMA, &record)?;
+
+        let mut record = Vec::new();
+        record.extend_from_slice(&id.to_le_bytes());
+        record.extend_from_slice(&id.to_le_bytes());
+        put_string(&mut record, topic);
+        put_string(&mut record, "json");
+        // No metadata: an empty map.
+        record.extend_from_slice(&0u32.to_le_bytes());
+        write_record(&mut self.out, OP_CHANNEL, &record)
+    }
+
+    /// Writes a message logged at `time_us`, µs since the UNIX epoch.
+    fn message(&mut self, channel: u16, time_us: u64, data: &[u8]) -> anyhow::Result<()> {
+        let time_ns = time_us * 1000;
+        let mut record = Vec::with_capacity(22 + data.len());
+        record.extend_from_slice(&channel.to_le_bytes());
+        record.extend_from_slice(&self.sequence.to_le_bytes());
+        record.extend_from_slice(&time_ns.to_le_bytes());
+        record.extend_from_slice(&time_ns.to_le_bytes());
+        record.extend_from_slice(data);
+        self.sequence += 1;
+        write_record(&mut self.out, OP_MESSAGE, &record)
+    }
+
+    fn finish(mut self) -> anyhow::Result<()> {
+        // A zero CRC means "not computed".
+        write_record(&mut self.out, OP_DATA_END, &0u32.to_le_bytes())?;
+        let mut footer = Vec::new();
+        footer.extend_from_slice(&0u64.to_le_bytes());
+        footer.extend_from_slice(&0u64.to_le_bytes());
+        footer.extend_from_slice(&0u32.to_le_bytes());
+        write_record(&mut self.out, OP_FOOTER, &footer)?;
+        self.out.write_all(MAGIC)?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+fn write_record(out: &mut impl std::io::Write, opcode: u8, content: &[u8]) -> anyhow::Result<()> {
+    out.write_all(&[opcode])?;
+    out.write_all(&(content.len() as u64).to_le_bytes())?;
+    out.write_all(content)?;
+    Ok(())
+}
+
+fn put_string(buffer: &mut Vec<u8>, text: &str) {
+    put_bytes(buffer, text.as_bytes());
+}
+
+fn put_bytes(buffer: &mut Vec<u8>, bytes: &[u8]) {
+    buffer.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    buffer.extend_from_slice(bytes);
+}
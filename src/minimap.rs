@@ -0,0 +1,112 @@
+//! Overview of the capture while zoomed into it: a small copy of the capture rect in the
+//! bottom right corner of the 2D view, with the part that's in view outlined. Clicking in
+//! it centers the view there, dragging moves the view along.
+
+use re_renderer::{Color32, LineDrawableBuilder, Size};
+
+/// Width of the minimap in pixels, its height follows the capture's aspect ratio.
+const WIDTH: f32 = 240.0;
+
+/// Distance from the corner of the view in pixels.
+const MARGIN: f32 = 16.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Layout {
+    /// Min and size of the minimap in the 2D view.
+    pub rect: (glam::Vec2, glam::Vec2),
+
+    /// Min and size of the part of the capture rect in view, within the minimap.
+    pub visible: (glam::Vec2, glam::Vec2),
+}
+
+impl Layout {
+    /// The minimap for the capture rect at `capture_rect` in a 2D view of `view_size`, while
+    /// the capture rect doesn't fit in the view.
+    pub fn new(view_size: glam::Vec2, capture_rect: (glam::Vec2, glam::Vec2)) -> Option<Self> {
+        let (min, size) = capture_rect;
+        let max = min + size;
+        if min.cmpge(glam::Vec2::ZERO).all() && max.cmple(view_size).all() {
+            return None;
+        }
+        let minimap_size = glam::vec2(WIDTH, WIDTH * size.y / size.x);
+        let minimap_min = view_size - minimap_size - MARGIN;
+        let scale = minimap_size / size;
+        let visible_min = min.max(glam::Vec2::ZERO);
+        let visible_size = (max.min(view_size) - visible_min).max(glam::Vec2::ZERO);
+        Some(Self {
+            rect: (minimap_min, minimap_size),
+            visible: (minimap_min + (visible_min - min) * scale, visible_size * scale),
+        })
+    }
+
+    fn contains(&self, point: glam::Vec2) -> bool {
+        let (min, size) = self.rect;
+        point.cmpge(min).all() && point.cmplt(min + size).all()
+    }
+
+    /// Position in the capture, 0–1 across the rect, at `point`, clamped to the minimap.
+    fn content_at(&self, point: glam::Vec2) -> glam::Vec2 {
+        let (min, size) = self.rect;
+        ((point - min) / size).clamp(glam::Vec2::ZERO, glam::Vec2::ONE)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Minimap {
+    layout: Option<Layout>,
+    dragging: bool,
+
+    /// Position in the capture to center the view on, since it was last drawn.
+    target: Option<glam::Vec2>,
+}
+
+impl Minimap {
+    /// The layout as drawn, `None` hides the minimap.
+    pub fn set_layout(&mut self, layout: Option<Layout>) {
+        if layout.is_none() {
+            self.dragging = false;
+        }
+        self.layout = layout;
+    }
+
+    /// Starts dragging if `point` in the 2D view is on the minimap, `false` otherwise.
+    pub fn press(&mut self, point: glam::Vec2) -> bool {
+        let Some(layout) = self.layout.filter(|layout| layout.contains(point)) else {
+            return false;
+        };
+        self.dragging = true;
+        self.target = Some(layout.content_at(point));
+        true
+    }
+
+    /// Moves the view along with the pointer at `point` in the 2D view, while dragging.
+    pub fn drag(&mut self, point: glam::Vec2) {
+        if let Some(layout) = self.layout.filter(|_| self.dragging) {
+            self.target = Some(layout.content_at(point));
+        }
+    }
+
+    pub fn release(&mut self) {
+        self.dragging = false;
+    }
+
+    /// Position in the capture, 0–1 across the rect, the view is to be centered on.
+    pub fn take_target(&mut self) -> Option<glam::Vec2> {
+        self.target.take()
+    }
+
+    /// Outlines the minimap and the part of the capture in view.
+    pub fn add_outlines(&self, builder: &mut LineDrawableBuilder<'_>, color: Color32) {
+        let Some(layout) = self.layout else {
+            return;
+        };
+        let mut batch = builder.batch("minimap").depth_offset(1);
+        for ((min, size), outline_color) in [(layout.rect, Color32::WHITE), (layout.visible, color)]
+        {
+            batch
+                .add_rectangle_outline_2d(min, glam::vec2(size.x, 0.0), glam::vec2(0.0, size.y))
+                .radius(Size::new_points(1.5))
+                .color(outline_color);
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! Mirror of the capture on another monitor, e.g. for an audience: a borderless fullscreen
+//! window showing the processed capture, without overlays, zoomed the way the 2D view is.
+//! The control window stays where it is, on the primary monitor.
+//!
+//! The mirror shows the part of the capture rect that's in the 2D view, filling the
+//! monitor as far as its aspect ratio allows. It presents without waiting for vsync, and
+//! with `direct` the capture texture is drawn straight into its swapchain, without the
+//! view builder's passes, exposure and gamma. The time from capturing a frame to
+//! presenting it in the mirror is `mirror_latency_ms` on the status page.
+
+use serde::Deserialize;
+use winit::monitor::MonitorHandle;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct MirrorConfig {
+    /// Monitor to mirror the capture on, by its index in `--list-monitors` or (part of) its
+    /// name. No mirror without one.
+    pub monitor: Option<String>,
+
+    /// Draw the capture texture straight into the mirror, for the least latency.
+    pub direct: bool,
+}
+
+/// The monitor of `monitors` that `spec` names, by index or (part of) its name.
+pub fn find_monitor(
+    monitors: impl Iterator<Item = MonitorHandle>,
+    spec: &str,
+) -> Option<MonitorHandle> {
+    let index = spec.parse::<usize>().ok();
+    monitors.enumerate().find_map(|(i, monitor)| {
+        let matches = match index {
+            Some(index) => i == index,
+            None => monitor.name().is_some_and(|name| name.contains(spec)),
+        };
+        matches.then_some(monitor)
+    })
+}
+
+/// Prints every monitor with the index `[mirror] monitor` takes.
+pub fn list_monitors(
+    monitors: impl Iterator<Item = MonitorHandle>,
+    primary: Option<MonitorHandle>,
+) {
+    for (index, monitor) in monitors.enumerate() {
+        let size = monitor.size();
+        println!(
+            "{index}: {} ({}x{}){}",
+            monitor.name().unwrap_or_else(|| "unnamed".to_owned()),
+            size.width,
+            size.height,
+            if primary.as_ref() == Some(&monitor) { ", primary" } else { "" },
+        );
+    }
+}
+
+/// Min and size of the capture rect, at `capture_rect` in a 2D view of `view_size`, in a
+/// mirror of `mirror_size`, so the part in the 2D view fills the middle of the mirror.
+pub fn layout(
+    capture_rect: (glam::Vec2, glam::Vec2),
+    view_size: glam::Vec2,
+    mirror_size: glam::Vec2,
+) -> Option<(glam::Vec2, glam::Vec2)> {
+    let (min, size) = capture_rect;
+    let visible_min = min.max(glam::Vec2::ZERO);
+    let visible_size = (min + size).min(view_size) - visible_min;
+    if visible_size.cmple(glam::Vec2::ZERO).any() {
+        return None;
+    }
+    let scale = (mirror_size / visible_size).min_element();
+    let offset = (mirror_size - visible_size * scale) * 0.5;
+    Some(((min - visible_min) * scale + offset, size * scale))
+}
+
+/// The part of the capture texture, min and max 0–1 across it, that's on a mirror of
+/// `mirror_size` with the capture rect at `layout`, and min and size of where it goes.
+pub fn blit_rects(
+    layout: (glam::Vec2, glam::Vec2),
+    mirror_size: glam::Vec2,
+) -> Option<((glam::Vec2, glam::Vec2), (glam::Vec2, glam::Vec2))> {
+    let (min, size) = layout;
+    let target_min = min.max(glam::Vec2::ZERO);
+    let target_max = (min + size).min(mirror_size);
+    if target_min.cmpge(target_max).any() {
+        return None;
+    }
+    let uv = ((target_min - min) / size, (target_max - min) / size);
+    Some((uv, (target_min, target_max - target_min)))
+}
@@ -0,0 +1,94 @@
+//! Captures all displays at once, one stream per display, shown side by side in a grid in
+//! the 2D view.
+//!
+//! The first display feeds everything a single captured display does (recording, watch
+//! regions, picking, the status page); the others only get their latest frame kept in a
+//! slot, for drawing.
+
+use std::sync::{Mutex, MutexGuard};
+
+use crabgrab::prelude::CaptureStream;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+
+use crate::capture::{self, Frame};
+use crate::{capture_manager, status};
+
+/// Scene units between the grid cells.
+const GAP: f32 = 24.0;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct MultiDisplayConfig {
+    /// Capture all displays instead of the first one.
+    pub enabled: bool,
+}
+
+/// Latest frame of every display after the first, display `n` in slot `n - 2`.
+static SLOTS: Lazy<Mutex<Vec<Option<Frame>>>> = Lazy::new(Default::default);
+
+/// Starts a stream per display on a background thread; frames of the first go to
+/// `on_frame`, the others to their slot. The streams live until capturing stops.
+pub fn start(runtime: tokio::runtime::Handle, on_frame: impl FnMut(Frame) + Send + 'static) {
+    std::thread::Builder::new()
+        .name("multi display capture".to_owned())
+        .spawn(move || {
+            let owner = capture_manager::owner();
+            match runtime.block_on(run(on_frame)) {
+                Ok(streams) => {
+                    owner.wait_for_stop();
+                    for mut stream in streams {
+                        stream.stop().ok();
+                    }
+                }
+                Err(err) => eprintln!("Failed to capture the displays: {err:#}"),
+            }
+        })
+        .expect("Failed to spawn the multi display capture thread");
+}
+
+async fn run(on_frame: impl FnMut(Frame) + Send + 'static) -> anyhow::Result<Vec<CaptureStream>> {
+    let displays = capture::find_displays().await?;
+    anyhow::ensure!(!displays.is_empty(), "no capturable display");
+    let count = displays.len();
+    SLOTS.lock().unwrap().resize_with(count - 1, || None);
+
+    let mut displays = displays.into_iter().zip(1..);
+    let (first, _) = displays.next().expect("checked above");
+    let mut streams = vec![capture::capture_display(first, 1, on_frame, |_| {}).await?];
+    for (display, index) in displays {
+        let store_frame = move |frame: Frame| {
+            SLOTS.lock().unwrap()[index - 2] = Some(frame);
+        };
+        match capture::capture_display(display, index, store_frame, |_| {}).await {
+            Ok(stream) => streams.push(stream),
+            Err(err) => eprintln!("Not capturing display {index}: {err:#}"),
+        }
+    }
+    status::update(|status| {
+        status.source_name = "displays".to_owned();
+        status.display = Some(1);
+        status.source = format!("{count} displays side by side");
+    });
+    Ok(streams)
+}
+
+/// Latest frames of the displays after the first, `None` for those without one yet.
+pub fn other_frames() -> MutexGuard<'static, Vec<Option<Frame>>> {
+    SLOTS.lock().unwrap()
+}
+
+/// Top left corner and size of `count` grid cells filling the rect at `min` with `size`,
+/// row by row.
+pub fn grid(count: usize, min: glam::Vec2, size: glam::Vec2) -> Vec<(glam::Vec2, glam::Vec2)> {
+    let columns = (count as f32).sqrt().ceil().max(1.0) as usize;
+    let rows = count.div_ceil(columns).max(1);
+    let gaps = glam::vec2((columns - 1) as f32, (rows - 1) as f32) * GAP;
+    let cell_size = (size - gaps) / glam::vec2(columns as f32, rows as f32);
+    (0..count)
+        .map(|index| {
+            let cell = glam::vec2((index % columns) as f32, (index / columns) as f32);
+            (min + cell * (cell_size + GAP), cell_size)
+        })
+        .collect()
+}
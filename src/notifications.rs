@@ -0,0 +1,137 @@
+//! Notification backends. Every subsystem reports through [`crate::events`], which hands
+//! each event to all configured [`Notifier`]s: the terminal, system notifications, toasts
+//! in the 2D view and webhooks.
+
+use crossbeam_channel::Sender;
+use once_cell::sync::{Lazy, OnceCell};
+use serde::Deserialize;
+
+use crate::events::{Event, Severity};
+use crate::toasts;
+use crate::webhook::{Webhook, WebhookConfig};
+
+pub trait Notifier: Send + Sync {
+    fn notify(&self, event: &Event);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Backend {
+    Stdout,
+    System,
+    Toast,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct NotificationsConfig {
+    /// Where every event goes, webhooks are configured separately.
+    pub backends: Vec<Backend>,
+
+    /// Events below this severity skip the system notifications, which pile up quickly.
+    pub system_min_severity: Severity,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            backends: vec![Backend::Stdout, Backend::Toast],
+            system_min_severity: Severity::Warning,
+        }
+    }
+}
+
+static NOTIFIERS: OnceCell<Vec<Box<dyn Notifier>>> = OnceCell::new();
+
+/// System notifications waiting to be shown, newer ones are dropped while it's full.
+const MAX_QUEUED_SYSTEM_NOTIFICATIONS: usize = 20;
+
+/// AppleScripts run one after the other by a single thread, `osascript` takes a while.
+static SYSTEM_NOTIFICATION_QUEUE: Lazy<Sender<String>> = Lazy::new(|| {
+    let (sender, receiver) = crossbeam_channel::bounded::<String>(MAX_QUEUED_SYSTEM_NOTIFICATIONS);
+    std::thread::Builder::new()
+        .name("system notifications".to_owned())
+        .spawn(move || {
+            for script in receiver {
+                let result = std::process::Command::new("osascript").arg("-e").arg(script).status();
+                match result {
+                    Ok(status) if status.success() => {}
+                    Ok(status) => eprintln!("Failed to show a notification: osascript {status}"),
+                    Err(err) => eprintln!("Failed to show a notification: {err}"),
+                }
+            }
+        })
+        .expect("Failed to spawn the system notification thread");
+    sender
+});
+
+/// Sets up the configured backends, events emitted before only go to stdout.
+pub fn start(config: &NotificationsConfig, webhooks: &[WebhookConfig]) -> anyhow::Result<()> {
+    let mut notifiers: Vec<Box<dyn Notifier>> = Vec::new();
+    for backend in &config.backends {
+        notifiers.push(match backend {
+            Backend::Stdout => Box::new(Stdout),
+            Backend::System => Box::new(SystemNotification {
+                min_severity: config.system_min_severity,
+            }),
+            Backend::Toast => Box::new(Toast),
+        });
+    }
+    for webhook in webhooks {
+        notifiers.push(Box::new(Webhook::start(webhook)?));
+    }
+    NOTIFIERS
+        .set(notifiers)
+        .map_err(|_| anyhow::anyhow!("notifications already started"))
+}
+
+pub fn dispatch(event: &Event) {
+    match NOTIFIERS.get() {
+        Some(notifiers) => notifiers.iter().for_each(|notifier| notifier.notify(event)),
+        None => Stdout.notify(event),
+    }
+}
+
+pub struct Stdout;
+
+impl Notifier for Stdout {
+    fn notify(&self, event: &Event) {
+        let Event { kind, source, message, .. } = event;
+        match event.severity {
+            Severity::Info => println!("{kind} ({source}): {message}"),
+            Severity::Warning | Severity::Error => eprintln!("{kind} ({source}): {message}"),
+        }
+    }
+}
+
+/// Notification center banners through `osascript`, shown in the background.
+pub struct SystemNotification {
+    pub min_severity: Severity,
+}
+
+impl Notifier for SystemNotification {
+    fn notify(&self, event: &Event) {
+        if event.severity < self.min_severity {
+            return;
+        }
+        // AppleScript string literals only need quotes and backslashes escaped.
+        let escape = |text: &str| text.replace('\\', "\\\\").replace('"', "\\\"");
+        let script = format!(
+            "display notification \"{}\" with title \"{}\"",
+            escape(&event.message),
+            escape(&event.source),
+        );
+        if SYSTEM_NOTIFICATION_QUEUE.try_send(script).is_err() {
+            eprintln!("System notification queue full, dropping event {}", event.id);
+        }
+    }
+}
+
+/// Toasts in the corner of the 2D view, see [`toasts`].
+pub struct Toast;
+
+impl Notifier for Toast {
+    fn notify(&self, event: &Event) {
+        toasts::push(event);
+    }
+}
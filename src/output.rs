@@ -0,0 +1,307 @@
+//! Output directory policy shared by everything that writes files.
+//!
+//! Paths are built from per-kind filename templates inside a single output directory
+//! (optionally with per-day subdirectories). Placeholder values can't escape that directory
+//! and existing files are never overwritten.
+
+use std::fs::{File, OpenOptions};
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::Context as _;
+use serde::Deserialize;
+
+use crate::status::SessionStatus;
+
+/// Placeholders understood by [`OutputManager::path_for`].
+const PLACEHOLDERS: &[&str] = &[
+    "date",
+    "time",
+    "source",
+    "window_title",
+    "display",
+    "fps",
+    "frame_id",
+    "seq",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputKind {
+    Screenshot,
+    Recording,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct OutputConfig {
+    pub directory: PathBuf,
+
+    /// Put files into a `YYYY-MM-DD` subdirectory of [`Self::directory`].
+    pub per_day_subdirectories: bool,
+
+    /// Filename templates without extension, see [`OutputManager::path_for`] for the
+    /// placeholders.
+    pub screenshot_template: String,
+    pub recording_template: String,
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self {
+            directory: PathBuf::from("output"),
+            per_day_subdirectories: false,
+            screenshot_template: "{date}_{source}_{seq}".to_owned(),
+            recording_template: "{date}_{time}_{source}_{seq}".to_owned(),
+        }
+    }
+}
+
+/// Capture metadata the placeholders are resolved from, taken at save time.
+#[derive(Debug, Clone, Default)]
+pub struct Metadata {
+    /// Short name of the capture source.
+    pub source: String,
+    pub window_title: Option<String>,
+
+    /// 1-based index of the captured display.
+    pub display: Option<usize>,
+    pub fps: f32,
+    pub frame_id: Option<u64>,
+}
+
+impl From<&SessionStatus> for Metadata {
+    fn from(status: &SessionStatus) -> Self {
+        Self {
+            source: status.source_name.clone(),
+            window_title: status.window_title.clone(),
+            display: status.display,
+            fps: status.capture_fps,
+            frame_id: status.latest_frame_id,
+        }
+    }
+}
+
+pub struct OutputManager {
+    config: OutputConfig,
+}
+
+impl OutputManager {
+    pub fn new(config: OutputConfig) -> anyhow::Result<Self> {
+        let manager = Self { config };
+        for kind in [OutputKind::Screenshot, OutputKind::Recording] {
+            let template = manager.template(kind);
+            validate_template(template)
+                .with_context(|| format!("invalid {kind:?} filename template {template:?}"))?;
+        }
+        Ok(manager)
+    }
+
+    /// Returns a path for a new file of `kind` with `extension` (none if empty) that didn't
+    /// exist yet, creating its directory if needed.
+    ///
+    /// The file is created empty (a directory without extension) to reserve the name, so
+    /// saves running at the same time never get the same path; writers overwrite it.
+    ///
+    /// Templates may contain these placeholders:
+    /// * `{date}` – local date as `YYYY-MM-DD`
+    /// * `{time}` – local time as `HH-MM-SS`
+    /// * `{source}` – short name of the capture source
+    /// * `{window_title}` – title of the captured window, `unknown` for displays
+    /// * `{display}` – 1-based index of the captured display, `unknown` for windows
+    /// * `{fps}` – current capture rate, rounded
+    /// * `{frame_id}` – id of the latest captured frame
+    /// * `{seq}` – smallest sequence number (from 1, four digits) that results in a new file
+    ///
+    /// Templates without `{seq}` get a number appended if the file already exists.
+    pub fn path_for(
+        &self,
+        kind: OutputKind,
+        metadata: &Metadata,
+        extension: &str,
+    ) -> anyhow::Result<PathBuf> {
+        let (path, _) = self.reserve(kind, metadata, extension, |path| {
+            if extension.is_empty() {
+                std::fs::create_dir(path)
+            } else {
+                create_new(path).map(drop)
+            }
+        })?;
+        Ok(path)
+    }
+
+    /// Like [`Self::path_for`], but returns the new file opened for writing.
+    pub fn create_file(
+        &self,
+        kind: OutputKind,
+        metadata: &Metadata,
+        extension: &str,
+    ) -> anyhow::Result<(PathBuf, File)> {
+        self.reserve(kind, metadata, extension, create_new)
+    }
+
+    /// Tries `create` on the candidate paths until one doesn't exist yet.
+    fn reserve<T>(
+        &self,
+        kind: OutputKind,
+        metadata: &Metadata,
+        extension: &str,
+        create: impl Fn(&Path) -> std::io::Result<T>,
+    ) -> anyhow::Result<(PathBuf, T)> {
+        let now = chrono::Local::now();
+        let mut directory = self.config.directory.clone();
+        if self.config.per_day_subdirectories {
+            directory.push(now.format("%Y-%m-%d").to_string());
+        }
+        std::fs::create_dir_all(&directory)
+            .with_context(|| format!("failed to create output directory {}", directory.display()))?;
+        anyhow::ensure!(
+            directory.is_dir(),
+            "output path {} is not a directory",
+            directory.display()
+        );
+
+        let template = self.template(kind);
+        let has_seq = template.contains("{seq}");
+        for seq in 1u32.. {
+            let name = expand(template, |placeholder| match placeholder {
+                "date" => Some(now.format("%Y-%m-%d").to_string()),
+                "time" => Some(now.format("%H-%M-%S").to_string()),
+                "source" => Some(metadata.source.clone()),
+                "window_title" => Some(
+                    metadata
+                        .window_title
+                        .clone()
+                        .unwrap_or_else(|| "unknown".to_owned()),
+                ),
+                "display" => Some(
+                    metadata
+                        .display
+                        .map_or_else(|| "unknown".to_owned(), |display| display.to_string()),
+                ),
+                "fps" => Some(format!("{:.0}", metadata.fps)),
+                "frame_id" => Some(
+                    metadata
+                        .frame_id
+                        .map_or_else(|| "unknown".to_owned(), |frame_id| frame_id.to_string()),
+                ),
+                "seq" => Some(format!("{seq:04}")),
+                _ => None,
+            })?;
+            let name = if has_seq || seq == 1 {
+                name
+            } else {
+                format!("{name}_{seq}")
+            };
+            let name = if extension.is_empty() {
+                name
+            } else {
+                format!("{name}.{extension}")
+            };
+
+            let path = directory.join(name);
+            match create(&path) {
+                Ok(created) => return Ok((path, created)),
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => {}
+                Err(err) => {
+                    let path = path.display();
+                    return Err(err).with_context(|| format!("failed to create {path}"));
+                }
+            }
+        }
+        unreachable!("ran out of sequence numbers")
+    }
+
+    fn template(&self, kind: OutputKind) -> &str {
+        match kind {
+            OutputKind::Screenshot => &self.config.screenshot_template,
+            OutputKind::Recording => &self.config.recording_template,
+        }
+    }
+}
+
+/// Opens `path` for writing if no file exists there yet, atomically.
+fn create_new(path: &Path) -> std::io::Result<File> {
+    OpenOptions::new().write(true).create_new(true).open(path)
+}
+
+/// Replaces every `{name}` in `template` with `resolve(name)`, made safe for file names.
+fn expand(template: &str, resolve: impl Fn(&str) -> Option<String>) -> anyhow::Result<String> {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .context("unterminated placeholder")?
+            + start;
+        let name = &rest[start + 1..end];
+        let value = resolve(name).with_context(|| format!("unknown placeholder {{{name}}}"))?;
+        result.push_str(&sanitize(&value));
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+/// Keeps placeholder values from introducing directories or characters that are invalid
+/// in file names on some platforms.
+fn sanitize(value: &str) -> String {
+    let sanitized = value
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>();
+    // A value of ".." would otherwise still point outside of the output directory.
+    sanitized.trim_matches('.').to_owned()
+}
+
+/// Checks that a template only produces plain file names inside the output directory.
+fn validate_template(template: &str) -> anyhow::Result<()> {
+    let sample = expand(template, |name| PLACEHOLDERS.contains(&name).then(|| "x".to_owned()))?;
+    let mut components = Path::new(&sample).components();
+    anyhow::ensure!(
+        matches!(components.next(), Some(Component::Normal(_))) && components.next().is_none(),
+        "templates must produce a file name without directories"
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand_resolves_placeholders() {
+        let name = expand("{date}_{seq}", |name| Some(name.to_uppercase())).unwrap();
+        assert_eq!(name, "DATE_SEQ");
+    }
+
+    #[test]
+    fn expand_rejects_bad_placeholders() {
+        assert!(expand("{date", |_| Some(String::new())).is_err());
+        assert!(expand("{nope}", |_| None).is_err());
+    }
+
+    #[test]
+    fn expand_sanitizes_values() {
+        let name = expand("{title}.png", |_| Some("../a/b:c".to_owned())).unwrap();
+        assert_eq!(name, "_a_b_c.png");
+    }
+
+    #[test]
+    fn sanitize_keeps_values_in_the_directory() {
+        assert_eq!(sanitize(".."), "");
+        assert_eq!(sanitize("a\\b\nc"), "a_b_c");
+        assert_eq!(sanitize("Café – 1"), "Café – 1");
+    }
+
+    #[test]
+    fn templates_with_directories_are_rejected() {
+        assert!(validate_template("{date}_{seq}").is_ok());
+        assert!(validate_template("sub/{seq}").is_err());
+        assert!(validate_template("{unknown}").is_err());
+    }
+}
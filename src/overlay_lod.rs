@@ -0,0 +1,121 @@
+//! Sheds optional overlay work while frames take longer than a configured budget.
+//!
+//! Degradations are enabled one at a time in [`PRIORITY`] order while the average frame
+//! time stays above the budget and disabled again in reverse order once it's well below,
+//! so the capture itself stays smooth.
+
+use serde::Deserialize;
+
+/// Frames in a row over the budget before the next degradation gets enabled.
+const FRAMES_OVER_BUDGET: u32 = 30;
+
+/// Frames in a row comfortably under the budget before the last degradation gets disabled.
+const FRAMES_UNDER_BUDGET: u32 = 180;
+
+/// Fraction of the budget that counts as comfortably under it.
+const RECOVERY_FRACTION: f32 = 0.75;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Degradation {
+    /// Keep the blurred capture background instead of blurring every new frame.
+    FreezeBackgroundBlur,
+
+    /// Skip the drop shadow of the capture rect.
+    NoShadow,
+
+    /// Stop the overlap demo, so the overlay draw data doesn't get rebuilt.
+    FreezeOverlapDemo,
+}
+
+/// Cheapest loss of quality first.
+pub static PRIORITY: [Degradation; 3] = [
+    Degradation::FreezeBackgroundBlur,
+    Degradation::NoShadow,
+    Degradation::FreezeOverlapDemo,
+];
+
+impl Degradation {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::FreezeBackgroundBlur => "frozen background blur",
+            Self::NoShadow => "no shadow",
+            Self::FreezeOverlapDemo => "frozen overlap demo",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default)]
+pub struct OverlayBudgetConfig {
+    /// Frame time budget in ms, no degradations if unset.
+    pub frame_ms: Option<f32>,
+}
+
+pub struct OverlayLod {
+    budget_ms: Option<f32>,
+
+    /// Number of [`PRIORITY`] entries enabled.
+    active: usize,
+    average_ms: f32,
+    frames_over: u32,
+    frames_under: u32,
+}
+
+impl OverlayLod {
+    pub fn new(config: OverlayBudgetConfig) -> Self {
+        Self {
+            budget_ms: config.frame_ms,
+            active: 0,
+            average_ms: 0.0,
+            frames_over: 0,
+            frames_under: 0,
+        }
+    }
+
+    /// Accounts for a frame that took `frame_ms`.
+    pub fn update(&mut self, frame_ms: f32) {
+        let Some(budget_ms) = self.budget_ms else {
+            return;
+        };
+        self.average_ms += (frame_ms - self.average_ms) * 0.1;
+
+        if self.average_ms > budget_ms {
+            self.frames_over += 1;
+            self.frames_under = 0;
+        } else if self.average_ms < budget_ms * RECOVERY_FRACTION {
+            self.frames_under += 1;
+            self.frames_over = 0;
+        } else {
+            self.frames_over = 0;
+            self.frames_under = 0;
+        }
+
+        if self.frames_over >= FRAMES_OVER_BUDGET && self.active < PRIORITY.len() {
+            self.active += 1;
+            self.frames_over = 0;
+            println!(
+                "Frames take {:.1} ms, over the {budget_ms:.1} ms budget: {}",
+                self.average_ms,
+                PRIORITY[self.active - 1].name()
+            );
+        } else if self.frames_under >= FRAMES_UNDER_BUDGET && self.active > 0 {
+            self.active -= 1;
+            self.frames_under = 0;
+            println!("Frames within budget again, restoring: {}", PRIORITY[self.active].name());
+        }
+    }
+
+    pub fn is_active(&self, degradation: Degradation) -> bool {
+        PRIORITY[..self.active].contains(&degradation)
+    }
+
+    pub fn active(&self) -> &'static [Degradation] {
+        &PRIORITY[..self.active]
+    }
+
+    /// HUD text listing the active degradations, if any.
+    pub fn summary(&self) -> Option<String> {
+        let names = self.active().iter().map(|degradation| degradation.name()).collect::<Vec<_>>();
+        (!names.is_empty()).then(|| format!("over frame budget: {}", names.join(", ")))
+    }
+}
@@ -0,0 +1,162 @@
+//! Render performance stats that separate startup from the steady state.
+//!
+//! The first seconds include shader compilation and the capture permission check and
+//! content enumeration, which would skew frame time stats. Warm-up ends once frame times
+//! have been stable for a while without new render pipelines being compiled, only frames
+//! after that count towards the reported stats. Pipeline compilations are logged separately
+//! whenever they happen.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use re_renderer::RenderContext;
+use serde::Serialize;
+use web_time::Instant;
+
+/// Frames that have to be stable, and free of pipeline compilations, to end warm-up.
+const STABLE_FRAMES: usize = 60;
+
+/// A frame taking longer than this multiple of the median is not stable.
+const MAX_SPIKE: f32 = 3.0;
+
+/// Warm-up ends after this at the latest, e.g. if frame times never settle.
+const MAX_WARMUP: Duration = Duration::from_secs(10);
+
+/// Steady state frame times the percentiles are computed over.
+const STEADY_WINDOW: usize = 600;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FrameTimeStats {
+    /// Frames since the end of warm-up, the mean and max cover all of them, the percentiles
+    /// only the most recent ones.
+    pub frames: u64,
+    pub mean_ms: f32,
+    pub median_ms: f32,
+    pub p95_ms: f32,
+    pub p99_ms: f32,
+    pub max_ms: f32,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PerfSummary {
+    /// Length of the warm-up, `None` while still warming up.
+    pub warmup_seconds: Option<f32>,
+    pub warmup_frames: u64,
+    pub pipeline_compilations: u64,
+
+    /// Frame times after warm-up.
+    pub steady: Option<FrameTimeStats>,
+}
+
+pub struct PerfStats {
+    started_at: Instant,
+    frames: u64,
+    known_pipelines: usize,
+    pipeline_compilations: u64,
+    frames_since_compilation: usize,
+
+    /// Frame times in ms; the latest ones while warming up, afterwards the steady state ones.
+    recent: VecDeque<f32>,
+    warmup: Option<(Duration, u64)>,
+    steady_frames: u64,
+    steady_total_ms: f64,
+    steady_max_ms: f32,
+}
+
+impl PerfStats {
+    pub fn new() -> Self {
+        Self {
+            started_at: Instant::now(),
+            frames: 0,
+            known_pipelines: 0,
+            pipeline_compilations: 0,
+            frames_since_compilation: 0,
+            recent: VecDeque::with_capacity(STEADY_WINDOW),
+            warmup: None,
+            steady_frames: 0,
+            steady_total_ms: 0.0,
+            steady_max_ms: 0.0,
+        }
+    }
+
+    pub fn is_warmed_up(&self) -> bool {
+        self.warmup.is_some()
+    }
+
+    /// Accounts for a drawn frame that took `frame_duration`.
+    pub fn record_frame(&mut self, re_ctx: &RenderContext, frame_duration: Duration) {
+        puffin::profile_function!();
+        self.frames += 1;
+        let frame_ms = frame_duration.as_secs_f32() * 1000.0;
+
+        let pipelines = re_ctx.gpu_resources.statistics().num_render_pipelines;
+        if pipelines > self.known_pipelines {
+            let compiled = pipelines - self.known_pipelines;
+            self.pipeline_compilations += compiled as u64;
+            self.frames_since_compilation = 0;
+            println!(
+                "Compiled {compiled} render pipeline(s) {}, frame took {frame_ms:.1} ms",
+                if self.is_warmed_up() { "after warm-up" } else { "during warm-up" },
+            );
+        } else {
+            self.frames_since_compilation += 1;
+        }
+        self.known_pipelines = pipelines;
+
+        if self.is_warmed_up() {
+            self.steady_frames += 1;
+            self.steady_total_ms += frame_ms as f64;
+            self.steady_max_ms = self.steady_max_ms.max(frame_ms);
+            if self.recent.len() == STEADY_WINDOW {
+                self.recent.pop_front();
+            }
+            self.recent.push_back(frame_ms);
+            return;
+        }
+
+        if self.recent.len() == STABLE_FRAMES {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(frame_ms);
+        let elapsed = self.started_at.elapsed();
+        let median_ms = percentile(&self.recent, 0.5);
+        let stable = self.recent.len() == STABLE_FRAMES
+            && self.frames_since_compilation >= STABLE_FRAMES
+            && self.recent.iter().all(|&ms| ms <= median_ms * MAX_SPIKE);
+        if stable || elapsed >= MAX_WARMUP {
+            println!(
+                "Warm-up finished after {:.1}s ({} frames)",
+                elapsed.as_secs_f32(),
+                self.frames
+            );
+            self.warmup = Some((elapsed, self.frames));
+            self.recent.clear();
+        }
+    }
+
+    pub fn summary(&self) -> PerfSummary {
+        let steady = (self.steady_frames > 0).then(|| FrameTimeStats {
+            frames: self.steady_frames,
+            mean_ms: (self.steady_total_ms / self.steady_frames as f64) as f32,
+            median_ms: percentile(&self.recent, 0.5),
+            p95_ms: percentile(&self.recent, 0.95),
+            p99_ms: percentile(&self.recent, 0.99),
+            max_ms: self.steady_max_ms,
+        });
+        PerfSummary {
+            warmup_seconds: self.warmup.map(|(duration, _)| duration.as_secs_f32()),
+            warmup_frames: self.warmup.map_or(self.frames, |(_, frames)| frames),
+            pipeline_compilations: self.pipeline_compilations,
+            steady,
+        }
+    }
+}
+
+fn percentile(values: &VecDeque<f32>, fraction: f32) -> f32 {
+    let mut sorted = values.iter().copied().collect::<Vec<_>>();
+    sorted.sort_by(f32::total_cmp);
+    sorted
+        .get(((sorted.len() as f32 * fraction) as usize).min(sorted.len().saturating_sub(1)))
+        .copied()
+        .unwrap_or(0.0)
+}
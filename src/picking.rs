@@ -0,0 +1,89 @@
+//! Maps clicks in the 2D view onto the pixels of a textured rect, e.g. the capture.
+//!
+//! re_renderer doesn't pick rectangles, so this intersects with their geometry on the CPU.
+//! Tools that need to know what's under the cursor (color picker, region selection) use
+//! [`pick_rect`] and [`Pick`] instead of redoing the math.
+
+use serde::Serialize;
+
+use crate::capture::Frame;
+
+/// Half the side of the square of pixels a pick averages over.
+const SAMPLE_RADIUS: i64 = 1;
+
+/// A pick on the capture rect.
+#[derive(Debug, Clone, Serialize)]
+pub struct Pick {
+    /// Position in the 2D view, in scene units.
+    pub view_position: [f32; 2],
+
+    /// Pixel of the captured frame under the cursor.
+    pub capture_pixel: [u32; 2],
+
+    /// Where that pixel is on screen, in global screen points.
+    pub screen_position: [f32; 2],
+    pub frame_id: u64,
+
+    /// Average sRGB color of the pixels around [`Self::capture_pixel`].
+    pub rgb: [u8; 3],
+}
+
+/// Pixel of a `texture_size` texture shown in the rect at `rect_min` with `rect_size` that
+/// `position` falls on, if any.
+pub fn pick_rect(
+    rect_min: glam::Vec2,
+    rect_size: glam::Vec2,
+    texture_size: [u32; 2],
+    position: glam::Vec2,
+) -> Option<[u32; 2]> {
+    let uv = (position - rect_min) / rect_size;
+    if !(0.0..1.0).contains(&uv.x) || !(0.0..1.0).contains(&uv.y) {
+        return None;
+    }
+    let texture_size = glam::uvec2(texture_size[0], texture_size[1]);
+    let pixel = (uv * texture_size.as_vec2()).as_uvec2().min(texture_size.saturating_sub(glam::UVec2::ONE));
+    Some(pixel.to_array())
+}
+
+/// Picks the pixel of `frame`, shown in the rect at `rect_min` with `rect_size`, at
+/// `position`.
+pub fn pick_frame(
+    frame: &Frame,
+    rect_min: glam::Vec2,
+    rect_size: glam::Vec2,
+    position: glam::Vec2,
+) -> Option<Pick> {
+    let bitmap = &frame.frame_bitmap;
+    let [x, y] = pick_rect(
+        rect_min,
+        rect_size,
+        [bitmap.width as u32, bitmap.height as u32],
+        position,
+    )?;
+
+    // Sampling a few pixels keeps single pixel noise and antialiasing out of the color.
+    let mut sum = [0u32; 3];
+    let mut count = 0;
+    for dy in -SAMPLE_RADIUS..=SAMPLE_RADIUS {
+        for dx in -SAMPLE_RADIUS..=SAMPLE_RADIUS {
+            let sample_x = (x as i64 + dx).clamp(0, bitmap.width as i64 - 1) as usize;
+            let sample_y = (y as i64 + dy).clamp(0, bitmap.height as i64 - 1) as usize;
+            let [b, g, r, _] = bitmap.data[sample_y * bitmap.width + sample_x];
+            for (sum, channel) in sum.iter_mut().zip([r, g, b]) {
+                *sum += channel as u32;
+            }
+            count += 1;
+        }
+    }
+
+    let source_rect = frame.source_rect;
+    let uv = (glam::vec2(x as f32, y as f32) + 0.5)
+        / glam::vec2(bitmap.width as f32, bitmap.height as f32);
+    Some(Pick {
+        view_position: position.to_array(),
+        capture_pixel: [x, y],
+        screen_position: (source_rect.min + uv * source_rect.size).to_array(),
+        frame_id: frame.frame_id,
+        rgb: sum.map(|sum| (sum / count) as u8),
+    })
+}
@@ -0,0 +1,88 @@
+//! Pins the capture to a UI element, e.g. one panel of an application: captured frames are
+//! cropped to the element's bounds, which are tracked through the accessibility API, so the
+//! crop follows the element as its window moves or resizes.
+//!
+//! Needs the `focus-highlight` feature on macOS, see [`crate::focus`].
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+
+use crate::capture::Frame;
+use crate::focus::{self, ScreenRect};
+
+/// How often the pinned element's bounds are queried, every frame is cropped to the
+/// latest ones.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PinnedElementConfig {
+    /// Point in global screen points, the element there is pinned at startup.
+    pub point: Option<[f32; 2]>,
+
+    /// Pin the ancestor this many levels above the element under the point, e.g. the
+    /// panel around a button.
+    pub levels_up: u32,
+}
+
+#[derive(Default)]
+struct Pinned {
+    /// Incremented on every (un)pin, tells a polling thread that its element is obsolete.
+    generation: u64,
+    is_pinned: bool,
+
+    /// Latest bounds, `None` while the element is hidden or gone.
+    bounds: Option<ScreenRect>,
+}
+
+static PINNED: Lazy<Mutex<Pinned>> = Lazy::new(Default::default);
+
+/// Pins the element at `point` in global screen points, replacing a pinned one.
+pub fn pin(point: glam::Vec2, levels_up: u32) -> anyhow::Result<()> {
+    let element = focus::element_at(point, levels_up)
+        .ok_or_else(|| anyhow::anyhow!("no UI element at {}, {}", point.x, point.y))?;
+    let generation = {
+        let mut pinned = PINNED.lock().unwrap();
+        pinned.generation += 1;
+        pinned.is_pinned = true;
+        pinned.bounds = element.bounds();
+        pinned.generation
+    };
+    println!("Pinned the UI element at {}, {}", point.x, point.y);
+    std::thread::Builder::new()
+        .name("pinned element".to_owned())
+        .spawn(move || loop {
+            std::thread::sleep(POLL_INTERVAL);
+            let bounds = element.bounds();
+            let mut pinned = PINNED.lock().unwrap();
+            if pinned.generation != generation {
+                return;
+            }
+            pinned.bounds = bounds;
+        })?;
+    Ok(())
+}
+
+/// Goes back to capturing the whole source.
+pub fn unpin() {
+    let mut pinned = PINNED.lock().unwrap();
+    pinned.generation += 1;
+    pinned.is_pinned = false;
+    pinned.bounds = None;
+}
+
+pub fn is_pinned() -> bool {
+    PINNED.lock().unwrap().is_pinned
+}
+
+/// Crops `frame` to the pinned element, frames are passed through unchanged while nothing
+/// is pinned or the element isn't visible in them.
+pub fn crop(frame: Frame) -> Frame {
+    let Some(bounds) = PINNED.lock().unwrap().bounds else {
+        return frame;
+    };
+    frame.crop_to_rect(bounds).unwrap_or(frame)
+}
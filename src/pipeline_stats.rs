@@ -0,0 +1,170 @@
+//! Where captured frames go missing and how long they take to show: the frames a capture
+//! source produced against the ones drawn, judged by the gaps in their ids, the time from
+//! capturing a frame to presenting it and the time its upload took.
+//!
+//! The `pipeline` HUD widget shows the totals, with a bar graph of the latest frames in the
+//! bottom right corner of the 2D view: one bar per frame drawn, as high as its latency,
+//! red after dropped frames, with its upload time at the bottom in blue.
+
+use std::collections::VecDeque;
+
+use re_renderer::{Color32, LineDrawableBuilder, Size};
+use serde::Serialize;
+
+/// Frames in the graph.
+const BARS: usize = 64;
+
+/// Width of a bar and the gap to the next, in points.
+const BAR_STEP: f32 = 4.0;
+
+const GRAPH_HEIGHT: f32 = 64.0;
+
+/// Latency at the full height of the graph.
+const GRAPH_MAX_MS: f32 = 100.0;
+
+/// Distance from the corner of the view, and from the minimap, in points.
+const MARGIN: f32 = 16.0;
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct PipelineSummary {
+    /// Frames the capture source produced since the first one drawn, by their ids.
+    pub frames_produced: u64,
+    pub frames_drawn: u64,
+
+    /// Frames produced but replaced by a newer one before being drawn, including the ones
+    /// `max_capture_fps` drops on purpose.
+    pub frames_dropped: u64,
+
+    /// From capturing the latest frame presented to presenting it.
+    pub present_latency_ms: Option<f32>,
+
+    /// CPU time uploading the latest new frame as texture.
+    pub upload_ms: Option<f32>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bar {
+    latency_ms: f32,
+    upload_ms: f32,
+
+    /// Whether frames went missing right before this one.
+    after_drop: bool,
+}
+
+#[derive(Default)]
+pub struct PipelineStats {
+    first_frame_id: Option<u64>,
+    latest_frame_id: Option<u64>,
+    summary: PipelineSummary,
+
+    /// Drawn and waiting to be presented: capture time, upload time and whether frames
+    /// went missing before it.
+    pending: Option<(u64, f32, bool)>,
+    bars: VecDeque<Bar>,
+}
+
+impl PipelineStats {
+    /// Counts the frame `frame_id`, captured at `capture_time_us`, as drawn, the first time
+    /// it's drawn. A frame id lower than the latest means another source, which starts the
+    /// counts over.
+    pub fn frame_drawn(&mut self, frame_id: u64, capture_time_us: u64, upload_ms: Option<f32>) {
+        if let Some(latest) = self.latest_frame_id {
+            if frame_id == latest {
+                return;
+            }
+            if frame_id < latest {
+                *self = Self::default();
+            }
+        }
+        let first = *self.first_frame_id.get_or_insert(frame_id);
+        let dropped = self.latest_frame_id.map_or(0, |latest| frame_id - latest - 1);
+        self.latest_frame_id = Some(frame_id);
+        self.summary.frames_produced = frame_id - first + 1;
+        self.summary.frames_drawn += 1;
+        self.summary.frames_dropped += dropped;
+        self.summary.upload_ms = upload_ms;
+        self.pending = Some((capture_time_us, upload_ms.unwrap_or(0.0), dropped > 0));
+    }
+
+    /// The frame drawn last is presented, as of `now_us`.
+    pub fn presented(&mut self, now_us: u64) {
+        let Some((capture_time_us, upload_ms, after_drop)) = self.pending.take() else {
+            return;
+        };
+        let latency_ms = now_us.saturating_sub(capture_time_us) as f32 / 1000.0;
+        self.summary.present_latency_ms = Some(latency_ms);
+        if self.bars.len() == BARS {
+            self.bars.pop_front();
+        }
+        self.bars.push_back(Bar {
+            latency_ms,
+            upload_ms,
+            after_drop,
+        });
+    }
+
+    pub fn summary(&self) -> PipelineSummary {
+        self.summary
+    }
+
+    /// One line for the HUD.
+    pub fn text(&self) -> String {
+        let summary = &self.summary;
+        let milliseconds = |ms: Option<f32>| ms.map_or("-".to_owned(), |ms| format!("{ms:.1} ms"));
+        format!(
+            "{}/{} frames drawn, {} dropped, {} to present, {} upload",
+            summary.frames_drawn,
+            summary.frames_produced,
+            summary.frames_dropped,
+            milliseconds(summary.present_latency_ms),
+            milliseconds(summary.upload_ms),
+        )
+    }
+
+    /// Strips and vertices [`Self::add_graph`] needs at most.
+    pub fn graph_size() -> (usize, usize) {
+        (BARS * 2 + 1, BARS * 4 + 5)
+    }
+
+    /// Adds the bar graph in the bottom right corner of a 2D view of `view_size`, above
+    /// `below` if given, e.g. the top of the minimap.
+    pub fn add_graph(
+        &self,
+        builder: &mut LineDrawableBuilder<'_>,
+        view_size: glam::Vec2,
+        below: Option<f32>,
+    ) {
+        let width = BARS as f32 * BAR_STEP;
+        let bottom = below.unwrap_or(view_size.y) - MARGIN;
+        let min = glam::vec2(view_size.x - MARGIN - width, bottom - GRAPH_HEIGHT);
+        let mut batch = builder.batch("pipeline stats").depth_offset(1);
+        batch
+            .add_rectangle_outline_2d(min, glam::vec2(width, 0.0), glam::vec2(0.0, GRAPH_HEIGHT))
+            .radius(Size::new_points(0.5))
+            .color(Color32::from_gray(160));
+        let height = |ms: f32| (ms / GRAPH_MAX_MS).clamp(0.0, 1.0) * GRAPH_HEIGHT;
+        let x = |index: usize| min.x + (index as f32 + 0.5) * BAR_STEP;
+        let radius = Size::new_points(BAR_STEP * 0.375);
+        for (index, bar) in self.bars.iter().enumerate() {
+            let color = if bar.after_drop {
+                Color32::from_rgb(230, 60, 60)
+            } else {
+                Color32::from_rgb(80, 200, 80)
+            };
+            let top = bottom - height(bar.latency_ms);
+            batch
+                .add_segment_2d(glam::vec2(x(index), bottom), glam::vec2(x(index), top))
+                .radius(radius)
+                .color(color);
+        }
+        // In front of the latency bars, the upload is part of the latency.
+        let mut batch = builder.batch("pipeline stats uploads").depth_offset(2);
+        for (index, bar) in self.bars.iter().enumerate().filter(|(_, bar)| bar.upload_ms > 0.0) {
+            let top = bottom - height(bar.upload_ms.min(bar.latency_ms));
+            batch
+                .add_segment_2d(glam::vec2(x(index), bottom), glam::vec2(x(index), top))
+                .radius(radius)
+                .color(Color32::from_rgb(80, 140, 255));
+        }
+    }
+}
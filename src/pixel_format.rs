@@ -0,0 +1,315 @@
+//! Capture pixel formats and their conversion to the BGRA8 frames the rest of the example
+//! works with, plus a benchmark that picks the cheapest format on this machine.
+//!
+//! The benchmark briefly captures with every candidate format and measures reading back
+//! and converting a frame. Results are cached per display size in [`CACHE_PATH`], so it
+//! only runs again when the display changes or `--benchmark-pixel-formats` is passed.
+
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use crabgrab::feature::bitmap::FrameBitmap;
+use crabgrab::prelude::{
+    CapturableDisplay, CaptureConfig, CapturePixelFormat, CaptureStream, FrameBitmapBgraUnorm8x4,
+    StreamEvent, VideoFrameBitmap,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::io_pool::{self, Priority};
+
+const CACHE_PATH: &str = "re_render_crabgrab.pixel_formats.json";
+
+/// Frames measured per format.
+const BENCHMARK_FRAMES: usize = 30;
+
+/// Longest time spent on a format, static screens deliver few frames.
+const BENCHMARK_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PixelFormat {
+    Bgra8888,
+
+    /// 10 bits per color channel.
+    Argb2101010,
+
+    /// 4:2:0 YCbCr with video range.
+    V420,
+
+    /// 4:2:0 YCbCr with full range.
+    F420,
+}
+
+impl PixelFormat {
+    pub const ALL: [Self; 4] = [Self::Bgra8888, Self::Argb2101010, Self::V420, Self::F420];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|format| format.name() == name)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Bgra8888 => "bgra8888",
+            Self::Argb2101010 => "argb2101010",
+            Self::V420 => "v420",
+            Self::F420 => "f420",
+        }
+    }
+
+    pub fn capture_format(self) -> CapturePixelFormat {
+        match self {
+            Self::Bgra8888 => CapturePixelFormat::Bgra8888,
+            Self::Argb2101010 => CapturePixelFormat::Argb2101010,
+            Self::V420 => CapturePixelFormat::V420,
+            Self::F420 => CapturePixelFormat::F420,
+        }
+    }
+
+    /// Halves the chroma resolution, which visibly blurs colored text and thin lines.
+    fn subsamples_chroma(self) -> bool {
+        matches!(self, Self::V420 | Self::F420)
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PixelFormatConfig {
+    /// Capture pixel format, picked by the benchmark if unset.
+    pub format: Option<PixelFormat>,
+
+    /// Let the benchmark pick 4:2:0 formats.
+    pub allow_chroma_subsampling: bool,
+
+    /// Upload 4:2:0 captures as luma and chroma textures and convert the preview to RGB on
+    /// the GPU, with the chroma interpolated between samples.
+    pub gpu_conversion: bool,
+
+    /// Run the benchmark even if a cached result exists.
+    #[serde(skip)]
+    pub rebenchmark: bool,
+}
+
+/// Showing `argb2101010` captures with all 10 bits instead of the 8 bit frames the rest of
+/// the example works with, which band in gradients.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct HdrConfig {
+    /// Capture with `argb2101010` and upload the preview as 10 bit texture.
+    pub enabled: bool,
+
+    /// Brightness factor of the capture rect, below 1 brings detail back into highlights.
+    pub exposure: f32,
+
+    /// Exponent applied to the capture rect's colors after `exposure`.
+    pub gamma: f32,
+}
+
+impl Default for HdrConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            exposure: 1.0,
+            gamma: 1.0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct BenchmarkCache {
+    display_size: [u32; 2],
+
+    /// Average read back and conversion time per frame in ms, per format that worked.
+    results: Vec<(PixelFormat, f32)>,
+}
+
+/// Converts any captured bitmap to BGRA8, `None` for formats without conversion.
+pub fn to_bgra(bitmap: FrameBitmap) -> Option<FrameBitmapBgraUnorm8x4> {
+    puffin::profile_function!();
+    match bitmap {
+        FrameBitmap::BgraUnorm8x4(bitmap) => Some(bitmap),
+        FrameBitmap::RgbaUnormPacked1010102(bitmap) => Some(FrameBitmapBgraUnorm8x4 {
+            // ARGB2101010: 2 bits alpha, then 10 bits each of red, green and blue.
+            data: bitmap
+                .data
+                .iter()
+                .map(|pixel| {
+                    let channel = |shift: u32| ((pixel >> shift) & 0x3ff) >> 2;
+                    [channel(0) as u8, channel(10) as u8, channel(20) as u8, 255]
+                })
+                .collect(),
+            width: bitmap.width,
+            height: bitmap.height,
+        }),
+        FrameBitmap::YCbCr(bitmap) => {
+            let full_range = matches!(bitmap.range, crabgrab::feature::bitmap::VideoRange::Full);
+            let mut data = Vec::with_capacity(bitmap.luma_width * bitmap.luma_height);
+            for y in 0..bitmap.luma_height {
+                let chroma_row = (y * bitmap.chroma_height / bitmap.luma_height) * bitmap.chroma_width;
+                for x in 0..bitmap.luma_width {
+                    let luma = bitmap.luma_data[y * bitmap.luma_width + x] as f32;
+                    let [cb, cr] =
+                        bitmap.chroma_data[chroma_row + x * bitmap.chroma_width / bitmap.luma_width];
+                    data.push(ycbcr_to_bgra(luma, cb as f32, cr as f32, full_range));
+                }
+            }
+            Some(FrameBitmapBgraUnorm8x4 {
+                data: data.into(),
+                width: bitmap.luma_width,
+                height: bitmap.luma_height,
+            })
+        }
+        FrameBitmap::RgbaF16x4(_) => None,
+    }
+}
+
+/// The planes of a 4:2:0 capture, as captured.
+pub struct YCbCrPlanes {
+    pub luma: Box<[u8]>,
+    pub luma_size: [usize; 2],
+
+    /// Cb and Cr per sample.
+    pub chroma: Box<[[u8; 2]]>,
+    pub chroma_size: [usize; 2],
+    pub full_range: bool,
+}
+
+/// A copy of the planes of a 4:2:0 capture, `None` for the other formats.
+pub fn ycbcr_planes(bitmap: &FrameBitmap) -> Option<YCbCrPlanes> {
+    let FrameBitmap::YCbCr(bitmap) = bitmap else {
+        return None;
+    };
+    puffin::profile_function!();
+    Some(YCbCrPlanes {
+        luma: bitmap.luma_data.clone(),
+        luma_size: [bitmap.luma_width, bitmap.luma_height],
+        chroma: bitmap.chroma_data.clone(),
+        chroma_size: [bitmap.chroma_width, bitmap.chroma_height],
+        full_range: matches!(bitmap.range, crabgrab::feature::bitmap::VideoRange::Full),
+    })
+}
+
+/// Repacks a 10 bit capture for [`wgpu::TextureFormat::Rgb10a2Unorm`], which has red
+/// rather than blue in the low bits. `None` for the other formats.
+pub fn to_rgb10a2(bitmap: &FrameBitmap) -> Option<Box<[u32]>> {
+    let FrameBitmap::RgbaUnormPacked1010102(bitmap) = bitmap else {
+        return None;
+    };
+    puffin::profile_function!();
+    let data = bitmap
+        .data
+        .iter()
+        .map(|pixel| {
+            let channel = |shift: u32| (pixel >> shift) & 0x3ff;
+            channel(20) | channel(10) << 10 | channel(0) << 20 | 0b11 << 30
+        })
+        .collect();
+    Some(data)
+}
+
+/// BT.709, as used by macOS screen capture.
+fn ycbcr_to_bgra(luma: f32, cb: f32, cr: f32, full_range: bool) -> [u8; 4] {
+    let (luma, cb, cr) = if full_range {
+        (luma / 255.0, (cb - 128.0) / 255.0, (cr - 128.0) / 255.0)
+    } else {
+        (
+            (luma - 16.0) / 219.0,
+            (cb - 128.0) / 224.0,
+            (cr - 128.0) / 224.0,
+        )
+    };
+    let r = luma + 1.5748 * cr;
+    let g = luma - 0.1873 * cb - 0.4681 * cr;
+    let b = luma + 1.8556 * cb;
+    let to_u8 = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    [to_u8(b), to_u8(g), to_u8(r), 255]
+}
+
+/// The configured format, or the cheapest one that meets the configured quality according
+/// to the (cached) benchmark.
+pub async fn select(display: &CapturableDisplay, config: &PixelFormatConfig) -> PixelFormat {
+    if let Some(format) = config.format {
+        return format;
+    }
+
+    let rect = display.rect();
+    let display_size = [rect.size.width as u32, rect.size.height as u32];
+    let cached = std::fs::read(CACHE_PATH)
+        .ok()
+        .and_then(|json| serde_json::from_slice::<BenchmarkCache>(&json).ok())
+        .filter(|cache| cache.display_size == display_size && !config.rebenchmark);
+    let cache = match cached {
+        Some(cache) => cache,
+        None => {
+            let cache = BenchmarkCache {
+                display_size,
+                results: benchmark(display),
+            };
+            match serde_json::to_vec_pretty(&cache) {
+                Ok(json) => io_pool::submit(Priority::Stats, "benchmark cache", move || {
+                    if let Err(err) = std::fs::write(Path::new(CACHE_PATH), json) {
+                        eprintln!("Failed to cache pixel format benchmark: {err:#}");
+                    }
+                }),
+                Err(err) => eprintln!("Failed to cache pixel format benchmark: {err:#}"),
+            }
+            cache
+        }
+    };
+
+    let selected = cache
+        .results
+        .iter()
+        .filter(|(format, _)| config.allow_chroma_subsampling || !format.subsamples_chroma())
+        .min_by(|(_, left), (_, right)| left.total_cmp(right))
+        .map_or(PixelFormat::Bgra8888, |(format, _)| *format);
+    println!("Capturing with pixel format {}", selected.name());
+    selected
+}
+
+/// Average cost per frame of every format that could be captured with.
+fn benchmark(display: &CapturableDisplay) -> Vec<(PixelFormat, f32)> {
+    println!("Benchmarking capture pixel formats…");
+    let mut results = Vec::new();
+    for format in PixelFormat::ALL {
+        match benchmark_format(display, format) {
+            Some(ms) => {
+                println!("  {}: {ms:.2} ms per frame", format.name());
+                results.push((format, ms));
+            }
+            None => println!("  {}: not supported", format.name()),
+        }
+    }
+    results
+}
+
+fn benchmark_format(display: &CapturableDisplay, format: PixelFormat) -> Option<f32> {
+    let token = CaptureStream::test_access(false)?;
+    let config = CaptureConfig::with_display(display.clone(), format.capture_format());
+    let (sender, receiver) = mpsc::channel();
+    let mut stream = CaptureStream::new(token, config, move |result| {
+        if let Ok(StreamEvent::Video(frame)) = result {
+            let start = Instant::now();
+            let converted = frame.get_bitmap().ok().and_then(to_bgra);
+            sender.send(converted.map(|_| start.elapsed())).ok();
+        }
+    })
+    .ok()?;
+
+    let deadline = Instant::now() + BENCHMARK_TIMEOUT;
+    let mut durations = Vec::new();
+    while durations.len() < BENCHMARK_FRAMES {
+        let Some(timeout) = deadline.checked_duration_since(Instant::now()) else {
+            break;
+        };
+        match receiver.recv_timeout(timeout) {
+            Ok(Some(duration)) => durations.push(duration),
+            Ok(None) | Err(_) => break,
+        }
+    }
+    stream.stop().ok();
+
+    // The first frames include setting up the stream.
+    let measured = durations.get(durations.len().min(3)..).filter(|rest| !rest.is_empty())?;
+    Some(measured.iter().sum::<Duration>().as_secs_f32() * 1000.0 / measured.len() as f32)
+}
@@ -0,0 +1,79 @@
+//! Named workspace presets, switched with Ctrl+1 to Ctrl+9.
+//!
+//! Presets come from `[[presets]]` in the config file, the n-th one on Ctrl+n. Ctrl+Shift+n
+//! saves the current state into slot n instead, saved presets are kept in [`SAVED_PATH`]
+//! and take precedence over the config file.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::background::BackgroundKind;
+use crate::recorder::RecordingSource;
+
+const SAVED_PATH: &str = "re_render_crabgrab.presets.json";
+
+/// Number of preset slots, one per digit key.
+pub const SLOTS: usize = 9;
+
+/// Settings a preset switches to, unset ones are left as they are.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preset {
+    pub name: String,
+    pub background: Option<BackgroundKind>,
+    pub feed_source: Option<RecordingSource>,
+    pub high_contrast: Option<bool>,
+    pub reduced_motion: Option<bool>,
+}
+
+pub struct Presets {
+    slots: [Option<Preset>; SLOTS],
+}
+
+impl Presets {
+    /// The presets of the config file, overridden by the saved ones.
+    pub fn load(configured: &[Preset]) -> Self {
+        if configured.len() > SLOTS {
+            eprintln!("Only the first {SLOTS} presets get a shortcut");
+        }
+        let mut slots: [Option<Preset>; SLOTS] = Default::default();
+        for (slot, preset) in slots.iter_mut().zip(configured) {
+            *slot = Some(preset.clone());
+        }
+        for (index, preset) in load_saved() {
+            if let Some(slot) = index.checked_sub(1).and_then(|index| slots.get_mut(index)) {
+                *slot = Some(preset);
+            }
+        }
+        Self { slots }
+    }
+
+    /// The preset with `name`, or in slot `name` if it's a number from 1.
+    pub fn find(&self, name: &str) -> Option<&Preset> {
+        match name.parse::<usize>() {
+            Ok(slot) => self.slots.get(slot.checked_sub(1)?)?.as_ref(),
+            Err(_) => self.slots.iter().flatten().find(|preset| preset.name == name),
+        }
+    }
+
+    /// Puts `preset` into `slot` (from 1) and saves it for later sessions.
+    pub fn save(&mut self, slot: usize, preset: Preset) -> anyhow::Result<()> {
+        anyhow::ensure!((1..=SLOTS).contains(&slot), "no preset slot {slot}");
+        let mut saved = load_saved();
+        saved.insert(slot, preset.clone());
+        self.slots[slot - 1] = Some(preset);
+        std::fs::write(SAVED_PATH, serde_json::to_vec_pretty(&saved)?)?;
+        Ok(())
+    }
+}
+
+fn load_saved() -> BTreeMap<usize, Preset> {
+    let Ok(json) = std::fs::read(SAVED_PATH) else {
+        return BTreeMap::new();
+    };
+    serde_json::from_slice(&json).unwrap_or_else(|err| {
+        eprintln!("Ignoring invalid {SAVED_PATH}: {err}");
+        BTreeMap::new()
+    })
+}
@@ -0,0 +1,188 @@
+//! Built-in profiles bundling the settings for one purpose: `latency`, `quality` or
+//! `battery`. `--profile <name>` (or `profile = "<name>"`) starts with one, `F` and
+//! `POST /api/profile` switch while running.
+//!
+//! A profile overrides parts of the config, [`config::get`] returns the config with the
+//! active one applied. Whatever reads the config when starting, e.g. a recording or the
+//! capture stream, picks the profile up the next time it starts.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::Context as _;
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+
+use crate::config::{self, Config};
+use crate::encoder::EncoderKind;
+use crate::pixel_format::PixelFormat;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Profile {
+    /// Least delay between the screen and the view, recording and stream.
+    Latency,
+
+    /// Best looking recordings and streams, whatever they cost.
+    Quality,
+
+    /// Least work per second, for capturing on battery.
+    Battery,
+}
+
+impl Profile {
+    pub const ALL: [Self; 3] = [Self::Latency, Self::Quality, Self::Battery];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|profile| profile.name() == name)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Latency => "latency",
+            Self::Quality => "quality",
+            Self::Battery => "battery",
+        }
+    }
+
+    /// Index in [`Self::ALL`].
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    /// `config` with the settings of this profile. The pixel format stays `argb2101010`
+    /// with `[hdr]` enabled.
+    pub fn apply(self, config: &Config) -> Config {
+        let mut config = config.clone();
+        config.profile = Some(self);
+        let pixel_format = config.pixel_format.format;
+        match self {
+            Self::Latency => {
+                // The capture needs no conversion, and nothing waits for more frames.
+                config.pixel_format.format = Some(PixelFormat::Bgra8888);
+                config.scene_changes.detect = false;
+                config.black_bars.detect = false;
+                config.black_bars.auto_crop = false;
+                config.recording.encoder = EncoderKind::VideoToolbox;
+                config.recording.queue_size = 2;
+                config.stream.hardware_encoder = true;
+                config.stream.srt_latency_ms = 40;
+                config.stream.hls_segment_seconds = 1.0;
+                config.overlay_budget.frame_ms = Some(8.0);
+            }
+            Self::Quality => {
+                config.pixel_format.format = Some(PixelFormat::Bgra8888);
+                config.pixel_format.allow_chroma_subsampling = false;
+                config.recording.encoder = EncoderKind::Av1;
+                config.recording.av1_speed = 4;
+                config.recording.av1_quantizer = 40;
+                config.recording.fps = 60.0;
+                config.stream.hardware_encoder = false;
+                config.stream.bitrate_kbps = 12000;
+                config.overlay_budget.frame_ms = None;
+            }
+            Self::Battery => {
+                config.max_capture_fps = Some(15.0);
+                config.pixel_format.format = Some(PixelFormat::V420);
+                config.scene_changes.detect = false;
+                config.recording.encoder = EncoderKind::VideoToolbox;
+                config.recording.fps = 15.0;
+                config.stream.hardware_encoder = true;
+                config.stream.fps = 15.0;
+                config.stream.bitrate_kbps = 2500;
+            }
+        }
+        if config.hdr.enabled {
+            config.pixel_format.format = pixel_format;
+        }
+        config
+    }
+
+    /// What this profile changes in `config`, one `setting: before -> after` per line.
+    pub fn differences(self, config: &Config) -> Vec<String> {
+        let applied = self.apply(config);
+        SETTINGS
+            .iter()
+            .filter_map(|(name, show)| {
+                let (before, after) = (show(config), show(&applied));
+                (before != after).then(|| format!("{name}: {before} -> {after}"))
+            })
+            .collect()
+    }
+}
+
+/// The settings profiles change, shown the way [`Profile::differences`] lists them.
+const SETTINGS: [(&str, fn(&Config) -> String); 15] = [
+    ("max capture fps", |config| optional(config.max_capture_fps, "all")),
+    ("pixel format", |config| {
+        config.pixel_format.format.map_or("auto", PixelFormat::name).to_owned()
+    }),
+    ("chroma subsampling", |config| config.pixel_format.allow_chroma_subsampling.to_string()),
+    ("scene changes", |config| config.scene_changes.detect.to_string()),
+    ("black bars", |config| {
+        (config.black_bars.detect || config.black_bars.auto_crop).to_string()
+    }),
+    ("encoder", |config| config.recording.encoder.name().to_owned()),
+    ("av1 speed", |config| config.recording.av1_speed.to_string()),
+    ("av1 quantizer", |config| config.recording.av1_quantizer.to_string()),
+    ("recording fps", |config| config.recording.fps.to_string()),
+    ("encoder queue", |config| config.recording.queue_size.to_string()),
+    ("stream encoder", |config| {
+        let encoder = if config.stream.hardware_encoder { "video_toolbox" } else { "libx264" };
+        encoder.to_owned()
+    }),
+    ("stream fps", |config| config.stream.fps.to_string()),
+    ("stream kbit/s", |config| config.stream.bitrate_kbps.to_string()),
+    ("srt latency ms", |config| config.stream.srt_latency_ms.to_string()),
+    ("hls segment s", |config| config.stream.hls_segment_seconds.to_string()),
+    ("frame budget ms", |config| optional(config.overlay_budget.frame_ms, "none")),
+];
+
+fn optional(value: Option<f32>, unset: &str) -> String {
+    value.map_or_else(|| unset.to_owned(), |value| value.to_string())
+}
+
+/// Index of the active profile in [`Profile::ALL`], [`NO_PROFILE`] without one.
+static ACTIVE: AtomicUsize = AtomicUsize::new(NO_PROFILE);
+const NO_PROFILE: usize = usize::MAX;
+
+/// The config with each profile applied, indexed like [`Profile::ALL`]. Built on first
+/// use and kept, so [`config::get`] can keep handing out `&'static` references.
+static APPLIED: [OnceCell<Config>; 3] = [OnceCell::new(), OnceCell::new(), OnceCell::new()];
+
+pub fn active() -> Option<Profile> {
+    Profile::ALL.get(ACTIVE.load(Ordering::Relaxed)).copied()
+}
+
+/// The config file and flags with `profile` applied.
+pub fn applied(profile: Profile) -> &'static Config {
+    APPLIED[profile.index()].get_or_init(|| profile.apply(config::base()))
+}
+
+/// Switches to `profile`, or back to the config file and flags without one. Fails,
+/// keeping the current profile, if the capture source can't do what the profile asks.
+pub fn switch(profile: Option<Profile>) -> anyhow::Result<()> {
+    if let Some(profile) = profile {
+        applied(profile)
+            .validate()
+            .with_context(|| format!("the {} profile doesn't apply", profile.name()))?;
+    }
+    ACTIVE.store(profile.map_or(NO_PROFILE, Profile::index), Ordering::Relaxed);
+    Ok(())
+}
+
+/// The profile after the active one, with no profile after the last.
+pub fn next() -> Option<Profile> {
+    match active() {
+        None => Some(Profile::ALL[0]),
+        Some(profile) => Profile::ALL.get(profile.index() + 1).copied(),
+    }
+}
+
+/// HUD text naming the active profile and what it changes, if one is active.
+pub fn summary() -> Option<String> {
+    let profile = active()?;
+    let mut lines = vec![format!("profile {}", profile.name())];
+    let differences = profile.differences(config::base());
+    lines.extend(differences.into_iter().map(|line| format!("  {line}")));
+    Some(lines.join("\n"))
+}
@@ -0,0 +1,438 @@
+//! Records captured frames through an [`Encoder`] on a worker thread.
+//!
+//! Capture delivers frames at a variable rate; the recorder turns them into a constant
+//! rate stream by repeating the previous frame into gaps and skipping frames that arrive
+//! faster than the output rate.
+//!
+//! Slow encoders (e.g. AV1) may not keep up with the capture. Whenever the queue runs full
+//! the recorder only queues every n-th frame from then on, the gaps get filled by repeating
+//! frames, so the recording keeps its timing at a lower effective frame rate.
+//!
+//! While recording, a `<name>.recording.json` marker sits next to the output. If the
+//! process dies before the recording is finalized, [`recover_interrupted`] finds the marker
+//! on the next start and finalizes what was written. MP4s are fragmented, so they're
+//! playable up to the last fragment even before that.
+//!
+//! Markers added while recording end up in a `<name>.markers.json` sidecar and, for
+//! containers that support them, as chapters in the recording itself. Captions end up in
+//! SRT and WebVTT sidecars and can be burned into the frames as well.
+
+use std::path::{Path, PathBuf};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use anyhow::Context as _;
+use crossbeam_channel::{Receiver, Sender, TrySendError};
+use serde::{Deserialize, Serialize};
+
+use crate::capture::Frame;
+use crate::captions::Captions;
+use crate::encoder::{self, Encoder, EncoderFrame, EncoderKind, VideoFormat};
+use crate::events::{self, Severity};
+use crate::session_pause;
+
+/// Highest decimation, i.e. at worst every n-th captured frame is recorded.
+const MAX_DECIMATION: u64 = 8;
+
+/// Queued frames in a row the queue has to stay at most a quarter full for before the
+/// decimation is lowered again.
+const CAUGHT_UP_FRAMES: u64 = 60;
+
+/// What feeds the recorder and live stream, can be switched at runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RecordingSource {
+    /// The raw captured frames.
+    #[serde(rename = "capture")]
+    Capture,
+
+    /// The rendered output of the 2D view, read back from the GPU every frame.
+    #[serde(rename = "view_2d")]
+    View2d,
+
+    /// The rendered output of the 3D view, including the camera animation.
+    #[serde(rename = "view_3d")]
+    View3d,
+
+    /// Both views side by side, like the window.
+    #[serde(rename = "composite")]
+    Composite,
+}
+
+impl RecordingSource {
+    pub const ALL: [Self; 4] = [Self::Capture, Self::View2d, Self::View3d, Self::Composite];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|source| source.name() == name)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Capture => "capture",
+            Self::View2d => "view_2d",
+            Self::View3d => "view_3d",
+            Self::Composite => "composite",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|source| *source == self).unwrap();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// Indices of the views read back for this source.
+    pub fn views(self) -> &'static [usize] {
+        match self {
+            Self::Capture => &[],
+            Self::View2d => &[0],
+            Self::View3d => &[1],
+            Self::Composite => &[0, 1],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RecordingConfig {
+    pub encoder: EncoderKind,
+    pub source: RecordingSource,
+
+    /// Frame rate of the recording.
+    pub fps: f32,
+
+    /// `ffmpeg` executable used by the ffmpeg based encoders.
+    pub ffmpeg_path: String,
+
+    /// Frames buffered for the encoder before new frames get dropped, at least 1.
+    pub queue_size: usize,
+
+    /// rav1e speed preset for the AV1 encoder, from 0 (best quality) to 10 (fastest).
+    pub av1_speed: u8,
+
+    /// rav1e quantizer for the AV1 encoder, from 0 (lossless) to 255 (smallest files).
+    pub av1_quantizer: u8,
+
+    /// Read caption lines from stdin.
+    pub captions_from_stdin: bool,
+
+    /// Draw the current caption into the recorded frames, in addition to the sidecars.
+    pub burn_in_captions: bool,
+}
+
+impl Default for RecordingConfig {
+    fn default() -> Self {
+        Self {
+            encoder: EncoderKind::Ffmpeg,
+            source: RecordingSource::Capture,
+            fps: 30.0,
+            ffmpeg_path: "ffmpeg".to_owned(),
+            queue_size: 8,
+            av1_speed: 8,
+            av1_quantizer: 80,
+            captions_from_stdin: false,
+            burn_in_captions: false,
+        }
+    }
+}
+
+/// A named point in time of a recording.
+#[derive(Debug, Clone, Serialize)]
+pub struct Marker {
+    pub name: String,
+
+    /// Seconds since the start of the recording.
+    pub time: f64,
+}
+
+struct QueuedFrame {
+    bgra: Vec<u8>,
+    captured_at: Instant,
+    capture_time_us: u64,
+}
+
+pub struct Recorder {
+    pub path: PathBuf,
+    format: VideoFormat,
+    sender: Option<Sender<QueuedFrame>>,
+    worker: Option<JoinHandle<anyhow::Result<()>>>,
+
+    /// Frames that couldn't be recorded because the encoder fell behind or the capture
+    /// size changed.
+    pub frames_dropped: u64,
+
+    /// Only every `decimation`-th frame gets queued, raised while the encoder falls behind
+    /// and lowered again once it keeps up.
+    decimation: u64,
+    frames_seen: u64,
+
+    /// Frames queued in a row with the queue at most a quarter full.
+    frames_caught_up: u64,
+
+    encoder: EncoderKind,
+    ffmpeg_path: String,
+
+    /// Capture time of the first recorded frame, the recording starts there.
+    first_frame_at: Option<Instant>,
+    markers: Vec<Marker>,
+    captions: Captions,
+    burn_in_captions: bool,
+}
+
+impl Recorder {
+    /// Starts recording `width` x `height` frames to `path`.
+    pub fn start(
+        config: &RecordingConfig,
+        path: PathBuf,
+        width: u32,
+        height: u32,
+    ) -> anyhow::Result<Self> {
+        let format = VideoFormat {
+            width,
+            height,
+            fps: config.fps,
+        };
+        let mut encoder = config.encoder.create(config);
+        encoder
+            .open(&path, format)
+            .with_context(|| format!("failed to start recording to {}", path.display()))?;
+
+        let marker = serde_json::json!({
+            "recording": path.file_name().map(|name| name.to_string_lossy()),
+            "encoder": config.encoder.name(),
+        });
+        std::fs::write(in_progress_marker(&path), marker.to_string())
+            .context("failed to write the recording in progress marker")?;
+
+        let (sender, receiver) = crossbeam_channel::bounded(config.queue_size);
+        let worker = std::thread::Builder::new()
+            .name("recorder".to_owned())
+            .spawn(move || encode_frames(encoder, format, receiver))?;
+
+        println!("Recording to {}", path.display());
+        Ok(Self {
+            path,
+            format,
+            sender: Some(sender),
+            worker: Some(worker),
+            frames_dropped: 0,
+            decimation: 1,
+            frames_seen: 0,
+            frames_caught_up: 0,
+            encoder: config.encoder,
+            ffmpeg_path: config.ffmpeg_path.clone(),
+            first_frame_at: None,
+            markers: Vec::new(),
+            captions: Captions::default(),
+            burn_in_captions: config.burn_in_captions,
+        })
+    }
+
+    /// Queues a newly captured frame.
+    pub fn push(&mut self, frame: &Frame) {
+        puffin::profile_function!();
+
+        let bitmap = &frame.frame_bitmap;
+        if [bitmap.width as u32, bitmap.height as u32] != [self.format.width, self.format.height] {
+            self.frames_dropped += 1;
+            return;
+        }
+
+        self.frames_seen += 1;
+        if self.frames_seen % self.decimation != 0 {
+            return;
+        }
+
+        let mut queued = QueuedFrame {
+            bgra: bitmap.data.iter().flatten().copied().collect(),
+            captured_at: session_pause::now(),
+            capture_time_us: frame.capture_time_us,
+        };
+        self.first_frame_at.get_or_insert(queued.captured_at);
+        if let Some(caption) = self.captions.current().filter(|_| self.burn_in_captions) {
+            crate::text::burn_in_bgra(&mut queued.bgra, bitmap.width, bitmap.height, caption);
+        }
+        let Some(sender) = &self.sender else {
+            return;
+        };
+        match sender.try_send(queued) {
+            Ok(()) => {
+                let low_water = sender.capacity().unwrap_or(0) / 4;
+                if sender.len() > low_water {
+                    self.frames_caught_up = 0;
+                    return;
+                }
+                self.frames_caught_up += 1;
+                if self.decimation > 1 && self.frames_caught_up >= CAUGHT_UP_FRAMES {
+                    self.decimation -= 1;
+                    self.frames_caught_up = 0;
+                    println!("Encoder caught up, recording 1 of every {} frames", self.decimation);
+                }
+            }
+            Err(TrySendError::Full(_)) => {
+                self.frames_dropped += 1;
+                self.frames_caught_up = 0;
+                if self.decimation < MAX_DECIMATION {
+                    self.decimation += 1;
+                    eprintln!(
+                        "Encoder can't keep up with the capture rate, recording 1 of every {} frames",
+                        self.decimation
+                    );
+                }
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                // The worker stopped because of an error, `stop` reports it.
+                self.sender = None;
+            }
+        }
+    }
+
+    /// Marks the current point of the recording, numbered `Marker <n>` if unnamed.
+    pub fn add_marker(&mut self, name: Option<String>) {
+        let time = self.elapsed();
+        let name = name.unwrap_or_else(|| format!("Marker {}", self.markers.len() + 1));
+        let message = format!("{name} at {:.1}s", time.as_secs_f64());
+        events::emit(Severity::Info, "marker_added", "recorder", &message);
+        self.markers.push(Marker {
+            name,
+            time: time.as_secs_f64(),
+        });
+    }
+
+    /// Shows `text` as caption from now on, an empty text clears the caption.
+    pub fn add_caption(&mut self, text: &str) {
+        let time = self.elapsed();
+        self.captions.add(text, time);
+    }
+
+    /// Time since the start of the recording, excluding the time spent paused.
+    fn elapsed(&self) -> Duration {
+        self.first_frame_at.map_or(Duration::ZERO, |first_frame_at| {
+            session_pause::now().saturating_duration_since(first_frame_at)
+        })
+    }
+
+    /// Encodes the queued frames and finalizes the output, returns its path.
+    pub fn stop(mut self) -> anyhow::Result<PathBuf> {
+        drop(self.sender.take());
+        if let Some(worker) = self.worker.take() {
+            worker
+                .join()
+                .map_err(|_| anyhow::anyhow!("recorder thread panicked"))??;
+        }
+        if self.frames_dropped > 0 {
+            eprintln!("Recording dropped {} frames", self.frames_dropped);
+        }
+        if !self.markers.is_empty() {
+            self.write_markers()?;
+        }
+        if !self.captions.is_empty() {
+            self.captions.write_sidecars(&self.path, self.elapsed())?;
+        }
+        std::fs::remove_file(in_progress_marker(&self.path)).ok();
+        Ok(self.path)
+    }
+
+    fn write_markers(&self) -> anyhow::Result<()> {
+        let sidecar = self.path.with_extension("markers.json");
+        let json = serde_json::to_vec_pretty(&serde_json::json!({ "markers": self.markers }))?;
+        std::fs::write(&sidecar, json)
+            .with_context(|| format!("failed to write {}", sidecar.display()))?;
+
+        if self.encoder.supports_chapters() {
+            let duration = self.elapsed();
+            let chapters = self
+                .markers
+                .iter()
+                .map(|marker| (marker.name.as_str(), Duration::from_secs_f64(marker.time)))
+                .collect::<Vec<_>>();
+            encoder::add_chapters(&self.ffmpeg_path, &self.path, &chapters, duration)?;
+        }
+        Ok(())
+    }
+}
+
+fn in_progress_marker(path: &Path) -> PathBuf {
+    path.with_extension("recording.json")
+}
+
+/// Finalizes recordings in `directory`, or its per-day subdirectories, that were cut off
+/// because the process died while recording.
+pub fn recover_interrupted(directory: &Path, ffmpeg_path: &str) {
+    let Ok(entries) = std::fs::read_dir(directory) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            recover_interrupted(&path, ffmpeg_path);
+        } else if path.to_string_lossy().ends_with(".recording.json") {
+            if let Err(err) = recover(&path, ffmpeg_path) {
+                eprintln!("Failed to recover {}: {err:#}", path.display());
+            }
+        }
+    }
+}
+
+fn recover(marker_path: &Path, ffmpeg_path: &str) -> anyhow::Result<()> {
+    #[derive(Deserialize)]
+    struct Marker {
+        recording: String,
+        encoder: String,
+    }
+    let marker: Marker = serde_json::from_slice(&std::fs::read(marker_path)?)?;
+    let encoder = EncoderKind::parse(&marker.encoder)
+        .with_context(|| format!("unknown encoder {:?}", marker.encoder))?;
+    let path = marker_path.with_file_name(&marker.recording);
+    if path.exists() {
+        println!("Recovering interrupted recording {}", path.display());
+        if encoder.is_recoverable() {
+            encoder::remux(ffmpeg_path, &path)?;
+        } else {
+            eprintln!("{} recordings can't be finalized, keeping it as is", encoder.name());
+        }
+    }
+    std::fs::remove_file(marker_path)?;
+    Ok(())
+}
+
+fn encode_frames(
+    mut encoder: Box<dyn Encoder>,
+    format: VideoFormat,
+    receiver: Receiver<QueuedFrame>,
+) -> anyhow::Result<()> {
+    let frame_interval = Duration::from_secs_f64(1.0 / format.fps as f64);
+    let mut start = None;
+    let mut frames_written = 0u64;
+    let mut previous: Option<QueuedFrame> = None;
+
+    for frame in receiver {
+        let start = *start.get_or_insert(frame.captured_at);
+        let slot = ((frame.captured_at - start).as_secs_f64() * format.fps as f64).round() as u64;
+        if slot < frames_written {
+            // Arrived faster than the output rate, the slot is taken already.
+            previous = Some(frame);
+            continue;
+        }
+
+        if let Some(previous) = &previous {
+            while frames_written < slot {
+                encoder.push_frame(&EncoderFrame {
+                    bgra: &previous.bgra,
+                    timestamp: frame_interval * frames_written as u32,
+                    captured_at: previous.captured_at - start,
+                    capture_time_us: previous.capture_time_us,
+                })?;
+                frames_written += 1;
+            }
+        }
+        encoder.push_frame(&EncoderFrame {
+            bgra: &frame.bgra,
+            timestamp: frame_interval * slot as u32,
+            captured_at: frame.captured_at - start,
+            capture_time_us: frame.capture_time_us,
+        })?;
+        frames_written = slot + 1;
+        previous = Some(frame);
+    }
+
+    encoder.finish()
+}
@@ -0,0 +1,165 @@
+//! Detects hard cuts in the capture: the color histogram of a frame jumping away from the
+//! previous one, far more than it does between the frames before.
+//!
+//! Scene changes are logged with their capture time, shown on the status page, marked in
+//! the recording in progress (they become chapters on its timeline) and optionally
+//! screenshotted.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::capture::Frame;
+use crate::control::{self, ControlCommand};
+use crate::status;
+
+/// Histogram bins per color channel.
+const BINS: usize = 16;
+
+/// Every n-th pixel of every n-th row goes into the histogram.
+const SAMPLE_STEP: usize = 4;
+
+/// Recent frame-to-frame distances a spike is compared against.
+const BASELINE_FRAMES: usize = 30;
+
+/// How many times the baseline distance a spike has to be.
+const SPIKE_FACTOR: f32 = 3.0;
+
+/// Cuts closer together than this are one scene change, e.g. a flash.
+const MIN_INTERVAL_US: u64 = 500_000;
+
+/// Scene changes kept for the status page.
+const MAX_LOGGED: usize = 50;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SceneChangeConfig {
+    pub detect: bool,
+
+    /// Lowest histogram distance (0–1) of a scene change, whatever the baseline.
+    pub threshold: f32,
+
+    /// Save a screenshot of the first frame of every new scene.
+    pub screenshot: bool,
+}
+
+impl Default for SceneChangeConfig {
+    fn default() -> Self {
+        Self {
+            detect: false,
+            threshold: 0.35,
+            screenshot: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SceneChange {
+    /// Wall clock capture time of the first frame of the new scene, in µs since the UNIX
+    /// epoch.
+    pub capture_time_us: u64,
+    pub frame_id: u64,
+
+    /// Histogram distance to the previous frame, 0–1.
+    pub distance: f32,
+}
+
+#[derive(Default)]
+struct Detector {
+    previous: Option<Vec<f32>>,
+    distances: VecDeque<f32>,
+    last_change_us: Option<u64>,
+    changes: u64,
+}
+
+static DETECTOR: Lazy<Mutex<Detector>> = Lazy::new(Default::default);
+
+/// Compares `frame` with the previous one and reports a scene change if it starts one.
+pub fn observe(frame: &Frame, config: &SceneChangeConfig) {
+    if !config.detect {
+        return;
+    }
+    puffin::profile_function!();
+
+    let histogram = histogram(frame);
+    let mut detector = DETECTOR.lock().unwrap();
+    let Some(previous) = detector.previous.replace(histogram) else {
+        return;
+    };
+    let distance = distance(&previous, detector.previous.as_ref().unwrap());
+
+    let baseline = if detector.distances.is_empty() {
+        0.0
+    } else {
+        detector.distances.iter().sum::<f32>() / detector.distances.len() as f32
+    };
+    let is_spike = detector.distances.len() == BASELINE_FRAMES
+        && distance >= config.threshold
+        && distance >= baseline * SPIKE_FACTOR;
+    let is_new_scene = match detector.last_change_us {
+        Some(last) => frame.capture_time_us.saturating_sub(last) >= MIN_INTERVAL_US,
+        None => true,
+    };
+
+    if detector.distances.len() == BASELINE_FRAMES {
+        detector.distances.pop_front();
+    }
+    detector.distances.push_back(distance);
+    if !is_spike || !is_new_scene {
+        return;
+    }
+    detector.last_change_us = Some(frame.capture_time_us);
+    detector.changes += 1;
+    let number = detector.changes;
+    drop(detector);
+
+    let change = SceneChange {
+        capture_time_us: frame.capture_time_us,
+        frame_id: frame.frame_id,
+        distance,
+    };
+    println!(
+        "Scene change {number} at frame {} ({} us), distance {distance:.2}",
+        change.frame_id, change.capture_time_us,
+    );
+    let is_recording = status::snapshot().recording.is_some();
+    status::update(|status| {
+        if status.scene_changes.len() == MAX_LOGGED {
+            status.scene_changes.remove(0);
+        }
+        status.scene_changes.push(change);
+    });
+    if is_recording {
+        control::send(ControlCommand::Marker(Some(format!("Scene {}", number + 1))));
+    }
+    if config.screenshot {
+        control::send(ControlCommand::Screenshot);
+    }
+}
+
+/// Per channel histograms, each normalized to sum up to 1.
+fn histogram(frame: &Frame) -> Vec<f32> {
+    let bitmap = &frame.frame_bitmap;
+    let mut histogram = vec![0.0; 3 * BINS];
+    let mut count = 0.0;
+    for y in (0..bitmap.height).step_by(SAMPLE_STEP) {
+        for pixel in bitmap.data[y * bitmap.width..][..bitmap.width].iter().step_by(SAMPLE_STEP) {
+            for (channel, value) in pixel[..3].iter().enumerate() {
+                histogram[channel * BINS + *value as usize * BINS / 256] += 1.0;
+            }
+            count += 1.0;
+        }
+    }
+    if count > 0.0 {
+        histogram.iter_mut().for_each(|bin| *bin /= count);
+    }
+    histogram
+}
+
+/// Half the L1 distance averaged over the channels: 0 for equal, 1 for disjoint
+/// histograms.
+fn distance(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(a, b)| (a - b).abs()).sum::<f32>() / 6.0
+}
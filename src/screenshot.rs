@@ -0,0 +1,75 @@
+//! Saving captured frames as images.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+
+use anyhow::Context as _;
+use crabgrab::prelude::FrameBitmapBgraUnorm8x4;
+use image::ImageEncoder as _;
+
+use crate::events::{self, Severity};
+use crate::io_pool::{self, Priority};
+use crate::status;
+
+/// RGBA8 pixels of `bitmap`, sampled down so that neither side exceeds `max_size`.
+pub fn to_rgba(bitmap: &FrameBitmapBgraUnorm8x4, max_size: usize) -> (Vec<u8>, u32, u32) {
+    let step = bitmap.width.max(bitmap.height).div_ceil(max_size.max(1)).max(1);
+    let width = bitmap.width.div_ceil(step);
+    let height = bitmap.height.div_ceil(step);
+    let data = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .flat_map(|(x, y)| {
+            let [b, g, r, a] = bitmap.data[y * step * bitmap.width + x * step];
+            [r, g, b, a]
+        })
+        .collect();
+    (data, width as u32, height as u32)
+}
+
+pub fn encode_png(rgba: &[u8], width: u32, height: u32) -> anyhow::Result<Vec<u8>> {
+    let mut png = Vec::new();
+    image::codecs::png::PngEncoder::new(&mut png)
+        .write_image(rgba, width, height, image::ColorType::Rgba8)
+        .context("failed to encode PNG")?;
+    Ok(png)
+}
+
+pub fn encode_jpeg(rgba: &[u8], width: u32, height: u32, quality: u8) -> anyhow::Result<Vec<u8>> {
+    let rgb = rgba
+        .chunks_exact(4)
+        .flat_map(|pixel| [pixel[0], pixel[1], pixel[2]])
+        .collect::<Vec<_>>();
+    let mut jpeg = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, quality)
+        .write_image(&rgb, width, height, image::ColorType::Rgb8)
+        .context("failed to encode JPEG")?;
+    Ok(jpeg)
+}
+
+/// Writes `bitmap` at full resolution as PNG to `file`, opened at `path`; encoding happens
+/// on an I/O worker.
+pub fn save_in_background(bitmap: &FrameBitmapBgraUnorm8x4, path: PathBuf, file: File) {
+    let (rgba, width, height) = to_rgba(bitmap, usize::MAX);
+    io_pool::submit(Priority::Screenshot, "screenshot", move || {
+        let result = image::codecs::png::PngEncoder::new(BufWriter::new(file)).write_image(
+            &rgba,
+            width,
+            height,
+            image::ColorType::Rgba8,
+        );
+        match result {
+            Ok(()) => {
+                let path = path.display().to_string();
+                events::emit(Severity::Info, "screenshot_saved", "screenshot", &path);
+                status::update(|status| status.screenshots_saved += 1);
+            }
+            Err(err) => {
+                // Don't leave the reserved, empty file behind.
+                let _ = std::fs::remove_file(&path);
+                let message = format!("{}: {err}", path.display());
+                events::emit(Severity::Error, "screenshot_failed", "screenshot", &message);
+            }
+        }
+    });
+}
@@ -0,0 +1,44 @@
+//! Session pause: while paused, captured frames are dropped, nothing reaches the recorder
+//! and the stats don't accumulate. The recorder runs on [`now`], a clock standing still
+//! while paused, so the recording continues right where it paused, without a gap.
+//!
+//! Unlike frame stepping, which only holds the preview, nothing queues up while paused.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+
+#[derive(Default)]
+struct SessionPause {
+    paused_since: Option<Instant>,
+
+    /// Time spent paused before `paused_since`.
+    paused_total: Duration,
+}
+
+static PAUSE: Lazy<Mutex<SessionPause>> = Lazy::new(Default::default);
+
+pub fn is_paused() -> bool {
+    PAUSE.lock().unwrap().paused_since.is_some()
+}
+
+/// Pauses or resumes, returns whether that changed anything.
+pub fn set_paused(paused: bool) -> bool {
+    let mut pause = PAUSE.lock().unwrap();
+    match (pause.paused_since, paused) {
+        (None, true) => pause.paused_since = Some(Instant::now()),
+        (Some(since), false) => {
+            pause.paused_total += since.elapsed();
+            pause.paused_since = None;
+        }
+        _ => return false,
+    }
+    true
+}
+
+/// Monotonic time excluding the time spent paused, stands still while paused.
+pub fn now() -> Instant {
+    let pause = PAUSE.lock().unwrap();
+    pause.paused_since.unwrap_or_else(Instant::now) - pause.paused_total
+}
@@ -0,0 +1,117 @@
+//! Session state shared with the status server.
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::sync::Mutex;
+
+use crate::black_bars::Bars;
+use crate::capture::CaptureFailure;
+use crate::capture_backend::Capabilities;
+use crate::live_stream::StreamHealth;
+use crate::perf_stats::PerfSummary;
+use crate::picking::Pick;
+use crate::pipeline_stats::PipelineSummary;
+use crate::scene_change::SceneChange;
+use crate::texture_pool::PoolStats;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SessionStatus {
+    /// Human readable description of what is being captured.
+    pub source: String,
+    /// Short, file name friendly name of the capture source.
+    pub source_name: String,
+    pub window_title: Option<String>,
+    /// 1-based index of the captured display.
+    pub display: Option<usize>,
+    pub capture_size: Option<[u32; 2]>,
+
+    /// Whether the mouse pointer is left out of the capture.
+    pub cursor_hidden: bool,
+
+    /// Why the display capture failed, while it's waiting to capture again.
+    pub capture_failure: Option<CaptureFailure>,
+
+    /// What the capture backend supports, `None` for sources that aren't one.
+    pub capabilities: Option<Capabilities>,
+
+    /// Letterbox and pillarbox bars of the capture, when detecting them.
+    pub black_bars: Option<Bars>,
+
+    /// Latest scene changes of the capture, oldest first, when detecting them.
+    pub scene_changes: Vec<SceneChange>,
+    pub latest_frame_id: Option<u64>,
+    pub capture_fps: f32,
+    pub frames_rendered: u64,
+    pub render_fps: f32,
+
+    /// Frames submitted to the GPU that it hasn't finished yet.
+    pub frames_in_flight: usize,
+
+    /// Estimated time from capturing the latest new frame to it being in the main window.
+    pub preview_latency_ms: Option<f32>,
+
+    /// Frames dropped between capture and drawing, and where the time goes, see
+    /// [`crate::pipeline_stats`].
+    pub pipeline: PipelineSummary,
+
+    /// How many frames the shown one is behind the newest, while scrubbing through the
+    /// frame history.
+    pub scrubbed_frames_back: Option<usize>,
+
+    /// Frame time stats excluding the warm-up.
+    pub performance: PerfSummary,
+    pub texture_pool: PoolStats,
+
+    /// Capture pixel last clicked in the 2D view, `None` if the click missed the capture.
+    pub last_pick: Option<Pick>,
+
+    /// Region the capture is cropped to in global screen points, x, y, width and height,
+    /// see [`crate::capture_crop`].
+    pub crop: Option<[f32; 4]>,
+
+    /// Overlay work currently skipped to stay within the frame budget.
+    pub overlay_degradations: Vec<String>,
+
+    /// CPU time spent encoding the command buffers of both views.
+    pub view_encoding_ms: f32,
+
+    /// From capturing the latest new frame in the mirror to presenting it there.
+    pub mirror_latency_ms: Option<f32>,
+    pub uptime_seconds: f32,
+    pub screenshots_saved: u64,
+
+    /// Disk writes waiting for an I/O worker, and those dropped because their queue was
+    /// full, see [`crate::io_pool`].
+    pub io_jobs_queued: usize,
+    pub io_jobs_dropped: u64,
+
+    /// Path of the recording in progress.
+    pub recording: Option<String>,
+
+    /// Name of the active profile, see [`crate::profiles`].
+    pub profile: Option<String>,
+
+    /// Whether the session is paused, see [`crate::session_pause`].
+    pub paused: bool,
+
+    /// What feeds the recorder and live stream, see
+    /// [`RecordingSource`](crate::recorder::RecordingSource).
+    pub feed_source: String,
+
+    pub streaming: Option<StreamHealth>,
+
+    /// How far the capture daemon's clock is ahead of ours, and the round trip the
+    /// estimate is based on, when connected to one.
+    pub clock_offset_ms: Option<f32>,
+    pub clock_round_trip_ms: Option<f32>,
+}
+
+static STATUS: Lazy<Mutex<SessionStatus>> = Lazy::new(Default::default);
+
+pub fn update(f: impl FnOnce(&mut SessionStatus)) {
+    f(&mut STATUS.lock().unwrap());
+}
+
+pub fn snapshot() -> SessionStatus {
+    STATUS.lock().unwrap().clone()
+}
@@ -0,0 +1,462 @@
+//! Small HTTP server with a status page, a live thumbnail and buttons for the control API,
+//! so a running session can be managed from another device on the network.
+//!
+//! Routes:
+//! * `GET /` – the status page
+//! * `GET /api/status` – [`SessionStatus`](crate::status::SessionStatus) as JSON
+//! * `GET /api/events?after=<id>` – recent [events](crate::events) as JSON
+//! * `GET /thumbnail.png` – downscaled latest frame
+//! * `GET /stream.mjpeg` – MJPEG stream of the capture
+//! * `GET /hls/` – player for the HLS output of the live stream, with the playlist and
+//!   segments below it
+//! * `GET /hls.js` – the local copy of hls.js, if configured
+//! * `POST /api/screenshot` – save the latest frame, see [`ControlCommand::Screenshot`]
+//! * `POST /api/recording/start`, `POST /api/recording/stop` – start/stop recording
+//! * `POST /api/stream/start`, `POST /api/stream/stop` – start/stop live streaming
+//! * `POST /api/session/pause`, `POST /api/session/resume` – pause/resume the session
+//! * `POST /api/feed_source` – switch what feeds the recorder and live stream, the body is
+//!   the name of the source
+//! * `POST /api/marker` – mark the current point of the recording, the body is its name
+//! * `POST /api/caption` – show the body as caption in the recording, empty to clear it
+//! * `POST /api/preset` – switch to a workspace preset, the body is its name or number
+//! * `POST /api/display` – capture another display, the body is its 1-based number
+//! * `POST /api/hud` – show the body in the HUD, empty to hide it
+//! * `POST /api/cursor` – show or hide the mouse pointer, the body is `show` or `hide`
+//! * `POST /api/profile` – switch profile, the body is its name or `none`
+//! * `POST /api/backend` – switch the capture backend, the body is its name
+//!
+//! With a token set, every route needs it, as `Authorization: Bearer <token>`, as `?token=`
+//! query or as the cookie the server sets when the query is used.
+
+use std::io::{BufRead as _, BufReader, Read as _, Write as _};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Context as _;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+
+use crate::capture::Frame;
+use crate::capture_backend::BackendKind;
+use crate::control::{self, ControlCommand};
+use crate::live_stream::HLS_PLAYLIST;
+use crate::profiles::Profile;
+use crate::recorder::RecordingSource;
+use crate::{config, events, screenshot, status};
+
+/// Longest side of the thumbnail in pixels.
+const THUMBNAIL_SIZE: usize = 480;
+
+/// Longest side of the MJPEG stream in pixels.
+const MJPEG_SIZE: usize = 1280;
+
+const MJPEG_QUALITY: u8 = 75;
+
+/// How often the MJPEG stream checks for a new frame.
+const MJPEG_POLL_INTERVAL: Duration = Duration::from_millis(33);
+
+/// Largest request body accepted, bodies are names and captions.
+const MAX_BODY_BYTES: usize = 4096;
+
+/// How long a client may take to send its request, or to take a chunk of the response.
+const SOCKET_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where the HLS player loads hls.js from without a `hls_js` copy.
+const HLS_JS_CDN: &str = "https://cdn.jsdelivr.net/npm/hls.js@1";
+
+/// The frame shown in the capture rect, published by the renderer.
+static LATEST_FRAME: Lazy<Mutex<Option<Arc<Frame>>>> = Lazy::new(Default::default);
+
+const STATUS_PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>re_render_crabgrab</title>
+<style>
+body { font-family: sans-serif; background: #111; color: #eee; margin: 1em; }
+img { max-width: 100%; border: 1px solid #444; }
+button { font-size: 1.2em; margin: 0.5em 0.5em 0.5em 0; padding: 0.4em 1em; }
+td { padding: 0.1em 1em 0.1em 0; }
+</style>
+</head>
+<body>
+<h1>re_render_crabgrab</h1>
+<img id="thumbnail" src="/thumbnail.png" alt="no frame captured yet">
+<div id="controls">
+<button onclick="post('/api/screenshot')">Screenshot</button>
+<button onclick="post('/api/recording/start')">Start recording</button>
+<button onclick="post('/api/recording/stop')">Stop recording</button>
+<button onclick="post('/api/stream/start')">Start streaming</button>
+<button onclick="post('/api/stream/stop')">Stop streaming</button>
+<select id="feed_source" onchange="post('/api/feed_source', this.value)">
+<option value="capture">Record capture</option>
+<option value="view_2d">Record 2D view</option>
+<option value="view_3d">Record 3D view</option>
+<option value="composite">Record all views</option>
+</select>
+<button onclick="post('/api/marker', prompt('Marker name') || '')">Marker</button>
+<button onclick="post('/api/caption', prompt('Caption') || '')">Caption</button>
+</div>
+<table id="status"></table>
+<script>
+function post(path, body) {
+  fetch(path, { method: 'POST', body });
+}
+async function refresh() {
+  try {
+    const status = await (await fetch('/api/status')).json();
+    const rows = Object.entries(status).map(([key, value]) => {
+      const row = document.createElement('tr');
+      for (const text of [key, JSON.stringify(value)]) {
+        const cell = document.createElement('td');
+        cell.textContent = text;
+        row.appendChild(cell);
+      }
+      return row;
+    });
+    document.getElementById('status').replaceChildren(...rows);
+    document.getElementById('feed_source').value = status.feed_source;
+    document.getElementById('thumbnail').src = '/thumbnail.png?' + Date.now();
+  } catch (e) {}
+}
+setInterval(refresh, 1000);
+refresh();
+</script>
+</body>
+</html>
+"#;
+
+/// Plays the HLS playlist natively (Safari) or through hls.js, loaded from `HLS_JS_SRC`.
+const HLS_PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>re_render_crabgrab live</title>
+<style>body { background: #111; margin: 0; } video { width: 100%; }</style>
+<script src="HLS_JS_SRC"></script>
+</head>
+<body>
+<video id="video" controls autoplay muted playsinline></video>
+<script>
+const video = document.getElementById('video');
+const playlist = '/hls/stream.m3u8';
+if (video.canPlayType('application/vnd.apple.mpegurl')) {
+  video.src = playlist;
+} else if (window.Hls && Hls.isSupported()) {
+  const hls = new Hls();
+  hls.loadSource(playlist);
+  hls.attachMedia(video);
+}
+</script>
+</body>
+</html>
+"#;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct StatusServerConfig {
+    /// Address to listen on, e.g. `0.0.0.0:8080`. The server is disabled if unset.
+    pub address: Option<String>,
+
+    /// Required by every route if set, see the [module docs](self). Opened once as
+    /// `/?token=<token>`, the page keeps it in a cookie.
+    pub token: Option<String>,
+
+    /// Local copy of hls.js for the HLS player, which otherwise loads it from jsDelivr.
+    /// Browsers that play HLS natively, like Safari, need neither.
+    pub hls_js: Option<PathBuf>,
+}
+
+/// Starts serving on `address` in the background, returns the address bound to.
+pub fn start(address: &str) -> anyhow::Result<SocketAddr> {
+    let listener =
+        TcpListener::bind(address).with_context(|| format!("failed to listen on {address}"))?;
+    let local_addr = listener.local_addr()?;
+    println!("Status page at http://{local_addr}");
+
+    std::thread::Builder::new()
+        .name("status server".to_owned())
+        .spawn(move || {
+            for stream in listener.incoming().flatten() {
+                std::thread::spawn(move || {
+                    if let Err(err) = handle_connection(stream) {
+                        eprintln!("Status server: {err:#}");
+                    }
+                });
+            }
+        })?;
+    Ok(local_addr)
+}
+
+fn handle_connection(stream: TcpStream) -> anyhow::Result<()> {
+    stream.set_read_timeout(Some(SOCKET_TIMEOUT))?;
+    stream.set_write_timeout(Some(SOCKET_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // The body length, and what the control routes check the request against.
+    let mut content_length = 0;
+    let (mut host, mut origin, mut authorization, mut cookie) = (None, None, None, None);
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        if let Some((name, value)) = header.split_once(':') {
+            let value = value.trim();
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.parse().context("invalid Content-Length")?;
+            } else if name.eq_ignore_ascii_case("host") {
+                host = Some(value.to_owned());
+            } else if name.eq_ignore_ascii_case("origin") {
+                origin = Some(value.to_owned());
+            } else if name.eq_ignore_ascii_case("authorization") {
+                authorization = Some(value.to_owned());
+            } else if name.eq_ignore_ascii_case("cookie") {
+                cookie = Some(value.to_owned());
+            }
+        }
+        header.clear();
+    }
+    if content_length > MAX_BODY_BYTES {
+        return respond(stream, "413 Payload Too Large", b"request body too large");
+    }
+    let mut request_body = vec![0u8; content_length];
+    reader.read_exact(&mut request_body)?;
+    let request_body = String::from_utf8_lossy(&request_body);
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let target = parts.next().unwrap_or_default();
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let query_token = query_param(query, "token");
+
+    if method == "POST" {
+        // Pages of other sites can send POSTs too, but not without their own Origin.
+        let same_origin = match (&origin, &host) {
+            (Some(origin), Some(host)) => *origin == format!("http://{host}"),
+            (Some(_), None) => false,
+            (None, _) => true,
+        };
+        if !same_origin {
+            return respond(stream, "403 Forbidden", b"cross-origin request");
+        }
+    }
+    // A `?token=` that checked out is kept in a cookie, so the requests of the pages, their
+    // images and the video player pass it along.
+    let mut set_cookie = None;
+    if let Some(token) = &config::get().status_server.token {
+        let bearer = authorization.as_deref().and_then(|value| value.strip_prefix("Bearer "));
+        let cookie_token = cookie.as_deref().and_then(|cookie| {
+            cookie
+                .split(';')
+                .find_map(|pair| pair.trim().strip_prefix("token="))
+        });
+        if ![bearer, query_token, cookie_token].contains(&Some(token.as_str())) {
+            return respond(stream, "401 Unauthorized", b"missing or wrong token");
+        }
+        if query_token.is_some() && cookie_token != query_token {
+            set_cookie = Some(format!("token={token}; Path=/; HttpOnly; SameSite=Strict"));
+        }
+    }
+
+    let (status_line, content_type, body) = match (method, path) {
+        ("GET", "/") => ("200 OK", "text/html; charset=utf-8", STATUS_PAGE.as_bytes().to_vec()),
+        ("GET", "/api/status") => (
+            "200 OK",
+            "application/json",
+            serde_json::to_vec(&status::snapshot())?,
+        ),
+        ("GET", "/api/events") => {
+            let after = query_param(query, "after").and_then(|after| after.parse().ok());
+            ("200 OK", "application/json", serde_json::to_vec(&events::since(after))?)
+        }
+        ("GET", "/thumbnail.png") => match thumbnail()? {
+            Some(png) => ("200 OK", "image/png", png),
+            None => ("404 Not Found", "text/plain", b"no frame captured yet".to_vec()),
+        },
+        ("GET", "/stream.mjpeg") => return stream_mjpeg(stream),
+        ("GET", "/hls/") => {
+            let hls_js = if config::get().status_server.hls_js.is_some() {
+                "/hls.js"
+            } else {
+                HLS_JS_CDN
+            };
+            let page = HLS_PAGE.replace("HLS_JS_SRC", hls_js);
+            ("200 OK", "text/html; charset=utf-8", page.into_bytes())
+        }
+        ("GET", "/hls.js") => match &config::get().status_server.hls_js {
+            Some(hls_js) => match std::fs::read(hls_js) {
+                Ok(script) => ("200 OK", "text/javascript", script),
+                Err(err) => {
+                    eprintln!("Status server: failed to read {}: {err}", hls_js.display());
+                    ("404 Not Found", "text/plain", b"not found".to_vec())
+                }
+            },
+            None => ("404 Not Found", "text/plain", b"not found".to_vec()),
+        },
+        ("GET", path) if path.starts_with("/hls/") => hls_file(&path["/hls/".len()..]),
+        ("POST", "/api/screenshot") => accept(ControlCommand::Screenshot),
+        ("POST", "/api/recording/start") => accept(ControlCommand::StartRecording),
+        ("POST", "/api/recording/stop") => accept(ControlCommand::StopRecording),
+        ("POST", "/api/stream/start") => accept(ControlCommand::StartStreaming),
+        ("POST", "/api/stream/stop") => accept(ControlCommand::StopStreaming),
+        ("POST", "/api/session/pause") => accept(ControlCommand::PauseSession),
+        ("POST", "/api/session/resume") => accept(ControlCommand::ResumeSession),
+        ("POST", "/api/feed_source") => match RecordingSource::parse(request_body.trim()) {
+            Some(source) => accept(ControlCommand::SetFeedSource(source)),
+            None => ("400 Bad Request", "text/plain", b"unknown source".to_vec()),
+        },
+        ("POST", "/api/marker") => {
+            let name = request_body.trim();
+            accept(ControlCommand::Marker((!name.is_empty()).then(|| name.to_owned())))
+        }
+        ("POST", "/api/caption") => accept(ControlCommand::Caption(request_body.into_owned())),
+        ("POST", "/api/hud") => accept(ControlCommand::HudText(request_body.into_owned())),
+        ("POST", "/api/preset") => {
+            accept(ControlCommand::ApplyPreset(request_body.trim().to_owned()))
+        }
+        ("POST", "/api/display") => match request_body.trim().parse() {
+            Ok(index) => accept(ControlCommand::SwitchDisplay(index)),
+            Err(_) => ("400 Bad Request", "text/plain", b"expected a display number".to_vec()),
+        },
+        ("POST", "/api/cursor") => match request_body.trim() {
+            "show" => accept(ControlCommand::SetCursorHidden(false)),
+            "hide" => accept(ControlCommand::SetCursorHidden(true)),
+            _ => ("400 Bad Request", "text/plain", b"expected show or hide".to_vec()),
+        },
+        ("POST", "/api/profile") => match request_body.trim() {
+            "none" => accept(ControlCommand::SetProfile(None)),
+            name => match Profile::parse(name) {
+                Some(profile) => accept(ControlCommand::SetProfile(Some(profile))),
+                None => ("400 Bad Request", "text/plain", b"unknown profile".to_vec()),
+            },
+        },
+        ("POST", "/api/backend") => match BackendKind::parse(request_body.trim()) {
+            Some(kind) => accept(ControlCommand::SwitchBackend(kind)),
+            None => ("400 Bad Request", "text/plain", b"unknown backend".to_vec()),
+        },
+        _ => ("404 Not Found", "text/plain", b"not found".to_vec()),
+    };
+    write_response(stream, status_line, content_type, set_cookie.as_deref(), &body)
+}
+
+/// Value of the first `name` parameter of a query string, without percent-decoding.
+fn query_param<'a>(query: &'a str, name: &str) -> Option<&'a str> {
+    query.split('&').find_map(|pair| {
+        pair.split_once('=')
+            .filter(|(key, _)| *key == name)
+            .map(|(_, value)| value)
+    })
+}
+
+fn write_response(
+    mut stream: TcpStream,
+    status_line: &str,
+    content_type: &str,
+    set_cookie: Option<&str>,
+    body: &[u8],
+) -> anyhow::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {status_line}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n",
+        body.len()
+    )?;
+    if let Some(cookie) = set_cookie {
+        write!(stream, "Set-Cookie: {cookie}\r\n")?;
+    }
+    write!(stream, "Cache-Control: no-store\r\nConnection: close\r\n\r\n")?;
+    stream.write_all(body)?;
+    Ok(())
+}
+
+/// Rejects a request with `status_line` and a plain text explanation.
+fn respond(stream: TcpStream, status_line: &str, message: &[u8]) -> anyhow::Result<()> {
+    write_response(stream, status_line, "text/plain", None, message)
+}
+
+/// Serves a file of the HLS output directory.
+fn hls_file(name: &str) -> (&'static str, &'static str, Vec<u8>) {
+    let not_found = ("404 Not Found", "text/plain", b"not found".to_vec());
+    let Some(directory) = &config::get().stream.hls_directory else {
+        return not_found;
+    };
+    // Only plain file names, nothing outside of the directory.
+    if name.starts_with('.')
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+    {
+        return not_found;
+    }
+    let content_type = if name == HLS_PLAYLIST {
+        "application/vnd.apple.mpegurl"
+    } else if name.ends_with(".m4s") {
+        "video/iso.segment"
+    } else {
+        "video/mp4"
+    };
+    match std::fs::read(directory.join(name)) {
+        Ok(contents) => ("200 OK", content_type, contents),
+        Err(_) => not_found,
+    }
+}
+
+fn accept(command: ControlCommand) -> (&'static str, &'static str, Vec<u8>) {
+    control::send(command);
+    ("202 Accepted", "text/plain", b"ok".to_vec())
+}
+
+/// Makes `frame` the one served as thumbnail and MJPEG stream.
+pub fn publish_frame(frame: Arc<Frame>) {
+    LATEST_FRAME.lock().unwrap().replace(frame);
+}
+
+fn latest_frame() -> Option<Arc<Frame>> {
+    LATEST_FRAME.lock().unwrap().clone()
+}
+
+fn thumbnail() -> anyhow::Result<Option<Vec<u8>>> {
+    let Some(frame) = latest_frame() else {
+        return Ok(None);
+    };
+    let (rgba, width, height) = screenshot::to_rgba(&frame.frame_bitmap, THUMBNAIL_SIZE);
+    screenshot::encode_png(&rgba, width, height).map(Some)
+}
+
+/// Sends every new frame as a part of a `multipart/x-mixed-replace` response until the
+/// client disconnects.
+fn stream_mjpeg(mut stream: TcpStream) -> anyhow::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: multipart/x-mixed-replace; boundary=frame\r\n\
+         Cache-Control: no-store\r\nConnection: close\r\n\r\n"
+    )?;
+
+    let mut last_frame_id = None;
+    loop {
+        let latest = latest_frame()
+            .filter(|frame| Some(frame.frame_id) != last_frame_id)
+            .map(|frame| {
+                (
+                    frame.frame_id,
+                    screenshot::to_rgba(&frame.frame_bitmap, MJPEG_SIZE),
+                )
+            });
+
+        if let Some((frame_id, (rgba, width, height))) = latest {
+            last_frame_id = Some(frame_id);
+            let jpeg = screenshot::encode_jpeg(&rgba, width, height, MJPEG_QUALITY)?;
+            write!(
+                stream,
+                "--frame\r\nContent-Type: image/jpeg\r\nContent-Length: {}\r\n\r\n",
+                jpeg.len()
+            )?;
+            stream.write_all(&jpeg)?;
+            stream.write_all(b"\r\n")?;
+        }
+
+        std::thread::sleep(MJPEG_POLL_INTERVAL);
+    }
+}
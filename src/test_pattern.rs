@@ -0,0 +1,220 @@
+//! Synthetic capture source: SMPTE-style color bars or gradients with a block showing the
+//! frame number and time moving across, generated on the CPU. Needs no screen recording
+//! permission, e.g. on CI machines, and frame `n` always looks the same, so uploading and
+//! drawing can be benchmarked on identical frames from run to run.
+
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crabgrab::prelude::FrameBitmapBgraUnorm8x4;
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+use serde::Deserialize;
+
+use crate::capture::{self, Frame};
+use crate::focus::ScreenRect;
+use crate::{status, text};
+
+/// Seconds the timestamp block takes to cross the pattern.
+const CROSSING_SECONDS: f64 = 4.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PatternKind {
+    /// Color bars after SMPTE EG 1, at 75 % with the PLUGE row at the bottom.
+    Bars,
+
+    /// Gray, red, green and blue ramps from black to full.
+    Gradient,
+}
+
+impl PatternKind {
+    pub const ALL: [Self; 2] = [Self::Bars, Self::Gradient];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Bars => "bars",
+            Self::Gradient => "gradient",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TestPatternConfig {
+    /// Pattern to generate instead of capturing.
+    pub kind: Option<PatternKind>,
+    pub width: usize,
+    pub height: usize,
+    pub fps: f32,
+}
+
+impl Default for TestPatternConfig {
+    fn default() -> Self {
+        Self {
+            kind: None,
+            width: 1920,
+            height: 1080,
+            fps: 60.0,
+        }
+    }
+}
+
+/// Generates `kind` on a background thread, handing every frame to `on_frame`, until
+/// `stop` disconnects.
+pub fn start(
+    kind: PatternKind,
+    config: TestPatternConfig,
+    stop: Receiver<()>,
+    mut on_frame: impl FnMut(Frame) + Send + 'static,
+) -> JoinHandle<()> {
+    status::update(|status| {
+        status.source = format!("{} test pattern", kind.name());
+        status.source_name = "test_pattern".to_owned();
+    });
+    std::thread::Builder::new()
+        .name("test pattern".to_owned())
+        .spawn(move || {
+            let (width, height) = (config.width.max(16), config.height.max(16));
+            let background = match kind {
+                PatternKind::Bars => bars(width, height),
+                PatternKind::Gradient => gradient(width, height),
+            };
+            let fps = f64::from(config.fps.max(1.0));
+            let frame_interval = Duration::from_secs_f64(1.0 / fps);
+            let started = Instant::now();
+            for frame_index in 0_u64.. {
+                // Keep the frame rate, from the start so waiting doesn't add up.
+                let due = started + frame_interval.mul_f64(frame_index as f64);
+                let wait = stop.recv_timeout(due.saturating_duration_since(Instant::now()));
+                if !matches!(wait, Err(RecvTimeoutError::Timeout)) {
+                    return;
+                }
+                let mut bgra = background.clone();
+                add_timestamp_block(&mut bgra, width, height, frame_index, fps);
+                on_frame(to_frame(bgra, width, height, frame_index + 1));
+            }
+        })
+        .expect("Failed to spawn the test pattern thread")
+}
+
+/// Fills `width` by `height` BGRA pixels with rows of columns, each row covering a
+/// fraction of the height and each column a fraction of the width.
+fn fill_rows(width: usize, height: usize, rows: &[(f32, &[(f32, [u8; 3])])]) -> Vec<[u8; 4]> {
+    let mut bgra = Vec::with_capacity(width * height);
+    let mut row_start = 0.0;
+    for &(row_height, columns) in rows {
+        let row_end = row_start + row_height;
+        let row_pixels = (row_end * height as f32) as usize - (row_start * height as f32) as usize;
+        let mut line = Vec::with_capacity(width);
+        let mut column_start = 0.0;
+        for &(column_width, [r, g, b]) in columns {
+            let column_end = column_start + column_width;
+            let pixels =
+                (column_end * width as f32) as usize - (column_start * width as f32) as usize;
+            line.extend(std::iter::repeat([b, g, r, 255]).take(pixels));
+            column_start = column_end;
+        }
+        line.resize(width, [0, 0, 0, 255]);
+        for _ in 0..row_pixels {
+            bgra.extend_from_slice(&line);
+        }
+        row_start = row_end;
+    }
+    bgra.resize(width * height, [0, 0, 0, 255]);
+    bgra
+}
+
+fn bars(width: usize, height: usize) -> Vec<[u8; 4]> {
+    const BAR: f32 = 1.0 / 7.0;
+    const WHITE: [u8; 3] = [191, 191, 191];
+    const YELLOW: [u8; 3] = [191, 191, 0];
+    const CYAN: [u8; 3] = [0, 191, 191];
+    const GREEN: [u8; 3] = [0, 191, 0];
+    const MAGENTA: [u8; 3] = [191, 0, 191];
+    const RED: [u8; 3] = [191, 0, 0];
+    const BLUE: [u8; 3] = [0, 0, 191];
+    const BLACK: [u8; 3] = [0, 0, 0];
+    let top = [WHITE, YELLOW, CYAN, GREEN, MAGENTA, RED, BLUE].map(|color| (BAR, color));
+    let middle = [BLUE, BLACK, MAGENTA, BLACK, CYAN, BLACK, WHITE].map(|color| (BAR, color));
+    // -I, full white, +Q and the PLUGE: below, at and above black.
+    let bottom = [
+        (BAR * 1.25, [0, 33, 76]),
+        (BAR * 1.25, [255, 255, 255]),
+        (BAR * 1.25, [50, 0, 106]),
+        (BAR * 1.25, BLACK),
+        (BAR / 3.0, [0, 0, 0]),
+        (BAR / 3.0, [10, 10, 10]),
+        (BAR / 3.0, [20, 20, 20]),
+        (BAR, BLACK),
+    ];
+    fill_rows(width, height, &[(0.67, &top[..]), (0.08, &middle[..]), (0.25, &bottom[..])])
+}
+
+fn gradient(width: usize, height: usize) -> Vec<[u8; 4]> {
+    let channels: [[u8; 3]; 4] = [[1, 1, 1], [1, 0, 0], [0, 1, 0], [0, 0, 1]];
+    let mut bgra = Vec::with_capacity(width * height);
+    for y in 0..height {
+        let [r, g, b] = channels[(y * channels.len() / height).min(channels.len() - 1)];
+        bgra.extend((0..width).map(|x| {
+            let value = (x * 255 / (width - 1).max(1)) as u8;
+            [b * value, g * value, r * value, 255]
+        }));
+    }
+    bgra
+}
+
+/// Draws the frame number and time since the first frame into a block that moves across
+/// the middle of the pattern and back.
+fn add_timestamp_block(
+    bgra: &mut [[u8; 4]],
+    width: usize,
+    height: usize,
+    frame_index: u64,
+    fps: f64,
+) {
+    let seconds = frame_index as f64 / fps;
+    let label = format!(
+        "{frame_index:08}\n{:02}:{:02}:{:06.3}",
+        (seconds / 3600.0) as u64,
+        (seconds / 60.0) as u64 % 60,
+        seconds % 60.0,
+    );
+    let scale = (height / 180).max(1);
+    let (rgba, text_width, text_height) =
+        text::rasterize(&label, scale, [0, 0, 0, 255], [255, 255, 255, 255]);
+    let (block_width, block_height) = (text_width.min(width), text_height.min(height));
+    // Back and forth, like a ping pong ball.
+    let phase = (seconds / CROSSING_SECONDS) % 2.0;
+    let travel = (width - block_width) as f64;
+    let x0 = (if phase < 1.0 { phase } else { 2.0 - phase } * travel) as usize;
+    let y0 = (height - block_height) / 2;
+    for y in 0..block_height {
+        for x in 0..block_width {
+            let pixel = &rgba[(y * text_width + x) * 4..][..4];
+            bgra[(y0 + y) * width + x0 + x] = [pixel[2], pixel[1], pixel[0], pixel[3]];
+        }
+    }
+}
+
+fn to_frame(bgra: Vec<[u8; 4]>, width: usize, height: usize, frame_id: u64) -> Frame {
+    Frame {
+        frame_bitmap: FrameBitmapBgraUnorm8x4 {
+            data: bgra.into(),
+            width,
+            height,
+        },
+        frame_id,
+        capture_time_us: capture::now_us(),
+        // Not on this screen, but one point per pixel keeps picking working.
+        source_rect: ScreenRect {
+            min: glam::Vec2::ZERO,
+            size: glam::vec2(width as f32, height as f32),
+        },
+        ycbcr: None,
+        hdr_data: None,
+    }
+}
@@ -0,0 +1,144 @@
+//! Minimal text rendering with the 8x8 bitmap font of `font8x8`, enough for captions and
+//! status text without a font rasterizer.
+
+use font8x8::UnicodeFonts as _;
+use re_renderer::{
+    renderer::{ColormappedTexture, RectangleOptions, TextureFilterMag, TextureFilterMin, TexturedRect},
+    resource_managers::{GpuTexture2D, Texture2DCreationDesc},
+    RenderContext,
+};
+
+/// Glyph width and height in font pixels.
+const GLYPH_SIZE: usize = 8;
+
+/// Empty font pixels around the text.
+const PADDING: usize = 2;
+
+/// Rasterizes `text`, one line per `\n`, as RGBA with `scale` pixels per font pixel.
+///
+/// Returns the pixels, width and height. Block elements like `▄` come from the block font,
+/// other characters missing from the font are drawn as `?`.
+pub fn rasterize(
+    text: &str,
+    scale: usize,
+    color: [u8; 4],
+    background: [u8; 4],
+) -> (Vec<u8>, usize, usize) {
+    let lines = text.lines().collect::<Vec<_>>();
+    let columns = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+    let width = (columns * GLYPH_SIZE + 2 * PADDING) * scale;
+    let height = (lines.len() * GLYPH_SIZE + 2 * PADDING) * scale;
+
+    let mut rgba = background.repeat(width * height);
+    for (row, line) in lines.iter().enumerate() {
+        for (column, c) in line.chars().enumerate() {
+            let glyph = font8x8::BASIC_FONTS
+                .get(c)
+                .or_else(|| font8x8::BLOCK_FONTS.get(c))
+                .or_else(|| font8x8::BASIC_FONTS.get('?'))
+                .unwrap_or_default();
+            for (glyph_y, bits) in glyph.iter().enumerate() {
+                for glyph_x in (0..GLYPH_SIZE).filter(|x| bits & (1 << x) != 0) {
+                    let x0 = (PADDING + column * GLYPH_SIZE + glyph_x) * scale;
+                    let y0 = (PADDING + row * GLYPH_SIZE + glyph_y) * scale;
+                    for y in y0..y0 + scale {
+                        let start = (y * width + x0) * 4;
+                        for pixel in rgba[start..start + scale * 4].chunks_exact_mut(4) {
+                            pixel.copy_from_slice(&color);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    (rgba, width, height)
+}
+
+/// Blends `text` onto a tightly packed BGRA image, centered horizontally near the bottom,
+/// scaled with the image height. Text that doesn't fit gets cut off.
+pub fn burn_in_bgra(bgra: &mut [u8], width: usize, height: usize, text: &str) {
+    let scale = (height / 360).max(1);
+    let (rgba, text_width, text_height) = rasterize(text, scale, [255, 255, 255, 255], [0, 0, 0, 160]);
+
+    let x0 = width.saturating_sub(text_width) / 2;
+    let y0 = height.saturating_sub(text_height + height / 20);
+    for y in 0..text_height.min(height - y0) {
+        for x in 0..text_width.min(width - x0) {
+            let source = &rgba[(y * text_width + x) * 4..][..4];
+            let target = &mut bgra[((y0 + y) * width + x0 + x) * 4..][..4];
+            let alpha = source[3] as u32;
+            for (target, source) in target[..3].iter_mut().zip([source[2], source[1], source[0]]) {
+                *target = ((source as u32 * alpha + *target as u32 * (255 - alpha)) / 255) as u8;
+            }
+        }
+    }
+}
+
+/// Text drawn as a textured rect, the texture is only recreated when the text changes.
+#[derive(Default)]
+pub struct TextRect {
+    cached: Option<(String, [u8; 4], [u8; 4], GpuTexture2D, glam::Vec2)>,
+}
+
+impl TextRect {
+    /// Rect showing `text` with its top left corner at `position`, `scale` scene units per
+    /// font pixel.
+    pub fn rect(
+        &mut self,
+        re_ctx: &RenderContext,
+        text: &str,
+        color: [u8; 4],
+        position: glam::Vec3,
+        scale: f32,
+    ) -> TexturedRect {
+        self.rect_on(re_ctx, text, color, [0, 0, 0, 160], position, scale)
+    }
+
+    /// Like [`Self::rect`], on a `background` of another color.
+    pub fn rect_on(
+        &mut self,
+        re_ctx: &RenderContext,
+        text: &str,
+        color: [u8; 4],
+        background: [u8; 4],
+        position: glam::Vec3,
+        scale: f32,
+    ) -> TexturedRect {
+        let is_cached = matches!(
+            &self.cached,
+            Some((cached_text, cached_color, cached_background, ..))
+                if cached_text == text && *cached_color == color && *cached_background == background
+        );
+        if !is_cached {
+            let (rgba, width, height) = rasterize(text, 1, color, background);
+            let texture = re_ctx
+                .texture_manager_2d
+                .create(
+                    &re_ctx.gpu_resources.textures,
+                    &Texture2DCreationDesc {
+                        label: "text".into(),
+                        data: rgba.into(),
+                        format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                        width: width as u32,
+                        height: height as u32,
+                    },
+                )
+                .expect("Failed to create text texture");
+            let size = glam::vec2(width as f32, height as f32);
+            self.cached = Some((text.to_owned(), color, background, texture, size));
+        }
+        let (_, _, _, texture, size) = self.cached.as_ref().unwrap();
+
+        TexturedRect {
+            top_left_corner_position: position,
+            extent_u: size.x * scale * glam::Vec3::X,
+            extent_v: size.y * scale * glam::Vec3::Y,
+            colormapped_texture: ColormappedTexture::from_unorm_rgba(texture.clone()),
+            options: RectangleOptions {
+                texture_filter_magnification: TextureFilterMag::Nearest,
+                texture_filter_minification: TextureFilterMin::Linear,
+                ..Default::default()
+            },
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! Text watch regions: a region of the capture is read with OCR every few seconds, and an
+//! event fires when its text changes or starts matching a pattern, e.g. a build log
+//! printing `FAILED`. Turns the renderer into a lightweight screen monitor.
+//!
+//! OCR runs through the `tesseract` command line tool, one thread per region so a slow
+//! read never holds up capturing.
+
+use std::io::Write as _;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::Context as _;
+use crossbeam_channel::Sender;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::capture::Frame;
+use crate::events::{self, Delivery, Severity};
+use crate::screenshot;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TextWatchConfig {
+    pub tesseract_path: String,
+    pub regions: Vec<TextRegion>,
+}
+
+impl Default for TextWatchConfig {
+    fn default() -> Self {
+        Self {
+            tesseract_path: "tesseract".to_owned(),
+            regions: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TextRegion {
+    pub name: String,
+
+    /// `[x, y, width, height]` in capture pixels.
+    pub region: [u32; 4],
+
+    /// Regex the text is matched against, fires when it starts matching. Without one, every
+    /// change of the text fires.
+    pub pattern: Option<String>,
+    pub interval_seconds: f32,
+
+    #[serde(flatten)]
+    pub delivery: Delivery,
+}
+
+impl Default for TextRegion {
+    fn default() -> Self {
+        Self {
+            name: "text".to_owned(),
+            region: [0, 0, 0, 0],
+            pattern: None,
+            interval_seconds: 2.0,
+            delivery: Delivery::default(),
+        }
+    }
+}
+
+struct Watcher {
+    region: [u32; 4],
+    interval_us: u64,
+    last_read_us: Option<u64>,
+
+    /// Cropped regions to read, the reader thread skips frames while busy.
+    sender: Sender<Frame>,
+}
+
+static WATCHERS: Lazy<Mutex<Vec<Watcher>>> = Lazy::new(Default::default);
+
+/// Starts a reader thread per configured region.
+pub fn start(config: &TextWatchConfig) -> anyhow::Result<()> {
+    let mut watchers = WATCHERS.lock().unwrap();
+    for region in &config.regions {
+        let pattern = region
+            .pattern
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .with_context(|| format!("invalid pattern of text watch {:?}", region.name))?;
+        let (sender, receiver) = crossbeam_channel::bounded::<Frame>(1);
+        let tesseract_path = config.tesseract_path.clone();
+        let region = region.clone();
+        watchers.push(Watcher {
+            region: region.region,
+            interval_us: (region.interval_seconds.max(0.1) * 1_000_000.0) as u64,
+            last_read_us: None,
+            sender,
+        });
+        std::thread::Builder::new()
+            .name(format!("text watch {}", region.name))
+            .spawn(move || {
+                let mut previous: Option<String> = None;
+                let mut was_matching = false;
+                for frame in receiver {
+                    let text = match read_text(&tesseract_path, &frame) {
+                        Ok(text) => text,
+                        Err(err) => {
+                            eprintln!("Text watch {:?}: {err:#}", region.name);
+                            // Don't flood the log if tesseract is missing.
+                            std::thread::sleep(Duration::from_secs(10));
+                            continue;
+                        }
+                    };
+                    let kind = match &pattern {
+                        Some(pattern) => {
+                            let is_matching = pattern.is_match(&text);
+                            let started_matching = is_matching && !was_matching;
+                            was_matching = is_matching;
+                            started_matching.then_some("text_matched")
+                        }
+                        None => previous
+                            .as_ref()
+                            .is_some_and(|previous| *previous != text)
+                            .then_some("text_changed"),
+                    };
+                    if let Some(kind) = kind {
+                        let event = events::emit(Severity::Warning, kind, &region.name, &text);
+                        events::deliver(&event, &region.delivery);
+                    }
+                    previous = Some(text);
+                }
+            })?;
+    }
+    Ok(())
+}
+
+/// Hands `frame` to the regions due for another read.
+pub fn observe(frame: &Frame) {
+    let mut watchers = WATCHERS.lock().unwrap();
+    for watcher in watchers.iter_mut() {
+        let is_due = match watcher.last_read_us {
+            Some(last) => frame.capture_time_us.saturating_sub(last) >= watcher.interval_us,
+            None => true,
+        };
+        let Some(crop) = is_due.then(|| crop(frame, watcher.region)).flatten() else {
+            continue;
+        };
+        // While the reader is busy the region stays due and goes with the next frame.
+        if watcher.sender.try_send(crop).is_ok() {
+            watcher.last_read_us = Some(frame.capture_time_us);
+        }
+    }
+}
+
+/// The part of `region` within the frame, `None` if that's empty.
+fn crop(frame: &Frame, [x, y, width, height]: [u32; 4]) -> Option<Frame> {
+    let bitmap = &frame.frame_bitmap;
+    let [x, y, width, height] = [x, y, width, height].map(|value| value as usize);
+    let width = width.min(bitmap.width.saturating_sub(x));
+    let height = height.min(bitmap.height.saturating_sub(y));
+    (width > 0 && height > 0).then(|| frame.crop([x, y], [width, height]))
+}
+
+/// Text in `frame`, whitespace trimmed, read with tesseract.
+fn read_text(tesseract_path: &str, frame: &Frame) -> anyhow::Result<String> {
+    let (rgba, width, height) = screenshot::to_rgba(&frame.frame_bitmap, usize::MAX);
+    let png = screenshot::encode_png(&rgba, width, height)?;
+    let mut child = Command::new(tesseract_path)
+        .args(["stdin", "stdout"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("failed to run {tesseract_path:?}"))?;
+    child.stdin.take().context("no tesseract stdin")?.write_all(&png)?;
+    let output = child.wait_with_output()?;
+    anyhow::ensure!(output.status.success(), "tesseract failed: {}", output.status);
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
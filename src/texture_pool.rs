@@ -0,0 +1,160 @@
+//! Pool for textures that get new contents every frame, like the uploaded capture.
+//!
+//! Creating a texture per upload means a new allocation every frame; instead textures are
+//! handed out per frame, keyed by size and format, and recycled at the start of the next
+//! frame, new contents are written into them. Textures unused for [`MAX_IDLE_FRAMES`] get
+//! freed, e.g. after the capture size changed.
+
+use std::collections::HashMap;
+
+use re_renderer::{
+    resource_managers::{GpuTexture2D, Texture2DCreationDesc},
+    RenderContext,
+};
+use serde::Serialize;
+
+const MAX_IDLE_FRAMES: u64 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Key {
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct PoolStats {
+    pub textures: usize,
+    pub bytes: u64,
+    pub created_this_frame: u32,
+    pub reused_this_frame: u32,
+
+    /// Textures kept with their contents, e.g. because the capture didn't change.
+    pub kept_this_frame: u32,
+}
+
+impl PoolStats {
+    /// One line for the HUD.
+    pub fn summary(&self) -> String {
+        format!(
+            "texture pool: {} textures, {:.1} MB, {} created / {} reused / {} kept this frame",
+            self.textures,
+            self.bytes as f64 / 1e6,
+            self.created_this_frame,
+            self.reused_this_frame,
+            self.kept_this_frame,
+        )
+    }
+}
+
+#[derive(Default)]
+pub struct TexturePool {
+    /// Textures available for this frame, with the frame they were last used in.
+    free: HashMap<Key, Vec<(u64, GpuTexture2D)>>,
+    in_use: Vec<(Key, GpuTexture2D)>,
+    frame: u64,
+    stats: PoolStats,
+}
+
+impl TexturePool {
+    /// Recycles the textures handed out during the previous frame.
+    pub fn begin_frame(&mut self) {
+        for (key, texture) in self.in_use.drain(..) {
+            self.free.entry(key).or_default().push((self.frame, texture));
+        }
+        self.frame += 1;
+        let frame = self.frame;
+        for textures in self.free.values_mut() {
+            textures.retain(|(last_used, _)| frame - last_used <= MAX_IDLE_FRAMES);
+        }
+        self.free.retain(|_, textures| !textures.is_empty());
+
+        self.stats.created_this_frame = 0;
+        self.stats.reused_this_frame = 0;
+        self.stats.kept_this_frame = 0;
+        let keys = self.free.iter().flat_map(|(key, textures)| std::iter::repeat(key).take(textures.len()));
+        self.stats.textures = keys.clone().count();
+        self.stats.bytes = keys
+            .map(|key| key.width as u64 * key.height as u64 * bytes_per_texel(key.format) as u64)
+            .sum();
+    }
+
+    /// A texture with `data` as contents, valid until the next [`Self::begin_frame`].
+    pub fn upload(
+        &mut self,
+        re_ctx: &RenderContext,
+        label: &'static str,
+        data: &[u8],
+        format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+    ) -> GpuTexture2D {
+        puffin::profile_function!();
+        let key = Key { width, height, format };
+        let texture = match self.free.get_mut(&key).and_then(Vec::pop) {
+            Some((_, texture)) => {
+                self.stats.reused_this_frame += 1;
+                re_ctx.queue.write_texture(
+                    wgpu::ImageCopyTexture {
+                        texture: &texture.texture,
+                        mip_level: 0,
+                        origin: wgpu::Origin3d::ZERO,
+                        aspect: wgpu::TextureAspect::All,
+                    },
+                    data,
+                    wgpu::ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: Some(width * bytes_per_texel(format)),
+                        rows_per_image: None,
+                    },
+                    wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+                );
+                texture
+            }
+            None => {
+                self.stats.created_this_frame += 1;
+                self.stats.textures += 1;
+                self.stats.bytes += width as u64 * height as u64 * bytes_per_texel(format) as u64;
+                re_ctx
+                    .texture_manager_2d
+                    .create(
+                        &re_ctx.gpu_resources.textures,
+                        &Texture2DCreationDesc {
+                            label: label.into(),
+                            data: data.into(),
+                            format,
+                            width,
+                            height,
+                        },
+                    )
+                    .expect("Failed to create pooled texture")
+            }
+        };
+        self.in_use.push((key, texture.clone()));
+        texture
+    }
+
+    /// Hands `texture`, handed out during the previous frame, out again for this one with
+    /// its contents as they are. Returns false if it got freed in the meantime.
+    pub fn keep(&mut self, texture: &GpuTexture2D) -> bool {
+        for (key, textures) in &mut self.free {
+            let Some(index) = textures.iter().position(|(_, free)| free.handle == texture.handle)
+            else {
+                continue;
+            };
+            let (_, texture) = textures.swap_remove(index);
+            self.stats.kept_this_frame += 1;
+            self.in_use.push((*key, texture));
+            return true;
+        }
+        false
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        self.stats
+    }
+}
+
+fn bytes_per_texel(format: wgpu::TextureFormat) -> u32 {
+    format.block_size(None).unwrap_or(4)
+}
@@ -0,0 +1,121 @@
+//! Toasts: short-lived event messages stacked in the top right corner of the 2D view,
+//! colored by severity. They go away on their own, or when clicked.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use re_renderer::{renderer::TexturedRect, RenderContext};
+
+use crate::events::{Event, Severity};
+use crate::picking;
+use crate::text::TextRect;
+
+/// How long a toast stays up, errors and warnings for longer.
+const LIFETIME: Duration = Duration::from_secs(5);
+const ERROR_LIFETIME: Duration = Duration::from_secs(10);
+
+/// Toasts shown at once, older ones wait for newer ones to go away.
+const MAX_SHOWN: usize = 4;
+
+/// Distance to the view's edges and between toasts, in scene units.
+const MARGIN: f32 = 16.0;
+
+/// Scene units per font pixel.
+const SCALE: f32 = 2.0;
+
+struct Toast {
+    event_id: u64,
+    severity: Severity,
+    text: String,
+
+    /// When it got shown first, `None` while waiting for a free spot.
+    shown_at: Option<Instant>,
+}
+
+impl Toast {
+    fn lifetime(&self) -> Duration {
+        match self.severity {
+            Severity::Info => LIFETIME,
+            Severity::Warning | Severity::Error => ERROR_LIFETIME,
+        }
+    }
+
+    fn color(&self) -> [u8; 4] {
+        match self.severity {
+            Severity::Info => [255, 255, 255, 255],
+            Severity::Warning => [255, 210, 60, 255],
+            Severity::Error => [255, 80, 80, 255],
+        }
+    }
+}
+
+static QUEUE: Lazy<Mutex<VecDeque<Toast>>> = Lazy::new(Default::default);
+
+/// Queues a toast for `event`.
+pub fn push(event: &Event) {
+    let text = if event.message.is_empty() {
+        format!("{}: {}", event.source, event.kind)
+    } else {
+        format!("{}: {}", event.source, event.message)
+    };
+    QUEUE.lock().unwrap().push_back(Toast {
+        event_id: event.id,
+        severity: event.severity,
+        text,
+        shown_at: None,
+    });
+}
+
+/// Draws the toasts in the 2D view.
+#[derive(Default)]
+pub struct Toasts {
+    rects: Vec<TextRect>,
+
+    /// Event id, top left corner and size of every toast drawn last frame.
+    shown: Vec<(u64, glam::Vec2, glam::Vec2)>,
+}
+
+impl Toasts {
+    pub fn rects(&mut self, re_ctx: &RenderContext, view_size: glam::Vec2) -> Vec<TexturedRect> {
+        let mut queue = QUEUE.lock().unwrap();
+        let now = Instant::now();
+        queue.retain(|toast| {
+            !toast.shown_at.is_some_and(|shown_at| now - shown_at >= toast.lifetime())
+        });
+
+        self.rects.resize_with(MAX_SHOWN, TextRect::default);
+        self.shown.clear();
+        let mut y = MARGIN;
+        let mut rects = Vec::new();
+        for (toast, text_rect) in queue.iter_mut().take(MAX_SHOWN).zip(&mut self.rects) {
+            toast.shown_at.get_or_insert(now);
+            let mut rect = text_rect.rect(
+                re_ctx,
+                &toast.text,
+                toast.color(),
+                glam::vec3(0.0, y, 0.0),
+                SCALE,
+            );
+            rect.top_left_corner_position.x = view_size.x - MARGIN - rect.extent_u.x;
+            let size = glam::vec2(rect.extent_u.x, rect.extent_v.y);
+            self.shown.push((toast.event_id, rect.top_left_corner_position.truncate(), size));
+            y += size.y + MARGIN * 0.5;
+            rects.push(rect);
+        }
+        rects
+    }
+
+    /// Dismisses the toast at `position` in the 2D view, returns whether there was one.
+    pub fn click(&mut self, position: glam::Vec2) -> bool {
+        let clicked = self.shown.iter().find(|(_, min, size)| {
+            picking::pick_rect(*min, *size, [1, 1], position).is_some()
+        });
+        let Some(&(event_id, _, _)) = clicked else {
+            return false;
+        };
+        QUEUE.lock().unwrap().retain(|toast| toast.event_id != event_id);
+        true
+    }
+}
@@ -0,0 +1,126 @@
+//! Touchscreen input for tablets: one finger draws annotations on the capture rect, two
+//! fingers pan and pinch to zoom it, a tap picks like a left click, and holding a finger
+//! still opens the context menu like a right click.
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use winit::event::{Touch, TouchPhase};
+
+/// How long a finger has to stay down without moving to open the context menu.
+const LONG_PRESS: Duration = Duration::from_millis(500);
+
+/// Movement in pixels below which a finger counts as still, fingers wobble.
+const SLOP: f32 = 12.0;
+
+/// What the fingers did, positions are in window pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Gesture {
+    Tap(glam::Vec2),
+    LongPress(glam::Vec2),
+
+    /// A one finger stroke started at the first point, or went on to the next one.
+    StrokeStart(glam::Vec2),
+    StrokeTo(glam::Vec2),
+
+    /// Two fingers moved their midpoint by `pan` and their distance by the factor `zoom`,
+    /// which zooms around `center`.
+    PanZoom {
+        pan: glam::Vec2,
+        zoom: f32,
+        center: glam::Vec2,
+    },
+}
+
+/// A finger that went down alone and hasn't been told apart as tap, stroke or long press.
+#[derive(Debug)]
+struct Press {
+    id: u64,
+    start: glam::Vec2,
+    since: Instant,
+}
+
+#[derive(Debug, Default)]
+pub struct Touches {
+    /// Position of every finger that's down, by id.
+    fingers: BTreeMap<u64, glam::Vec2>,
+    press: Option<Press>,
+
+    /// The finger drawing a stroke.
+    stroke: Option<u64>,
+}
+
+impl Touches {
+    pub fn update(&mut self, touch: &Touch) -> Option<Gesture> {
+        let position = glam::vec2(touch.location.x as f32, touch.location.y as f32);
+        match touch.phase {
+            TouchPhase::Started => {
+                self.fingers.insert(touch.id, position);
+                if self.fingers.len() == 1 {
+                    self.press = Some(Press {
+                        id: touch.id,
+                        start: position,
+                        since: Instant::now(),
+                    });
+                } else {
+                    // Until all fingers are lifted, none of them taps or draws.
+                    self.press = None;
+                    self.stroke = None;
+                }
+                None
+            }
+            TouchPhase::Moved => {
+                let (before_center, before_distance) = self.pair()?;
+                self.fingers.insert(touch.id, position);
+                if let Some((center, distance)) = self.pair().filter(|_| self.fingers.len() == 2) {
+                    return Some(Gesture::PanZoom {
+                        pan: center - before_center,
+                        zoom: if before_distance > 0.0 { distance / before_distance } else { 1.0 },
+                        center,
+                    });
+                }
+                if self.stroke == Some(touch.id) {
+                    return Some(Gesture::StrokeTo(position));
+                }
+                let press = self.press.as_ref().filter(|press| press.id == touch.id)?;
+                if press.start.distance(position) < SLOP {
+                    return None;
+                }
+                let start = press.start;
+                self.press = None;
+                self.stroke = Some(touch.id);
+                Some(Gesture::StrokeStart(start))
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                self.fingers.remove(&touch.id);
+                if self.stroke == Some(touch.id) {
+                    self.stroke = None;
+                }
+                if !self.press.as_ref().is_some_and(|press| press.id == touch.id) {
+                    return None;
+                }
+                let press = self.press.take()?;
+                (touch.phase == TouchPhase::Ended).then_some(Gesture::Tap(press.start))
+            }
+        }
+    }
+
+    /// A long press, once the finger stayed down long enough.
+    pub fn poll(&mut self) -> Option<Gesture> {
+        let is_long = self.press.as_ref().is_some_and(|press| press.since.elapsed() >= LONG_PRESS);
+        if !is_long {
+            return None;
+        }
+        self.press.take().map(|press| Gesture::LongPress(press.start))
+    }
+
+    /// Midpoint of the first two fingers and their distance, a single finger's position.
+    fn pair(&self) -> Option<(glam::Vec2, f32)> {
+        let mut fingers = self.fingers.values();
+        let first = *fingers.next()?;
+        Some(match fingers.next() {
+            Some(&second) => ((first + second) * 0.5, first.distance(second)),
+            None => (first, 0.0),
+        })
+    }
+}
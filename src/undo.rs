@@ -0,0 +1,137 @@
+//! Undo/redo for interactive edits, as a bounded history of reversible edits.
+//!
+//! The state these edits touch is saved to [`SESSION_PATH`] after every change, so the
+//! final state of a session can be picked up again with `--restore-session`.
+
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::background::BackgroundKind;
+use crate::capture_backend::BackendKind;
+use crate::focus::ScreenRect;
+use crate::io_pool::{self, Priority};
+use crate::recorder::RecordingSource;
+
+/// Edits kept for undo, older ones are forgotten.
+const MAX_HISTORY: usize = 100;
+
+const SESSION_PATH: &str = "re_render_crabgrab.session.json";
+
+/// Annotation strokes drawn on the capture, in units of the capture rect.
+pub type Strokes = Vec<Vec<glam::Vec2>>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Edit {
+    Background { from: BackgroundKind, to: BackgroundKind },
+    FeedSource { from: RecordingSource, to: RecordingSource },
+    HighContrast { to: bool },
+    ReducedMotion { to: bool },
+    /// A stroke drawn, or all of them cleared.
+    Annotations { from: Strokes, to: Strokes },
+    /// The 1-based display captured.
+    Display { from: usize, to: usize },
+    Backend { from: BackendKind, to: BackendKind },
+    /// The region of the capture cropped to, see [`crate::capture_crop`].
+    Crop { from: Option<ScreenRect>, to: Option<ScreenRect> },
+}
+
+impl Edit {
+    /// The edit that undoes this one.
+    pub fn inverse(self) -> Self {
+        match self {
+            Self::Background { from, to } => Self::Background { from: to, to: from },
+            Self::FeedSource { from, to } => Self::FeedSource { from: to, to: from },
+            Self::HighContrast { to } => Self::HighContrast { to: !to },
+            Self::ReducedMotion { to } => Self::ReducedMotion { to: !to },
+            Self::Annotations { from, to } => Self::Annotations { from: to, to: from },
+            Self::Display { from, to } => Self::Display { from: to, to: from },
+            Self::Backend { from, to } => Self::Backend { from: to, to: from },
+            Self::Crop { from, to } => Self::Crop { from: to, to: from },
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let on_off = |on: bool| if on { "on" } else { "off" };
+        match self {
+            Self::Background { to, .. } => format!("background {}", to.name()),
+            Self::FeedSource { to, .. } => format!("feed source {}", to.name()),
+            Self::HighContrast { to } => format!("high contrast {}", on_off(*to)),
+            Self::ReducedMotion { to } => format!("reduced motion {}", on_off(*to)),
+            Self::Annotations { to, .. } => format!("{} annotation strokes", to.len()),
+            Self::Display { to, .. } => format!("display {to}"),
+            Self::Backend { to, .. } => format!("backend {}", to.name()),
+            Self::Crop { to, .. } => format!("crop {}", on_off(to.is_some())),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct UndoStack {
+    undo: VecDeque<Edit>,
+    redo: Vec<Edit>,
+}
+
+impl UndoStack {
+    /// Records an edit that was just applied, this discards the redo history.
+    pub fn push(&mut self, edit: Edit) {
+        if self.undo.len() == MAX_HISTORY {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(edit);
+        self.redo.clear();
+    }
+
+    /// The latest edit, to update while it's still going on, like a stroke being drawn.
+    pub fn latest_mut(&mut self) -> Option<&mut Edit> {
+        self.undo.back_mut()
+    }
+
+    /// The edit to apply to undo the latest one.
+    pub fn undo(&mut self) -> Option<Edit> {
+        let edit = self.undo.pop_back()?;
+        self.redo.push(edit.clone());
+        Some(edit.inverse())
+    }
+
+    /// The edit to apply to redo the latest undone one.
+    pub fn redo(&mut self) -> Option<Edit> {
+        let edit = self.redo.pop()?;
+        self.undo.push_back(edit.clone());
+        Some(edit)
+    }
+}
+
+/// Everything edits can change, by name.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionState {
+    pub background: String,
+    pub feed_source: String,
+    pub high_contrast: bool,
+    pub reduced_motion: bool,
+}
+
+impl SessionState {
+    /// Saves the state on an I/O worker.
+    pub fn save(&self) {
+        let json = match serde_json::to_vec_pretty(self) {
+            Ok(json) => json,
+            Err(err) => {
+                eprintln!("Failed to save session state: {err:#}");
+                return;
+            }
+        };
+        io_pool::submit(Priority::Stats, "session state", move || {
+            if let Err(err) = std::fs::write(SESSION_PATH, json) {
+                eprintln!("Failed to save session state: {err:#}");
+            }
+        });
+    }
+
+    pub fn load() -> Option<Self> {
+        let json = std::fs::read(SESSION_PATH).ok()?;
+        serde_json::from_slice(&json)
+            .map_err(|err| eprintln!("Ignoring invalid {SESSION_PATH}: {err}"))
+            .ok()
+    }
+}
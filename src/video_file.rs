@@ -0,0 +1,180 @@
+//! Plays a local video file (mp4, mov or anything else ffmpeg reads) as capture source,
+//! frame by frame at its frame rate, e.g. for trying out the views without screen
+//! recording permission.
+//!
+//! ffmpeg decodes the file to raw BGRA frames on its stdout, the same pixels a display
+//! capture has, so frames go through the same processing and texture uploads. The size
+//! and frame rate come from ffprobe, next to the `[recording] ffmpeg_path`.
+
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use anyhow::Context as _;
+use crabgrab::prelude::FrameBitmapBgraUnorm8x4;
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+use serde::Deserialize;
+
+use crate::capture::{self, Frame};
+use crate::focus::ScreenRect;
+use crate::status;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct VideoConfig {
+    /// Video file to play instead of capturing.
+    pub file: Option<PathBuf>,
+
+    /// Start over at the end instead of holding the last frame.
+    pub looping: bool,
+}
+
+/// What ffprobe says about the first video stream.
+#[derive(Debug, Clone, Copy)]
+struct VideoInfo {
+    width: usize,
+    height: usize,
+    fps: f64,
+}
+
+/// Plays `path` on a background thread, handing every frame to `on_frame`, until `stop`
+/// disconnects.
+pub fn start(
+    path: PathBuf,
+    looping: bool,
+    ffmpeg_path: String,
+    stop: Receiver<()>,
+    mut on_frame: impl FnMut(Frame) + Send + 'static,
+) -> JoinHandle<()> {
+    status::update(|status| {
+        status.source = format!("video {}", path.display());
+        status.source_name = "video".to_owned();
+    });
+    std::thread::Builder::new()
+        .name("video file".to_owned())
+        .spawn(move || {
+            let mut frame_id = 0;
+            loop {
+                match play(&path, &ffmpeg_path, &stop, &mut frame_id, &mut on_frame) {
+                    Ok(None) => return,
+                    Ok(Some(0)) => {
+                        eprintln!("No frames in {}", path.display());
+                        return;
+                    }
+                    Ok(Some(_)) if looping => {}
+                    Ok(Some(frames)) => {
+                        println!("Played {frames} frames of {}", path.display());
+                        return;
+                    }
+                    Err(err) => {
+                        eprintln!("Playing {}: {err:#}", path.display());
+                        return;
+                    }
+                }
+            }
+        })
+        .expect("Failed to spawn the video file thread")
+}
+
+/// Plays the file once, returns the number of frames played, `None` if stopped.
+fn play(
+    path: &Path,
+    ffmpeg_path: &str,
+    stop: &Receiver<()>,
+    frame_id: &mut u64,
+    on_frame: &mut impl FnMut(Frame),
+) -> anyhow::Result<Option<u64>> {
+    let info = probe(path, ffmpeg_path)?;
+    let mut child = Command::new(ffmpeg_path)
+        .args(["-hide_banner", "-loglevel", "error", "-i"])
+        .arg(path)
+        .args(["-map", "0:v:0", "-f", "rawvideo", "-pix_fmt", "bgra", "-"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to run {ffmpeg_path:?}, is ffmpeg installed?"))?;
+    let mut output = BufReader::new(child.stdout.take().context("ffmpeg has no stdout")?);
+
+    let frame_interval = Duration::from_secs_f64(1.0 / info.fps);
+    let started = Instant::now();
+    let mut frames_played = 0;
+    let mut bgra = vec![0; info.width * info.height * 4];
+    let result = loop {
+        match output.read_exact(&mut bgra) {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+                break Ok(Some(frames_played));
+            }
+            Err(err) => break Err(anyhow::Error::new(err).context("failed to read a frame")),
+        }
+        // Keep the frame rate, from the start so waiting doesn't add up.
+        let due = started + frame_interval * frames_played as u32;
+        let wait = stop.recv_timeout(due.saturating_duration_since(Instant::now()));
+        if !matches!(wait, Err(RecvTimeoutError::Timeout)) {
+            break Ok(None);
+        }
+        frames_played += 1;
+        *frame_id += 1;
+        on_frame(to_frame(&bgra, info, *frame_id));
+    };
+    child.kill().ok();
+    child.wait().ok();
+    result
+}
+
+/// Size and frame rate of the first video stream of `path`.
+fn probe(path: &Path, ffmpeg_path: &str) -> anyhow::Result<VideoInfo> {
+    let ffprobe_path = Path::new(ffmpeg_path).with_file_name("ffprobe");
+    let output = Command::new(&ffprobe_path)
+        .args(["-v", "error", "-select_streams", "v:0"])
+        .args(["-show_entries", "stream=width,height,avg_frame_rate", "-of", "csv=p=0"])
+        .arg(path)
+        .output()
+        .with_context(|| format!("failed to run {ffprobe_path:?}, is ffmpeg installed?"))?;
+    anyhow::ensure!(
+        output.status.success(),
+        "ffprobe failed: {}",
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut fields = stdout.trim().split(',');
+    let mut next_field = |name| fields.next().with_context(|| format!("ffprobe gave no {name}"));
+    let width = next_field("width")?.parse().context("invalid width")?;
+    let height = next_field("height")?.parse().context("invalid height")?;
+    let frame_rate = next_field("frame rate")?;
+    // A fraction like 30000/1001.
+    let fps = match frame_rate.split_once('/') {
+        Some((numerator, denominator)) => {
+            numerator.parse::<f64>().context("invalid frame rate")?
+                / denominator.parse::<f64>().context("invalid frame rate")?
+        }
+        None => frame_rate.parse().context("invalid frame rate")?,
+    };
+    anyhow::ensure!(fps.is_finite() && fps > 0.0, "no frame rate in {}", path.display());
+    Ok(VideoInfo { width, height, fps })
+}
+
+fn to_frame(bgra: &[u8], info: VideoInfo, frame_id: u64) -> Frame {
+    let data = bgra
+        .chunks_exact(4)
+        .map(|pixel| [pixel[0], pixel[1], pixel[2], pixel[3]])
+        .collect::<Vec<_>>();
+    Frame {
+        frame_bitmap: FrameBitmapBgraUnorm8x4 {
+            data: data.into(),
+            width: info.width,
+            height: info.height,
+        },
+        frame_id,
+        capture_time_us: capture::now_us(),
+        // Not on this screen, but one point per pixel keeps picking working.
+        source_rect: ScreenRect {
+            min: glam::Vec2::ZERO,
+            size: glam::vec2(info.width as f32, info.height as f32),
+        },
+        ycbcr: None,
+        hdr_data: None,
+    }
+}
@@ -0,0 +1,88 @@
+//! Trigger files for harnesses that can't speak the control API: creating e.g.
+//! `start_recording` in the watched directory sends the matching [`ControlCommand`], then
+//! the file is deleted. Other files are left alone and reported once.
+//!
+//! Files named after commands that take an argument (`marker`, `caption`, `preset`) pass
+//! their contents as argument. The directory is polled, which works the same on every
+//! platform and filesystem.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::control::{self, ControlCommand};
+use crate::recorder::RecordingSource;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct WatchFolderConfig {
+    /// Directory watched for trigger files, disabled if unset.
+    pub directory: Option<PathBuf>,
+}
+
+/// Watches `directory` for trigger files on a background thread.
+pub fn start(directory: &Path) -> anyhow::Result<()> {
+    std::fs::create_dir_all(directory)?;
+    let directory = directory.to_owned();
+    println!("Watching {} for trigger files", directory.display());
+    std::thread::Builder::new()
+        .name("watch folder".to_owned())
+        .spawn(move || {
+            let mut ignored = HashSet::new();
+            loop {
+                if let Err(err) = poll(&directory, &mut ignored) {
+                    eprintln!("Watch folder: {err:#}");
+                }
+                std::thread::sleep(POLL_INTERVAL);
+            }
+        })?;
+    Ok(())
+}
+
+/// Runs and deletes the trigger files in `directory`. Other files stay, `ignored` has the
+/// ones reported already.
+fn poll(directory: &Path, ignored: &mut HashSet<PathBuf>) -> anyhow::Result<()> {
+    let mut triggers = std::fs::read_dir(directory)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .collect::<Vec<_>>();
+    // Triggers created within one poll run in a predictable order.
+    triggers.sort();
+    ignored.retain(|path| triggers.contains(path));
+    for path in triggers {
+        let name = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+        let contents = std::fs::read_to_string(&path).unwrap_or_default();
+        let Some(command) = command(&name, contents.trim()) else {
+            if ignored.insert(path.clone()) {
+                eprintln!("Watch folder: ignoring {}, it's no trigger", path.display());
+            }
+            continue;
+        };
+        std::fs::remove_file(&path)?;
+        ignored.remove(&path);
+        println!("Watch folder: {name}");
+        control::send(command);
+    }
+    Ok(())
+}
+
+fn command(name: &str, argument: &str) -> Option<ControlCommand> {
+    Some(match name {
+        "screenshot" => ControlCommand::Screenshot,
+        "start_recording" => ControlCommand::StartRecording,
+        "stop_recording" => ControlCommand::StopRecording,
+        "toggle_recording" => ControlCommand::ToggleRecording,
+        "start_streaming" => ControlCommand::StartStreaming,
+        "stop_streaming" => ControlCommand::StopStreaming,
+        "feed_source" => ControlCommand::SetFeedSource(RecordingSource::parse(argument)?),
+        "marker" => ControlCommand::Marker((!argument.is_empty()).then(|| argument.to_owned())),
+        "caption" => ControlCommand::Caption(argument.to_owned()),
+        "preset" => ControlCommand::ApplyPreset(argument.to_owned()),
+        _ => return None,
+    })
+}
@@ -0,0 +1,233 @@
+//! Outgoing webhooks: events POSTed as JSON to plain `http://` URLs, e.g. a local chat bot
+//! or CI service. HTTPS endpoints need a local relay.
+//!
+//! Every webhook has its own queue and thread, failed deliveries are retried with
+//! exponential backoff without holding up the other webhooks.
+
+use std::io::{Read as _, Write as _};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use anyhow::Context as _;
+use crossbeam_channel::Sender;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+
+use crate::events::Event;
+use crate::notifications::Notifier;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Wait before the first retry, doubled for every further one.
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(1);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Undelivered events queued per webhook, newer ones are dropped while it's full.
+const MAX_QUEUED: usize = 100;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    pub url: String,
+
+    /// Event kinds to send, e.g. `recording_started`; all if empty.
+    pub events: Vec<String>,
+
+    /// Retries before an event is given up on.
+    pub max_retries: u32,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            events: Vec::new(),
+            max_retries: 5,
+        }
+    }
+}
+
+/// A webhook with its delivery thread.
+pub struct Webhook {
+    events: Vec<String>,
+    sender: Sender<Event>,
+}
+
+impl Webhook {
+    /// Starts the thread delivering events to `config.url`.
+    pub fn start(config: &WebhookConfig) -> anyhow::Result<Self> {
+        split_url(&config.url).context("invalid webhook")?;
+        let (sender, receiver) = crossbeam_channel::bounded::<Event>(MAX_QUEUED);
+        let url = config.url.clone();
+        let max_retries = config.max_retries;
+        std::thread::Builder::new()
+            .name(format!("webhook {url}"))
+            .spawn(move || {
+                for event in receiver {
+                    let body = match serde_json::to_vec(&event) {
+                        Ok(body) => body,
+                        Err(err) => {
+                            eprintln!("Failed to serialize event {} for {url}: {err}", event.id);
+                            continue;
+                        }
+                    };
+                    if let Err(err) = post_with_retries(&url, &body, max_retries) {
+                        eprintln!("Webhook {url} gave up on event {}: {err:#}", event.id);
+                    }
+                }
+            })?;
+        Ok(Self {
+            events: config.events.clone(),
+            sender,
+        })
+    }
+}
+
+impl Notifier for Webhook {
+    /// Queues `event` if the webhook is subscribed to its kind.
+    fn notify(&self, event: &Event) {
+        let is_subscribed =
+            self.events.is_empty() || self.events.iter().any(|kind| kind == event.kind);
+        if is_subscribed && self.sender.try_send(event.clone()).is_err() {
+            eprintln!("Webhook queue full, dropping event {}", event.id);
+        }
+    }
+}
+
+/// Queue of [`post_in_background`], delivered one after the other by a single thread.
+static ONE_OFF_QUEUE: Lazy<Sender<(String, Event)>> = Lazy::new(|| {
+    let (sender, receiver) = crossbeam_channel::bounded::<(String, Event)>(MAX_QUEUED);
+    std::thread::Builder::new()
+        .name("one-off webhooks".to_owned())
+        .spawn(move || {
+            let max_retries = WebhookConfig::default().max_retries;
+            for (url, event) in receiver {
+                let result = serde_json::to_vec(&event)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|body| post_with_retries(&url, &body, max_retries));
+                if let Err(err) = result {
+                    eprintln!("Webhook {url} gave up on event {}: {err:#}", event.id);
+                }
+            }
+        })
+        .expect("Failed to spawn the one-off webhook thread");
+    sender
+});
+
+/// POSTs `event` to `url` on a background thread, retrying like the configured webhooks.
+/// Dropped while [`MAX_QUEUED`] are waiting already.
+pub fn post_in_background(url: &str, event: &Event) {
+    if ONE_OFF_QUEUE.try_send((url.to_owned(), event.clone())).is_err() {
+        eprintln!("Webhook queue full, dropping event {} for {url}", event.id);
+    }
+}
+
+fn post_with_retries(url: &str, body: &[u8], max_retries: u32) -> anyhow::Result<()> {
+    let mut delay = FIRST_RETRY_DELAY;
+    let mut retries = 0;
+    loop {
+        match post(url, body) {
+            Ok(()) => return Ok(()),
+            Err(err) if retries < max_retries => {
+                eprintln!("Webhook {url} failed, retrying in {} s: {err:#}", delay.as_secs());
+                std::thread::sleep(delay);
+                delay = (delay * 2).min(MAX_RETRY_DELAY);
+                retries += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// POSTs the JSON `body` to `url`, an error unless answered with a 2xx status.
+pub fn post(url: &str, body: &[u8]) -> anyhow::Result<()> {
+    let (host, path) = split_url(url)?;
+    let address = with_port(host);
+
+    let mut stream =
+        TcpStream::connect(&address).with_context(|| format!("failed to connect to {address}"))?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    write!(
+        stream,
+        "POST {path} HTTP/1.1\r\nHost: {host}\r\nContent-Type: application/json\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    )?;
+    stream.write_all(body)?;
+
+    // Only the status code matters, the status line fits into the first read.
+    let mut response = [0; 64];
+    let read = stream.read(&mut response)?;
+    let status_line = String::from_utf8_lossy(&response[..read]);
+    let code = status_line.split_whitespace().nth(1).unwrap_or_default();
+    anyhow::ensure!(
+        code.starts_with('2'),
+        "answered {:?}",
+        status_line.lines().next().unwrap_or_default()
+    );
+    Ok(())
+}
+
+/// The host (with the port, if any) and path of an `http://` URL.
+fn split_url(url: &str) -> anyhow::Result<(&str, &str)> {
+    let rest = url
+        .strip_prefix("http://")
+        .with_context(|| format!("only http:// webhooks are supported, not {url:?}"))?;
+    Ok(match rest.find('/') {
+        Some(slash) => rest.split_at(slash),
+        None => (rest, "/"),
+    })
+}
+
+/// `host` with the default port appended, unless it has a port already. IPv6 addresses come
+/// in brackets, `[::1]` or `[::1]:8080`.
+fn with_port(host: &str) -> String {
+    let has_port = host.rsplit_once(':').is_some_and(|(address, port)| {
+        !port.is_empty()
+            && port.bytes().all(|byte| byte.is_ascii_digit())
+            && (!address.starts_with('[') || address.ends_with(']'))
+    });
+    if has_port {
+        host.to_owned()
+    } else {
+        format!("{host}:80")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_url_separates_host_and_path() {
+        assert_eq!(split_url("http://localhost:8080/hook").unwrap(), ("localhost:8080", "/hook"));
+        assert_eq!(split_url("http://example.com/a/b?c=d").unwrap(), ("example.com", "/a/b?c=d"));
+    }
+
+    #[test]
+    fn split_url_defaults_to_the_root_path() {
+        assert_eq!(split_url("http://example.com").unwrap(), ("example.com", "/"));
+    }
+
+    #[test]
+    fn split_url_rejects_other_schemes() {
+        assert!(split_url("https://example.com/hook").is_err());
+        assert!(split_url("example.com/hook").is_err());
+    }
+
+    #[test]
+    fn with_port_keeps_ports_and_defaults_to_80() {
+        assert_eq!(with_port("example.com"), "example.com:80");
+        assert_eq!(with_port("localhost:8080"), "localhost:8080");
+        assert_eq!(with_port("127.0.0.1"), "127.0.0.1:80");
+    }
+
+    #[test]
+    fn with_port_handles_ipv6_addresses() {
+        assert_eq!(with_port("[::1]"), "[::1]:80");
+        assert_eq!(with_port("[::1]:8080"), "[::1]:8080");
+        assert_eq!(with_port("[2001:db8::1]"), "[2001:db8::1]:80");
+        assert_eq!(split_url("http://[::1]:8080/hook").unwrap(), ("[::1]:8080", "/hook"));
+    }
+}